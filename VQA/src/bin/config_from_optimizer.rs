@@ -0,0 +1,76 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use structopt::StructOpt;
+
+use vqa_workload::optimizer::{OptimizerOutput, WorkerInventory, ExecutionProfile, WorkflowOptions};
+use vqa_workload::optimizer::build_workflow_config;
+
+#[derive(StructOpt, Debug, Clone)]
+#[structopt(about = "Construct the VQA workflow config from the query optimizer's output")]
+pub struct Opts {
+    /// Optimizer output (JSON)
+    #[structopt(long, parse(from_os_str))]
+    pub optimizer_output: PathBuf,
+
+    /// Worker inventory (JSON), worker name -> host, device
+    #[structopt(long, parse(from_os_str))]
+    pub inventory: PathBuf,
+
+    /// Execution profile used by the optimizer, e.g., examples/optimizer_inputs/execution_profile.csv
+    #[structopt(long, parse(from_os_str))]
+    pub execution_profile: PathBuf,
+
+    #[structopt(long)]
+    pub request_rate: f64,
+
+    #[structopt(long)]
+    pub dataset_path: String,
+
+    #[structopt(long, default_value = "~/VQAWorkflowLogging")]
+    pub logging_dir: String,
+
+    #[structopt(long)]
+    pub buffer_read: bool,
+
+    #[structopt(long)]
+    pub num_instances: Option<usize>,
+
+    /// Path to write the workflow config, printed to stdout if not provided
+    #[structopt(short, long, parse(from_os_str))]
+    pub output: Option<PathBuf>,
+}
+
+// cargo run --bin config_from_optimizer -- --optimizer-output examples/optimizer_output.json --inventory examples/worker_inventory.json \
+//     --execution-profile examples/optimizer_inputs/execution_profile.csv --request-rate 40 --dataset-path /data/VQA_Workflow_Datasets
+fn main() {
+    let opt = Opts::from_args();
+
+    let file = File::open(&opt.optimizer_output).expect("could not open optimizer output");
+    let optimizer_output: OptimizerOutput = serde_json::from_reader(BufReader::new(file)).expect("invalid optimizer output");
+    let file = File::open(&opt.inventory).expect("could not open worker inventory");
+    let inventory: WorkerInventory = serde_json::from_reader(BufReader::new(file)).expect("invalid worker inventory");
+    let profile = ExecutionProfile::from_csv_file(&opt.execution_profile).unwrap();
+
+    let options = WorkflowOptions {
+        request_rate: opt.request_rate,
+        dataset_path: opt.dataset_path,
+        logging_dir: opt.logging_dir,
+        buffer_read: if opt.buffer_read { Some(true) } else { None },
+        num_instances: opt.num_instances,
+    };
+    let config = match build_workflow_config(&optimizer_output, &inventory, &profile, options) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("failed to construct workflow config: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let config = serde_json::to_string_pretty(&config).unwrap();
+    match opt.output {
+        Some(path) => std::fs::write(path, config).expect("Unable to write workflow config"),
+        None => println!("{}", config),
+    }
+}
@@ -1,6 +1,5 @@
 mod relay;
 mod worker;
-mod config;
 
 use std::collections::{HashMap, HashSet};
 use std::fs::File;
@@ -13,11 +12,11 @@ use std::any::Any;
 use structopt::StructOpt;
 use shellexpand::tilde;
 
-use mlflow::{PipelineConfig, ExecutionConfig};
+use mlflow::{PipelineConfig, ExecutionConfig, PipelineGraphBuilder};
 
-use crate::config::VQAWorkflowConfig;
+use vqa_workload::workflow_config::VQAWorkflowConfig;
 use crate::relay::run_pipeline_relay;
-use crate::worker::run_pipeline_worker;
+use crate::worker::{run_pipeline_worker, vqa_dag_builder};
 
 #[derive(StructOpt, Debug, Clone)]
 #[structopt(about = "VQA Workflow")]
@@ -36,10 +35,15 @@ pub struct Opts {
     pub pipeline: usize,
 
     #[structopt(short, long)]
-    pub index: usize
+    pub index: usize,
+
+    /// Write the dataflow topology (JSON and DOT) to this directory and exit
+    #[structopt(long, parse(from_os_str))]
+    pub export_topology: Option<PathBuf>
 }
 
 // cargo run --bin workflow -- -c [CONFIG_PATH] -p [PIPELINE_INDEX] -i [WORKER_INDEX] -r (or -w)
+// cargo run --bin workflow -- -c [CONFIG_PATH] -p 0 -i 0 --export-topology [OUTPUT_DIR]
 fn main() {
     let opt = Opts::from_args();
     if opt.worker || opt.relay {
@@ -64,6 +68,17 @@ fn main() {
     let dataset_path = tilde(&config.dataset_path).into_owned();
     let num_instances = config.num_instances;
 
+    if let Some(export_dir) = opt.export_topology {
+        let mut builder_configs: HashMap<String, Arc<dyn Any + Send + Sync>> = HashMap::new();
+        builder_configs.insert(String::from("model_assignments"), Arc::new(HashMap::<String, String>::new()));
+        builder_configs.insert(String::from("model_device_placements"), Arc::new(HashMap::<(String, usize), String>::new()));
+        let topology = PipelineGraphBuilder::extract_topology(&vqa_dag_builder(buffer_read, num_instances), builder_configs);
+        std::fs::create_dir_all(&export_dir).unwrap();
+        std::fs::write(export_dir.join("vqa_topology.json"), topology.to_json()).expect("Unable to write topology");
+        std::fs::write(export_dir.join("vqa_topology.dot"), topology.to_dot()).expect("Unable to write topology");
+        return;
+    }
+
     let node_index = opt.index;
     let pipeline_index = opt.pipeline;
     let num_workers = pipeline_specs.get(&format!("pipeline_{}", pipeline_index)).expect("wrong pipeline index").worker_addrs.len();
@@ -90,19 +105,20 @@ fn main() {
             assigned_ops: vec![
                 String::from("ReadSpeechAudio"),
             ],
-            required_input_ops: Some(vec![]),
-            output_ops: Some(vec![
-                String::from("ReadSpeechAudio")
-            ]),
+            required_input_ops: None,
+            output_ops: None,
             worker_addrs: pipeline_spec.worker_addrs,
             relay_addrs: pipeline_spec.relay_addrs,
             relay_load_balance_weights: pipeline_spec.relay_weights,
             input_pipelines: vec![],
-            output_pipelines: vec![2],
+            output_pipelines: vec![],
             builder_configs,
             operator_configs: HashMap::new(),
             request_rates: HashMap::from_iter([(String::from("ReadSpeechAudio"), request_rate)]),
             source_operators: HashSet::new(),
+            flow_control: None,
+            threads: 1,
+            metrics_endpoint: None,
         };
     }
     else {
@@ -112,19 +128,20 @@ fn main() {
                 String::from("InputSpeechPath"), 
                 String::from("ReadSpeechAudio"),
             ],
-            required_input_ops: Some(vec![]),
-            output_ops: Some(vec![
-                String::from("ReadSpeechAudio")
-            ]),
+            required_input_ops: None,
+            output_ops: None,
             worker_addrs: pipeline_spec.worker_addrs,
             relay_addrs: pipeline_spec.relay_addrs,
             relay_load_balance_weights: pipeline_spec.relay_weights,
             input_pipelines: vec![],
-            output_pipelines: vec![2],
+            output_pipelines: vec![],
             builder_configs,
             operator_configs: HashMap::new(),
             request_rates: HashMap::from_iter([(String::from("InputSpeechPath"), request_rate)]),
-            source_operators: HashSet::from_iter([String::from("ReadSpeechAudio")])
+            source_operators: HashSet::from_iter([String::from("ReadSpeechAudio")]),
+            flow_control: None,
+            threads: 1,
+            metrics_endpoint: None,
         };
     }
 
@@ -141,19 +158,20 @@ fn main() {
             assigned_ops: vec![
                 String::from("ReadImage"),
             ],
-            required_input_ops: Some(vec![]),
-            output_ops: Some(vec![
-                String::from("ReadImage")
-            ]),
+            required_input_ops: None,
+            output_ops: None,
             worker_addrs: pipeline_spec.worker_addrs,
             relay_addrs: pipeline_spec.relay_addrs,
             relay_load_balance_weights: pipeline_spec.relay_weights,
             input_pipelines: vec![],
-            output_pipelines: vec![3],
+            output_pipelines: vec![],
             builder_configs,
             operator_configs: HashMap::new(),
             request_rates: HashMap::from_iter([(String::from("ReadImage"), request_rate)]),
-            source_operators: HashSet::new()
+            source_operators: HashSet::new(),
+            flow_control: None,
+            threads: 1,
+            metrics_endpoint: None,
         };
     }
     else {
@@ -163,19 +181,20 @@ fn main() {
                 String::from("InputImagePath"),
                 String::from("ReadImage"),
             ],
-            required_input_ops: Some(vec![]),
-            output_ops: Some(vec![
-                String::from("ReadImage")
-            ]),
+            required_input_ops: None,
+            output_ops: None,
             worker_addrs: pipeline_spec.worker_addrs,
             relay_addrs: pipeline_spec.relay_addrs,
             relay_load_balance_weights: pipeline_spec.relay_weights,
             input_pipelines: vec![],
-            output_pipelines: vec![3],
+            output_pipelines: vec![],
             builder_configs,
             operator_configs: HashMap::new(),
             request_rates: HashMap::from_iter([(String::from("InputImagePath"), request_rate)]),
-            source_operators: HashSet::from_iter([String::from("ReadImage")])
+            source_operators: HashSet::from_iter([String::from("ReadImage")]),
+            flow_control: None,
+            threads: 1,
+            metrics_endpoint: None,
         };
     }
     
@@ -202,21 +221,20 @@ fn main() {
         assigned_ops: vec![
             String::from("SpeechRecognition"),
         ],
-        required_input_ops: Some(vec![
-            String::from("ReadSpeechAudio")
-        ]),
-        output_ops: Some(vec![
-            String::from("SpeechRecognition")
-        ]),
+        required_input_ops: None,
+        output_ops: None,
         worker_addrs: pipeline_spec.worker_addrs,
         relay_addrs: pipeline_spec.relay_addrs,
         relay_load_balance_weights: pipeline_spec.relay_weights,
-        input_pipelines: vec![0],
-        output_pipelines: vec![4],
+        input_pipelines: vec![],
+        output_pipelines: vec![],
         builder_configs,
         operator_configs,
         request_rates: HashMap::new(),
         source_operators: HashSet::new(),
+        flow_control: None,
+        threads: 1,
+        metrics_endpoint: None,
     };
 
     let pipeline_spec = pipeline_specs.remove("pipeline_3").unwrap();
@@ -242,21 +260,20 @@ fn main() {
         assigned_ops: vec![
             String::from("ImageFeatureExtract")
         ],
-        required_input_ops: Some(vec![
-            String::from("ReadImage")
-        ]),
-        output_ops: Some(vec![
-            String::from("ImageFeatureExtract")
-        ]),
+        required_input_ops: None,
+        output_ops: None,
         worker_addrs: pipeline_spec.worker_addrs,
         relay_addrs: pipeline_spec.relay_addrs,
         relay_load_balance_weights: pipeline_spec.relay_weights,
-        input_pipelines: vec![1],
-        output_pipelines: vec![4],
+        input_pipelines: vec![],
+        output_pipelines: vec![],
         builder_configs,
         operator_configs,
         request_rates: HashMap::new(),
         source_operators: HashSet::new(),
+        flow_control: None,
+        threads: 1,
+        metrics_endpoint: None,
     };
   
     let pipeline_spec = pipeline_specs.remove("pipeline_4").unwrap();
@@ -288,20 +305,20 @@ fn main() {
             String::from("GatherResults"),
             String::from("InspectAnswer")
         ],
-        required_input_ops: Some(vec![
-            String::from("ImageFeatureExtract"),
-            String::from("SpeechRecognition")
-        ]),
-        output_ops: Some(vec![]),
+        required_input_ops: None,
+        output_ops: None,
         worker_addrs: pipeline_spec.worker_addrs,
         relay_addrs: pipeline_spec.relay_addrs,
         relay_load_balance_weights: pipeline_spec.relay_weights,
-        input_pipelines: vec![2, 3],
+        input_pipelines: vec![],
         output_pipelines: vec![],
         builder_configs,
         operator_configs,
         request_rates: HashMap::new(),
         source_operators: HashSet::new(),
+        flow_control: None,
+        threads: 1,
+        metrics_endpoint: None,
     };
 
 
@@ -314,18 +331,27 @@ fn main() {
     ]);
 
     let timely_message_buffer_size = Some(1);
-    let config = ExecutionConfig::new_with_default_mapping(pipeline_configs, Some(logging_dir), timely_message_buffer_size);
-    if opt.worker {
-        run_pipeline_worker(config, opt.pipeline, node_index, buffer_read, num_instances);
+    let mut config = ExecutionConfig::new_with_default_mapping(pipeline_configs, Some(logging_dir), timely_message_buffer_size);
+    // pipeline inputs / outputs are inferred from the dataflow edges
+    if let Err(err) = config.capture_graph_connections(&vqa_dag_builder(buffer_read, num_instances)) {
+        eprintln!("invalid pipeline assignment: {}", err);
+        std::process::exit(1);
+    }
+    let result = if opt.worker {
+        run_pipeline_worker(config, opt.pipeline, node_index, buffer_read, num_instances)
     }
     else if opt.relay {
-        run_pipeline_relay(config, opt.pipeline, node_index);
+        run_pipeline_relay(config, opt.pipeline, node_index)
     }
     else {
+        // the worker retries connecting to the relay node until it listens
         let relay_config = config.clone();
         let handle = std::thread::spawn(move || run_pipeline_relay(relay_config, pipeline_index, node_index));
-        std::thread::sleep(std::time::Duration::from_secs(1));
-        run_pipeline_worker(config, pipeline_index, node_index, buffer_read, num_instances);
-        handle.join().unwrap();
+        run_pipeline_worker(config, pipeline_index, node_index, buffer_read, num_instances)
+            .and_then(|_| handle.join().unwrap())
+    };
+    if let Err(err) = result {
+        eprintln!("pipeline {} failed: {}", pipeline_index, err);
+        std::process::exit(1);
     }
 }
\ No newline at end of file
@@ -17,12 +17,12 @@ use vqa_workload::transcribe_speech;
 use mlflow::{Map, Join, Inspect};
 use mlflow::PipelineGraphBuilder;
 use mlflow::ExecutionConfig;
-use mlflow::pipeline_worker_execute;
+use mlflow::{pipeline_worker_execute, ExecuteError};
 
 const READ_BUFFER_SIZE: usize = 1024;
 
-pub fn run_pipeline_worker(config: ExecutionConfig, pipeline_index: usize, worker_index: usize, buffer_input_read: bool, num_instances: Option<usize>) {
-    let builder = move |builder: &mut PipelineGraphBuilder<u64>| {
+pub fn vqa_dag_builder(buffer_input_read: bool, num_instances: Option<usize>) -> impl Fn(&mut PipelineGraphBuilder<u64>) + Send + Sync + 'static {
+    move |builder: &mut PipelineGraphBuilder<u64>| {
         let worker_index = builder.worker_index();
         let assigned_ops = builder.get_assigned_operators().expect("could not acquire assigned operators");
 
@@ -112,7 +112,14 @@ pub fn run_pipeline_worker(config: ExecutionConfig, pipeline_index: usize, worke
             load_utils_module: true
         };
 
-        let resources = Rc::new(PyResources::new(setup_config));
+        // no operator runs in a topology-only pass (see `PipelineGraphBuilder::extract_topology`),
+        // so the python interpreter is not started to capture the graph edges
+        let resources = if assigned_ops.is_empty() {
+            None
+        }
+        else {
+            Some(Rc::new(PyResources::new(setup_config)))
+        };
 
         let (image_paths, speech_paths) = if assigned_ops.contains(&String::from("InputImagePath")) 
                 || assigned_ops.contains(&String::from("InputSpeechPath")) 
@@ -131,7 +138,7 @@ pub fn run_pipeline_worker(config: ExecutionConfig, pipeline_index: usize, worke
         if buffer_input_read {
             image_handle = builder.new_input_buffered_from_source_distributed(
                 image_paths, 
-                move |(uid, path)| (VQAImageContained::from(read_image(path, uid, &shared_resources).unwrap()), uid),
+                move |(uid, path)| (VQAImageContained::from(read_image(path, uid, shared_resources.as_ref().unwrap()).unwrap()), uid),
                 READ_BUFFER_SIZE,
                 "ReadImage"
             );
@@ -143,7 +150,7 @@ pub fn run_pipeline_worker(config: ExecutionConfig, pipeline_index: usize, worke
                 "InputImagePath"
             );
             image_handle = handle.map(
-                move |(uid, path)| VQAImageContained::from(read_image(path, uid, &shared_resources).unwrap()),
+                move |(uid, path)| VQAImageContained::from(read_image(path, uid, shared_resources.as_ref().unwrap()).unwrap()),
                 "ReadImage"
             );
         }
@@ -154,7 +161,7 @@ pub fn run_pipeline_worker(config: ExecutionConfig, pipeline_index: usize, worke
             speech_handle = builder.new_input_buffered_from_source_distributed(
                 speech_paths,
                 move |(uid, path)| (VQAQuestionRawSpeechContained::from(
-                    read_audio(path, uid, 16000, &shared_resources).unwrap()
+                    read_audio(path, uid, 16000, shared_resources.as_ref().unwrap()).unwrap()
                 ), uid),
                 READ_BUFFER_SIZE,
                 "ReadSpeechAudio"
@@ -168,7 +175,7 @@ pub fn run_pipeline_worker(config: ExecutionConfig, pipeline_index: usize, worke
             );
             speech_handle = handle.map(
                 move |(uid, path)| VQAQuestionRawSpeechContained::from(
-                    read_audio(path, uid, 16000, &shared_resources).unwrap()
+                    read_audio(path, uid, 16000, shared_resources.as_ref().unwrap()).unwrap()
                 ),
                 "ReadSpeechAudio"
             );            
@@ -176,14 +183,14 @@ pub fn run_pipeline_worker(config: ExecutionConfig, pipeline_index: usize, worke
 
         let shared_resources = resources.clone();
         let speech_handle = speech_handle.map(
-            move |speech| transcribe_speech(speech.into(), &shared_resources).unwrap(),
+            move |speech| transcribe_speech(speech.into(), shared_resources.as_ref().unwrap()).unwrap(),
             "SpeechRecognition"
         );        
 
         let shared_resources = resources.clone();
         let image_handle = image_handle.map(
             move |img| {
-                let feat = extract_image_features(img.into(), &shared_resources).unwrap();
+                let feat = extract_image_features(img.into(), shared_resources.as_ref().unwrap()).unwrap();
                 VQAImageFeatureContained::from(feat)
             },
             "ImageFeatureExtract" 
@@ -208,7 +215,7 @@ pub fn run_pipeline_worker(config: ExecutionConfig, pipeline_index: usize, worke
                 image_feat: img.feat.into(),
                 question: question.text
             };
-            vqa_model_inference(iq_pair, &shared_resources).unwrap()
+            vqa_model_inference(iq_pair, shared_resources.as_ref().unwrap()).unwrap()
         }, "VQAInference")
         .intra_pipeline_gather(0, "GatherResults")
         .inspect(|x| {
@@ -216,8 +223,10 @@ pub fn run_pipeline_worker(config: ExecutionConfig, pipeline_index: usize, worke
             let answer = x.answer.clone();
             println!("Image-Question Pair #{}, answer={}", uid, answer); 
         }, "InspectAnswer");
-    };
-    
-    pipeline_worker_execute(builder, &config, pipeline_index, worker_index);
+    }
+}
 
+pub fn run_pipeline_worker(config: ExecutionConfig, pipeline_index: usize, worker_index: usize, buffer_input_read: bool, num_instances: Option<usize>) -> Result<(), ExecuteError> {
+    let builder = vqa_dag_builder(buffer_input_read, num_instances);
+    pipeline_worker_execute(builder, &config, pipeline_index, worker_index)
 }
\ No newline at end of file
@@ -2,6 +2,8 @@ pub mod utils;
 pub mod data;
 pub mod resources;
 pub mod config;
+pub mod workflow_config;
+pub mod optimizer;
 pub mod image_feature_extract;
 pub mod vqa_inference;
 pub mod speech_recognition;
@@ -0,0 +1,187 @@
+//! Construct the VQA workflow config from the query optimizer's output
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Serialize, Deserialize};
+
+use crate::workflow_config::{PipelineSpecification, VQAWorkflowConfig};
+
+/// Nodes in the optimizer's logical graph and where they are placed in the VQA workflow:
+/// (optimizer node, pipeline index, operator running the node's model)
+const VQA_PIPELINE_NODES: [(&str, usize, Option<&str>); 5] = [
+    ("ReadSpeech", 0, None),
+    ("ReadImage", 1, None),
+    ("SpeechRecognition", 2, Some("SpeechRecognition")),
+    ("ExtractImageFeature", 3, Some("ImageFeatureExtract")),
+    ("VQA", 4, Some("VQAInference")),
+];
+
+/// Variant name the optimizer uses for nodes without model choices
+const DEFAULT_VARIANT: &str = "default";
+
+/// Output of `optimizer/main.py`
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct OptimizerOutput {
+    /// node -> model variant
+    pub model_assignment: HashMap<String, String>,
+    /// node -> names of the assigned workers
+    pub worker_assignment: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub compute_cost: Option<f64>,
+    #[serde(default)]
+    pub communication_cost: Option<f64>,
+    #[serde(default)]
+    pub end_to_end_accuracy: Option<f64>,
+}
+
+/// A worker (machine) available for deployment
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct WorkerSpecification {
+    /// IP address or hostname of the worker
+    pub host: String,
+    /// Device to run models on, e.g., `cpu` or `cuda:0`
+    pub device: String,
+}
+
+/// Workers available for deployment, keyed by the worker names used in the optimizer's inputs
+#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize)]
+pub struct WorkerInventory {
+    pub workers: HashMap<String, WorkerSpecification>,
+    /// Workers running the source nodes that are not assigned by the optimizer (node -> worker names)
+    #[serde(default)]
+    pub source_workers: HashMap<String, Vec<String>>,
+    /// Port of the first timely worker, the k-th worker of pipeline p listens on `worker_base_port + 100 * p + k`
+    #[serde(default = "default_worker_base_port")]
+    pub worker_base_port: u16,
+    /// Port of the first relay node, the k-th relay node of pipeline p listens on `relay_base_port + 100 * p + k`
+    #[serde(default = "default_relay_base_port")]
+    pub relay_base_port: u16,
+}
+
+fn default_worker_base_port() -> u16 { 5000 }
+
+fn default_relay_base_port() -> u16 { 7000 }
+
+/// Execution latency (in milliseconds) of the model variants on each worker,
+/// read from the optimizer's `execution_profile.csv` (node,worker,variant,latency)
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionProfile {
+    latencies: HashMap<(String, String, String), f64>,
+}
+
+impl ExecutionProfile {
+    pub fn from_csv_str(profile: &str) -> Result<Self, String> {
+        let mut latencies = HashMap::new();
+        for (line_idx, line) in profile.lines().enumerate() {
+            let fields = line.split(',').map(|x| x.trim()).filter(|x| !x.is_empty()).collect::<Vec<_>>();
+            if fields.is_empty() {
+                continue;
+            }
+            if fields.len() != 4 {
+                return Err(format!("invalid execution profile at line {}: {}", line_idx + 1, line));
+            }
+            let latency = fields[3].parse::<f64>().map_err(
+                |err| format!("invalid execution latency at line {}: {}", line_idx + 1, err)
+            )?;
+            latencies.insert((fields[0].to_owned(), fields[1].to_owned(), fields[2].to_owned()), latency);
+        }
+        Ok(ExecutionProfile { latencies })
+    }
+
+    pub fn from_csv_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let profile = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        Self::from_csv_str(&profile)
+    }
+
+    /// Throughput (requests per second) of the model variant on the worker
+    pub fn throughput(&self, node: &str, worker: &str, variant: &str) -> Option<f64> {
+        self.latencies.get(&(node.to_owned(), worker.to_owned(), variant.to_owned())).map(|latency| 1000.0 / latency)
+    }
+}
+
+/// Options of the workflow that are not decided by the optimizer
+#[derive(Debug, Clone)]
+pub struct WorkflowOptions {
+    pub request_rate: f64,
+    pub dataset_path: String,
+    pub logging_dir: String,
+    pub buffer_read: Option<bool>,
+    pub num_instances: Option<usize>,
+}
+
+/// Build the VQA workflow config from the optimizer's assignments.
+/// Relay nodes of a pipeline with multiple workers are weighted by the throughput
+/// of the assigned model variant on each worker
+pub fn build_workflow_config(
+    output: &OptimizerOutput,
+    inventory: &WorkerInventory,
+    profile: &ExecutionProfile,
+    options: WorkflowOptions
+) -> Result<VQAWorkflowConfig, String> {
+    let mut pipeline_specs = HashMap::with_capacity(VQA_PIPELINE_NODES.len());
+    for (node, pipeline_index, operator) in VQA_PIPELINE_NODES.iter() {
+        let workers = match output.worker_assignment.get(*node).or_else(|| inventory.source_workers.get(*node)) {
+            Some(workers) if !workers.is_empty() => workers,
+            _ => return Err(format!("no worker is assigned to {}", node)),
+        };
+
+        let mut worker_addrs = Vec::with_capacity(workers.len());
+        let mut relay_addrs = Vec::with_capacity(workers.len());
+        let mut devices = Vec::with_capacity(workers.len());
+        for (worker_index, worker) in workers.iter().enumerate() {
+            let spec = inventory.workers.get(worker).ok_or_else(|| format!("worker {} is not in the inventory", worker))?;
+            let port_offset = (100 * pipeline_index + worker_index) as u16;
+            worker_addrs.push(format!("{}:{}", spec.host, inventory.worker_base_port + port_offset));
+            relay_addrs.push(format!("{}:{}", spec.host, inventory.relay_base_port + port_offset));
+            devices.push(spec.device.clone());
+        }
+
+        let mut model_assignments = HashMap::new();
+        let mut device_placements = HashMap::new();
+        let mut relay_weights = None;
+        if let Some(operator) = operator {
+            let variant = output.model_assignment.get(*node).map(|x| &x[..]).unwrap_or(DEFAULT_VARIANT);
+            // the VQA model is trained on the features of the image feature extractor
+            let model = if *node == "VQA" && variant == DEFAULT_VARIANT {
+                output.model_assignment.get("ExtractImageFeature").ok_or("no model is assigned to ExtractImageFeature")?
+            }
+            else {
+                variant
+            };
+            model_assignments.insert(operator.to_string(), model.to_owned());
+            device_placements.insert(operator.to_string(), devices);
+
+            if workers.len() > 1 {
+                let weights = workers.iter().map(
+                    |worker| profile.throughput(node, worker, variant).ok_or_else(
+                        || format!("execution profile of {} ({}) on worker {} is missing", node, variant, worker)
+                    )
+                ).collect::<Result<Vec<_>, _>>()?;
+                relay_weights = Some(weights);
+            }
+        }
+
+        let spec = PipelineSpecification {
+            worker_addrs,
+            relay_addrs,
+            relay_weights,
+            model_assignments,
+            device_placements,
+            simulate_network_latency: None,
+        };
+        pipeline_specs.insert(format!("pipeline_{}", pipeline_index), spec);
+    }
+
+    Ok(VQAWorkflowConfig {
+        request_rate: options.request_rate,
+        dataset_path: options.dataset_path,
+        logging_dir: options.logging_dir,
+        pipeline_specs,
+        buffer_read: options.buffer_read,
+        num_instances: options.num_instances,
+    })
+}
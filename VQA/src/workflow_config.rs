@@ -2,6 +2,7 @@ use std::collections::HashMap;
 
 use serde::{Serialize, Deserialize};
 
+/// Deployment of a pipeline in the VQA workflow
 #[derive(Debug, Clone)]
 #[derive(Serialize, Deserialize)]
 pub struct PipelineSpecification {
@@ -17,6 +18,8 @@ pub struct PipelineSpecification {
     pub simulate_network_latency: Option<HashMap<String, i64>>,
 }
 
+/// Config of the VQA workflow binary,
+/// `pipeline_specs` is keyed by `pipeline_{index}`
 #[derive(Debug, Clone)]
 #[derive(Serialize, Deserialize)]
 pub struct VQAWorkflowConfig {
@@ -0,0 +1,64 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use float_cmp::approx_eq;
+
+use vqa_workload::optimizer::{OptimizerOutput, WorkerInventory, ExecutionProfile, WorkflowOptions};
+use vqa_workload::optimizer::build_workflow_config;
+
+#[test]
+fn test_build_workflow_config() {
+    let src_dir = env!("CARGO_MANIFEST_DIR");
+    let file = File::open(Path::new(src_dir).join("examples/optimizer_output.json")).unwrap();
+    let output: OptimizerOutput = serde_json::from_reader(BufReader::new(file)).unwrap();
+    let file = File::open(Path::new(src_dir).join("examples/worker_inventory.json")).unwrap();
+    let inventory: WorkerInventory = serde_json::from_reader(BufReader::new(file)).unwrap();
+    let profile = ExecutionProfile::from_csv_file(Path::new(src_dir).join("examples/optimizer_inputs/execution_profile.csv")).unwrap();
+
+    let options = WorkflowOptions {
+        request_rate: 40.0,
+        dataset_path: String::from("/data/VQA_Workflow_Datasets"),
+        logging_dir: String::from("~/VQAWorkflowLogging"),
+        buffer_read: None,
+        num_instances: None,
+    };
+    let config = build_workflow_config(&output, &inventory, &profile, options).unwrap();
+    assert_eq!(config.pipeline_specs.len(), 5);
+
+    let source = config.pipeline_specs.get("pipeline_0").unwrap();
+    assert_eq!(source.worker_addrs, vec![String::from("10.241.64.17:5000")]);
+    assert!(source.relay_weights.is_none());
+
+    let asr = config.pipeline_specs.get("pipeline_2").unwrap();
+    assert_eq!(asr.worker_addrs, vec![String::from("10.241.64.12:5200"), String::from("10.241.64.4:5201")]);
+    assert_eq!(asr.relay_addrs, vec![String::from("10.241.64.12:7200"), String::from("10.241.64.4:7201")]);
+    assert_eq!(asr.device_placements.get("SpeechRecognition").unwrap(), &vec![String::from("cpu"), String::from("cuda:0")]);
+    let weights = asr.relay_weights.as_ref().unwrap();
+    assert!(approx_eq!(f64, weights[0], 3.33720376, epsilon=0.0001));
+    assert!(approx_eq!(f64, weights[1], 37.18199495, epsilon=0.0001));
+
+    let vqa = config.pipeline_specs.get("pipeline_4").unwrap();
+    assert_eq!(vqa.model_assignments.get("VQAInference").unwrap(), "resnet18");
+}
+
+#[test]
+fn test_missing_worker() {
+    let mut output: OptimizerOutput = serde_json::from_str(r#"{
+        "model_assignment": {"ExtractImageFeature": "resnet18", "SpeechRecognition": "wav2vec2-base-960h", "VQA": "default"},
+        "worker_assignment": {"ExtractImageFeature": ["a"], "SpeechRecognition": ["a"], "VQA": ["a"], "ReadImage": ["a"], "ReadSpeech": ["a"]}
+    }"#).unwrap();
+    let inventory: WorkerInventory = serde_json::from_str(r#"{"workers": {"a": {"host": "127.0.0.1", "device": "cpu"}}}"#).unwrap();
+    let options = WorkflowOptions {
+        request_rate: 1.0,
+        dataset_path: String::new(),
+        logging_dir: String::new(),
+        buffer_read: None,
+        num_instances: None,
+    };
+    let config = build_workflow_config(&output, &inventory, &ExecutionProfile::default(), options.clone()).unwrap();
+    assert_eq!(config.pipeline_specs.get("pipeline_3").unwrap().relay_addrs, vec![String::from("127.0.0.1:7300")]);
+
+    output.worker_assignment.insert(String::from("VQA"), vec![String::from("b")]);
+    assert!(build_workflow_config(&output, &inventory, &ExecutionProfile::default(), options).is_err());
+}
@@ -3,13 +3,14 @@ mod worker;
 
 use std::collections::{HashMap, HashSet};
 use std::iter::FromIterator;
+use std::path::PathBuf;
 
 use structopt::StructOpt;
 
-use mlflow::{PipelineConfigGUID, ExecutionConfigGUID};
+use mlflow::{PipelineConfigGUID, ExecutionConfigGUID, ExecutionConfig, LaunchConfig, local_launch};
 
 use crate::relay::run_pipeline_relay;
-use crate::worker::run_pipeline_worker;
+use crate::worker::{run_pipeline_worker, dag_builder};
 
 #[derive(StructOpt, Debug, Clone)]
 #[structopt(about = "Simple two stages pipeline MLflow API test")]
@@ -19,8 +20,14 @@ pub struct Opts {
     #[structopt(short, long)]
     pub relay: bool,
     /// Which pipeline, pipeline 0 or 1
-    #[structopt(short, long)]
-    pub pipeline: usize
+    #[structopt(short, long, default_value = "0")]
+    pub pipeline: usize,
+    /// Deployment spec (JSON or YAML), e.g., examples/simple_two_stages_pipeline/deployment.yaml
+    #[structopt(short, long, parse(from_os_str))]
+    pub spec: Option<PathBuf>,
+    /// Launch all workers and relay nodes of the deployment spec on localhost
+    #[structopt(short, long, requires = "spec")]
+    pub launch: bool
 }
 
 
@@ -39,6 +46,9 @@ fn main() {
         operator_configs: HashMap::new(),
         request_rates: HashMap::new(),
         source_operators: HashSet::new(),
+        flow_control: None,
+        threads: 1,
+        metrics_endpoint: None,
     };
 
     let pipeline_1_config = PipelineConfigGUID {
@@ -55,6 +65,9 @@ fn main() {
         operator_configs: HashMap::new(),
         request_rates: HashMap::new(),
         source_operators: HashSet::new(),
+        flow_control: None,
+        threads: 1,
+        metrics_endpoint: None,
     };
 
     let config = HashMap::from_iter([
@@ -66,15 +79,40 @@ fn main() {
         pipeline_configs: config,
         op_name_guid_mapping: None,
         graph_connections: None,
-        message_buffer_size: None
+        message_buffer_size: None,
+        connection_config: None,
+        tracing: None
     };
     
     let opt = Opts::from_args();
+    if opt.launch {
+        let config = ExecutionConfig::from_deployment_file(opt.spec.as_ref().unwrap()).unwrap();
+        match local_launch(dag_builder, &config, LaunchConfig::default()) {
+            Ok(report) => println!("{}", serde_json::to_string_pretty(&report.metrics).unwrap()),
+            Err(err) => {
+                eprintln!("launch failed: {}", err);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    let config = match &opt.spec {
+        Some(spec_path) => {
+            let mut config = ExecutionConfig::from_deployment_file(spec_path).unwrap();
+            config.capture_graph_connections(&dag_builder).unwrap();
+            config.to_guid().unwrap()
+        },
+        None => config
+    };
     assert!(opt.worker ^ opt.relay, "run either pipeline worker or relay");
-    if opt.worker {
-        run_pipeline_worker(&config, opt.pipeline);
+    let result = if opt.worker {
+        run_pipeline_worker(&config, opt.pipeline)
     }
     else {
-        run_pipeline_relay(&config, opt.pipeline);
+        run_pipeline_relay(&config, opt.pipeline)
+    };
+    if let Err(err) = result {
+        eprintln!("pipeline {} failed: {}", opt.pipeline, err);
+        std::process::exit(1);
     }
 }
@@ -1,6 +1,6 @@
-use mlflow::ExecutionConfigGUID;
+use mlflow::{ExecuteError, ExecutionConfigGUID};
 use mlflow::execute::pipeline_relay_execute_guid;
 
-pub fn run_pipeline_relay(config: &ExecutionConfigGUID, pipeline_index: usize) {
-    pipeline_relay_execute_guid(&config, pipeline_index, 0);
+pub fn run_pipeline_relay(config: &ExecutionConfigGUID, pipeline_index: usize) -> Result<(), ExecuteError> {
+    pipeline_relay_execute_guid(config, pipeline_index, 0)
 }
\ No newline at end of file
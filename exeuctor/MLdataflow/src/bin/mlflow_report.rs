@@ -0,0 +1,54 @@
+//! Merge the metrics logged by all pipeline workers of a run into a summary
+//! e.g., `cargo run --bin mlflow_report -- metrics -t topology.json -f markdown -o report.md`
+use std::fs;
+use std::path::PathBuf;
+
+use structopt::StructOpt;
+
+use mlflow::GraphTopology;
+use mlflow::report::MetricsReport;
+
+#[derive(StructOpt, Debug)]
+#[structopt(about = "Cluster-wide metrics report of a run")]
+struct Opts {
+    /// Metrics logging directory of the run
+    #[structopt(parse(from_os_str))]
+    metrics_logging_dir: PathBuf,
+    /// Topology of the dataflow (`GraphTopology::to_json`), required for the end-to-end latency
+    #[structopt(short, long, parse(from_os_str))]
+    topology: Option<PathBuf>,
+    /// json, markdown or html
+    #[structopt(short, long, default_value = "json")]
+    format: String,
+    /// Write the report to a file instead of stdout
+    #[structopt(short, long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+fn main() {
+    let opts = Opts::from_args();
+    let topology = opts.topology.as_ref().map(|path| {
+        let json = fs::read_to_string(path).unwrap_or_else(|err| panic!("failed to read {}: {}", path.display(), err));
+        GraphTopology::from_json(&json).unwrap_or_else(|err| panic!("invalid topology {}: {}", path.display(), err))
+    });
+    let report = match MetricsReport::from_dir(&opts.metrics_logging_dir, topology.as_ref()) {
+        Ok(report) => report,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+    let rendered = match opts.format.as_str() {
+        "json" => report.to_json(),
+        "markdown" | "md" => report.to_markdown(),
+        "html" => report.to_html(),
+        format => {
+            eprintln!("unknown format {}, expected json, markdown or html", format);
+            std::process::exit(1);
+        }
+    };
+    match &opts.output {
+        Some(path) => fs::write(path, rendered).unwrap_or_else(|err| panic!("failed to write {}: {}", path.display(), err)),
+        None => println!("{}", rendered),
+    }
+}
@@ -3,6 +3,7 @@ use std::cell::RefCell;
 use std::collections::{VecDeque, HashMap};
 use std::marker::PhantomData;
 use std::sync::Arc;
+use std::time::Duration;
 
 use timely::{ExchangeData, Data};
 use timely::progress::Timestamp;
@@ -11,10 +12,12 @@ use timely::order::TotalOrder;
 use timely::communication::allocator::{Generic, GenericToRelay};
 
 
-use crate::graph::ComputeGraph;
+use crate::graph::{ComputeGraph, GraphTopology};
 use crate::graph::{GraphNode::LocalInputNode, GraphNode::ExchangeInputNode};
 use crate::handle::Handle;
 use crate::input::{ClosureInputSource, ContainedInputSource, WorkerDistributedContainedInputSource, BufferedWorkerDistributedContainedInputSource, BufferedContainedInputSource};
+use crate::input::{IngestFormat, ListenerInputSource, RequestId, RequestInputSource};
+use crate::serving::RequestEndpoint;
 use crate::static_timely::timely_static_pipeline_scope::PipelineScope;
 use crate::static_timely::timely_static_scope::Child;
 use crate::static_timely::timely_static_worker::Worker;
@@ -59,6 +62,18 @@ where
         graph.into_inner()
     }
 
+    /// Run the dag builder without executing the dataflow,
+    /// and describe the operators and edges it declares.
+    /// No operator is assigned to the builder, i.e., `get_assigned_operators` returns an empty list
+    pub fn extract_topology<F>(dag_builder: &F, builder_configs: HashMap<String, Arc<dyn Any + Send + Sync>>) -> GraphTopology
+    where
+        F: Fn(&mut PipelineGraphBuilder<T>)
+    {
+        let mut builder = PipelineGraphBuilder::new(0, 0, 1, Some(Vec::new()), builder_configs);
+        (dag_builder)(&mut builder);
+        builder.get_graph().topology()
+    }
+
     pub fn get_config<D: 'static + Send + Sync>(&self, key: &str) -> Option<&D> {
         self.builder_configs.get(key).and_then(|val| val.downcast_ref())
     }
@@ -410,6 +425,79 @@ where
             phantom_data: PhantomData           
         }
     }
+
+    /// Input fed by the requests received on a TCP / HTTP listener (see `ListenerInputSource`),
+    /// emits `(request_id, data)` and advances the timestamp with `advance_logic` every `epoch_interval`
+    pub fn new_input_from_listener<'a, D, F, L>(&'a self, addr: &str, format: IngestFormat, decode: F, epoch_interval: Duration, advance_logic: L, name: &str) -> Handle<'a, T, PipelineScope<GenericToRelay, T>, (RequestId, D)>
+    where
+        D: ExchangeData,
+        F: Fn(&[u8]) -> Result<D, String> + Send + Sync + 'static,
+        L: FnMut(&T) -> T + 'static,
+    {
+        let input_node = ListenerInputSource::<_, _, F, L, PipelineScope<GenericToRelay, T>>::new(
+            addr,
+            format,
+            decode,
+            epoch_interval,
+            advance_logic
+        );
+        let index = *self.counter.borrow();
+        self.graph.borrow_mut().operators.insert(index, ExchangeInputNode(Box::new(input_node)));
+        self.graph.borrow_mut().input_operator_indices.push(index);
+        *self.counter.borrow_mut() += 1;
+
+        let mut duplicate = 0;
+        let mut unique_name = name.to_owned();
+        while self.graph.borrow().op_name_local_id_mapping.contains_key(&unique_name) {
+            duplicate += 1;
+            unique_name = format!("{}_{}", name, duplicate);
+        }
+        self.graph.borrow_mut().op_name_local_id_mapping.insert(unique_name.to_owned(), index);
+        self.graph.borrow_mut().op_local_id_name_mapping.insert(index, unique_name);
+
+        Handle {
+            graph: &self.graph,
+            counter: &self.counter,
+            id: index,
+            phantom_scope: PhantomData,
+            phantom_data: PhantomData           
+        }
+    }
+
+    /// Input fed by the requests submitted through the endpoint's clients (see `mlflow::serving`),
+    /// emits `(request_id, data)` and advances the timestamp with `advance_logic` every `epoch_interval`
+    pub fn new_request_input<'a, D, R, L>(&'a self, endpoint: &RequestEndpoint<D, R>, epoch_interval: Duration, advance_logic: L, name: &str) -> Handle<'a, T, PipelineScope<GenericToRelay, T>, (RequestId, D)>
+    where
+        D: ExchangeData,
+        L: FnMut(&T) -> T + 'static,
+    {
+        let input_node = RequestInputSource::<_, _, L, PipelineScope<GenericToRelay, T>>::new(
+            endpoint.requests.clone(),
+            epoch_interval,
+            advance_logic
+        );
+        let index = *self.counter.borrow();
+        self.graph.borrow_mut().operators.insert(index, ExchangeInputNode(Box::new(input_node)));
+        self.graph.borrow_mut().input_operator_indices.push(index);
+        *self.counter.borrow_mut() += 1;
+
+        let mut duplicate = 0;
+        let mut unique_name = name.to_owned();
+        while self.graph.borrow().op_name_local_id_mapping.contains_key(&unique_name) {
+            duplicate += 1;
+            unique_name = format!("{}_{}", name, duplicate);
+        }
+        self.graph.borrow_mut().op_name_local_id_mapping.insert(unique_name.to_owned(), index);
+        self.graph.borrow_mut().op_local_id_name_mapping.insert(index, unique_name);
+
+        Handle {
+            graph: &self.graph,
+            counter: &self.counter,
+            id: index,
+            phantom_scope: PhantomData,
+            phantom_data: PhantomData           
+        }
+    }
 }
 
 pub struct GraphBuilder<T> 
@@ -444,6 +532,17 @@ where
         graph.into_inner()
     }
 
+    /// Run the dag builder without executing the dataflow,
+    /// and describe the operators and edges it declares
+    pub fn extract_topology<F>(dag_builder: &F) -> GraphTopology
+    where
+        F: Fn(&mut GraphBuilder<T>)
+    {
+        let mut builder = GraphBuilder::new(0, 1);
+        (dag_builder)(&mut builder);
+        builder.get_graph().topology()
+    }
+
     pub fn new_input<'a, D, L>(&'a self, emit_logic: L, name: &str) -> Handle<'a, T, Child<Worker<Generic>, T>, D>
     where 
         D: Data,
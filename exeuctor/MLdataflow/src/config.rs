@@ -1,10 +1,25 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
 use std::iter::FromIterator;
 use std::any::Any;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
-use crate::graph::GraphConnections;
+use serde::{Serialize, Deserialize};
+
+use timely::communication::{ConnectionConfig, FlowControlConfig};
+use timely::order::TotalOrder;
+use timely::progress::Timestamp;
+use timely::progress::timestamp::Refines;
+
+use crate::builder::PipelineGraphBuilder;
+use crate::graph::{GraphConnections, GraphTopology};
+use crate::input::ArrivalModel;
+use crate::trace::TracingConfig;
+use crate::utils::{infer_pipeline_io_all, PipelineIoError};
 
 /// Config for a pipeline
 /// Including the nodes to execute on this pipeline
@@ -27,6 +42,12 @@ pub struct PipelineConfigGUID {
     pub request_rates: HashMap<usize, f64>,
     /// Source operators of the dataflow that we mark the start timestamp
     pub source_operators: HashSet<usize>,
+    /// Watermarks of the messages queued at the workers and relay nodes of the pipeline, no backpressure if None
+    pub flow_control: Option<FlowControlConfig>,
+    /// Number of timely worker threads per worker process
+    pub threads: usize,
+    /// Live metrics endpoints of the workers and relay nodes of the pipeline, no endpoint if None
+    pub metrics_endpoint: Option<MetricsEndpointConfig>,
 }
 
 /// Execution config for the entire dataflow (all pipelines)
@@ -36,7 +57,11 @@ pub struct ExecutionConfigGUID {
     pub pipeline_configs: HashMap<usize, PipelineConfigGUID>,
     pub op_name_guid_mapping: Option<HashMap<String, usize>>,
     pub graph_connections: Option<GraphConnections>,
-    pub message_buffer_size: Option<usize>
+    pub message_buffer_size: Option<usize>,
+    /// Connect retries and startup timeout of workers and relay nodes, retry indefinitely if None
+    pub connection_config: Option<ConnectionConfig>,
+    /// Per-request tracing of the sampled requests, no tracing if None
+    pub tracing: Option<TracingConfig>
 }
 
 #[derive(Clone, Debug)]
@@ -56,6 +81,12 @@ pub struct PipelineConfig {
     pub request_rates: HashMap<String, f64>,
     /// Source operators of the dataflow that we mark the start timestamp
     pub source_operators: HashSet<String>,    
+    /// Watermarks of the messages queued at the workers and relay nodes of the pipeline, no backpressure if None
+    pub flow_control: Option<FlowControlConfig>,
+    /// Number of timely worker threads per worker process
+    pub threads: usize,
+    /// Live metrics endpoints of the workers and relay nodes of the pipeline, no endpoint if None
+    pub metrics_endpoint: Option<MetricsEndpointConfig>,
 }
 
 #[derive(Clone, Debug)]
@@ -64,7 +95,11 @@ pub struct ExecutionConfig {
     pub op_name_guid_mapping: HashMap<String, usize>,
     pub graph_connections: Option<GraphConnections>,
     pub metrics_logging_dir: Option<PathBuf>,
-    pub message_buffer_size: Option<usize>
+    pub message_buffer_size: Option<usize>,
+    /// Connect retries and startup timeout of workers and relay nodes, retry indefinitely if None
+    pub connection_config: Option<ConnectionConfig>,
+    /// Per-request tracing of the sampled requests, no tracing if None
+    pub tracing: Option<TracingConfig>
 }
 
 impl ExecutionConfig {
@@ -84,14 +119,89 @@ impl ExecutionConfig {
             op_name_guid_mapping: mapping,
             graph_connections: None,
             metrics_logging_dir,
-            message_buffer_size
+            message_buffer_size,
+            connection_config: None,
+            tracing: None
+        }
+    }
+
+    /// Whether some pipelines leave their required input or output operators to be inferred
+    pub fn requires_io_inference(&self) -> bool {
+        self.pipeline_configs.values().any(
+            |config| config.required_input_ops.is_none() || config.output_ops.is_none()
+        )
+    }
+
+    /// Capture the edges declared by the dag builder (without executing the dataflow),
+    /// so that pipeline inputs and outputs can be inferred on both worker and relay sides.
+    /// The dag builder is run with the builder configs of all pipelines,
+    /// fails if the dag builder declares operators not assigned to any pipeline
+    pub fn capture_graph_connections<T, F>(&mut self, dag_builder: &F) -> Result<(), PipelineIoError>
+    where
+        T: Timestamp + Refines<()> + TotalOrder,
+        F: Fn(&mut PipelineGraphBuilder<T>)
+    {
+        let topology = self.extract_topology(dag_builder);
+        let mut unassigned = topology.operators.iter().filter(
+            |op| !self.op_name_guid_mapping.contains_key(&op.name)
+        ).map(|op| op.name.clone()).collect::<Vec<_>>();
+        unassigned.sort();
+        if !unassigned.is_empty() {
+            return Err(PipelineIoError::Unassigned(unassigned));
+        }
+        self.graph_connections = Some(topology.to_guid(&self.op_name_guid_mapping).connections);
+        Ok(())
+    }
+
+    /// Check the operator assignment against the operators declared by the dag builder,
+    /// fails if a dead-letter operator is not assigned to the same pipeline as its fallible operator
+    pub fn validate_graph<T, F>(&self, dag_builder: &F) -> Result<(), ConfigError>
+    where
+        T: Timestamp + Refines<()> + TotalOrder,
+        F: Fn(&mut PipelineGraphBuilder<T>)
+    {
+        let op_pipeline_mapping = self.pipeline_configs.iter().flat_map(
+            |(pipeline_idx, config)| config.assigned_ops.iter().map(move |op| (op.as_str(), *pipeline_idx))
+        ).collect::<HashMap<_, _>>();
+        let topology = self.extract_topology(dag_builder);
+        let op_names = topology.operators.iter().map(|op| (op.id, op.name.as_str())).collect::<HashMap<_, _>>();
+        for op in topology.operators.iter() {
+            if let Some(producer) = op.dead_letters_of.map(|id| *op_names.get(&id).unwrap()) {
+                let pipeline_idx = op_pipeline_mapping.get(op.name.as_str());
+                if pipeline_idx.is_some() && pipeline_idx != op_pipeline_mapping.get(producer) {
+                    return Err(ConfigError::Invalid(format!("dead-letter operator {} must be assigned to the same pipeline as operator {}", op.name, producer)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run the dag builder with the builder configs of all pipelines (without executing the dataflow)
+    fn extract_topology<T, F>(&self, dag_builder: &F) -> GraphTopology
+    where
+        T: Timestamp + Refines<()> + TotalOrder,
+        F: Fn(&mut PipelineGraphBuilder<T>)
+    {
+        let mut pipeline_indices = self.pipeline_configs.keys().copied().collect::<Vec<_>>();
+        pipeline_indices.sort();
+        let mut builder_configs = HashMap::new();
+        for pipeline_idx in pipeline_indices {
+            for (key, val) in self.pipeline_configs.get(&pipeline_idx).unwrap().builder_configs.iter() {
+                builder_configs.entry(key.clone()).or_insert_with(|| val.clone());
+            }
         }
+        PipelineGraphBuilder::extract_topology(dag_builder, builder_configs)
     }
 
-    pub fn to_guid(&self) -> ExecutionConfigGUID {
+    /// Re-index the operators of the pipelines with GUIDs,
+    /// fails if a pipeline has no thread per worker process
+    pub fn to_guid(&self) -> Result<ExecutionConfigGUID, ConfigError> {
         let mut pipeline_configs_guid = HashMap::with_capacity(self.pipeline_configs.len());
         let op_name_guid_mapping = self.op_name_guid_mapping.clone();
         for (pipeline_idx, pipeline_config) in self.pipeline_configs.clone() {
+            if pipeline_config.threads == 0 {
+                return Err(ConfigError::Invalid(format!("pipeline {} requires at least one thread per worker process", pipeline_idx)));
+            }
             let required_input_ops = pipeline_config.required_input_ops.map(
                 |ops| ops.into_iter().map(
                     |x| *op_name_guid_mapping.get(&x).unwrap()
@@ -132,15 +242,692 @@ impl ExecutionConfig {
                 operator_configs: operators_configs,
                 request_rates,
                 source_operators,
+                flow_control: pipeline_config.flow_control,
+                threads: pipeline_config.threads,
+                metrics_endpoint: pipeline_config.metrics_endpoint,
             };
             pipeline_configs_guid.insert(pipeline_idx, pipeline_config_guid);
         }
 
-        ExecutionConfigGUID {
+        Ok(ExecutionConfigGUID {
             pipeline_configs: pipeline_configs_guid,
             op_name_guid_mapping: Some(self.op_name_guid_mapping.clone()),
             graph_connections: self.graph_connections.clone(),
-            message_buffer_size: self.message_buffer_size
+            message_buffer_size: self.message_buffer_size,
+            connection_config: self.connection_config,
+            tracing: self.tracing.clone()
+        })
+    }
+}
+
+impl ExecutionConfigGUID {
+    /// Fill in the missing pipeline inputs / outputs from the graph connections
+    pub fn infer_pipeline_io(&mut self) -> Result<(), PipelineIoError> {
+        let requires_inference = self.pipeline_configs.values().any(
+            |config| config.required_input_ops.is_none() || config.output_ops.is_none()
+        );
+        if requires_inference {
+            if self.graph_connections.is_none() {
+                return Err(PipelineIoError::MissingGraphConnections);
+            }
+            infer_pipeline_io_all(self)?;
+        }
+        Ok(())
+    }
+}
+
+/// Errors when loading a deployment spec
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Yaml(serde_yaml::Error),
+    /// The spec is well-formed but describes an invalid deployment
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "failed to read deployment spec: {}", err),
+            ConfigError::Json(err) => write!(f, "failed to parse JSON deployment spec: {}", err),
+            ConfigError::Yaml(err) => write!(f, "failed to parse YAML deployment spec: {}", err),
+            ConfigError::Invalid(msg) => write!(f, "invalid deployment spec: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Typed options of an operator, converted into the operator config passed to the operator builders
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OperatorOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_size: Option<usize>,
+    /// Maximum time (in nanoseconds) a batch map waits for a batch to fill up, enables dynamic batching
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_batch_wait: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buffer_size: Option<usize>,
+    /// Simulated network latency (in nanoseconds) added to the operator's inputs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub simulate_network_latency: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reset_timestamp: Option<bool>,
+    /// Latency SLO (in nanoseconds), map operators drop the requests whose latency already exceeds it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_slo: Option<i64>,
+    /// Arrival model of the requests emitted by an input source, overrides its request rate
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub arrival: Option<ArrivalModel>,
+    /// Interval (in nanoseconds) between the timestamp advances of a listener input source
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epoch_interval: Option<i64>,
+    /// Upper bound (in bytes) of a request received by a listener input source, larger requests close the connection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_request_bytes: Option<usize>,
+    /// Upper bound of the connections a listener input source serves at the same time, further connections are rejected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_connections: Option<usize>,
+    /// Size (in bytes) after which a sink rotates to a new file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_file_bytes: Option<usize>,
+    /// Time (in nanoseconds) after which a join evicts the unmatched data of a key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub join_ttl: Option<i64>,
+    /// Maximum number of attempts of a retrying map operator, overrides its retry policy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_max_attempts: Option<usize>,
+    /// Backoff (in nanoseconds) before the first retry, doubled on every following retry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_backoff: Option<i64>,
+    /// Upper bound (in nanoseconds) of the retry backoff
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_max_backoff: Option<i64>,
+    /// Number of significant digits (0 to 5) of the latency histograms, 3 by default
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_precision: Option<u8>,
+    /// Keep the raw latencies besides the histograms and dump them to `latency_logs` in the metrics logging directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keep_latency_samples: Option<bool>,
+}
+
+impl OperatorOptions {
+    pub fn to_operator_config(&self) -> HashMap<String, Arc<dyn Any + Send + Sync>> {
+        let mut config: HashMap<String, Arc<dyn Any + Send + Sync>> = HashMap::new();
+        if let Some(batch_size) = self.batch_size {
+            config.insert(String::from("batch_size"), Arc::new(batch_size));
+        }
+        if let Some(max_batch_wait) = self.max_batch_wait {
+            config.insert(String::from("max_batch_wait"), Arc::new(max_batch_wait));
+        }
+        if let Some(buffer_size) = self.buffer_size {
+            config.insert(String::from("buffer_size"), Arc::new(buffer_size));
+        }
+        if let Some(net_latency) = self.simulate_network_latency {
+            config.insert(String::from("simulate_network_latency"), Arc::new(net_latency));
+        }
+        if let Some(reset_timestamp) = self.reset_timestamp {
+            config.insert(String::from("reset_timestamp"), Arc::new(reset_timestamp));
+        }
+        if let Some(latency_slo) = self.latency_slo {
+            config.insert(String::from("latency_slo"), Arc::new(latency_slo));
+        }
+        if let Some(arrival) = &self.arrival {
+            config.insert(String::from("arrival_model"), Arc::new(arrival.clone()));
+        }
+        if let Some(epoch_interval) = self.epoch_interval {
+            config.insert(String::from("epoch_interval"), Arc::new(epoch_interval));
+        }
+        if let Some(max_request_bytes) = self.max_request_bytes {
+            config.insert(String::from("max_request_bytes"), Arc::new(max_request_bytes));
+        }
+        if let Some(max_connections) = self.max_connections {
+            config.insert(String::from("max_connections"), Arc::new(max_connections));
+        }
+        if let Some(max_file_bytes) = self.max_file_bytes {
+            config.insert(String::from("max_file_bytes"), Arc::new(max_file_bytes));
+        }
+        if let Some(join_ttl) = self.join_ttl {
+            config.insert(String::from("join_ttl"), Arc::new(join_ttl));
+        }
+        if let Some(retry_max_attempts) = self.retry_max_attempts {
+            config.insert(String::from("retry_max_attempts"), Arc::new(retry_max_attempts));
+        }
+        if let Some(retry_backoff) = self.retry_backoff {
+            config.insert(String::from("retry_backoff"), Arc::new(retry_backoff));
+        }
+        if let Some(retry_max_backoff) = self.retry_max_backoff {
+            config.insert(String::from("retry_max_backoff"), Arc::new(retry_max_backoff));
+        }
+        if let Some(latency_precision) = self.latency_precision {
+            config.insert(String::from("latency_precision"), Arc::new(latency_precision));
+        }
+        if let Some(keep_latency_samples) = self.keep_latency_samples {
+            config.insert(String::from("keep_latency_samples"), Arc::new(keep_latency_samples));
+        }
+        config
+    }
+}
+
+/// Connect retries, startup timeout and delivery guarantee of workers and relay nodes,
+/// unspecified fields take the defaults of `ConnectionConfig`
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConnectionOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_connect_attempts: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_backoff_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_backoff_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub startup_timeout_secs: Option<f64>,
+    /// Replay the messages in-flight to a lost relay node to a surviving one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub at_least_once: Option<bool>,
+}
+
+impl ConnectionOptions {
+    pub fn to_connection_config(&self) -> ConnectionConfig {
+        let default = ConnectionConfig::default();
+        ConnectionConfig {
+            max_connect_attempts: self.max_connect_attempts,
+            initial_backoff: self.initial_backoff_ms.map_or(default.initial_backoff, Duration::from_millis),
+            max_backoff: self.max_backoff_ms.map_or(default.max_backoff, Duration::from_millis),
+            startup_timeout: self.startup_timeout_secs.map(Duration::from_secs_f64),
+            at_least_once: self.at_least_once.unwrap_or(default.at_least_once),
+            // set per pipeline (see `PipelineSpec::flow_control`)
+            flow_control: None,
+        }
+    }
+}
+
+/// Addresses of the live `/metrics` endpoints (Prometheus text format) served by the worker processes and relay nodes of a pipeline,
+/// indexed like `worker_addrs` / `relay_addrs` (no endpoint is served by the processes without an address)
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MetricsEndpointConfig {
+    #[serde(default)]
+    pub worker_addrs: Vec<String>,
+    #[serde(default)]
+    pub relay_addrs: Vec<String>,
+    /// Interval (in milliseconds) at which the worker threads refresh the exposed metrics, defaults to 1000
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_interval_ms: Option<u64>,
+}
+
+impl MetricsEndpointConfig {
+    pub fn refresh_interval(&self) -> Duration {
+        Duration::from_millis(self.refresh_interval_ms.unwrap_or(1000))
+    }
+}
+
+/// Deployment of a pipeline, operators are referred to by names.
+/// Required input / output operators and input / output pipelines may be omitted,
+/// they are then inferred from the graph connections at execution
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineSpec {
+    pub assigned_ops: Vec<String>,
+    #[serde(default)]
+    pub required_input_ops: Option<Vec<String>>,
+    #[serde(default)]
+    pub output_ops: Option<Vec<String>>,
+    pub worker_addrs: Vec<String>,
+    pub relay_addrs: Vec<String>,
+    #[serde(default)]
+    pub relay_load_balance_weights: Option<Vec<f64>>,
+    #[serde(default)]
+    pub input_pipelines: Vec<usize>,
+    #[serde(default)]
+    pub output_pipelines: Vec<usize>,
+    /// Request sending rate for input sources (request rate per timely worker)
+    #[serde(default)]
+    pub request_rates: HashMap<String, f64>,
+    /// Source operators of the dataflow that we mark the start timestamp
+    #[serde(default)]
+    pub source_operators: HashSet<String>,
+    #[serde(default)]
+    pub operator_options: HashMap<String, OperatorOptions>,
+    /// Free-form builder configs, available to the dag builder as `serde_json::Value`
+    /// (e.g., `builder.get_config::<serde_json::Value>("dataset_path")`)
+    #[serde(default)]
+    pub builder_options: HashMap<String, serde_json::Value>,
+    /// High / low watermarks (in bytes) of the messages queued at the workers and relay nodes,
+    /// reading from upstream (and the input sources) pauses above the high watermark
+    #[serde(default)]
+    pub flow_control: Option<FlowControlConfig>,
+    /// Number of timely worker threads per worker process, defaults to 1
+    #[serde(default)]
+    pub threads: Option<usize>,
+    /// Addresses of the live `/metrics` endpoints of the worker processes and relay nodes
+    #[serde(default)]
+    pub metrics_endpoint: Option<MetricsEndpointConfig>,
+}
+
+/// Declarative deployment of the entire dataflow (all pipelines), loadable from JSON or YAML
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeploymentSpec {
+    /// Map from pipeline index to pipeline deployment
+    pub pipelines: BTreeMap<usize, PipelineSpec>,
+    /// Operator name to GUID mapping, defaults to the sorted operator names
+    #[serde(default)]
+    pub op_name_guid_mapping: Option<HashMap<String, usize>>,
+    #[serde(default)]
+    pub metrics_logging_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub message_buffer_size: Option<usize>,
+    #[serde(default)]
+    pub connection: Option<ConnectionOptions>,
+    /// Latency SLO (in nanoseconds) of the dataflow, applied to the operators without their own `latency_slo`
+    #[serde(default)]
+    pub latency_slo: Option<i64>,
+    /// Precision of the latency histograms of the operators without their own `latency_precision`
+    #[serde(default)]
+    pub latency_precision: Option<u8>,
+    /// Whether the operators without their own `keep_latency_samples` keep the raw latencies
+    #[serde(default)]
+    pub keep_latency_samples: Option<bool>,
+    /// Per-request tracing, the trace files default to the metrics logging directory
+    #[serde(default)]
+    pub tracing: Option<TracingConfig>,
+}
+
+impl DeploymentSpec {
+    pub fn from_json_str(spec: &str) -> Result<Self, ConfigError> {
+        serde_json::from_str(spec).map_err(ConfigError::Json)
+    }
+
+    pub fn from_yaml_str(spec: &str) -> Result<Self, ConfigError> {
+        serde_yaml::from_str(spec).map_err(ConfigError::Yaml)
+    }
+
+    /// Load the spec from a file, files with `.yaml` or `.yml` extension are parsed as YAML, otherwise as JSON
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let reader = BufReader::new(File::open(path).map_err(ConfigError::Io)?);
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("yaml") | Some("yml") => serde_yaml::from_reader(reader).map_err(ConfigError::Yaml),
+            _ => serde_json::from_reader(reader).map_err(ConfigError::Json),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn to_yaml(&self) -> String {
+        serde_yaml::to_string(self).unwrap()
+    }
+
+    /// Check that the operators referred to by a pipeline are assigned to some pipeline
+    /// and the pipelines' addresses and weights are consistent
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.pipelines.is_empty() {
+            return Err(ConfigError::Invalid(String::from("no pipeline is specified")));
+        }
+        let mut assigned = HashMap::new();
+        for (pipeline_idx, pipeline) in self.pipelines.iter() {
+            for op in pipeline.assigned_ops.iter() {
+                if let Some(prev_idx) = assigned.insert(op, *pipeline_idx) {
+                    return Err(ConfigError::Invalid(format!("operator {} is assigned to both pipeline {} and pipeline {}", op, prev_idx, pipeline_idx)));
+                }
+            }
+        }
+        for (pipeline_idx, pipeline) in self.pipelines.iter() {
+            if pipeline.worker_addrs.is_empty() || pipeline.relay_addrs.is_empty() {
+                return Err(ConfigError::Invalid(format!("pipeline {} requires at least one worker and one relay node", pipeline_idx)));
+            }
+            if let Some(weights) = &pipeline.relay_load_balance_weights {
+                if weights.len() != pipeline.relay_addrs.len() {
+                    return Err(ConfigError::Invalid(format!("load balance weights must be provided for each relay node in pipeline {}", pipeline_idx)));
+                }
+            }
+            if pipeline.threads == Some(0) {
+                return Err(ConfigError::Invalid(format!("pipeline {} requires at least one thread per worker process", pipeline_idx)));
+            }
+            if let Some(endpoint) = &pipeline.metrics_endpoint {
+                if endpoint.worker_addrs.len() > pipeline.worker_addrs.len() || endpoint.relay_addrs.len() > pipeline.relay_addrs.len() {
+                    return Err(ConfigError::Invalid(format!("pipeline {} has more metrics endpoints than workers or relay nodes", pipeline_idx)));
+                }
+                if endpoint.refresh_interval_ms == Some(0) {
+                    return Err(ConfigError::Invalid(format!("pipeline {} requires a positive metrics refresh interval", pipeline_idx)));
+                }
+            }
+            if let Some(flow_control) = &pipeline.flow_control {
+                if flow_control.high_watermark == 0 || flow_control.low_watermark > flow_control.high_watermark {
+                    return Err(ConfigError::Invalid(format!("pipeline {} requires 0 <= low watermark <= high watermark and a positive high watermark", pipeline_idx)));
+                }
+            }
+            for idx in pipeline.input_pipelines.iter().chain(pipeline.output_pipelines.iter()) {
+                if !self.pipelines.contains_key(idx) {
+                    return Err(ConfigError::Invalid(format!("pipeline {} is connected to unknown pipeline {}", pipeline_idx, idx)));
+                }
+            }
+            let referred_ops = pipeline.required_input_ops.iter().flatten()
+                .chain(pipeline.output_ops.iter().flatten())
+                .chain(pipeline.request_rates.keys())
+                .chain(pipeline.source_operators.iter())
+                .chain(pipeline.operator_options.keys());
+            for op in referred_ops {
+                if !assigned.contains_key(op) {
+                    return Err(ConfigError::Invalid(format!("operator {} referred to by pipeline {} is not assigned to any pipeline", op, pipeline_idx)));
+                }
+            }
+        }
+        for (pipeline_idx, pipeline) in self.pipelines.iter() {
+            for (op, options) in pipeline.operator_options.iter() {
+                match options.arrival.as_ref().map(|arrival| arrival.validate()) {
+                    Some(Err(ConfigError::Invalid(msg))) => return Err(ConfigError::Invalid(format!("operator {} in pipeline {}: {}", op, pipeline_idx, msg))),
+                    Some(Err(err)) => return Err(err),
+                    _ => {},
+                }
+                if matches!(options.epoch_interval, Some(epoch_interval) if epoch_interval <= 0) {
+                    return Err(ConfigError::Invalid(format!("operator {} in pipeline {}: epoch interval must be positive", op, pipeline_idx)));
+                }
+                if options.max_request_bytes == Some(0) || options.max_connections == Some(0) {
+                    return Err(ConfigError::Invalid(format!("operator {} in pipeline {}: listener limits must be positive", op, pipeline_idx)));
+                }
+                if matches!(options.max_batch_wait, Some(max_batch_wait) if max_batch_wait < 0) {
+                    return Err(ConfigError::Invalid(format!("operator {} in pipeline {}: max batch wait must not be negative", op, pipeline_idx)));
+                }
+                if matches!(options.join_ttl, Some(join_ttl) if join_ttl <= 0) {
+                    return Err(ConfigError::Invalid(format!("operator {} in pipeline {}: join ttl must be positive", op, pipeline_idx)));
+                }
+                if options.retry_max_attempts == Some(0) {
+                    return Err(ConfigError::Invalid(format!("operator {} in pipeline {}: retry max attempts must be positive", op, pipeline_idx)));
+                }
+                if [options.retry_backoff, options.retry_max_backoff].iter().any(|backoff| matches!(backoff, Some(backoff) if *backoff < 0)) {
+                    return Err(ConfigError::Invalid(format!("operator {} in pipeline {}: retry backoff must not be negative", op, pipeline_idx)));
+                }
+            }
+        }
+        let latency_precisions = self.pipelines.values()
+            .flat_map(|pipeline| pipeline.operator_options.values().filter_map(|options| options.latency_precision))
+            .chain(self.latency_precision);
+        for latency_precision in latency_precisions {
+            if latency_precision > 5 {
+                return Err(ConfigError::Invalid(format!("latency precision must be at most 5 significant digits, got {}", latency_precision)));
+            }
+        }
+        let latency_slos = self.pipelines.values()
+            .flat_map(|pipeline| pipeline.operator_options.values().filter_map(|options| options.latency_slo))
+            .chain(self.latency_slo);
+        for latency_slo in latency_slos {
+            if latency_slo <= 0 {
+                return Err(ConfigError::Invalid(format!("latency SLO must be positive, got {}", latency_slo)));
+            }
+        }
+        if let Some(timeout) = self.connection.as_ref().and_then(|options| options.startup_timeout_secs) {
+            if !timeout.is_finite() || timeout <= 0.0 {
+                return Err(ConfigError::Invalid(format!("startup timeout must be positive, got {}", timeout)));
+            }
+        }
+        if let Some(tracing) = &self.tracing {
+            if !(tracing.sample_rate > 0.0 && tracing.sample_rate <= 1.0) {
+                return Err(ConfigError::Invalid(format!("trace sample rate must be in (0, 1], got {}", tracing.sample_rate)));
+            }
+            if tracing.dir.is_none() && self.metrics_logging_dir.is_none() {
+                return Err(ConfigError::Invalid(String::from("tracing requires a trace directory or a metrics logging directory")));
+            }
+        }
+        if let Some(mapping) = &self.op_name_guid_mapping {
+            for op in assigned.keys() {
+                if !mapping.contains_key(*op) {
+                    return Err(ConfigError::Invalid(format!("operator {} is missing in the operator GUID mapping", op)));
+                }
+            }
         }
+        Ok(())
     }
-}
\ No newline at end of file
+
+    pub fn to_execution_config(&self) -> Result<ExecutionConfig, ConfigError> {
+        self.validate()?;
+        let mut pipeline_configs = HashMap::with_capacity(self.pipelines.len());
+        for (pipeline_idx, pipeline) in self.pipelines.iter() {
+            let builder_configs = pipeline.builder_options.iter().map(
+                |(k, v)| (k.clone(), Arc::new(v.clone()) as Arc<dyn Any + Send + Sync>)
+            ).collect();
+            let mut operator_configs: HashMap<String, HashMap<String, Arc<dyn Any + Send + Sync>>> = pipeline.operator_options.iter().map(
+                |(op, options)| (op.clone(), options.to_operator_config())
+            ).collect();
+            if let Some(latency_slo) = self.latency_slo {
+                for op in pipeline.assigned_ops.iter() {
+                    operator_configs.entry(op.clone()).or_default()
+                        .entry(String::from("latency_slo")).or_insert_with(|| Arc::new(latency_slo));
+                }
+            }
+            if let Some(latency_precision) = self.latency_precision {
+                for op in pipeline.assigned_ops.iter() {
+                    operator_configs.entry(op.clone()).or_default()
+                        .entry(String::from("latency_precision")).or_insert_with(|| Arc::new(latency_precision));
+                }
+            }
+            if let Some(keep_latency_samples) = self.keep_latency_samples {
+                for op in pipeline.assigned_ops.iter() {
+                    operator_configs.entry(op.clone()).or_default()
+                        .entry(String::from("keep_latency_samples")).or_insert_with(|| Arc::new(keep_latency_samples));
+                }
+            }
+            let pipeline_config = PipelineConfig {
+                pipeline_index: *pipeline_idx,
+                assigned_ops: pipeline.assigned_ops.clone(),
+                required_input_ops: pipeline.required_input_ops.clone(),
+                output_ops: pipeline.output_ops.clone(),
+                worker_addrs: pipeline.worker_addrs.clone(),
+                relay_addrs: pipeline.relay_addrs.clone(),
+                relay_load_balance_weights: pipeline.relay_load_balance_weights.clone(),
+                input_pipelines: pipeline.input_pipelines.clone(),
+                output_pipelines: pipeline.output_pipelines.clone(),
+                builder_configs,
+                operator_configs,
+                request_rates: pipeline.request_rates.clone(),
+                source_operators: pipeline.source_operators.clone(),
+                flow_control: pipeline.flow_control,
+                threads: pipeline.threads.unwrap_or(1),
+                metrics_endpoint: pipeline.metrics_endpoint.clone(),
+            };
+            pipeline_configs.insert(*pipeline_idx, pipeline_config);
+        }
+
+        let mut config = ExecutionConfig::new_with_default_mapping(pipeline_configs, self.metrics_logging_dir.clone(), self.message_buffer_size);
+        if let Some(mapping) = &self.op_name_guid_mapping {
+            config.op_name_guid_mapping = mapping.clone();
+        }
+        config.connection_config = self.connection.as_ref().map(|options| options.to_connection_config());
+        config.tracing = self.tracing.clone().map(|mut tracing| {
+            tracing.dir = tracing.dir.or_else(|| self.metrics_logging_dir.clone());
+            tracing
+        });
+        Ok(config)
+    }
+}
+
+impl ExecutionConfig {
+    /// Load an execution config from a JSON / YAML deployment spec (see `DeploymentSpec`)
+    pub fn from_deployment_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        DeploymentSpec::from_file(path)?.to_execution_config()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use super::DeploymentSpec;
+    use crate::builder::PipelineGraphBuilder;
+    use crate::input::ArrivalModel;
+    use crate::{Map, Inspect};
+    use crate::trace::TraceFormat;
+
+    const YAML_SPEC: &str = "
+pipelines:
+  0:
+    assigned_ops: [Input, Map]
+    worker_addrs: [\"127.0.0.1:5000\"]
+    relay_addrs: [\"127.0.0.1:6000\"]
+    request_rates:
+      Input: 100.0
+    source_operators: [Map]
+    operator_options:
+      Input:
+        arrival: {type: poisson, rate: 50.0, seed: 1}
+        epoch_interval: 10000000
+    builder_options:
+      dataset_path: ~/data
+  1:
+    assigned_ops: [Inspect]
+    worker_addrs: [\"127.0.0.1:5001\", \"127.0.0.1:5002\"]
+    relay_addrs: [\"127.0.0.1:6001\", \"127.0.0.1:6002\"]
+    relay_load_balance_weights: [1.0, 2.0]
+    operator_options:
+      Inspect:
+        batch_size: 8
+        simulate_network_latency: 1000
+        latency_slo: 50000000
+        latency_precision: 2
+    flow_control:
+      high_watermark: 1048576
+      low_watermark: 524288
+    threads: 4
+    metrics_endpoint:
+      worker_addrs: [\"127.0.0.1:9001\", \"127.0.0.1:9002\"]
+      relay_addrs: [\"127.0.0.1:9101\"]
+message_buffer_size: 1
+latency_slo: 100000000
+keep_latency_samples: true
+metrics_logging_dir: metrics
+tracing:
+  sample_rate: 0.1
+  format: otlp
+connection:
+  max_connect_attempts: 5
+  startup_timeout_secs: 1.5
+  at_least_once: true
+";
+
+    #[test]
+    fn load_deployment_spec() {
+        let spec = DeploymentSpec::from_yaml_str(YAML_SPEC).unwrap();
+        let config = spec.to_execution_config().unwrap();
+        assert_eq!(config.op_name_guid_mapping.len(), 3);
+        assert_eq!(*config.op_name_guid_mapping.get("Input").unwrap(), 0);
+        let pipeline_0 = config.pipeline_configs.get(&0).unwrap();
+        assert!(pipeline_0.required_input_ops.is_none());
+        assert_eq!(*pipeline_0.request_rates.get("Input").unwrap(), 100.0);
+        let dataset_path = pipeline_0.builder_configs.get("dataset_path").unwrap();
+        assert_eq!(dataset_path.downcast_ref::<serde_json::Value>().unwrap().as_str(), Some("~/data"));
+        let op_config = config.pipeline_configs.get(&1).unwrap().operator_configs.get("Inspect").unwrap();
+        assert_eq!(*op_config.get("batch_size").unwrap().downcast_ref::<usize>().unwrap(), 8);
+        assert_eq!(*op_config.get("simulate_network_latency").unwrap().downcast_ref::<i64>().unwrap(), 1000);
+        assert_eq!(*op_config.get("latency_slo").unwrap().downcast_ref::<i64>().unwrap(), 50000000);
+        let map_config = pipeline_0.operator_configs.get("Map").unwrap();
+        assert_eq!(*map_config.get("latency_slo").unwrap().downcast_ref::<i64>().unwrap(), 100000000);
+        assert_eq!(*op_config.get("latency_precision").unwrap().downcast_ref::<u8>().unwrap(), 2);
+        assert!(*map_config.get("keep_latency_samples").unwrap().downcast_ref::<bool>().unwrap());
+        let input_config = pipeline_0.operator_configs.get("Input").unwrap();
+        let arrival = input_config.get("arrival_model").unwrap().downcast_ref::<ArrivalModel>().unwrap();
+        assert_eq!(*arrival, ArrivalModel::Poisson { rate: 50.0, seed: 1 });
+        assert_eq!(*input_config.get("epoch_interval").unwrap().downcast_ref::<i64>().unwrap(), 10000000);
+        assert!(pipeline_0.flow_control.is_none());
+        let flow_control = config.pipeline_configs.get(&1).unwrap().flow_control.unwrap();
+        assert_eq!((flow_control.high_watermark, flow_control.low_watermark), (1048576, 524288));
+        assert_eq!((pipeline_0.threads, config.pipeline_configs.get(&1).unwrap().threads), (1, 4));
+        assert!(pipeline_0.metrics_endpoint.is_none());
+        let endpoint = config.pipeline_configs.get(&1).unwrap().metrics_endpoint.clone().unwrap();
+        assert_eq!(endpoint.relay_addrs, vec![String::from("127.0.0.1:9101")]);
+        assert_eq!(endpoint.refresh_interval(), Duration::from_secs(1));
+        let connection_config = config.connection_config.unwrap();
+        assert_eq!(connection_config.max_connect_attempts, Some(5));
+        assert_eq!(connection_config.startup_timeout, Some(Duration::from_millis(1500)));
+        assert!(connection_config.at_least_once);
+        let tracing = config.tracing.unwrap();
+        assert_eq!((tracing.format, tracing.dir), (TraceFormat::Otlp, Some(PathBuf::from("metrics"))));
+
+        let json_spec = DeploymentSpec::from_json_str(&spec.to_json()).unwrap();
+        assert_eq!(json_spec.pipelines.get(&1).unwrap().relay_load_balance_weights, Some(vec![1.0, 2.0]));
+    }
+
+    #[test]
+    fn invalid_deployment_spec() {
+        let mut spec = DeploymentSpec::from_yaml_str(YAML_SPEC).unwrap();
+        spec.pipelines.get_mut(&1).unwrap().assigned_ops.push(String::from("Map"));
+        assert!(spec.to_execution_config().is_err());
+
+        let mut spec = DeploymentSpec::from_yaml_str(YAML_SPEC).unwrap();
+        spec.pipelines.get_mut(&1).unwrap().relay_load_balance_weights = Some(vec![1.0]);
+        assert!(spec.validate().is_err());
+
+        let mut spec = DeploymentSpec::from_yaml_str(YAML_SPEC).unwrap();
+        spec.connection.as_mut().unwrap().startup_timeout_secs = Some(0.0);
+        assert!(spec.validate().is_err());
+
+        let mut spec = DeploymentSpec::from_yaml_str(YAML_SPEC).unwrap();
+        spec.pipelines.get_mut(&1).unwrap().flow_control.as_mut().unwrap().low_watermark = 2097152;
+        assert!(spec.validate().is_err());
+
+        let mut spec = DeploymentSpec::from_yaml_str(YAML_SPEC).unwrap();
+        spec.pipelines.get_mut(&1).unwrap().threads = Some(0);
+        assert!(spec.validate().is_err());
+
+        let mut spec = DeploymentSpec::from_yaml_str(YAML_SPEC).unwrap();
+        spec.pipelines.get_mut(&1).unwrap().metrics_endpoint.as_mut().unwrap().worker_addrs.push(String::from("127.0.0.1:9003"));
+        assert!(spec.validate().is_err());
+
+        let mut spec = DeploymentSpec::from_yaml_str(YAML_SPEC).unwrap();
+        spec.latency_slo = Some(0);
+        assert!(spec.validate().is_err());
+
+        let mut spec = DeploymentSpec::from_yaml_str(YAML_SPEC).unwrap();
+        spec.latency_precision = Some(6);
+        assert!(spec.validate().is_err());
+
+        let mut spec = DeploymentSpec::from_yaml_str(YAML_SPEC).unwrap();
+        let input_options = spec.pipelines.get_mut(&0).unwrap().operator_options.get_mut("Input").unwrap();
+        input_options.arrival = Some(ArrivalModel::Step { schedule: vec![] });
+        assert!(spec.validate().is_err());
+
+        let mut spec = DeploymentSpec::from_yaml_str(YAML_SPEC).unwrap();
+        spec.tracing.as_mut().unwrap().sample_rate = 0.0;
+        assert!(spec.validate().is_err());
+
+        let mut spec = DeploymentSpec::from_yaml_str(YAML_SPEC).unwrap();
+        spec.metrics_logging_dir = None;
+        assert!(spec.validate().is_err());
+
+        let mut config = DeploymentSpec::from_yaml_str(YAML_SPEC).unwrap().to_execution_config().unwrap();
+        config.pipeline_configs.get_mut(&1).unwrap().threads = 0;
+        assert!(config.to_guid().is_err());
+    }
+
+    #[test]
+    fn misplaced_dead_letters() {
+        let dag = |builder: &mut PipelineGraphBuilder<u64>| {
+            let input = builder.new_input_from_source(VecDeque::from(vec![(0u64, 1u64)]), |_, t| *t + 1, "Input");
+            let (_, dead_letters) = input.try_map(|x| if x.1 % 2 == 0 { Ok(x.1) } else { Err(x.1) }, "Parse");
+            dead_letters.inspect(|_| {}, "Inspect");
+        };
+        let spec = "
+pipelines:
+  0:
+    assigned_ops: [Input, Parse, Parse_dead_letter]
+    worker_addrs: [\"127.0.0.1:5000\"]
+    relay_addrs: [\"127.0.0.1:6000\"]
+  1:
+    assigned_ops: [Inspect]
+    worker_addrs: [\"127.0.0.1:5001\"]
+    relay_addrs: [\"127.0.0.1:6001\"]
+";
+        let mut spec = DeploymentSpec::from_yaml_str(spec).unwrap();
+        assert!(spec.to_execution_config().unwrap().validate_graph(&dag).is_ok());
+
+        spec.pipelines.get_mut(&0).unwrap().assigned_ops.pop();
+        spec.pipelines.get_mut(&1).unwrap().assigned_ops.push(String::from("Parse_dead_letter"));
+        assert!(spec.to_execution_config().unwrap().validate_graph(&dag).is_err());
+    }
+}
@@ -0,0 +1,418 @@
+//! Launch all pipeline workers and relay nodes of a dataflow on localhost,
+//! each worker / relay node runs in a child process re-executing the current binary
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Serialize, Deserialize};
+use timely::communication::ConnectionConfig;
+use timely::communication::networking::prebind_listener;
+use timely::order::TotalOrder;
+use timely::progress::Timestamp;
+use timely::progress::timestamp::Refines;
+
+use crate::builder::PipelineGraphBuilder;
+use crate::config::ExecutionConfig;
+
+use super::pipeline::{pipeline_worker_execute, pipeline_relay_execute};
+
+/// Environment variables passed to the child processes
+const LAUNCH_ROLE_ENV: &str = "MLFLOW_LAUNCH_ROLE";
+const LAUNCH_CONTROL_ENV: &str = "MLFLOW_LAUNCH_CONTROL";
+
+/// Line printed by `pipeline_worker_execute` and `pipeline_relay_execute` before the metrics
+const METRICS_MARKER: &str = "=====METRICS======";
+
+pub struct LaunchConfig {
+    /// Maximum time to wait for all workers and relay nodes to listen for connections,
+    /// also the startup timeout of the workers and relay nodes if the config does not specify one
+    pub startup_timeout: Duration,
+    /// Forward the children's stdout (prefixed by the stage) to stdout
+    pub forward_output: bool,
+    /// Arguments of the re-executed binary, the arguments of the current process by default
+    pub child_args: Option<Vec<String>>,
+}
+
+impl Default for LaunchConfig {
+    fn default() -> Self {
+        LaunchConfig {
+            startup_timeout: Duration::from_secs(30),
+            forward_output: true,
+            child_args: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum StageKind {
+    Worker,
+    Relay,
+}
+
+/// A pipeline worker or relay node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Stage {
+    pub kind: StageKind,
+    pub pipeline_index: usize,
+    pub index: usize,
+}
+
+impl std::fmt::Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            StageKind::Worker => write!(f, "p{}w{}", self.pipeline_index, self.index),
+            StageKind::Relay => write!(f, "p{}r{}", self.pipeline_index, self.index),
+        }
+    }
+}
+
+/// Addresses of the workers and relay nodes of a pipeline on localhost
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PipelineAddrs {
+    worker_addrs: Vec<String>,
+    relay_addrs: Vec<String>,
+}
+
+/// Sent by a worker or relay node to the launcher once it listens on `addr`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ListeningStage {
+    stage: Stage,
+    addr: String,
+}
+
+/// Metrics reported by the pipeline workers, keyed by `p{pipeline_index}w{worker_index}`,
+/// and the lost workers reported by the relay nodes, keyed by `p{pipeline_index}r{relay_index}`.
+/// Metrics are kept as JSON values since unavailable percentiles (NaN) are reported as null
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchReport {
+    pub metrics: BTreeMap<String, serde_json::Value>,
+}
+
+/// A line printed by a worker or relay node
+type ChildEvent = (Stage, String);
+
+/// Run every pipeline worker and relay node of the dataflow on localhost with remapped ports.
+///
+/// The current binary is re-executed (with the same arguments) for each worker and relay node,
+/// and is expected to call `local_launch` with the same dag builder and config:
+/// in the child processes `local_launch` runs the assigned worker / relay node and exits the process.
+/// Each child listens on a port picked by the OS and reports its address to the launcher over a control connection,
+/// the launcher replies with the addresses of all workers and relay nodes once every child listens,
+/// so no port is released between being picked and being listened on.
+/// Returns an error if any worker or relay node fails.
+pub fn local_launch<T, F>(dag_builder: F, config: &ExecutionConfig, launch_config: LaunchConfig) -> Result<LaunchReport, String>
+where
+    T: Timestamp + Refines<()> + TotalOrder,
+    F: Fn(&mut PipelineGraphBuilder<T>) + Send + Sync + 'static
+{
+    if let Ok(role) = env::var(LAUNCH_ROLE_ENV) {
+        let stage: Stage = serde_json::from_str(&role).map_err(|err| format!("invalid launch role {}: {}", role, err))?;
+        let control_addr = env::var(LAUNCH_CONTROL_ENV).map_err(|_| String::from("launch control address is not provided"))?;
+        let addrs = match await_launch_addrs(stage, &control_addr, launch_config.startup_timeout) {
+            Ok(addrs) => addrs,
+            Err(err) => {
+                eprintln!("{} failed: {}", stage, err);
+                std::process::exit(1);
+            }
+        };
+        let mut config = remap_addrs(config, &addrs);
+        if config.requires_io_inference() && config.graph_connections.is_none() {
+            // relay nodes do not run the dag builder, capture the edges for them
+            config.capture_graph_connections(&dag_builder).map_err(|err| err.to_string())?;
+        }
+        if config.connection_config.is_none() {
+            // do not wait indefinitely for the other workers / relay nodes
+            config.connection_config = Some(ConnectionConfig {
+                startup_timeout: Some(launch_config.startup_timeout),
+                ..Default::default()
+            });
+        }
+        let result = match stage.kind {
+            StageKind::Worker => pipeline_worker_execute(dag_builder, &config, stage.pipeline_index, stage.index),
+            StageKind::Relay => pipeline_relay_execute(&config, stage.pipeline_index, stage.index),
+        };
+        if let Err(err) = result {
+            eprintln!("{} failed: {}", stage, err);
+            std::process::exit(1);
+        }
+        std::process::exit(0);
+    }
+
+    let control = TcpListener::bind("127.0.0.1:0").map_err(|err| format!("failed to listen for the launched children: {}", err))?;
+    let control_addr = control.local_addr().map_err(|err| err.to_string())?.to_string();
+    let child_args = launch_config.child_args.clone().unwrap_or_else(|| env::args().skip(1).collect());
+    let (sender, receiver) = channel();
+    let mut children = Vec::new();
+
+    let mut pipeline_indices = config.pipeline_configs.keys().copied().collect::<Vec<_>>();
+    pipeline_indices.sort();
+    let stages = pipeline_indices.iter().flat_map(|p| {
+        let pipeline_config = &config.pipeline_configs[p];
+        let relays = (0..pipeline_config.relay_addrs.len()).map(move |i| Stage { kind: StageKind::Relay, pipeline_index: *p, index: i });
+        let workers = (0..pipeline_config.worker_addrs.len()).map(move |i| Stage { kind: StageKind::Worker, pipeline_index: *p, index: i });
+        relays.chain(workers)
+    }).collect::<Vec<_>>();
+
+    let mut monitor = ChildrenMonitor::new(launch_config.forward_output);
+    for stage in stages.iter() {
+        match spawn_stage(*stage, &child_args, &control_addr, sender.clone()) {
+            Ok(child) => children.push((*stage, child)),
+            Err(err) => {
+                kill_all(&mut children);
+                return Err(err);
+            }
+        }
+    }
+    drop(sender);
+
+    let result = distribute_addrs(config, &control, &stages, &mut children, &receiver, &mut monitor, launch_config.startup_timeout)
+        .and_then(|_| wait_children(&mut children, &receiver, &mut monitor));
+    if result.is_err() {
+        kill_all(&mut children);
+    }
+    result?;
+    // drain the remaining outputs
+    while let Ok(event) = receiver.recv() {
+        monitor.handle(event);
+    }
+
+    let metrics = monitor.metrics_outputs.into_iter().filter_map(
+        |(stage, output)| {
+            let mut stream = serde_json::Deserializer::from_str(&output).into_iter::<serde_json::Value>();
+            stream.next().and_then(|x| x.ok()).map(|x| (stage.to_string(), x))
+        }
+    ).collect();
+
+    Ok(LaunchReport { metrics })
+}
+
+/// Listen on a port picked by the OS, report it to the launcher,
+/// and wait for the addresses of all workers and relay nodes.
+/// The listener is taken over by the networking of the worker / relay node,
+/// the connections of the others arriving in the meantime are queued in its backlog
+fn await_launch_addrs(stage: Stage, control_addr: &str, timeout: Duration) -> std::io::Result<HashMap<usize, PipelineAddrs>> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let addr = listener.local_addr()?.to_string();
+    prebind_listener(listener);
+
+    let mut control = TcpStream::connect(control_addr)?;
+    control.set_read_timeout(Some(timeout))?;
+    let message = serde_json::to_string(&ListeningStage { stage, addr })?;
+    writeln!(control, "{}", message)?;
+    let mut line = String::new();
+    BufReader::new(control).read_line(&mut line)?;
+    if line.is_empty() {
+        return Err(std::io::Error::new(ErrorKind::UnexpectedEof, "launcher closed the control connection"));
+    }
+    Ok(serde_json::from_str(&line)?)
+}
+
+/// Collect the addresses the workers and relay nodes listen on,
+/// then send the addresses of all of them to each one
+fn distribute_addrs(
+    config: &ExecutionConfig,
+    control: &TcpListener,
+    stages: &[Stage],
+    children: &mut [(Stage, Child)],
+    receiver: &Receiver<ChildEvent>,
+    monitor: &mut ChildrenMonitor,
+    startup_timeout: Duration,
+) -> Result<(), String> {
+    let deadline = Instant::now() + startup_timeout;
+    control.set_nonblocking(true).map_err(|err| err.to_string())?;
+    let mut listening = HashMap::with_capacity(stages.len());
+    while listening.len() < stages.len() {
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(format!("{} of {} workers and relay nodes are not listening after {:?}", stages.len() - listening.len(), stages.len(), startup_timeout));
+        }
+        match control.accept() {
+            Ok((stream, _)) => {
+                stream.set_nonblocking(false).map_err(|err| err.to_string())?;
+                stream.set_read_timeout(Some(deadline - now)).map_err(|err| err.to_string())?;
+                let mut line = String::new();
+                BufReader::new(&stream).read_line(&mut line).map_err(|err| format!("failed to receive the address of a child: {}", err))?;
+                let message: ListeningStage = serde_json::from_str(&line).map_err(|err| format!("invalid address of a child {:?}: {}", line, err))?;
+                if !stages.contains(&message.stage) || listening.contains_key(&message.stage) {
+                    return Err(format!("unexpected address of {} from a child", message.stage));
+                }
+                listening.insert(message.stage, (message.addr, stream));
+            },
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                match receiver.recv_timeout(Duration::from_millis(10)) {
+                    Ok(event) => monitor.handle(event),
+                    Err(RecvTimeoutError::Timeout) => {},
+                    Err(RecvTimeoutError::Disconnected) => thread::sleep(Duration::from_millis(10)),
+                }
+            },
+            Err(err) => return Err(format!("failed to accept a child: {}", err)),
+        }
+        check_children(children)?;
+    }
+
+    let addr_of = |kind, pipeline_index, num| (0..num).map(|index| listening[&Stage { kind, pipeline_index, index }].0.clone()).collect::<Vec<_>>();
+    let addrs = config.pipeline_configs.iter().map(|(pipeline_index, pipeline_config)| {
+        let worker_addrs = addr_of(StageKind::Worker, *pipeline_index, pipeline_config.worker_addrs.len());
+        let relay_addrs = addr_of(StageKind::Relay, *pipeline_index, pipeline_config.relay_addrs.len());
+        (*pipeline_index, PipelineAddrs { worker_addrs, relay_addrs })
+    }).collect::<HashMap<_, _>>();
+    let addrs_json = serde_json::to_string(&addrs).unwrap();
+    for (stage, (_, stream)) in listening.iter_mut() {
+        writeln!(stream, "{}", addrs_json).map_err(|err| format!("failed to send the launch addresses to {}: {}", stage, err))?;
+    }
+    Ok(())
+}
+
+fn remap_addrs(config: &ExecutionConfig, addrs: &HashMap<usize, PipelineAddrs>) -> ExecutionConfig {
+    let mut config = config.clone();
+    for (pipeline_idx, pipeline_config) in config.pipeline_configs.iter_mut() {
+        let pipeline_addrs = addrs.get(pipeline_idx).expect("could not find launch addresses of the pipeline");
+        pipeline_config.worker_addrs = pipeline_addrs.worker_addrs.clone();
+        pipeline_config.relay_addrs = pipeline_addrs.relay_addrs.clone();
+    }
+    config
+}
+
+fn spawn_stage(stage: Stage, args: &[String], control_addr: &str, sender: Sender<ChildEvent>) -> Result<Child, String> {
+    let exe = env::current_exe().map_err(|err| format!("failed to locate current executable: {}", err))?;
+    let mut child = Command::new(exe)
+        .args(args)
+        .env(LAUNCH_ROLE_ENV, serde_json::to_string(&stage).unwrap())
+        .env(LAUNCH_CONTROL_ENV, control_addr)
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("failed to spawn {}: {}", stage, err))?;
+
+    let stdout = child.stdout.take().unwrap();
+    thread::Builder::new()
+        .name(format!("launch-{}", stage))
+        .spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line {
+                    Ok(line) => {
+                        if sender.send((stage, line)).is_err() {
+                            break;
+                        }
+                    },
+                    Err(_) => break,
+                }
+            }
+        })
+        .map_err(|err| format!("failed to spawn output thread for {}: {}", stage, err))?;
+    Ok(child)
+}
+
+/// Check whether any child has failed
+fn check_children(children: &mut [(Stage, Child)]) -> Result<bool, String> {
+    let mut all_exited = true;
+    for (stage, child) in children.iter_mut() {
+        match child.try_wait() {
+            Ok(Some(status)) if !status.success() => {
+                return Err(format!("{} exited with {}", stage, status));
+            },
+            Ok(Some(_)) => {},
+            Ok(None) => all_exited = false,
+            Err(err) => return Err(format!("failed to wait for {}: {}", stage, err)),
+        }
+    }
+    Ok(all_exited)
+}
+
+fn wait_children(children: &mut [(Stage, Child)], receiver: &Receiver<ChildEvent>, monitor: &mut ChildrenMonitor) -> Result<(), String> {
+    loop {
+        match receiver.recv_timeout(Duration::from_millis(100)) {
+            Ok(event) => monitor.handle(event),
+            Err(RecvTimeoutError::Timeout) => {},
+            // all outputs are closed
+            Err(RecvTimeoutError::Disconnected) => thread::sleep(Duration::from_millis(100)),
+        }
+        if check_children(children)? {
+            return Ok(());
+        }
+    }
+}
+
+fn kill_all(children: &mut [(Stage, Child)]) {
+    for (_, child) in children.iter_mut() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+/// Track the outputs of the children
+struct ChildrenMonitor {
+    forward_output: bool,
+    /// Outputs of the workers and relay nodes following the metrics marker
+    metrics_outputs: BTreeMap<Stage, String>,
+}
+
+impl ChildrenMonitor {
+    fn new(forward_output: bool) -> Self {
+        ChildrenMonitor {
+            forward_output,
+            metrics_outputs: BTreeMap::new(),
+        }
+    }
+
+    fn handle(&mut self, (stage, line): ChildEvent) {
+        if self.forward_output {
+            println!("[{}] {}", stage, line);
+        }
+        if let Some(output) = self.metrics_outputs.get_mut(&stage) {
+            output.push_str(&line);
+            output.push('\n');
+        }
+        else if line == METRICS_MARKER {
+            self.metrics_outputs.insert(stage, String::new());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use crate::builder::PipelineGraphBuilder;
+    use crate::config::DeploymentSpec;
+    use crate::Map;
+
+    use super::{local_launch, LaunchConfig};
+
+    fn dag_builder(builder: &mut PipelineGraphBuilder<usize>) {
+        let input = builder.new_input_from_source(VecDeque::from((0..20).collect::<Vec<usize>>()), |_, t| *t + 1, "Input");
+        input.map(|x| x * 2, "Double");
+    }
+
+    #[test]
+    fn launch_two_pipelines() {
+        // the addresses are remapped to the ones the launched workers and relay nodes listen on
+        let spec = "
+pipelines:
+  0:
+    assigned_ops: [Input]
+    worker_addrs: [\"127.0.0.1:1\"]
+    relay_addrs: [\"127.0.0.1:2\"]
+  1:
+    assigned_ops: [Double]
+    worker_addrs: [\"127.0.0.1:3\"]
+    relay_addrs: [\"127.0.0.1:4\"]
+";
+        let config = DeploymentSpec::from_yaml_str(spec).unwrap().to_execution_config().unwrap();
+        // the children only run this test, which runs their assigned worker / relay node
+        let launch_config = LaunchConfig {
+            forward_output: false,
+            child_args: Some(vec![String::from("execute::launch::tests::launch_two_pipelines"), String::from("--exact"), String::from("--nocapture")]),
+            ..Default::default()
+        };
+        let report = local_launch(dag_builder, &config, launch_config).unwrap();
+        let mut stages = report.metrics.keys().cloned().collect::<Vec<_>>();
+        stages.sort();
+        assert_eq!(stages, vec!["p0r0", "p0w0", "p1r0", "p1w0"]);
+        assert!(report.metrics["p1w0"].get("Double").is_some(), "no metrics of the downstream operator: {}", report.metrics["p1w0"]);
+    }
+}
@@ -54,20 +54,19 @@ where
         });
 
 
+        let mut pending_inputs = graph.input_operator_indices.clone();
         let mut completed = false; 
         while !completed {
-            completed = true;
-            for index in graph.input_operator_indices.iter() {
-                let input_node = graph.operators.get_mut(index).unwrap();
-                if let GraphNode::LocalInputNode(input_node) = input_node {
-                    if input_node.step() {
-                        completed = false;
-                    }
+            // completed inputs are not stepped again
+            pending_inputs.retain(|index| {
+                if let GraphNode::LocalInputNode(input_node) = graph.operators.get_mut(index).unwrap() {
+                    input_node.step()
                 }
                 else {
                     panic!("node {} should be a pipeline-local input node", index);
                 }
-            }
+            });
+            completed = pending_inputs.is_empty();
             worker.step();
         }
     }).unwrap();
@@ -89,3 +88,31 @@ where
 {
     local_execute(dag_builder, TimelyConfig::process(threads));
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+
+    use crate::builder::GraphBuilder;
+    use crate::InspectLocal;
+
+    use super::local_execute_thread;
+
+    #[test]
+    fn step_inputs_of_different_lengths() {
+        let outputs = Arc::new(Mutex::new(Vec::new()));
+        let outputs_cloned = outputs.clone();
+        local_execute_thread(move |builder: &mut GraphBuilder<usize>| {
+            // the short input completes while the long one is still feeding, it must not be stepped again
+            for (len, name) in [(2, "Short"), (5, "Long")] {
+                let outputs = outputs_cloned.clone();
+                builder.new_input_from_source_distributed(VecDeque::from((0..len).collect::<Vec<usize>>()), |_, t| *t + 1, name)
+                    .inspect_local(move |x| outputs.lock().unwrap().push((name, *x)), &format!("Inspect{}", name));
+            }
+        });
+        let outputs = outputs.lock().unwrap();
+        assert_eq!(outputs.iter().filter(|x| x.0 == "Short").count(), 2);
+        assert_eq!(outputs.iter().filter(|x| x.0 == "Long").count(), 5);
+    }
+}
@@ -0,0 +1,352 @@
+//! Live metrics of the pipeline workers and relay nodes, served at `GET /metrics` in the Prometheus text exposition format
+//! (enabled per pipeline by `MetricsEndpointConfig`).
+//! The worker threads publish the metrics of their operators every refresh interval,
+//! the relay nodes expose their queued bytes and failures as they are scraped.
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use timely::communication::allocator::relay::{FlowControl, OutputPipelineReplay, RelayNodeFlowControl, TimelyWorkersLiveness};
+
+use crate::metrics::{LatencyLogger, MetricsLogger};
+
+/// Type of a metric family
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+    Summary,
+}
+
+impl MetricKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+            MetricKind::Summary => "summary",
+        }
+    }
+}
+
+/// A sample of a metric family
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricSample {
+    pub family: &'static str,
+    pub kind: MetricKind,
+    pub help: &'static str,
+    /// Appended to the family name (e.g., `_count` of a summary)
+    pub suffix: &'static str,
+    pub labels: Vec<(&'static str, String)>,
+    pub value: f64,
+}
+
+impl MetricSample {
+    fn new(family: &'static str, kind: MetricKind, help: &'static str, labels: Vec<(&'static str, String)>, value: f64) -> Self {
+        MetricSample { family, kind, help, suffix: "", labels, value }
+    }
+}
+
+/// Render the samples in the Prometheus text exposition format, grouped by family
+pub fn render(samples: &[MetricSample]) -> String {
+    let mut families: BTreeMap<&str, Vec<&MetricSample>> = BTreeMap::new();
+    for sample in samples {
+        families.entry(sample.family).or_default().push(sample);
+    }
+    let mut text = String::new();
+    for (family, samples) in families {
+        text.push_str(&format!("# HELP {} {}\n", family, samples[0].help));
+        text.push_str(&format!("# TYPE {} {}\n", family, samples[0].kind.as_str()));
+        for sample in samples {
+            text.push_str(family);
+            text.push_str(sample.suffix);
+            if !sample.labels.is_empty() {
+                let labels = sample.labels.iter()
+                    .map(|(name, value)| format!("{}=\"{}\"", name, escape_label(value)))
+                    .collect::<Vec<_>>();
+                text.push_str(&format!("{{{}}}", labels.join(",")));
+            }
+            text.push_str(&format!(" {}\n", format_value(sample.value)));
+        }
+    }
+    text
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        String::from("NaN")
+    }
+    else if value.is_infinite() {
+        String::from(if value > 0.0 { "+Inf" } else { "-Inf" })
+    }
+    else {
+        value.to_string()
+    }
+}
+
+/// Latest samples published by each worker thread of a process
+#[derive(Default)]
+pub(crate) struct MetricsRegistry {
+    samples: Mutex<BTreeMap<usize, Vec<MetricSample>>>,
+}
+
+impl MetricsRegistry {
+    pub(crate) fn publish(&self, worker_index: usize, samples: Vec<MetricSample>) {
+        self.samples.lock().unwrap().insert(worker_index, samples);
+    }
+
+    pub(crate) fn collect(&self) -> Vec<MetricSample> {
+        self.samples.lock().unwrap().values().flatten().cloned().collect()
+    }
+}
+
+/// HTTP server of a `/metrics` endpoint, stopped once dropped
+pub struct MetricsEndpoint {
+    addr: SocketAddr,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl MetricsEndpoint {
+    /// Serve the samples returned by `collect` (called on every scrape) at `addr`
+    pub fn serve<F>(addr: &str, collect: F) -> io::Result<Self>
+    where
+        F: Fn() -> Vec<MetricSample> + Send + 'static
+    {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        // polled so that the server stops once the endpoint is dropped
+        listener.set_nonblocking(true)?;
+        let stopped = Arc::new(AtomicBool::new(false));
+        let stopped_cloned = stopped.clone();
+        let thread = thread::Builder::new()
+            .name(format!("metrics-endpoint-{}", addr))
+            .spawn(move || {
+                while !stopped_cloned.load(Ordering::SeqCst) {
+                    match listener.accept() {
+                        Ok((stream, _)) => {
+                            // a failed scrape only affects its own connection
+                            let _ = respond(stream, &collect);
+                        },
+                        Err(err) if err.kind() == io::ErrorKind::WouldBlock => thread::sleep(Duration::from_millis(50)),
+                        Err(_) => thread::sleep(Duration::from_millis(50)),
+                    }
+                }
+            })?;
+        Ok(MetricsEndpoint { addr, stopped, thread: Some(thread) })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for MetricsEndpoint {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn respond<F: Fn() -> Vec<MetricSample>>(mut stream: TcpStream, collect: &F) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    // only the request line is used, read until the end of the headers
+    while !request.windows(4).any(|window| window == b"\r\n\r\n") && request.len() < 8192 {
+        let read = stream.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..read]);
+    }
+    let request = String::from_utf8_lossy(&request);
+    let mut request_line = request.lines().next().unwrap_or("").split_whitespace();
+    let method = request_line.next().unwrap_or("");
+    let path = request_line.next().unwrap_or("").split('?').next().unwrap_or("");
+    let (status, content_type, body) = match (method, path) {
+        ("GET", "/metrics") => ("200 OK", "text/plain; version=0.0.4; charset=utf-8", render(&collect())),
+        ("GET", _) => ("404 Not Found", "text/plain; charset=utf-8", String::from("not found\n")),
+        _ => ("405 Method Not Allowed", "text/plain; charset=utf-8", String::from("method not allowed\n")),
+    };
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, content_type, body.len(), body
+    );
+    stream.write_all(response.as_bytes())?;
+    stream.flush()
+}
+
+const LATENCY_QUANTILES: [(&str, f64); 5] = [("0.5", 50.0), ("0.9", 90.0), ("0.95", 95.0), ("0.99", 99.0), ("0.999", 99.9)];
+
+fn latency_samples(samples: &mut Vec<MetricSample>, logger: &LatencyLogger, kind: &str, labels: &[(&'static str, String)]) {
+    const FAMILY: &str = "mlflow_operator_latency_seconds";
+    const HELP: &str = "Latency of the requests processed by the operator";
+    let latencies = logger.latencies.borrow();
+    let mut labels = labels.to_vec();
+    labels.push(("kind", String::from(kind)));
+    if !latencies.is_empty() {
+        for (quantile, percentile) in LATENCY_QUANTILES {
+            let mut labels = labels.clone();
+            labels.push(("quantile", String::from(quantile)));
+            samples.push(MetricSample::new(FAMILY, MetricKind::Summary, HELP, labels, latencies.percentile(percentile) as f64 / 1e9_f64));
+        }
+    }
+    samples.push(MetricSample {
+        suffix: "_count",
+        ..MetricSample::new(FAMILY, MetricKind::Summary, HELP, labels, latencies.len() as f64)
+    });
+}
+
+/// Current metrics of the operators of a worker thread,
+/// labelled with the operator name (the global id if the names are unknown) and `labels`
+pub(crate) fn operator_samples(loggers: &MetricsLogger, op_names: &HashMap<usize, String>, labels: &[(&'static str, String)]) -> Vec<MetricSample> {
+    let op_labels = |gid: &usize| {
+        let mut op_labels = labels.to_vec();
+        op_labels.push(("operator", op_names.get(gid).cloned().unwrap_or_else(|| gid.to_string())));
+        op_labels
+    };
+    let mut samples = Vec::new();
+    for (gid, logger) in loggers.throughput_loggers.iter() {
+        if let Some(tp) = logger.throughput.borrow().front() {
+            samples.push(MetricSample::new("mlflow_operator_throughput", MetricKind::Gauge,
+                "Throughput (in #req/s) of the operator over the latest window", op_labels(gid), *tp));
+        }
+    }
+    for (gid, logger) in loggers.input_throughput_loggers.iter() {
+        if let Some(tp) = logger.throughput.borrow().front() {
+            samples.push(MetricSample::new("mlflow_operator_input_throughput", MetricKind::Gauge,
+                "Rate (in #req/s) of the requests received by the operator over the latest window", op_labels(gid), *tp));
+        }
+    }
+    for (kind, latency_loggers) in [
+        ("operator_execution", &loggers.execution_latency_loggers),
+        ("dataflow_edge", &loggers.edge_latency_loggers),
+        ("dataflow_path", &loggers.path_latency_loggers),
+    ] {
+        for (gid, logger) in latency_loggers.iter() {
+            latency_samples(&mut samples, logger, kind, &op_labels(gid));
+        }
+    }
+    for (gid, logger) in loggers.jct_loggers.iter() {
+        if let Some(jct) = logger.compute_operator_job_completion_time() {
+            samples.push(MetricSample::new("mlflow_operator_jct_seconds", MetricKind::Gauge,
+                "Time from the first request received by the operator to the last request processed", op_labels(gid), jct));
+        }
+        if let Some(jct) = logger.compute_path_job_completion_time() {
+            samples.push(MetricSample::new("mlflow_path_jct_seconds", MetricKind::Gauge,
+                "Time from the first request launched to the last request processed by the operator", op_labels(gid), jct));
+        }
+    }
+    for (gid, logger) in loggers.slo_loggers.iter() {
+        samples.push(MetricSample::new("mlflow_operator_dropped_total", MetricKind::Counter,
+            "Requests dropped since their latency already exceeded the SLO", op_labels(gid), logger.compute_dropped() as f64));
+    }
+    for (gid, logger) in loggers.state_loggers.iter() {
+        samples.push(MetricSample::new("mlflow_operator_evicted_keys_total", MetricKind::Counter,
+            "Unmatched keys evicted from the state of the operator", op_labels(gid), logger.compute_evicted() as f64));
+        samples.push(MetricSample::new("mlflow_operator_pending_keys", MetricKind::Gauge,
+            "Keys waiting for a match or for the rest of their data in the state of the operator", op_labels(gid), logger.compute_pending() as f64));
+    }
+    for (gid, logger) in loggers.error_loggers.iter() {
+        samples.push(MetricSample::new("mlflow_operator_errors_total", MetricKind::Counter,
+            "Requests whose processing failed", op_labels(gid), logger.compute_errors() as f64));
+        samples.push(MetricSample::new("mlflow_operator_retries_total", MetricKind::Counter,
+            "Attempts retried after a retryable error", op_labels(gid), logger.compute_retries() as f64));
+    }
+    samples
+}
+
+fn queue_samples(samples: &mut Vec<MetricSample>, flow_control: &FlowControl, direction: &str, labels: &[(&'static str, String)]) {
+    let mut labels = labels.to_vec();
+    labels.push(("direction", String::from(direction)));
+    let report = flow_control.report();
+    samples.push(MetricSample::new("mlflow_relay_queued_bytes", MetricKind::Gauge,
+        "Bytes queued at the relay node and not written to the sockets yet", labels.clone(), flow_control.queued() as f64));
+    samples.push(MetricSample::new("mlflow_relay_max_queued_bytes", MetricKind::Gauge,
+        "Maximum number of bytes queued at the relay node", labels.clone(), report.max_queued_bytes as f64));
+    samples.push(MetricSample::new("mlflow_relay_paused", MetricKind::Gauge,
+        "Whether the relay node pauses reading from upstream", labels.clone(), if flow_control.is_paused() { 1.0 } else { 0.0 }));
+    samples.push(MetricSample::new("mlflow_relay_pauses_total", MetricKind::Counter,
+        "Number of times the relay node paused reading from upstream", labels, report.pauses as f64));
+}
+
+/// Current queue sizes and failures of a relay node, labelled with `labels`
+pub(crate) fn relay_samples(
+    liveness: &TimelyWorkersLiveness,
+    replays: &[Arc<OutputPipelineReplay>],
+    flow_control: Option<&RelayNodeFlowControl>,
+    labels: &[(&'static str, String)]
+) -> Vec<MetricSample> {
+    let mut samples = Vec::new();
+    if let Some(flow_control) = flow_control {
+        queue_samples(&mut samples, &flow_control.to_timely_workers, "to_timely_workers", labels);
+        queue_samples(&mut samples, &flow_control.to_output_pipelines, "to_output_pipelines", labels);
+    }
+    let lost_workers = liveness.report();
+    samples.push(MetricSample::new("mlflow_relay_lost_timely_workers", MetricKind::Gauge,
+        "Timely worker processes whose connection to the relay node is lost", labels.to_vec(), lost_workers.lost_timely_workers.len() as f64));
+    samples.push(MetricSample::new("mlflow_relay_lost_messages_total", MetricKind::Counter,
+        "Messages in-flight to the lost timely worker processes discarded by the relay node", labels.to_vec(), lost_workers.lost_messages as f64));
+    samples.push(MetricSample::new("mlflow_relay_redelivered_messages_total", MetricKind::Counter,
+        "Messages in-flight to the lost timely worker processes redelivered to the surviving ones", labels.to_vec(), lost_workers.redelivered_messages as f64));
+    for replay in replays {
+        let report = replay.report();
+        let mut labels = labels.to_vec();
+        labels.push(("output_pipeline", report.output_pipeline_index.to_string()));
+        samples.push(MetricSample::new("mlflow_relay_replayed_messages_total", MetricKind::Counter,
+            "Messages replayed to the surviving relay nodes of the output pipeline", labels, report.replayed_messages as f64));
+    }
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+
+    use super::{render, MetricKind, MetricSample, MetricsEndpoint};
+
+    #[test]
+    fn serve_metrics() {
+        let samples = vec![
+            MetricSample::new("mlflow_operator_throughput", MetricKind::Gauge, "Throughput",
+                vec![("pipeline", String::from("0")), ("operator", String::from("Map \"1\""))], 12.5),
+            MetricSample {
+                suffix: "_count",
+                ..MetricSample::new("mlflow_operator_latency_seconds", MetricKind::Summary, "Latency", vec![], 3.0)
+            },
+        ];
+        assert_eq!(render(&samples), "\
+# HELP mlflow_operator_latency_seconds Latency
+# TYPE mlflow_operator_latency_seconds summary
+mlflow_operator_latency_seconds_count 3
+# HELP mlflow_operator_throughput Throughput
+# TYPE mlflow_operator_throughput gauge
+mlflow_operator_throughput{pipeline=\"0\",operator=\"Map \\\"1\\\"\"} 12.5
+");
+
+        let endpoint = MetricsEndpoint::serve("127.0.0.1:0", move || samples.clone()).unwrap();
+        let scrape = |path: &str| {
+            let mut stream = TcpStream::connect(endpoint.local_addr()).unwrap();
+            stream.write_all(format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).as_bytes()).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        let response = scrape("/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with("mlflow_operator_latency_seconds_count 3\n# HELP mlflow_operator_throughput Throughput\n# TYPE mlflow_operator_throughput gauge\nmlflow_operator_throughput{pipeline=\"0\",operator=\"Map \\\"1\\\"\"} 12.5\n"));
+        assert!(scrape("/").starts_with("HTTP/1.1 404"));
+    }
+}
@@ -1,6 +1,10 @@
+pub mod launch;
 pub mod local;
 pub mod pipeline;
+pub mod metrics_endpoint;
 
-pub use pipeline::{pipeline_worker_execute, pipeline_relay_execute};
+pub use pipeline::{pipeline_worker_execute, pipeline_relay_execute, ExecuteError, RelayStats};
 pub use pipeline::{pipeline_worker_execute_guid, pipeline_relay_execute_guid};
-pub use local::{local_execute, local_execute_thread, local_execute_process};
\ No newline at end of file
+pub use local::{local_execute, local_execute_thread, local_execute_process};
+pub use launch::{local_launch, LaunchConfig, LaunchReport};
+pub use metrics_endpoint::{MetricsEndpoint, MetricKind, MetricSample};
\ No newline at end of file
@@ -1,81 +1,166 @@
 use std::collections::{HashMap, HashSet, BTreeMap};
+use std::fmt;
 use std::fs::File;
 use std::io::BufWriter;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
 
 use timely::order::TotalOrder;
 use timely::progress::Timestamp;
 use timely::progress::timestamp::Refines;
 use timely::CommunicationWithRelayConfig;
 use timely::WorkerConfig;
+use timely::worker::RelayConnector;
 
 use timely::relay::{RelayConfig, RelayToOutputExchangePattern};
-use timely::communication::RelayNodeConfig as RelayNodeCommConfig;
+use timely::communication::{LostWorkersReport, RelayFlowControlReport, RelayNodeConfig as RelayNodeCommConfig, ReplayReport};
 use timely::relay::execute_from_config as pipeline_relay_execute_from_config;
 
 use crate::builder::PipelineGraphBuilder;
-use crate::config::{ExecutionConfig, ExecutionConfigGUID};
+use crate::config::{ConfigError, ExecutionConfig, ExecutionConfigGUID};
 use crate::graph::GraphNode;
 use crate::input::GenericScope;
 use crate::metrics::{MetricsLogger, OperatorMetricsStats};
 use crate::node::GenericPipelineScope;
 use crate::static_timely::timely_static_pipeline_execute::execute as timely_pipeline_execute; 
 use crate::static_timely::timely_static_pipeline_execute::Config as TimelyPipelineConfig;
+use crate::trace::{TraceCollector, write_traces};
+use crate::utils::PipelineIoError;
+
+use super::metrics_endpoint::{MetricsEndpoint, MetricsRegistry, operator_samples, relay_samples};
+
+
+/// Errors when executing a pipeline worker or relay node
+#[derive(Debug)]
+pub enum ExecuteError {
+    /// Failed to establish the connections to the workers and relay nodes (e.g., startup timeout)
+    Connection(String),
+    /// A worker or relay thread panicked
+    Panicked(String),
+    /// Failed to infer the pipeline inputs / outputs from the graph connections
+    PipelineIo(PipelineIoError),
+    /// Failed to write the traces of the sampled requests
+    Trace(std::io::Error),
+    /// The execution config is invalid or does not match the dataflow graph
+    Config(ConfigError),
+    /// Fewer worker threads than configured are joined
+    MissingThreads { expected: usize, joined: usize },
+}
+
+impl fmt::Display for ExecuteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecuteError::Connection(msg) => write!(f, "failed to connect: {}", msg),
+            ExecuteError::Panicked(msg) => write!(f, "thread panicked: {}", msg),
+            ExecuteError::PipelineIo(err) => write!(f, "failed to infer pipeline inputs / outputs: {}", err),
+            ExecuteError::Trace(err) => write!(f, "failed to write traces: {}", err),
+            ExecuteError::Config(err) => write!(f, "{}", err),
+            ExecuteError::MissingThreads { expected, joined } => write!(f, "joined {} of {} worker threads", joined, expected),
+        }
+    }
+}
+
+impl std::error::Error for ExecuteError {}
+
+impl From<PipelineIoError> for ExecuteError {
+    fn from(err: PipelineIoError) -> Self {
+        ExecuteError::PipelineIo(err)
+    }
+}
 
+impl From<ConfigError> for ExecuteError {
+    fn from(err: ConfigError) -> Self {
+        ExecuteError::Config(err)
+    }
+}
 
-pub fn pipeline_worker_execute<T, F>(dag_builder: F, config: &ExecutionConfig, pipeline_index: usize, worker_index: usize)
+/// Execute a worker of the pipeline,
+/// pipeline inputs / outputs not specified in the config are inferred from the edges declared by `dag_builder`
+pub fn pipeline_worker_execute<T, F>(dag_builder: F, config: &ExecutionConfig, pipeline_index: usize, worker_index: usize) -> Result<(), ExecuteError>
 where
     T: Timestamp + Refines<()> + TotalOrder,
     F: Fn(&mut PipelineGraphBuilder<T>) + Send + Sync + 'static
 {
     let op_name_guid_mapping = config.op_name_guid_mapping.clone();
     let metrics_logging_dir = config.metrics_logging_dir.clone();
-    let config = config.to_guid();
+    let mut config = config.clone();
+    // fail before connecting instead of on building the dead-letter operators
+    config.validate_graph(&dag_builder)?;
+    if config.requires_io_inference() && config.graph_connections.is_none() {
+        // capture the edges from the dag builder to infer pipeline inputs / outputs
+        config.capture_graph_connections(&dag_builder)?;
+    }
+    let config = config.to_guid()?;
     
-    let loggers = pipeline_worker_execute_guid(dag_builder, &config, pipeline_index, worker_index);
+    let loggers = pipeline_worker_execute_guid(dag_builder, &config, pipeline_index, worker_index)?;
     let throughput_loggers = loggers.throughput_loggers;
+    let input_throughput_loggers = loggers.input_throughput_loggers;
     let execution_latency_loggers = loggers.execution_latency_loggers;
     let edge_latency_loggers = loggers.edge_latency_loggers;
     let path_latency_loggers = loggers.path_latency_loggers;
     let jct_loggers = loggers.jct_loggers;
+    let slo_loggers = loggers.slo_loggers;
+    let state_loggers = loggers.state_loggers;
+    let error_loggers = loggers.error_loggers;
+    let batch_loggers = loggers.batch_loggers;
 
     let mut system_metrics = BTreeMap::new();
     let mut all_exec_latencies = BTreeMap::new();
     let mut all_path_latencies = BTreeMap::new();
+    let mut all_latency_buckets = BTreeMap::new();
     for (op_name, gid) in op_name_guid_mapping.iter() {
         let mut op_metrics = OperatorMetricsStats {
             throughput: None,
             overall_throughput: None,
+            input_throughput: None,
             latency: None,
             operator_jct: None,
-            path_jct: None
+            path_jct: None,
+            dropped: None,
+            goodput: None,
+            evicted_keys: None,
+            pending_keys: None,
+            errors: None,
+            retries: None,
+            batch_sizes: None
         };
         let mut latency_metrics = BTreeMap::new();
-        if let Some(logger) = execution_latency_loggers.get(&gid) {
+        let mut latency_buckets = BTreeMap::new();
+        if let Some(logger) = execution_latency_loggers.get(gid) {
+            latency_buckets.insert(String::from("operator_execution"), logger.get_latency_buckets());
             let metrics = logger.compute_latency();
             if let Some(metrics) = metrics {
                 latency_metrics.insert(String::from("operator_execution"), metrics);
             }
-            all_exec_latencies.insert(op_name.clone(), logger.get_all_latencies());
+            if let Some(latencies) = logger.get_all_latencies() {
+                all_exec_latencies.insert(op_name.clone(), latencies);
+            }
         }
-        if let Some(logger) = edge_latency_loggers.get(&gid) {
+        if let Some(logger) = edge_latency_loggers.get(gid) {
+            latency_buckets.insert(String::from("dataflow_edge"), logger.get_latency_buckets());
             let metrics = logger.compute_latency();
             if let Some(metrics) = metrics {
                 latency_metrics.insert(String::from("dataflow_edge"), metrics);
             } 
         }
-        if let Some(logger) = path_latency_loggers.get(&gid) {
+        if let Some(logger) = path_latency_loggers.get(gid) {
+            latency_buckets.insert(String::from("dataflow_path"), logger.get_latency_buckets());
             let metrics = logger.compute_latency();
             if let Some(metrics) = metrics {
                 latency_metrics.insert(String::from("dataflow_path"), metrics);
             }
-            all_path_latencies.insert(op_name.clone(), logger.get_all_latencies());
+            if let Some(latencies) = logger.get_all_latencies() {
+                all_path_latencies.insert(op_name.clone(), latencies);
+            }
         }
         if !latency_metrics.is_empty() {
             op_metrics.latency = Some(latency_metrics);
+            all_latency_buckets.insert(op_name.clone(), latency_buckets);
         }
 
-        if let Some(logger) = throughput_loggers.get(&gid) {
+        if let Some(logger) = throughput_loggers.get(gid) {
             let metrics = logger.compute_throughput();
             if let Some(metrics) = metrics {
                 op_metrics.throughput = Some(metrics);
@@ -84,7 +169,10 @@ where
                 op_metrics.overall_throughput = Some(overall_th);
             }
         }
-        if let Some(logger) = jct_loggers.get(&gid) {
+        if let Some(logger) = input_throughput_loggers.get(gid) {
+            op_metrics.input_throughput = logger.compute_overall_throughput();
+        }
+        if let Some(logger) = jct_loggers.get(gid) {
             let op_jct = logger.compute_operator_job_completion_time();
             if let Some(op_jct) = op_jct {
                 op_metrics.operator_jct = Some(op_jct)
@@ -94,11 +182,31 @@ where
                 op_metrics.path_jct = Some(path_jct)
             }
         }
+        if let Some(logger) = slo_loggers.get(gid) {
+            op_metrics.dropped = Some(logger.compute_dropped());
+            op_metrics.goodput = logger.compute_goodput();
+        }
+        if let Some(logger) = state_loggers.get(gid) {
+            op_metrics.evicted_keys = Some(logger.compute_evicted());
+            op_metrics.pending_keys = Some(logger.compute_pending());
+        }
+        if let Some(logger) = error_loggers.get(gid) {
+            op_metrics.errors = Some(logger.compute_errors());
+            op_metrics.retries = Some(logger.compute_retries());
+        }
+        if let Some(logger) = batch_loggers.get(gid) {
+            op_metrics.batch_sizes = logger.compute_batch_sizes();
+        }
         if op_metrics.throughput.is_some() || 
             op_metrics.overall_throughput.is_some() || 
+            op_metrics.input_throughput.is_some() ||
             op_metrics.latency.is_some() || 
             op_metrics.operator_jct.is_some() ||
-            op_metrics.path_jct.is_some()
+            op_metrics.path_jct.is_some() ||
+            op_metrics.dropped.is_some() ||
+            op_metrics.evicted_keys.is_some() ||
+            op_metrics.errors.is_some() ||
+            op_metrics.batch_sizes.is_some()
         {
             system_metrics.insert(op_name.to_owned(), op_metrics);
         }
@@ -109,33 +217,49 @@ where
         let metrics_logging_path = logging_dir.join(format!("performance_metrics_p{}_w{}.json", pipeline_index, worker_index));
         let exec_latency_logging_path = logging_dir.join(format!("latency_logs/exec_latencies_p{}_w{}.json", pipeline_index, worker_index));
         let path_latency_logging_path = logging_dir.join(format!("latency_logs/path_latencies_p{}_w{}.json", pipeline_index, worker_index));
+        let latency_buckets_logging_path = logging_dir.join(format!("latency_logs/latency_histograms_p{}_w{}.json", pipeline_index, worker_index));
         
         let f = File::create(metrics_logging_path).expect("Unable to create file");
         let writer = BufWriter::new(f);
         serde_json::to_writer_pretty(writer, &system_metrics).unwrap();
 
-        let f = File::create(exec_latency_logging_path).expect("Unable to create file");
+        // merged across the workers by `MetricsReport`
+        let f = File::create(latency_buckets_logging_path).expect("Unable to create file");
         let writer = BufWriter::new(f);
-        serde_json::to_writer_pretty(writer, &all_exec_latencies).unwrap();
+        serde_json::to_writer(writer, &all_latency_buckets).unwrap();
 
-        let f = File::create(path_latency_logging_path).expect("Unable to create file");
-        let writer = BufWriter::new(f);
-        serde_json::to_writer_pretty(writer, &all_path_latencies).unwrap();
+        // the raw latencies are only kept if enabled by `keep_latency_samples`
+        if !all_exec_latencies.is_empty() {
+            let f = File::create(exec_latency_logging_path).expect("Unable to create file");
+            let writer = BufWriter::new(f);
+            serde_json::to_writer_pretty(writer, &all_exec_latencies).unwrap();
+        }
+
+        if !all_path_latencies.is_empty() {
+            let f = File::create(path_latency_logging_path).expect("Unable to create file");
+            let writer = BufWriter::new(f);
+            serde_json::to_writer_pretty(writer, &all_path_latencies).unwrap();
+        }
     }
     
     let stats = serde_json::to_string_pretty(&system_metrics).unwrap();
     println!("=====METRICS======");
     println!("{}", stats);
+    Ok(())
 }
 
 
-pub fn pipeline_worker_execute_guid<T, F>(dag_builder: F, config: &ExecutionConfigGUID, pipeline_index: usize, worker_index: usize) -> MetricsLogger
+pub fn pipeline_worker_execute_guid<T, F>(dag_builder: F, config: &ExecutionConfigGUID, pipeline_index: usize, worker_index: usize) -> Result<MetricsLogger, ExecuteError>
 where
     T: Timestamp + Refines<()> + TotalOrder,
     F: Fn(&mut PipelineGraphBuilder<T>) + Send + Sync + 'static
 {
+    let mut config = config.clone();
+    config.infer_pipeline_io()?;
     let current_pipeline_config = config.pipeline_configs.get(&pipeline_index).unwrap();
     let message_buffer_size = config.message_buffer_size;
+    let mut connection_config = config.connection_config.unwrap_or_default();
+    connection_config.flow_control = current_pipeline_config.flow_control;
     let op_name_guid_mapping = config.op_name_guid_mapping.clone();
 
     let mut required_inputs = current_pipeline_config.required_input_ops.as_ref().unwrap().clone();
@@ -151,22 +275,45 @@ where
     let request_rates = current_pipeline_config.request_rates.clone();
     let worker_addrs = current_pipeline_config.worker_addrs.clone();
     let relay_addrs = current_pipeline_config.relay_addrs.clone();
+    let threads = current_pipeline_config.threads;
+    // spans of the sampled requests recorded by the worker threads, written once the threads are joined
+    let tracing = config.tracing.clone();
+    let trace_collector = TraceCollector::default();
+
+    // served until the worker threads are joined, the worker threads publish their metrics every refresh interval
+    let metrics_registry = Arc::new(MetricsRegistry::default());
+    let metrics_endpoint_addr = current_pipeline_config.metrics_endpoint.as_ref()
+        .and_then(|endpoint| endpoint.worker_addrs.get(worker_index).cloned());
+    let _metrics_endpoint = match &metrics_endpoint_addr {
+        Some(addr) => {
+            let registry = metrics_registry.clone();
+            let endpoint = MetricsEndpoint::serve(addr, move || registry.collect())
+                .map_err(|err| ExecuteError::Connection(format!("failed to serve metrics at {}: {}", addr, err)))?;
+            Some(endpoint)
+        },
+        None => None
+    };
+    let metrics_refresh_interval = current_pipeline_config.metrics_endpoint.as_ref()
+        .filter(|_| metrics_endpoint_addr.is_some())
+        .map(|endpoint| endpoint.refresh_interval());
 
     let comm_config = if worker_addrs.len() == 1 {
         CommunicationWithRelayConfig::Process {
-            threads: 1,
+            threads,
             relay_addresses: relay_addrs,
             report: true,
+            connection: connection_config,
             relay_log_fn: Box::new(|_| None)
         }
     }
     else {
         CommunicationWithRelayConfig::Cluster {
-            threads: 1,
+            threads,
             process: worker_index,
             worker_addresses: worker_addrs,
             relay_addresses: relay_addrs,
             report: true,
+            connection: connection_config,
             worker_log_fn: Box::new(|_| None),
             relay_log_fn: Box::new(|_| None)
         }
@@ -181,6 +328,8 @@ where
         config.worker.set(String::from("message_buffer_size"), msg_buffer_size);
     }
 
+    let worker_trace_collector = trace_collector.clone();
+    let worker_tracing = tracing.clone();
     let guards = timely_pipeline_execute(config, move |worker| {
         let current_pipeline_nodes_names = match &op_name_guid_mapping {
             Some(mapping) => {
@@ -227,6 +376,7 @@ where
         ).collect::<Vec<_>>();
 
         current_pipeline_nodes_with_lid.sort_by_key(|(_gid, lid)| *lid);
+        let op_names = op_name_guid_mapping.iter().flatten().map(|(name, gid)| (*gid, name.clone())).collect::<HashMap<_, _>>();
 
         worker.pipeline_dataflow::<T, _, _>(|scope| {
             let mut streams = HashMap::new();
@@ -262,6 +412,11 @@ where
                         op_config = Some(config);
                     }
                 }
+                if let Some(tracing) = &worker_tracing {
+                    let op_name = op_names.get(node_index).cloned().unwrap_or_else(|| node_index.to_string());
+                    let tracer = worker_trace_collector.tracer(tracing, &op_name, worker_index);
+                    op_config.get_or_insert_with(HashMap::new).insert(String::from("tracer"), Arc::new(tracer));
+                }
                 match node {
                     GraphNode::LocalInputNode(node) => {
                         if let Some(request_rate) = request_rates.get(&node_index).map(|x| *x) {
@@ -327,10 +482,15 @@ where
 
 
         let mut throughput_loggers = HashMap::new();
+        let mut input_throughput_loggers = HashMap::new();
         let mut execution_latency_loggers = HashMap::new();
         let mut edge_latency_loggers = HashMap::new();
         let mut path_latency_loggers = HashMap::new();
         let mut jct_loggers = HashMap::new();
+        let mut slo_loggers = HashMap::new();
+        let mut state_loggers = HashMap::new();
+        let mut error_loggers = HashMap::new();
+        let mut batch_loggers = HashMap::new();
 
         for (node_gid, node_lid) in current_pipeline_nodes_with_lid.iter() {
             let node = graph.operators.get_mut(node_lid).expect("opeartor does not exist");
@@ -339,35 +499,71 @@ where
                     if let Some(logger) = node.get_throughput_logger() {
                         throughput_loggers.insert(*node_gid, logger);
                     }
+                    if let Some(logger) = node.get_input_throughput_logger() {
+                        input_throughput_loggers.insert(*node_gid, logger);
+                    }
                     if let Some(logger) = node.get_flow_compute_latency_logger() {
+                        logger.configure(operator_configs.get(node_gid));
                         execution_latency_loggers.insert(*node_gid, logger);
                     }
                     if let Some(logger) = node.get_flow_edge_latency_logger() {
+                        logger.configure(operator_configs.get(node_gid));
                         edge_latency_loggers.insert(*node_gid, logger);
                     }
                     if let Some(logger) = node.get_flow_path_latency_logger() {
+                        logger.configure(operator_configs.get(node_gid));
                         path_latency_loggers.insert(*node_gid, logger);
                     }
                     if let Some(logger) = node.get_jct_logger() {
                         jct_loggers.insert(*node_gid, logger);
                     }
+                    if let Some(logger) = node.get_slo_logger() {
+                        slo_loggers.insert(*node_gid, logger);
+                    }
+                    if let Some(logger) = node.get_state_logger() {
+                        state_loggers.insert(*node_gid, logger);
+                    }
+                    if let Some(logger) = node.get_error_logger() {
+                        error_loggers.insert(*node_gid, logger);
+                    }
+                    if let Some(logger) = node.get_batch_logger() {
+                        batch_loggers.insert(*node_gid, logger);
+                    }
                 },
                 GraphNode::ExchangeComputeNode(node) => {
                     if let Some(logger) = node.get_throughput_logger() {
                         throughput_loggers.insert(*node_gid, logger);
                     }
+                    if let Some(logger) = node.get_input_throughput_logger() {
+                        input_throughput_loggers.insert(*node_gid, logger);
+                    }
                     if let Some(logger) = node.get_flow_compute_latency_logger() {
+                        logger.configure(operator_configs.get(node_gid));
                         execution_latency_loggers.insert(*node_gid, logger);
                     }
                     if let Some(logger) = node.get_flow_edge_latency_logger() {
+                        logger.configure(operator_configs.get(node_gid));
                         edge_latency_loggers.insert(*node_gid, logger);
                     }
                     if let Some(logger) = node.get_flow_path_latency_logger() {
+                        logger.configure(operator_configs.get(node_gid));
                         path_latency_loggers.insert(*node_gid, logger);
                     }
                     if let Some(logger) = node.get_jct_logger() {
                         jct_loggers.insert(*node_gid, logger);
                     }
+                    if let Some(logger) = node.get_slo_logger() {
+                        slo_loggers.insert(*node_gid, logger);
+                    }
+                    if let Some(logger) = node.get_state_logger() {
+                        state_loggers.insert(*node_gid, logger);
+                    }
+                    if let Some(logger) = node.get_error_logger() {
+                        error_loggers.insert(*node_gid, logger);
+                    }
+                    if let Some(logger) = node.get_batch_logger() {
+                        batch_loggers.insert(*node_gid, logger);
+                    }
                 }
                 _ => {}
             }
@@ -375,60 +571,110 @@ where
 
         let metrics_loggers = MetricsLogger {
             throughput_loggers,
+            input_throughput_loggers,
             execution_latency_loggers,
             edge_latency_loggers,
             path_latency_loggers,
             jct_loggers,
+            slo_loggers,
+            state_loggers,
+            error_loggers,
+            batch_loggers,
         };
 
         let current_pipeline_nodes_lid = current_pipeline_nodes_with_lid.into_iter().map(
             |(_gid, lid)| lid
         ).collect::<HashSet<_>>();
 
+        let metrics_labels = [("pipeline", pipeline_index.to_string()), ("worker", worker_index.to_string())];
+        let publish_metrics = || metrics_registry.publish(worker_index, operator_samples(&metrics_loggers, &op_names, &metrics_labels));
+        let mut last_published = Instant::now();
+
+        let mut pending_inputs = graph.input_operator_indices.iter().copied().filter(
+            |index| current_pipeline_nodes_lid.contains(index)
+        ).collect::<Vec<_>>();
+        let has_sources = !pending_inputs.is_empty();
         let mut completed = false; 
         while !completed {
-            completed = true;
-            for index in graph.input_operator_indices.iter() {
-                if current_pipeline_nodes_lid.contains(&index) {
-                    let input_node = graph.operators.get_mut(index).unwrap();
-                    if let GraphNode::LocalInputNode(input_node) = input_node {
-                        if input_node.step() {
-                            completed = false;
-                        }
-                    }
-                    else if let GraphNode::ExchangeInputNode(input_node) = input_node {
-                        if input_node.step() {
-                            completed = false;
-                        }
-                    }
-                    else {
-                        panic!("node {} should be an input node", index);
-                    }
-                }
+            if matches!(metrics_refresh_interval, Some(interval) if last_published.elapsed() >= interval) {
+                publish_metrics();
+                last_published = Instant::now();
+            }
+            // hold the sources back while the messages to the relay nodes pile up
+            if has_sources && worker.relay_backpressured() {
+                worker.step_or_park(Some(Duration::from_millis(1)));
+                continue;
             }
+            // completed inputs are not stepped again
+            pending_inputs.retain(|index| {
+                match graph.operators.get_mut(index).unwrap() {
+                    GraphNode::LocalInputNode(input_node) => input_node.step(),
+                    GraphNode::ExchangeInputNode(input_node) => input_node.step(),
+                    _ => panic!("node {} should be an input node", index)
+                }
+            });
+            completed = pending_inputs.is_empty();
             worker.step();
         }
+        if let Some(interval) = metrics_refresh_interval {
+            // the dataflow keeps running after the sources complete (e.g., downstream of the pipeline inputs),
+            // step it here rather than in the execution to keep publishing its metrics
+            while worker.pipeline_step_or_park(Some(interval)) {
+                if last_published.elapsed() >= interval {
+                    publish_metrics();
+                    last_published = Instant::now();
+                }
+            }
+            publish_metrics();
+        }
 
         metrics_loggers
-    }).unwrap();
+    }).map_err(ExecuteError::Connection)?;
 
     let loggers = guards.join();
-    let mut loggers = loggers.into_iter().map(|x| x.unwrap()).collect::<Vec<_>>();
-    assert_eq!(loggers.len(), 1);
-    let loggers = loggers.pop().unwrap();
-    loggers
+    let loggers = loggers.into_iter().collect::<Result<Vec<_>, _>>().map_err(ExecuteError::Panicked)?;
+    if loggers.len() != threads {
+        return Err(ExecuteError::MissingThreads { expected: threads, joined: loggers.len() });
+    }
+    if let Some(tracing) = &tracing {
+        write_traces(&trace_collector.take(), tracing, pipeline_index, worker_index).map_err(ExecuteError::Trace)?;
+    }
+    // the worker threads are joined, merge their metrics into the metrics of the process
+    Ok(MetricsLogger::merge(loggers))
 }
 
 
-pub fn pipeline_relay_execute(config: &ExecutionConfig, pipeline_index: usize, relay_node_index: usize) {
-    let config = config.to_guid();
-    pipeline_relay_execute_guid(&config, pipeline_index, relay_node_index);
+/// Failures observed by a relay node, reported after the metrics marker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayStats {
+    #[serde(flatten)]
+    pub lost_workers: LostWorkersReport,
+    /// Messages replayed for each output pipeline (with at-least-once delivery)
+    #[serde(default)]
+    pub replay: Vec<ReplayReport>,
+    /// Pauses of reading from the input pipelines and the timely workers (with flow control)
+    #[serde(default)]
+    pub flow_control: Option<RelayFlowControlReport>,
+}
+
+pub fn pipeline_relay_execute(config: &ExecutionConfig, pipeline_index: usize, relay_node_index: usize) -> Result<(), ExecuteError> {
+    let config = config.to_guid()?;
+    pipeline_relay_execute_guid(&config, pipeline_index, relay_node_index)
 }
 
-pub fn pipeline_relay_execute_guid(config: &ExecutionConfigGUID, pipeline_index: usize, relay_node_index: usize) {
+/// Execute a relay node of the pipeline,
+/// pipeline inputs / outputs not specified in the config are inferred from the captured graph connections
+/// (see `ExecutionConfig::capture_graph_connections`)
+pub fn pipeline_relay_execute_guid(config: &ExecutionConfigGUID, pipeline_index: usize, relay_node_index: usize) -> Result<(), ExecuteError> {
+    let mut config = config.clone();
+    config.infer_pipeline_io()?;
     let current_pipeline_config = config.pipeline_configs.get(&pipeline_index).unwrap();
     let relay_addrs = current_pipeline_config.relay_addrs.clone();
     let worker_addrs = current_pipeline_config.worker_addrs.clone();
+    // each relay node is paired with the worker of the same index,
+    // the weights of the relay nodes are used to redistribute the data of lost workers
+    let workers_lb_weights = current_pipeline_config.relay_load_balance_weights.clone()
+        .filter(|weights| weights.len() == worker_addrs.len());
     
     let mut required_input_ops = current_pipeline_config.required_input_ops.as_ref().unwrap().clone();
     required_input_ops.sort();
@@ -484,15 +730,18 @@ pub fn pipeline_relay_execute_guid(config: &ExecutionConfigGUID, pipeline_index:
     }
 
     let num_relays = relay_addrs.len();
+    let mut connection_config = config.connection_config.unwrap_or_default();
+    connection_config.flow_control = current_pipeline_config.flow_control;
     let comm_config = RelayNodeCommConfig {
         input_relay_nodes_addresses: input_pipelines_relay_addrs,
         output_relay_nodes_addresses: output_pipelines_relay_addrs,
         timely_workers_addresses: worker_addrs,
-        threads_per_timely_worker_process: 1,
+        threads_per_timely_worker_process: current_pipeline_config.threads,
         my_addr: relay_addrs[relay_node_index].clone(),
         my_index: relay_node_index,
         num_relay_nodes_peers: num_relays,
         report: true,
+        connection: connection_config,
         relay_log_sender: Box::new(|_| None),
         timely_log_sender: Box::new(|_| None),
     };
@@ -510,8 +759,165 @@ pub fn pipeline_relay_execute_guid(config: &ExecutionConfigGUID, pipeline_index:
         required_outputs: output_pipelines_required_ops,
         input_to_worker_exchange_patterns: None,
         relay_to_output_exchange_pattern: Some(RelayToOutputExchangePattern::Balance),
-        output_pipelines_relay_load_balance_ratios: output_pipelines_load_balanced_ratios_map
+        output_pipelines_relay_load_balance_ratios: output_pipelines_load_balanced_ratios_map,
+        timely_workers_load_balance_ratios: workers_lb_weights
+    };
+
+    let guards = pipeline_relay_execute_from_config(relay_config).map_err(ExecuteError::Connection)?;
+    let liveness = guards.timely_workers_liveness().clone();
+    let replays = guards.output_pipelines_replay().to_vec();
+    let flow_control = guards.flow_control().cloned();
+    let metrics_endpoint_addr = current_pipeline_config.metrics_endpoint.as_ref()
+        .and_then(|endpoint| endpoint.relay_addrs.get(relay_node_index));
+    // served until the metrics are reported
+    let _metrics_endpoint = match metrics_endpoint_addr {
+        Some(addr) => {
+            let (liveness, replays, flow_control) = (liveness.clone(), replays.clone(), flow_control.clone());
+            let labels = [("pipeline", pipeline_index.to_string()), ("relay_node", relay_node_index.to_string())];
+            let endpoint = MetricsEndpoint::serve(addr, move || relay_samples(&liveness, &replays, flow_control.as_deref(), &labels))
+                .map_err(|err| ExecuteError::Connection(format!("failed to serve metrics at {}: {}", addr, err)))?;
+            Some(endpoint)
+        },
+        None => None
+    };
+    let (input_relay_results, output_relay_results) = guards.join();
+    for result in input_relay_results.into_iter().chain(output_relay_results) {
+        result.map_err(ExecuteError::Panicked)?;
+    }
+
+    // the network threads are joined, no more messages will be discarded or replayed
+    let stats = RelayStats {
+        lost_workers: liveness.report(),
+        replay: replays.iter().map(|replay| replay.report()).collect(),
+        flow_control: flow_control.map(|flow_control| flow_control.report()),
     };
+    let stats = serde_json::to_string_pretty(&stats).unwrap();
+    println!("=====METRICS======");
+    println!("{}", stats);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::time::Duration;
+
+    use crate::builder::PipelineGraphBuilder;
+    use crate::config::DeploymentSpec;
+    use crate::Map;
+
+    use super::{pipeline_relay_execute_guid, pipeline_worker_execute_guid};
+
+    fn dag_builder(builder: &mut PipelineGraphBuilder<usize>) {
+        let input = builder.new_input_from_source(VecDeque::from((0..20).collect::<Vec<usize>>()), |_, t| *t + 1, "Input");
+        input.map(|x| {
+            std::thread::sleep(Duration::from_millis(10));
+            x * 2
+        }, "Double");
+    }
+
+    fn free_addr() -> String {
+        TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string()
+    }
+
+    // number of requests processed by the operator, as exposed at the metrics endpoint
+    fn scrape_processed(addr: &str, operator: &str) -> Option<f64> {
+        let mut stream = TcpStream::connect(addr).ok()?;
+        stream.write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").ok()?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).ok()?;
+        let prefix = "mlflow_operator_latency_seconds_count{";
+        response.lines()
+            .filter(|line| line.starts_with(prefix) && line.contains(&format!("operator=\"{}\"", operator)) && line.contains("kind=\"operator_execution\""))
+            .find_map(|line| line.rsplit(' ').next().and_then(|value| value.parse().ok()))
+    }
 
-    pipeline_relay_execute_from_config(relay_config);
-}
\ No newline at end of file
+    #[test]
+    fn publish_downstream_metrics() {
+        let metrics_addr = free_addr();
+        let spec = format!("
+pipelines:
+  0:
+    assigned_ops: [Input]
+    worker_addrs: [\"{}\"]
+    relay_addrs: [\"{}\"]
+    operator_options:
+      Input:
+        arrival: {{type: uniform, rate: 50.0}}
+  1:
+    assigned_ops: [Double]
+    worker_addrs: [\"{}\"]
+    relay_addrs: [\"{}\"]
+    metrics_endpoint:
+      worker_addrs: [\"{}\"]
+      refresh_interval_ms: 20
+", free_addr(), free_addr(), free_addr(), free_addr(), metrics_addr);
+        let mut config = DeploymentSpec::from_yaml_str(&spec).unwrap().to_execution_config().unwrap();
+        config.capture_graph_connections(&dag_builder).unwrap();
+        let config = config.to_guid().unwrap();
+
+        let mut threads = Vec::new();
+        for pipeline_index in 0..2 {
+            let relay_config = config.clone();
+            threads.push(std::thread::spawn(move || pipeline_relay_execute_guid(&relay_config, pipeline_index, 0).unwrap()));
+        }
+        let worker_config = config.clone();
+        threads.push(std::thread::spawn(move || { pipeline_worker_execute_guid(dag_builder, &worker_config, 0, 0).unwrap(); }));
+        // the downstream pipeline has no source, its operators only run once the sources of its worker complete
+        let worker_config = config.clone();
+        let downstream = std::thread::spawn(move || pipeline_worker_execute_guid(dag_builder, &worker_config, 1, 0).unwrap());
+
+        let mut processed = 0.0;
+        while !downstream.is_finished() {
+            processed = scrape_processed(&metrics_addr, "Double").unwrap_or(0.0).max(processed);
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let metrics = downstream.join().unwrap();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert!(processed > 0.0, "no request processed by the downstream pipeline is exposed");
+        assert_eq!(metrics.execution_latency_loggers.values().map(|logger| logger.latencies.borrow().len()).sum::<u64>(), 20);
+    }
+
+    #[test]
+    fn merge_worker_thread_metrics() {
+        let spec = format!("
+pipelines:
+  0:
+    assigned_ops: [Input]
+    worker_addrs: [\"{}\"]
+    relay_addrs: [\"{}\"]
+    operator_options:
+      Input:
+        arrival: {{type: uniform, rate: 50.0}}
+  1:
+    assigned_ops: [Double]
+    worker_addrs: [\"{}\"]
+    relay_addrs: [\"{}\"]
+    threads: 2
+", free_addr(), free_addr(), free_addr(), free_addr());
+        let mut config = DeploymentSpec::from_yaml_str(&spec).unwrap().to_execution_config().unwrap();
+        config.capture_graph_connections(&dag_builder).unwrap();
+        let config = config.to_guid().unwrap();
+        let double = *config.op_name_guid_mapping.as_ref().unwrap().get("Double").unwrap();
+
+        let mut threads = Vec::new();
+        for pipeline_index in 0..2 {
+            let relay_config = config.clone();
+            threads.push(std::thread::spawn(move || pipeline_relay_execute_guid(&relay_config, pipeline_index, 0).unwrap()));
+        }
+        let worker_config = config.clone();
+        threads.push(std::thread::spawn(move || { pipeline_worker_execute_guid(dag_builder, &worker_config, 0, 0).unwrap(); }));
+        let metrics = pipeline_worker_execute_guid(dag_builder, &config, 1, 0).unwrap();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        // the requests are spread over the two worker threads of the downstream pipeline, their metrics are merged
+        let latencies = metrics.execution_latency_loggers.get(&double).unwrap();
+        assert_eq!(latencies.latencies.borrow().len(), 20);
+        assert!(metrics.jct_loggers.get(&double).unwrap().compute_operator_job_completion_time().is_some());
+    }
+}
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 use std::marker::PhantomData;
 
+use serde::{Serialize, Deserialize};
+
 use timely::order::TotalOrder;
 use timely::progress::Timestamp;
 use timely::progress::timestamp::Refines;
@@ -24,6 +26,8 @@ where
     pub input_operator_indices: Vec<usize>,
     pub(crate) op_name_local_id_mapping: HashMap<String, usize>,
     pub(crate) op_local_id_name_mapping: HashMap<usize, String>,
+    /// Local ids of the dead-letter operators to the local ids of the fallible operators producing them
+    pub(crate) dead_letter_producers: HashMap<usize, usize>,
     phantom_timestamp: PhantomData<T>
 }
 
@@ -37,13 +41,223 @@ where
             input_operator_indices: Vec::new(),
             op_name_local_id_mapping: HashMap::new(),
             op_local_id_name_mapping: HashMap::new(),
+            dead_letter_producers: HashMap::new(),
             phantom_timestamp: PhantomData
         }
     }
+
+    /// Describe the operators and edges declared in the graph (indexed by local ids),
+    /// edges are recovered from each compute node's required input nodes
+    pub fn topology(&self) -> GraphTopology {
+        let mut operators = Vec::with_capacity(self.operators.len());
+        let mut outgoing_edges: HashMap<usize, Vec<usize>> = HashMap::with_capacity(self.operators.len());
+        let mut incoming_edges = HashMap::with_capacity(self.operators.len());
+        for index in self.operators.keys() {
+            outgoing_edges.insert(*index, Vec::new());
+        }
+
+        let mut indices = self.operators.keys().copied().collect::<Vec<_>>();
+        indices.sort();
+        for index in indices {
+            let node = self.operators.get(&index).unwrap();
+            let (kind, prev_indices) = match node {
+                GraphNode::LocalInputNode(_) => (OperatorKind::LocalInput, Vec::new()),
+                GraphNode::ExchangeInputNode(_) => (OperatorKind::ExchangeInput, Vec::new()),
+                GraphNode::LocalComputeNode(node) => (OperatorKind::LocalCompute, node.required_prev_nodes()),
+                GraphNode::ExchangeComputeNode(node) => (OperatorKind::ExchangeCompute, node.required_prev_nodes()),
+            };
+            for prev_index in prev_indices.iter() {
+                outgoing_edges.get_mut(prev_index).expect("input operator does not exist").push(index);
+            }
+            incoming_edges.insert(index, prev_indices);
+            let name = self.op_local_id_name_mapping.get(&index).expect("operator is not named").clone();
+            let dead_letters_of = self.dead_letter_producers.get(&index).copied();
+            operators.push(OperatorDescription { id: index, name, kind, dead_letters_of });
+        }
+
+        GraphTopology {
+            operators,
+            connections: GraphConnections {
+                outgoing_edges,
+                incoming_edges
+            }
+        }
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GraphConnections {
     pub outgoing_edges: HashMap<usize, Vec<usize>>,
     pub incoming_edges: HashMap<usize, Vec<usize>>
-}
\ No newline at end of file
+}
+
+/// Kind of the graph node an operator is stored as
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OperatorKind {
+    LocalInput,
+    ExchangeInput,
+    LocalCompute,
+    ExchangeCompute,
+}
+
+impl OperatorKind {
+    /// Whether the operator is a source of the dataflow
+    pub fn is_input(&self) -> bool {
+        matches!(self, OperatorKind::LocalInput | OperatorKind::ExchangeInput)
+    }
+
+    /// Whether the operator's outputs can be sent across pipelines
+    pub fn is_exchangeable(&self) -> bool {
+        matches!(self, OperatorKind::ExchangeInput | OperatorKind::ExchangeCompute)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OperatorDescription {
+    pub id: usize,
+    pub name: String,
+    pub kind: OperatorKind,
+    /// Fallible operator whose dead letters are emitted by this operator,
+    /// both must be assigned to the same pipeline
+    #[serde(default)]
+    pub dead_letters_of: Option<usize>,
+}
+
+/// Serializable description of a dataflow graph,
+/// obtained from the builders without executing the dataflow
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GraphTopology {
+    /// Operators sorted by id
+    pub operators: Vec<OperatorDescription>,
+    pub connections: GraphConnections,
+}
+
+impl GraphTopology {
+    /// Default operator name to GUID mapping,
+    /// same as `ExecutionConfig::new_with_default_mapping` when all operators are assigned
+    pub fn default_op_name_guid_mapping(&self) -> HashMap<String, usize> {
+        let mut all_ops = self.operators.iter().map(|op| op.name.clone()).collect::<Vec<_>>();
+        all_ops.sort();
+        all_ops.into_iter().enumerate().map(|(i, x)| (x, i)).collect()
+    }
+
+    /// Re-index the operators and edges with GUIDs
+    pub fn to_guid(&self, op_name_guid_mapping: &HashMap<String, usize>) -> GraphTopology {
+        let mut lid_guid_mapping = HashMap::with_capacity(self.operators.len());
+        for op in self.operators.iter() {
+            let gid = op_name_guid_mapping.get(&op.name).unwrap_or_else(|| panic!("could not find operator {}", op.name));
+            lid_guid_mapping.insert(op.id, *gid);
+        }
+        let remap = |edges: &HashMap<usize, Vec<usize>>| edges.iter().map(
+            |(lid, lids)| (
+                *lid_guid_mapping.get(lid).unwrap(),
+                lids.iter().map(|x| *lid_guid_mapping.get(x).unwrap()).collect::<Vec<_>>()
+            )
+        ).collect::<HashMap<_, _>>();
+
+        let mut operators = self.operators.iter().map(
+            |op| OperatorDescription {
+                id: *lid_guid_mapping.get(&op.id).unwrap(),
+                name: op.name.clone(),
+                kind: op.kind,
+                dead_letters_of: op.dead_letters_of.map(|lid| *lid_guid_mapping.get(&lid).unwrap())
+            }
+        ).collect::<Vec<_>>();
+        operators.sort_by_key(|op| op.id);
+
+        GraphTopology {
+            operators,
+            connections: GraphConnections {
+                outgoing_edges: remap(&self.connections.outgoing_edges),
+                incoming_edges: remap(&self.connections.incoming_edges)
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    /// Render the graph in Graphviz DOT format,
+    /// input operators are drawn as ellipses and exchangeable operators with bold borders
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph dataflow {\n    rankdir=LR;\n");
+        for op in self.operators.iter() {
+            let shape = if op.kind.is_input() { "ellipse" } else { "box" };
+            let style = if op.kind.is_exchangeable() { "bold" } else { "solid" };
+            dot.push_str(&format!(
+                "    op{} [label=\"{}\", shape={}, style={}];\n",
+                op.id, op.name.replace('"', "\\\""), shape, style
+            ));
+        }
+        for op in self.operators.iter() {
+            if let Some(targets) = self.connections.outgoing_edges.get(&op.id) {
+                for target in targets.iter() {
+                    dot.push_str(&format!("    op{} -> op{};\n", op.id, target));
+                }
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use crate::builder::PipelineGraphBuilder;
+    use crate::{Map, Join, Inspect};
+
+    use super::OperatorKind;
+
+    #[test]
+    fn extract_topology() {
+        let dag = |builder: &mut PipelineGraphBuilder<u64>| {
+            let left = builder.new_input_from_source(VecDeque::from(vec![(0u64, 1u64)]), |_, t| *t + 1, "Left");
+            let right = builder.new_input_from_source(VecDeque::from(vec![(0u64, 2u64)]), |_, t| *t + 1, "Right");
+            let right = right.map(|x| x, "Identity");
+            let _ = left.concat(&right, |x| x.0, |x| x.0, "Join")
+                .inspect(|_| {}, "Inspect");
+        };
+        let topology = PipelineGraphBuilder::extract_topology(&dag, Default::default());
+        let names = topology.operators.iter().map(|op| op.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["Left", "Right", "Identity", "Join", "Inspect"]);
+        assert_eq!(topology.operators[0].kind, OperatorKind::ExchangeInput);
+        assert_eq!(topology.connections.incoming_edges.get(&3).unwrap(), &vec![0, 2]);
+        assert_eq!(topology.connections.outgoing_edges.get(&1).unwrap(), &vec![2]);
+        assert!(topology.connections.outgoing_edges.get(&4).unwrap().is_empty());
+
+        let mapping = topology.default_op_name_guid_mapping();
+        let guid_topology = topology.to_guid(&mapping);
+        let join_guid = *mapping.get("Join").unwrap();
+        let mut join_inputs = guid_topology.connections.incoming_edges.get(&join_guid).unwrap().clone();
+        join_inputs.sort();
+        let mut expected = vec![*mapping.get("Left").unwrap(), *mapping.get("Identity").unwrap()];
+        expected.sort();
+        assert_eq!(join_inputs, expected);
+
+        let parsed = super::GraphTopology::from_json(&topology.to_json()).unwrap();
+        assert_eq!(parsed.operators.len(), 5);
+        assert!(topology.to_dot().contains("op2 -> op3;"));
+    }
+
+    #[test]
+    fn name_dead_letter_operators() {
+        let dag = |builder: &mut PipelineGraphBuilder<u64>| {
+            let input = builder.new_input_from_source(VecDeque::from(vec![(0u64, 1u64)]), |_, t| *t + 1, "Input");
+            let _ = input.map(|x| x, "Parse_dead_letter");
+            let _ = input.try_map(|x| Ok::<_, u64>(*x), "Parse");
+            let _ = input.try_map(|x| Ok::<_, u64>(*x), "Parse");
+        };
+        let topology = PipelineGraphBuilder::extract_topology(&dag, Default::default());
+        let names = topology.operators.iter().map(|op| op.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, vec!["Input", "Parse_dead_letter", "Parse", "Parse_dead_letter_1", "Parse_1", "Parse_1_dead_letter"]);
+        let producers = topology.operators.iter().map(|op| op.dead_letters_of).collect::<Vec<_>>();
+        assert_eq!(producers, vec![None, None, None, Some(2), None, Some(4)]);
+    }
+}
@@ -10,6 +10,7 @@ use timely::order::TotalOrder;
 
 use crate::graph::{GraphNode::ExchangeComputeNode, GraphNode::LocalComputeNode};
 use crate::node::{AggregateNode, TimestampAggregateNode, IncrementalAggregateNode, TimestampIncrementalAggregateNode};
+use crate::node::{WindowAggregateNode, Window, WindowBounds, EpochTimestamp};
 use crate::static_timely::timely_static_pipeline_scope::PipelineScope;
 
 use super::Handle;
@@ -45,6 +46,16 @@ where
         H: Fn(&D) -> K + 'static,
         F: Fn(&K, D, &mut Di) + 'static,
         E: Fn(K, Di) -> R + 'static;
+
+    /// Incrementally aggregate data with the same key within each window,
+    /// `emit` gets the key, the bounds of the window and the intermediate result
+    fn window_aggregate<Di: Default + 'static, R: ExchangeData, K, H, F, E>(&self, window: Window, hash: H, fold: F, emit: E, name: &str) -> Handle<'a, T, S, R>
+    where
+        T: EpochTimestamp,
+        K: Clone + Hash + Eq + 'static,
+        H: Fn(&D) -> K + 'static,
+        F: Fn(&K, D, &mut Di) + 'static,
+        E: Fn(K, WindowBounds, Di) -> R + 'static;
 }
 
 pub trait AggregateLocal<'a, T, S, D>
@@ -78,6 +89,16 @@ where
         H: Fn(&D) -> K + 'static,
         F: Fn(&K, D, &mut Di) + 'static,
         E: Fn(K, Di) -> R + 'static;
+
+    /// Incrementally aggregate data with the same key within each window,
+    /// `emit` gets the key, the bounds of the window and the intermediate result
+    fn window_aggregate_local<Di: Default + 'static, R: Data, K, H, F, E>(&self, window: Window, hash: H, fold: F, emit: E, name: &str) -> Handle<'a, T, S, R>
+    where
+        T: EpochTimestamp,
+        K: Clone + Hash + Eq + 'static,
+        H: Fn(&D) -> K + 'static,
+        F: Fn(&K, D, &mut Di) + 'static,
+        E: Fn(K, WindowBounds, Di) -> R + 'static;
 }
 
 impl<'a, T, A, D> Aggregate<'a, T, PipelineScope<A, T>, D> for Handle<'a, T, PipelineScope<A, T>, D> 
@@ -195,6 +216,35 @@ where
             phantom_data: PhantomData
         }
     }
+
+    fn window_aggregate<Di: Default + 'static, R: ExchangeData, K, H, F, E>(&self, window: Window, hash: H, fold: F, emit: E, name: &str) -> Handle<'a, T, PipelineScope<A, T>, R>
+    where
+        T: EpochTimestamp,
+        K: Clone + Hash + Eq + 'static,
+        H: Fn(&D) -> K + 'static,
+        F: Fn(&K, D, &mut Di) + 'static,
+        E: Fn(K, WindowBounds, Di) -> R + 'static
+    {
+        let prev_id = self.id;
+        let next_id = self.allocate_new_operator_id();
+        let node = WindowAggregateNode::<_, _, _, _, _, _, _, PipelineScope<A, T>>::new(prev_id, window, hash, fold, emit);
+        self.graph.borrow_mut().operators.insert(next_id, ExchangeComputeNode(Box::new(node)));
+        let mut duplicate = 0;
+        let mut unique_name = name.to_owned();
+        while self.graph.borrow().op_name_local_id_mapping.contains_key(&unique_name) {
+            duplicate += 1;
+            unique_name = format!("{}_{}", name, duplicate);
+        }
+        self.graph.borrow_mut().op_name_local_id_mapping.insert(unique_name.clone(), next_id);
+        self.graph.borrow_mut().op_local_id_name_mapping.insert(next_id, unique_name);
+        Handle {
+            graph: self.graph,
+            counter: self.counter,
+            id: next_id,
+            phantom_scope: PhantomData,
+            phantom_data: PhantomData
+        }
+    }
 }
 
 impl<'a, T, S, D> AggregateLocal<'a, T, S, D> for Handle<'a, T, S, D>
@@ -312,4 +362,33 @@ where
             phantom_data: PhantomData
         }
     }
-}
\ No newline at end of file
+
+    fn window_aggregate_local<Di: Default + 'static, R: Data, K, H, F, E>(&self, window: Window, hash: H, fold: F, emit: E, name: &str) -> Handle<'a, T, S, R>
+    where
+        T: EpochTimestamp,
+        K: Clone + Hash + Eq + 'static,
+        H: Fn(&D) -> K + 'static,
+        F: Fn(&K, D, &mut Di) + 'static,
+        E: Fn(K, WindowBounds, Di) -> R + 'static
+    {
+        let prev_id = self.id;
+        let next_id = self.allocate_new_operator_id();
+        let node = WindowAggregateNode::<_, _, _, _, _, _, _, S>::new(prev_id, window, hash, fold, emit);
+        self.graph.borrow_mut().operators.insert(next_id, LocalComputeNode(Box::new(node)));
+        let mut duplicate = 0;
+        let mut unique_name = name.to_owned();
+        while self.graph.borrow().op_name_local_id_mapping.contains_key(&unique_name) {
+            duplicate += 1;
+            unique_name = format!("{}_{}", name, duplicate);
+        }
+        self.graph.borrow_mut().op_name_local_id_mapping.insert(unique_name.clone(), next_id);
+        self.graph.borrow_mut().op_local_id_name_mapping.insert(next_id, unique_name);
+        Handle {
+            graph: self.graph,
+            counter: self.counter,
+            id: next_id,
+            phantom_scope: PhantomData,
+            phantom_data: PhantomData
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use std::hash::Hash;
 use std::marker::PhantomData;
+use std::time::Duration;
 
 use timely::communication::RelayConnectAllocate;
 use timely::{Data, ExchangeData};
@@ -9,7 +10,7 @@ use timely::progress::timestamp::Refines;
 use timely::order::TotalOrder;
 
 use crate::graph::{GraphNode::ExchangeComputeNode, GraphNode::LocalComputeNode};
-use crate::node::{JoinNode, TimestampJoinNode, SingleItemJoinNode, TimestampSingleItemJoinNode};
+use crate::node::{JoinNode, TimestampJoinNode, SingleItemJoinNode, TimestampSingleItemJoinNode, OuterJoinNode, OuterJoinMode};
 use crate::static_timely::timely_static_pipeline_scope::PipelineScope;
 
 use super::Handle;
@@ -43,6 +44,14 @@ where
         K: Eq + Hash + 'static,
         H1: Fn(&D) -> K + 'static,
         H2: Fn(&D2) -> K + 'static;
+
+    /// Join the single items with the same key, the items unmatched after `timeout`
+    /// (or once the other stream completes) are emitted with `None` on the other side according to `mode`
+    fn outer_join<D2: ExchangeData, K, H1, H2>(&self, right_handle: &Handle<'a, T, S, D2>, hash_left: H1, hash_right: H2, mode: OuterJoinMode, timeout: Duration, name: &str) -> Handle<'a, T, S, (Option<D>, Option<D2>)>
+    where
+        K: Clone + Eq + Hash + 'static,
+        H1: Fn(&D) -> K + 'static,
+        H2: Fn(&D2) -> K + 'static;
 }
 
 pub trait JoinLocal<'a, T, S, D>
@@ -74,6 +83,14 @@ where
         K: Eq + Hash + 'static,
         H1: Fn(&D) -> K + 'static,
         H2: Fn(&D2) -> K + 'static;
+
+    /// Join the single items with the same key, the items unmatched after `timeout`
+    /// (or once the other stream completes) are emitted with `None` on the other side according to `mode`
+    fn outer_join_local<D2: Data, K, H1, H2>(&self, right_handle: &Handle<'a, T, S, D2>, hash_left: H1, hash_right: H2, mode: OuterJoinMode, timeout: Duration, name: &str) -> Handle<'a, T, S, (Option<D>, Option<D2>)>
+    where
+        K: Clone + Eq + Hash + 'static,
+        H1: Fn(&D) -> K + 'static,
+        H2: Fn(&D2) -> K + 'static;
 }
 
 impl<'a, T, A, D> Join<'a, T, PipelineScope<A, T>, D> for Handle<'a, T, PipelineScope<A, T>, D> 
@@ -213,6 +230,41 @@ where
             phantom_data: PhantomData
         }
     }
+
+    fn outer_join<D2: ExchangeData, K, H1, H2>(&self, right_handle: &Handle<'a, T, PipelineScope<A, T>, D2>, hash_left: H1, hash_right: H2, mode: OuterJoinMode, timeout: Duration, name: &str) -> Handle<'a, T, PipelineScope<A, T>, (Option<D>, Option<D2>)>
+    where
+        K: Clone + Eq + Hash + 'static,
+        H1: Fn(&D) -> K + 'static,
+        H2: Fn(&D2) -> K + 'static
+    {
+        let left_id = self.id;
+        let right_id = right_handle.id;
+        let next_id = self.allocate_new_operator_id();
+        let node = OuterJoinNode::<_, _, _, _, _, PipelineScope<A, T>>::new(
+            left_id,
+            right_id,
+            hash_left,
+            hash_right,
+            mode,
+            timeout
+        );
+        self.graph.borrow_mut().operators.insert(next_id, ExchangeComputeNode(Box::new(node)));
+        let mut duplicate = 0;
+        let mut unique_name = name.to_owned();
+        while self.graph.borrow().op_name_local_id_mapping.contains_key(&unique_name) {
+            duplicate += 1;
+            unique_name = format!("{}_{}", name, duplicate);
+        }
+        self.graph.borrow_mut().op_name_local_id_mapping.insert(unique_name.to_owned(), next_id);
+        self.graph.borrow_mut().op_local_id_name_mapping.insert(next_id, unique_name);
+        Handle {
+            graph: self.graph,
+            counter: self.counter,
+            id: next_id,
+            phantom_scope: PhantomData,
+            phantom_data: PhantomData
+        }
+    }
 }
 
 impl<'a, T, S, D> JoinLocal<'a, T, S, D> for Handle<'a, T, S, D> 
@@ -352,4 +404,39 @@ where
             phantom_data: PhantomData
         }
     }
-}
\ No newline at end of file
+
+    fn outer_join_local<D2: Data, K, H1, H2>(&self, right_handle: &Handle<'a, T, S, D2>, hash_left: H1, hash_right: H2, mode: OuterJoinMode, timeout: Duration, name: &str) -> Handle<'a, T, S, (Option<D>, Option<D2>)>
+    where
+        K: Clone + Eq + Hash + 'static,
+        H1: Fn(&D) -> K + 'static,
+        H2: Fn(&D2) -> K + 'static
+    {
+        let left_id = self.id;
+        let right_id = right_handle.id;
+        let next_id = self.allocate_new_operator_id();
+        let node = OuterJoinNode::<_, _, _, _, _, S>::new(
+            left_id,
+            right_id,
+            hash_left,
+            hash_right,
+            mode,
+            timeout
+        );
+        self.graph.borrow_mut().operators.insert(next_id, LocalComputeNode(Box::new(node)));
+        let mut duplicate = 0;
+        let mut unique_name = name.to_owned();
+        while self.graph.borrow().op_name_local_id_mapping.contains_key(&unique_name) {
+            duplicate += 1;
+            unique_name = format!("{}_{}", name, duplicate);
+        }
+        self.graph.borrow_mut().op_name_local_id_mapping.insert(unique_name.to_owned(), next_id);
+        self.graph.borrow_mut().op_local_id_name_mapping.insert(next_id, unique_name);
+        Handle {
+            graph: self.graph,
+            counter: self.counter,
+            id: next_id,
+            phantom_scope: PhantomData,
+            phantom_data: PhantomData
+        }
+    }
+}
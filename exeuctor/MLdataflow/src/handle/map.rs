@@ -7,12 +7,15 @@ use timely::progress::Timestamp;
 use timely::progress::timestamp::Refines;
 use timely::order::TotalOrder;
 
-use crate::node::{MapNode, FlatMapNode, BatchedMapNode, BufferedMapNode};
-use crate::graph::{GraphNode::ExchangeComputeNode, GraphNode::LocalComputeNode};
+use crate::node::{MapNode, FlatMapNode, BatchedMapNode, BufferedMapNode, TryMapNode, TryBatchedMapNode, DeadLetter, BatchSizeMismatch, RetryPolicy};
+use crate::graph::{GraphNode, GraphNode::ExchangeComputeNode, GraphNode::LocalComputeNode};
 use crate::static_timely::timely_static_pipeline_scope::PipelineScope;
 
 use super::Handle;
 
+/// Output stream and dead-letter stream of a fallible operator
+pub type TryHandles<'a, T, S, D, D2, E> = (Handle<'a, T, S, D2>, Handle<'a, T, S, DeadLetter<D, E>>);
+
 pub trait Map<'a, T, S, D>
 where
     T: Timestamp + Refines<()> + TotalOrder,
@@ -22,7 +25,21 @@ where
     fn map<D2: ExchangeData, L: FnMut(D) -> D2 + 'static>(&self, logic: L, name: &str) -> Handle<'a, T, S, D2>;
     fn flat_map<I: IntoIterator + 'static, L: FnMut(D) -> I + 'static>(&self, logic: L, name: &str) -> Handle<'a, T, S, I::Item> where I::Item: ExchangeData;
     fn batch_map<D2: ExchangeData, I2: IntoIterator<Item=D2> + 'static, L: FnMut(Vec<D>) -> I2 + 'static>(&self, logic: L, name: &str) -> Handle<'a, T, S, D2>;    
+    /// Batch map with dynamic batches of the inputs with the same bucket key (e.g., the input size),
+    /// the batches are closed on `batch_size` or `max_batch_wait` in the operator config
+    fn bucketed_batch_map<D2: ExchangeData, I2: IntoIterator<Item=D2> + 'static, L: FnMut(Vec<D>) -> I2 + 'static, B: Fn(&D) -> usize + 'static>(&self, logic: L, bucket: B, name: &str) -> Handle<'a, T, S, D2>;
     fn buffered_map<D2: ExchangeData, L: FnMut(D) -> D2 + 'static>(&self, logic: L,  name: &str) -> Handle<'a, T, S, D2>;
+    /// Map, routing the inputs whose logic fails to the returned dead-letter stream
+    fn try_map<D2: ExchangeData, E: ExchangeData, L: FnMut(&D) -> Result<D2, E> + 'static>(&self, logic: L, name: &str) -> TryHandles<'a, T, S, D, D2, E> where D: ExchangeData;
+    /// Flat map, routing the inputs whose logic fails to the returned dead-letter stream
+    fn try_flat_map<I: IntoIterator + 'static, E: ExchangeData, L: FnMut(&D) -> Result<I, E> + 'static>(&self, logic: L, name: &str) -> TryHandles<'a, T, S, D, I::Item, E> where D: ExchangeData, I::Item: ExchangeData;
+    /// `try_map`, re-invoking the logic on failures according to the retry policy
+    fn retry_map<D2: ExchangeData, E: ExchangeData, L: FnMut(&D) -> Result<D2, E> + 'static>(&self, logic: L, retry: RetryPolicy<E>, name: &str) -> TryHandles<'a, T, S, D, D2, E> where D: ExchangeData;
+    /// `try_flat_map`, re-invoking the logic on failures according to the retry policy
+    fn retry_flat_map<I: IntoIterator + 'static, E: ExchangeData, L: FnMut(&D) -> Result<I, E> + 'static>(&self, logic: L, retry: RetryPolicy<E>, name: &str) -> TryHandles<'a, T, S, D, I::Item, E> where D: ExchangeData, I::Item: ExchangeData;
+    /// Batch map, re-invoking the logic on failed batches according to the retry policy,
+    /// the batches still failing (or not yielding one output per input) are routed to the returned dead-letter stream
+    fn retry_batch_map<D2: ExchangeData, I2: IntoIterator<Item=D2> + 'static, E: ExchangeData + From<BatchSizeMismatch>, L: FnMut(&[D]) -> Result<I2, E> + 'static>(&self, logic: L, retry: RetryPolicy<E>, name: &str) -> TryHandles<'a, T, S, D, D2, E> where D: ExchangeData;
 }
 
 pub trait MapLocal<'a, T, S, D>
@@ -34,7 +51,13 @@ where
     fn map_local<D2: Data, L: FnMut(D) -> D2 + 'static>(&self, logic: L, name: &str) -> Handle<'a, T, S, D2>;
     fn flat_map_local<I: IntoIterator + 'static, L: FnMut(D) -> I + 'static>(&self, logic: L, name: &str) -> Handle<'a, T, S, I::Item> where I::Item: Data;
     fn batch_map_local<D2: Data, I2: IntoIterator<Item=D2> + 'static, L: FnMut(Vec<D>) -> I2 + 'static>(&self, logic: L, name: &str) -> Handle<'a, T, S, D2>;
+    fn bucketed_batch_map_local<D2: Data, I2: IntoIterator<Item=D2> + 'static, L: FnMut(Vec<D>) -> I2 + 'static, B: Fn(&D) -> usize + 'static>(&self, logic: L, bucket: B, name: &str) -> Handle<'a, T, S, D2>;
     fn buffered_map_local<D2: Data, L: FnMut(D) -> D2 + 'static>(&self, logic: L,  name: &str) -> Handle<'a, T, S, D2>;
+    fn try_map_local<D2: Data, E: Data, L: FnMut(&D) -> Result<D2, E> + 'static>(&self, logic: L, name: &str) -> TryHandles<'a, T, S, D, D2, E>;
+    fn try_flat_map_local<I: IntoIterator + 'static, E: Data, L: FnMut(&D) -> Result<I, E> + 'static>(&self, logic: L, name: &str) -> TryHandles<'a, T, S, D, I::Item, E> where I::Item: Data;
+    fn retry_map_local<D2: Data, E: Data, L: FnMut(&D) -> Result<D2, E> + 'static>(&self, logic: L, retry: RetryPolicy<E>, name: &str) -> TryHandles<'a, T, S, D, D2, E>;
+    fn retry_flat_map_local<I: IntoIterator + 'static, E: Data, L: FnMut(&D) -> Result<I, E> + 'static>(&self, logic: L, retry: RetryPolicy<E>, name: &str) -> TryHandles<'a, T, S, D, I::Item, E> where I::Item: Data;
+    fn retry_batch_map_local<D2: Data, I2: IntoIterator<Item=D2> + 'static, E: Data + From<BatchSizeMismatch>, L: FnMut(&[D]) -> Result<I2, E> + 'static>(&self, logic: L, retry: RetryPolicy<E>, name: &str) -> TryHandles<'a, T, S, D, D2, E>;
 }
 
 impl<'a, T, A, D> Map<'a, T, PipelineScope<A, T>, D> for Handle<'a, T, PipelineScope<A, T>, D> 
@@ -109,6 +132,28 @@ where
         }
     }
 
+    fn bucketed_batch_map<D2: ExchangeData, I2: IntoIterator<Item=D2> + 'static, L: FnMut(Vec<D>) -> I2 + 'static, B: Fn(&D) -> usize + 'static>(&self, logic: L, bucket: B, name: &str) -> Handle<'a, T, PipelineScope<A, T>, D2> {
+        let prev_id = self.id;
+        let next_id = self.allocate_new_operator_id();
+        let node = BatchedMapNode::<_, _, _, _, PipelineScope<A, T>>::new_bucketed(prev_id, logic, bucket);
+        self.graph.borrow_mut().operators.insert(next_id, ExchangeComputeNode(Box::new(node)));
+        let mut duplicate = 0;
+        let mut unique_name = name.to_owned();
+        while self.graph.borrow().op_name_local_id_mapping.contains_key(&unique_name) {
+            duplicate += 1;
+            unique_name = format!("{}_{}", name, duplicate);
+        }
+        self.graph.borrow_mut().op_name_local_id_mapping.insert(unique_name.to_owned(), next_id);
+        self.graph.borrow_mut().op_local_id_name_mapping.insert(next_id, unique_name);
+        Handle {
+            graph: self.graph,
+            counter: self.counter,
+            id: next_id,
+            phantom_scope: PhantomData,
+            phantom_data: PhantomData
+        }
+    }
+
     fn buffered_map<D2: ExchangeData, L: FnMut(D) -> D2 + 'static>(&self, logic: L, name: &str) -> Handle<'a, T, PipelineScope<A, T>, D2> {
         let prev_id = self.id;
         let next_id = self.allocate_new_operator_id();
@@ -130,6 +175,36 @@ where
             phantom_data: PhantomData
         }
     }
+
+    fn try_map<D2: ExchangeData, E: ExchangeData, L: FnMut(&D) -> Result<D2, E> + 'static>(&self, logic: L, name: &str) -> TryHandles<'a, T, PipelineScope<A, T>, D, D2, E> where D: ExchangeData {
+        self.retry_map(logic, RetryPolicy::no_retry(), name)
+    }
+
+    fn try_flat_map<I: IntoIterator + 'static, E: ExchangeData, L: FnMut(&D) -> Result<I, E> + 'static>(&self, logic: L, name: &str) -> TryHandles<'a, T, PipelineScope<A, T>, D, I::Item, E> where D: ExchangeData, I::Item: ExchangeData {
+        self.retry_flat_map(logic, RetryPolicy::no_retry(), name)
+    }
+
+    fn retry_map<D2: ExchangeData, E: ExchangeData, L: FnMut(&D) -> Result<D2, E> + 'static>(&self, mut logic: L, retry: RetryPolicy<E>, name: &str) -> TryHandles<'a, T, PipelineScope<A, T>, D, D2, E> where D: ExchangeData {
+        self.retry_flat_map(move |x: &D| logic(x).map(Some), retry, name)
+    }
+
+    fn retry_flat_map<I: IntoIterator + 'static, E: ExchangeData, L: FnMut(&D) -> Result<I, E> + 'static>(&self, logic: L, retry: RetryPolicy<E>, name: &str) -> TryHandles<'a, T, PipelineScope<A, T>, D, I::Item, E> where D: ExchangeData, I::Item: ExchangeData {
+        let prev_id = self.id;
+        self.add_fallible_operator(name, move |id, unique_name| {
+            let node = TryMapNode::<_, _, _, _, PipelineScope<A, T>>::new(prev_id, unique_name, logic, retry);
+            let dead_letter_node = node.dead_letter_node(id);
+            (ExchangeComputeNode(Box::new(node)), ExchangeComputeNode(Box::new(dead_letter_node)))
+        })
+    }
+
+    fn retry_batch_map<D2: ExchangeData, I2: IntoIterator<Item=D2> + 'static, E: ExchangeData + From<BatchSizeMismatch>, L: FnMut(&[D]) -> Result<I2, E> + 'static>(&self, logic: L, retry: RetryPolicy<E>, name: &str) -> TryHandles<'a, T, PipelineScope<A, T>, D, D2, E> where D: ExchangeData {
+        let prev_id = self.id;
+        self.add_fallible_operator(name, move |id, unique_name| {
+            let node = TryBatchedMapNode::<_, _, _, PipelineScope<A, T>>::new(prev_id, unique_name, logic, retry);
+            let dead_letter_node = node.dead_letter_node(id);
+            (ExchangeComputeNode(Box::new(node)), ExchangeComputeNode(Box::new(dead_letter_node)))
+        })
+    }
 }
 
 impl<'a, T, S, D> MapLocal<'a, T, S, D> for Handle<'a, T, S, D> 
@@ -205,6 +280,28 @@ where
         }
     }
 
+    fn bucketed_batch_map_local<D2: Data, I2: IntoIterator<Item=D2> + 'static, L: FnMut(Vec<D>) -> I2 + 'static, B: Fn(&D) -> usize + 'static>(&self, logic: L, bucket: B, name: &str) -> Handle<'a, T, S, D2> {
+        let prev_id = self.id;
+        let next_id = self.allocate_new_operator_id();
+        let node = BatchedMapNode::<_, _, _, _, S>::new_bucketed(prev_id, logic, bucket);
+        self.graph.borrow_mut().operators.insert(next_id, LocalComputeNode(Box::new(node)));
+        let mut duplicate = 0;
+        let mut unique_name = name.to_owned();
+        while self.graph.borrow().op_name_local_id_mapping.contains_key(&unique_name) {
+            duplicate += 1;
+            unique_name = format!("{}_{}", name, duplicate);
+        }
+        self.graph.borrow_mut().op_name_local_id_mapping.insert(unique_name.to_owned(), next_id);
+        self.graph.borrow_mut().op_local_id_name_mapping.insert(next_id, unique_name);
+        Handle {
+            graph: self.graph,
+            counter: self.counter,
+            id: next_id,
+            phantom_scope: PhantomData,
+            phantom_data: PhantomData
+        }
+    }
+
     fn buffered_map_local<D2: Data, L: FnMut(D) -> D2 + 'static>(&self, logic: L,  name: &str) -> Handle<'a, T, S, D2> {
         let prev_id = self.id;
         let next_id = self.allocate_new_operator_id();
@@ -226,4 +323,77 @@ where
             phantom_data: PhantomData
         }
     }
-}
\ No newline at end of file
+
+    fn try_map_local<D2: Data, E: Data, L: FnMut(&D) -> Result<D2, E> + 'static>(&self, logic: L, name: &str) -> TryHandles<'a, T, S, D, D2, E> {
+        self.retry_map_local(logic, RetryPolicy::no_retry(), name)
+    }
+
+    fn try_flat_map_local<I: IntoIterator + 'static, E: Data, L: FnMut(&D) -> Result<I, E> + 'static>(&self, logic: L, name: &str) -> TryHandles<'a, T, S, D, I::Item, E> where I::Item: Data {
+        self.retry_flat_map_local(logic, RetryPolicy::no_retry(), name)
+    }
+
+    fn retry_map_local<D2: Data, E: Data, L: FnMut(&D) -> Result<D2, E> + 'static>(&self, mut logic: L, retry: RetryPolicy<E>, name: &str) -> TryHandles<'a, T, S, D, D2, E> {
+        self.retry_flat_map_local(move |x: &D| logic(x).map(Some), retry, name)
+    }
+
+    fn retry_flat_map_local<I: IntoIterator + 'static, E: Data, L: FnMut(&D) -> Result<I, E> + 'static>(&self, logic: L, retry: RetryPolicy<E>, name: &str) -> TryHandles<'a, T, S, D, I::Item, E> where I::Item: Data {
+        let prev_id = self.id;
+        self.add_fallible_operator(name, move |id, unique_name| {
+            let node = TryMapNode::<_, _, _, _, S>::new(prev_id, unique_name, logic, retry);
+            let dead_letter_node = node.dead_letter_node(id);
+            (LocalComputeNode(Box::new(node)), LocalComputeNode(Box::new(dead_letter_node)))
+        })
+    }
+
+    fn retry_batch_map_local<D2: Data, I2: IntoIterator<Item=D2> + 'static, E: Data + From<BatchSizeMismatch>, L: FnMut(&[D]) -> Result<I2, E> + 'static>(&self, logic: L, retry: RetryPolicy<E>, name: &str) -> TryHandles<'a, T, S, D, D2, E> {
+        let prev_id = self.id;
+        self.add_fallible_operator(name, move |id, unique_name| {
+            let node = TryBatchedMapNode::<_, _, _, S>::new(prev_id, unique_name, logic, retry);
+            let dead_letter_node = node.dead_letter_node(id);
+            (LocalComputeNode(Box::new(node)), LocalComputeNode(Box::new(dead_letter_node)))
+        })
+    }
+}
+
+impl<'a, T, S, D> Handle<'a, T, S, D>
+where
+    T: Timestamp + Refines<()> + TotalOrder,
+    S: Scope<Timestamp = T> + 'static,
+    D: Data
+{
+    /// Declare a fallible operator together with the operator emitting its dead letters,
+    /// `build` is given the id and the deduplicated name of the fallible operator
+    fn add_fallible_operator<D2: Data, E: Data, B>(&self, name: &str, build: B) -> TryHandles<'a, T, S, D, D2, E>
+    where
+        B: FnOnce(usize, String) -> (GraphNode, GraphNode)
+    {
+        let next_id = self.allocate_new_operator_id();
+        let unique_name = self.unique_name(name);
+        let (node, dead_letter_node) = build(next_id, unique_name.clone());
+        self.graph.borrow_mut().operators.insert(next_id, node);
+        self.graph.borrow_mut().op_name_local_id_mapping.insert(unique_name.to_owned(), next_id);
+        self.graph.borrow_mut().op_local_id_name_mapping.insert(next_id, unique_name.to_owned());
+
+        let dead_letter_id = self.allocate_new_operator_id();
+        let dead_letter_name = self.unique_name(&format!("{}_dead_letter", unique_name));
+        self.graph.borrow_mut().operators.insert(dead_letter_id, dead_letter_node);
+        self.graph.borrow_mut().op_name_local_id_mapping.insert(dead_letter_name.to_owned(), dead_letter_id);
+        self.graph.borrow_mut().op_local_id_name_mapping.insert(dead_letter_id, dead_letter_name);
+        self.graph.borrow_mut().dead_letter_producers.insert(dead_letter_id, next_id);
+        let handle = Handle {
+            graph: self.graph,
+            counter: self.counter,
+            id: next_id,
+            phantom_scope: PhantomData,
+            phantom_data: PhantomData
+        };
+        let dead_letters = Handle {
+            graph: self.graph,
+            counter: self.counter,
+            id: dead_letter_id,
+            phantom_scope: PhantomData,
+            phantom_data: PhantomData
+        };
+        (handle, dead_letters)
+    }
+}
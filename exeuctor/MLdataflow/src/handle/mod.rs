@@ -14,6 +14,8 @@ pub mod filter;
 pub mod inspect;
 pub mod exchange;
 pub mod union;
+pub mod respond;
+pub mod sink;
 
 pub use map::{Map, MapLocal};
 pub use join::{Join, JoinLocal};
@@ -22,6 +24,8 @@ pub use filter::{Filter, FilterLocal};
 pub use inspect::{Inspect, InspectLocal};
 pub use exchange::Exchange;
 pub use union::{Union, UnionLocal};
+pub use respond::Respond;
+pub use sink::{Sink, SinkLocal};
 
 use crate::graph::ComputeGraph;
 
@@ -53,6 +57,17 @@ where
         *self.counter.borrow_mut() += 1;
         *self.counter.borrow() - 1
     }
+
+    /// `name` suffixed with a counter if an operator with the same name is already declared
+    fn unique_name(&self, name: &str) -> String {
+        let mut duplicate = 0;
+        let mut unique_name = name.to_owned();
+        while self.graph.borrow().op_name_local_id_mapping.contains_key(&unique_name) {
+            duplicate += 1;
+            unique_name = format!("{}_{}", name, duplicate);
+        }
+        unique_name
+    }
 }
 
 impl<'a, T, S, D> Clone for Handle<'a, T, S, D>
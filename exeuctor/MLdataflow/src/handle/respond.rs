@@ -0,0 +1,33 @@
+use timely::communication::RelayConnectAllocate;
+use timely::ExchangeData;
+use timely::progress::Timestamp;
+use timely::progress::timestamp::Refines;
+use timely::order::TotalOrder;
+
+use crate::input::RequestId;
+use crate::serving::RequestEndpoint;
+use crate::static_timely::timely_static_pipeline_scope::PipelineScope;
+
+use super::{Handle, Inspect};
+
+pub trait Respond<'a, T, A, R>
+where
+    T: Timestamp + Refines<()> + TotalOrder,
+    A: RelayConnectAllocate + 'static,
+    R: ExchangeData
+{
+    /// Terminal operator answering the requests submitted through the endpoint's clients with the correlated outputs
+    fn respond<D: Send + 'static>(&self, endpoint: &RequestEndpoint<D, R>, name: &str) -> Handle<'a, T, PipelineScope<A, T>, (RequestId, R)>;
+}
+
+impl<'a, T, A, R> Respond<'a, T, A, R> for Handle<'a, T, PipelineScope<A, T>, (RequestId, R)>
+where
+    T: Timestamp + Refines<()> + TotalOrder,
+    A: RelayConnectAllocate + 'static,
+    R: ExchangeData
+{
+    fn respond<D: Send + 'static>(&self, endpoint: &RequestEndpoint<D, R>, name: &str) -> Handle<'a, T, PipelineScope<A, T>, (RequestId, R)> {
+        let endpoint = endpoint.clone();
+        self.inspect(move |(request_id, output)| endpoint.resolve(*request_id, output.clone()), name)
+    }
+}
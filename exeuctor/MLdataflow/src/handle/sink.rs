@@ -0,0 +1,105 @@
+use std::marker::PhantomData;
+use std::path::Path;
+
+use serde::Serialize;
+use timely::communication::RelayConnectAllocate;
+use timely::{Data, ExchangeData};
+use timely::dataflow::Scope;
+use timely::progress::Timestamp;
+use timely::progress::timestamp::Refines;
+use timely::order::TotalOrder;
+
+use crate::graph::{GraphNode::ExchangeComputeNode, GraphNode::LocalComputeNode};
+use crate::node::{SinkFormat, SinkNode};
+use crate::static_timely::timely_static_pipeline_scope::PipelineScope;
+
+use super::Handle;
+
+/// Persist a data stream to files under `dir`, one set of files per timely worker (see `FileSink`)
+pub trait Sink<'a, T, S, D>
+where
+    T: Timestamp + Refines<()> + TotalOrder,
+    S: Scope<Timestamp = T> + 'static,
+    D: ExchangeData + Serialize
+{
+    fn sink<P: AsRef<Path>>(&self, dir: P, format: SinkFormat, name: &str) -> Handle<'a, T, S, D>;
+
+    fn sink_jsonl<P: AsRef<Path>>(&self, dir: P, name: &str) -> Handle<'a, T, S, D> {
+        self.sink(dir, SinkFormat::Jsonl, name)
+    }
+
+    fn sink_csv<P: AsRef<Path>>(&self, dir: P, name: &str) -> Handle<'a, T, S, D> {
+        self.sink(dir, SinkFormat::Csv, name)
+    }
+
+    fn sink_binary<P: AsRef<Path>>(&self, dir: P, name: &str) -> Handle<'a, T, S, D> {
+        self.sink(dir, SinkFormat::Binary, name)
+    }
+}
+
+pub trait SinkLocal<'a, T, S, D>
+where
+    T: Timestamp + Refines<()> + TotalOrder,
+    S: Scope<Timestamp = T> + 'static,
+    D: Data + Serialize
+{
+    fn sink_local<P: AsRef<Path>>(&self, dir: P, format: SinkFormat, name: &str) -> Handle<'a, T, S, D>;
+}
+
+impl<'a, T, A, D> Sink<'a, T, PipelineScope<A, T>, D> for Handle<'a, T, PipelineScope<A, T>, D> 
+where
+    T: Timestamp + Refines<()> + TotalOrder,
+    A: RelayConnectAllocate + 'static,
+    D: ExchangeData + Serialize
+{
+    fn sink<P: AsRef<Path>>(&self, dir: P, format: SinkFormat, name: &str) -> Handle<'a, T, PipelineScope<A, T>, D> {
+        let prev_id = self.id;
+        let next_id = self.allocate_new_operator_id();
+        let mut duplicate = 0;
+        let mut unique_name = name.to_owned();
+        while self.graph.borrow().op_name_local_id_mapping.contains_key(&unique_name) {
+            duplicate += 1;
+            unique_name = format!("{}_{}", name, duplicate);
+        }
+        let node = SinkNode::<D, PipelineScope<A, T>>::new(prev_id, dir.as_ref().to_owned(), unique_name.to_owned(), format);
+        self.graph.borrow_mut().operators.insert(next_id, ExchangeComputeNode(Box::new(node)));
+        self.graph.borrow_mut().op_name_local_id_mapping.insert(unique_name.to_owned(), next_id);
+        self.graph.borrow_mut().op_local_id_name_mapping.insert(next_id, unique_name);
+        Handle {
+            graph: self.graph,
+            counter: self.counter,
+            id: next_id,
+            phantom_scope: PhantomData,
+            phantom_data: PhantomData
+        }
+    }
+}
+
+impl<'a, T, S, D> SinkLocal<'a, T, S, D> for Handle<'a, T, S, D> 
+where
+    T: Timestamp + Refines<()> + TotalOrder,
+    S: Scope<Timestamp = T> + 'static,
+    D: Data + Serialize
+{
+    fn sink_local<P: AsRef<Path>>(&self, dir: P, format: SinkFormat, name: &str) -> Handle<'a, T, S, D> {
+        let prev_id = self.id;
+        let next_id = self.allocate_new_operator_id();
+        let mut duplicate = 0;
+        let mut unique_name = name.to_owned();
+        while self.graph.borrow().op_name_local_id_mapping.contains_key(&unique_name) {
+            duplicate += 1;
+            unique_name = format!("{}_{}", name, duplicate);
+        }
+        let node = SinkNode::<D, S>::new(prev_id, dir.as_ref().to_owned(), unique_name.to_owned(), format);
+        self.graph.borrow_mut().operators.insert(next_id, LocalComputeNode(Box::new(node)));
+        self.graph.borrow_mut().op_name_local_id_mapping.insert(unique_name.to_owned(), next_id);
+        self.graph.borrow_mut().op_local_id_name_mapping.insert(next_id, unique_name);        
+        Handle {
+            graph: self.graph,
+            counter: self.counter,
+            id: next_id,
+            phantom_scope: PhantomData,
+            phantom_data: PhantomData
+        }
+    }
+}
@@ -0,0 +1,324 @@
+//! Arrival processes pacing the requests emitted by the input sources
+use std::any::Any;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use rand::SeedableRng;
+use rand::distributions::Distribution;
+use rand::rngs::StdRng;
+use serde::{Serialize, Deserialize};
+use statrs::distribution::{Exp, Gamma};
+
+use crate::config::ConfigError;
+
+/// Arrival model of the requests emitted by an input source (per timely worker).
+/// Except for `Uniform`, arrivals are open-loop: they are scheduled independently of
+/// how fast the requests are emitted, so a source falling behind emits the due requests back-to-back.
+/// Random models are seeded (combined with the worker index) for reproducibility.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ArrivalModel {
+    /// Fixed interval since the last request (same as `request_rate`)
+    Uniform { rate: f64 },
+    /// Exponentially distributed inter-arrival times
+    Poisson {
+        rate: f64,
+        #[serde(default)]
+        seed: u64,
+    },
+    /// Gamma distributed inter-arrival times with the given coefficient of variation,
+    /// arrivals are bursty if cv > 1
+    Gamma {
+        rate: f64,
+        cv: f64,
+        #[serde(default)]
+        seed: u64,
+    },
+    /// Markov-modulated Poisson process, cycling through the states,
+    /// each with a rate and a mean (exponentially distributed) duration in seconds
+    Mmpp {
+        rates: Vec<f64>,
+        mean_durations_secs: Vec<f64>,
+        #[serde(default)]
+        seed: u64,
+    },
+    /// Piecewise constant rates, each `(start_secs, rate)` applies from its start (since the first request)
+    Step { schedule: Vec<(f64, f64)> },
+    /// Rate changing linearly from `start_rate` to `end_rate` over `duration_secs`, then stays at `end_rate`
+    Ramp { start_rate: f64, end_rate: f64, duration_secs: f64 },
+    /// Replay the arrival times (in seconds, one per line) of a trace file, relative to its first arrival,
+    /// the trace is repeated once exhausted
+    Trace { path: PathBuf },
+}
+
+impl ArrivalModel {
+    /// Check the parameters of the model, and that the trace file can be replayed
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        self.check().map_err(ConfigError::Invalid)
+    }
+
+    fn check(&self) -> Result<(), String> {
+        let positive = |name: &str, val: f64| {
+            if val.is_finite() && val > 0.0 { Ok(()) }
+            else { Err(format!("{} of the arrival model must be positive, got {}", name, val)) }
+        };
+        match self {
+            ArrivalModel::Uniform { rate } | ArrivalModel::Poisson { rate, .. } => positive("rate", *rate),
+            ArrivalModel::Gamma { rate, cv, .. } => {
+                positive("rate", *rate)?;
+                positive("cv", *cv)
+            },
+            ArrivalModel::Mmpp { rates, mean_durations_secs, .. } => {
+                if rates.is_empty() || rates.len() != mean_durations_secs.len() {
+                    return Err(String::from("MMPP requires a rate and a mean duration for each state"));
+                }
+                rates.iter().try_for_each(|rate| positive("rate", *rate))?;
+                mean_durations_secs.iter().try_for_each(|duration| positive("mean duration", *duration))
+            },
+            ArrivalModel::Step { schedule } => {
+                if schedule.is_empty() {
+                    return Err(String::from("step schedule is empty"));
+                }
+                if schedule.windows(2).any(|steps| steps[0].0 >= steps[1].0) {
+                    return Err(String::from("step schedule must be sorted by start time"));
+                }
+                schedule.iter().try_for_each(|(_, rate)| positive("rate", *rate))
+            },
+            ArrivalModel::Ramp { start_rate, end_rate, duration_secs } => {
+                positive("start rate", *start_rate)?;
+                positive("end rate", *end_rate)?;
+                positive("duration", *duration_secs)
+            },
+            ArrivalModel::Trace { path } => read_trace(path).map(|_| ()),
+        }
+    }
+}
+
+/// Schedules the arrival times (in nanoseconds) of the requests following an arrival model
+pub struct ArrivalProcess {
+    model: ArrivalModel,
+    rng: StdRng,
+    // arrival times of the trace, relative to the first arrival
+    trace: Vec<i64>,
+    // number of requests emitted
+    count: usize,
+    // timestamp of the first request
+    start_timestamp: Option<i64>,
+    // when the next request is due
+    next_arrival: Option<i64>,
+    // current state of the MMPP and when it ends
+    mmpp_state: usize,
+    mmpp_state_end: Option<i64>,
+}
+
+impl ArrivalProcess {
+    pub fn new(model: ArrivalModel, worker_index: usize) -> Self {
+        let seed = match &model {
+            ArrivalModel::Poisson { seed, .. } | ArrivalModel::Gamma { seed, .. } | ArrivalModel::Mmpp { seed, .. } => *seed,
+            _ => 0,
+        };
+        let trace = match &model {
+            ArrivalModel::Trace { path } => load_trace(path),
+            _ => Vec::new(),
+        };
+        ArrivalProcess {
+            model,
+            rng: StdRng::seed_from_u64(seed.wrapping_add(worker_index as u64)),
+            trace,
+            count: 0,
+            start_timestamp: None,
+            next_arrival: None,
+            mmpp_state: 0,
+            mmpp_state_end: None,
+        }
+    }
+
+    /// Arrival process configured by `arrival_model` (or `request_rate` for uniform arrivals) in the operator config
+    pub fn from_config(config: &HashMap<String, Arc<dyn Any + Send + Sync>>, worker_index: usize) -> Option<Self> {
+        if let Some(model) = config.get("arrival_model").and_then(|val| val.downcast_ref::<ArrivalModel>()) {
+            Some(Self::new(model.clone(), worker_index))
+        }
+        else {
+            let request_rate = config.get("request_rate").and_then(|val| val.downcast_ref::<f64>()).copied();
+            request_rate.map(|rate| Self::new(ArrivalModel::Uniform { rate }, worker_index))
+        }
+    }
+
+    /// Whether the next request is due at `now`
+    pub fn is_due(&self, now: i64) -> bool {
+        !matches!(self.next_arrival, Some(next_arrival) if now < next_arrival)
+    }
+
+    /// A request is emitted at `now`, schedule the next one
+    pub fn advance(&mut self, now: i64) {
+        let start_ts = *self.start_timestamp.get_or_insert(now);
+        // open-loop arrivals are scheduled from the previous arrival rather than the emission
+        let prev_arrival = self.next_arrival.unwrap_or(now);
+        self.count += 1;
+        let next_arrival = match &self.model {
+            ArrivalModel::Uniform { rate } => now + interval(*rate),
+            ArrivalModel::Poisson { rate, .. } => {
+                prev_arrival + sample_secs(&Exp::new(*rate).unwrap(), &mut self.rng)
+            },
+            ArrivalModel::Gamma { rate, cv, .. } => {
+                let shape = 1.0 / (cv * cv);
+                prev_arrival + sample_secs(&Gamma::new(shape, shape * rate).unwrap(), &mut self.rng)
+            },
+            ArrivalModel::Mmpp { .. } => self.next_mmpp_arrival(prev_arrival),
+            ArrivalModel::Step { schedule } => {
+                let elapsed = (prev_arrival - start_ts) as f64 / 1e9_f64;
+                let rate = schedule.iter().rev().find(|(start, _)| *start <= elapsed)
+                    .unwrap_or(&schedule[0]).1;
+                prev_arrival + interval(rate)
+            },
+            ArrivalModel::Ramp { start_rate, end_rate, duration_secs } => {
+                let elapsed = (prev_arrival - start_ts) as f64 / 1e9_f64;
+                let progress = (elapsed / duration_secs).min(1.0);
+                prev_arrival + interval(start_rate + (end_rate - start_rate) * progress)
+            },
+            ArrivalModel::Trace { .. } => {
+                // repeat the trace once exhausted
+                let period = self.trace.last().unwrap() + interval_of_trace(&self.trace);
+                let round = (self.count / self.trace.len()) as i64;
+                start_ts + round * period + self.trace[self.count % self.trace.len()]
+            },
+        };
+        self.next_arrival = Some(next_arrival);
+    }
+
+    fn next_mmpp_arrival(&mut self, prev_arrival: i64) -> i64 {
+        let (rates, mean_durations_secs) = match &self.model {
+            ArrivalModel::Mmpp { rates, mean_durations_secs, .. } => (rates.clone(), mean_durations_secs.clone()),
+            _ => unreachable!(),
+        };
+        let mut state_end = match self.mmpp_state_end {
+            Some(state_end) => state_end,
+            None => prev_arrival + sample_secs(&Exp::new(1.0 / mean_durations_secs[self.mmpp_state]).unwrap(), &mut self.rng),
+        };
+        let mut arrival = prev_arrival;
+        loop {
+            // inter-arrival times are memoryless, resample from the state switch if the state ends first
+            let candidate = arrival + sample_secs(&Exp::new(rates[self.mmpp_state]).unwrap(), &mut self.rng);
+            if candidate <= state_end {
+                self.mmpp_state_end = Some(state_end);
+                return candidate;
+            }
+            arrival = state_end;
+            self.mmpp_state = (self.mmpp_state + 1) % rates.len();
+            state_end += sample_secs(&Exp::new(1.0 / mean_durations_secs[self.mmpp_state]).unwrap(), &mut self.rng);
+        }
+    }
+}
+
+fn interval(rate: f64) -> i64 {
+    (1e9_f64 / rate) as i64
+}
+
+fn sample_secs<D: Distribution<f64>>(distribution: &D, rng: &mut StdRng) -> i64 {
+    (distribution.sample(rng) * 1e9_f64) as i64
+}
+
+// mean inter-arrival time of the trace, separates the repetitions
+fn interval_of_trace(trace: &[i64]) -> i64 {
+    if trace.len() < 2 { 0 }
+    else { trace.last().unwrap() / (trace.len() as i64 - 1) }
+}
+
+// the trace is checked by `ArrivalModel::validate` when the deployment spec is loaded
+fn load_trace(path: &PathBuf) -> Vec<i64> {
+    read_trace(path).unwrap_or_else(|msg| panic!("{}", msg))
+}
+
+fn read_trace(path: &PathBuf) -> Result<Vec<i64>, String> {
+    let file = File::open(path).map_err(|err| format!("failed to open arrival trace {}: {}", path.display(), err))?;
+    let mut arrivals = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|err| format!("failed to read arrival trace {}: {}", path.display(), err))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let secs = line.trim().parse::<f64>().map_err(|_| format!("invalid arrival time {} in trace {}", line, path.display()))?;
+        arrivals.push((secs * 1e9_f64) as i64);
+    }
+    if arrivals.len() < 2 {
+        return Err(format!("arrival trace {} requires at least two arrivals", path.display()));
+    }
+    arrivals.sort();
+    let first = arrivals[0];
+    arrivals.iter_mut().for_each(|arrival| *arrival -= first);
+    Ok(arrivals)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::ConfigError;
+
+    use super::{ArrivalModel, ArrivalProcess};
+
+    fn arrivals(model: ArrivalModel, num: usize) -> Vec<i64> {
+        let mut process = ArrivalProcess::new(model, 0);
+        let mut now = 0;
+        let mut arrivals = Vec::with_capacity(num);
+        for _ in 0..num {
+            // emit as soon as the request is due
+            if let Some(next_arrival) = process.next_arrival {
+                now = next_arrival;
+            }
+            assert!(process.is_due(now));
+            arrivals.push(now);
+            process.advance(now);
+        }
+        arrivals
+    }
+
+    #[test]
+    fn poisson_rate_and_seed() {
+        let model = ArrivalModel::Poisson { rate: 100.0, seed: 7 };
+        let first = arrivals(model.clone(), 10000);
+        assert_eq!(first, arrivals(model, 10000));
+        let rate = 9999.0 / (*first.last().unwrap() as f64 / 1e9);
+        assert!((rate - 100.0).abs() < 5.0, "rate {}", rate);
+    }
+
+    #[test]
+    fn step_schedule() {
+        let model = ArrivalModel::Step { schedule: vec![(0.0, 10.0), (1.0, 100.0)] };
+        let arrivals = arrivals(model, 110);
+        // 10 requests in the first second, then 100 requests per second
+        assert_eq!(arrivals[10], 1_000_000_000);
+        assert_eq!(arrivals[110 - 1], 1_000_000_000 + 99 * 10_000_000);
+    }
+
+    #[test]
+    fn invalid_models() {
+        assert!(ArrivalModel::Poisson { rate: 0.0, seed: 0 }.validate().is_err());
+        assert!(ArrivalModel::Mmpp { rates: vec![1.0, 2.0], mean_durations_secs: vec![1.0], seed: 0 }.validate().is_err());
+        assert!(ArrivalModel::Step { schedule: vec![(1.0, 1.0), (0.0, 1.0)] }.validate().is_err());
+        assert!(ArrivalModel::Gamma { rate: 10.0, cv: 2.0, seed: 0 }.validate().is_ok());
+    }
+
+    #[test]
+    fn invalid_traces() {
+        let dir = std::env::temp_dir().join(format!("mlflow_arrival_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let trace = |name: &str, content: &str| {
+            let path = dir.join(name);
+            std::fs::write(&path, content).unwrap();
+            ArrivalModel::Trace { path }
+        };
+        let missing = ArrivalModel::Trace { path: dir.join("missing.txt") };
+        assert!(matches!(missing.validate(), Err(ConfigError::Invalid(msg)) if msg.contains("failed to open")));
+        let single = trace("single.txt", "0.5\n\n");
+        assert!(matches!(single.validate(), Err(ConfigError::Invalid(msg)) if msg.contains("at least two arrivals")));
+        let malformed = trace("malformed.txt", "0.5\nsoon\n");
+        assert!(matches!(malformed.validate(), Err(ConfigError::Invalid(msg)) if msg.contains("invalid arrival time")));
+        let valid = trace("valid.txt", "1.5\n0.5\n");
+        assert!(valid.validate().is_ok());
+        // repeated after the mean inter-arrival time
+        assert_eq!(arrivals(valid, 3), vec![0, 1_000_000_000, 2_000_000_000]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -18,7 +18,8 @@ use crate::node::GenericStream;
 use crate::static_timely::timely_static_pipeline_scope::PipelineScope;
 
 use super::{GenericScope, ExchangeGenericInputFeeder};
-use super::GenericInputFeeder;
+use super::{GenericInputFeeder, RequestIds};
+use super::arrival::ArrivalProcess;
 
 
 /// A buffered contained input source, that distributes the inputs (VecDeque<D>) across multiple workers
@@ -37,9 +38,10 @@ where
     emit_logic: L,
     worker_index: usize,
     worker_peers: usize, 
-    request_interval: Option<i64>,
-    last_request_timestamp: Option<i64>,
+    // paces the requests (if configured)
+    arrival: Option<ArrivalProcess>,
     handle: Option<InputHandle<T, TimestampData<D2>>>,
+    request_ids: Option<RequestIds>,
     phantom: PhantomData<T>,
     phantom_scope: PhantomData<S>
 }
@@ -58,9 +60,9 @@ where
             emit_logic,
             worker_index,
             worker_peers,
-            request_interval: None,
-            last_request_timestamp: None,
+            arrival: None,
             handle: None,
+            request_ids: None,
             phantom: PhantomData,
             phantom_scope: PhantomData
         }
@@ -82,13 +84,13 @@ where
     S: Scope + ScopeParent<Timestamp = T> + 'static
 {
     fn build_stream(&mut self, scope: &mut dyn GenericScope, config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>) -> Box<dyn GenericStream> {
+        let scope = scope.as_any_mut().downcast_mut::<S>().unwrap();
         if let Some(config) = config {
-            let request_rate = config.get("request_rate").and_then(|val| val.downcast_ref::<f64>()).map(|val| *val);
-            self.request_interval = request_rate.map(|x|  (1e9_f64 / x) as i64);
+            self.arrival = ArrivalProcess::from_config(&config, scope.index());
         }
-        let scope = scope.as_any_mut().downcast_mut::<S>().unwrap();
         let (handle, stream) = scope.new_input();
         self.handle = Some(handle);
+        self.request_ids = Some(RequestIds::new(self.worker_index, self.worker_peers));
         Box::new(stream)
     }
 
@@ -101,16 +103,15 @@ where
             self.load_buffer()
         }
         let curr_ts = Utc::now().timestamp_nanos();
-        if let Some(last_ts) = self.last_request_timestamp {
-            if let Some(req_interval) = self.request_interval {
-                if curr_ts - last_ts < req_interval { return true }
-            }
+        if let Some(arrival) = self.arrival.as_ref() {
+            if !arrival.is_due(curr_ts) { return true }
         }
         let handle = self.handle.as_mut().unwrap();
         let (data, step_timestamp) = self.buffer.pop_front().unwrap();
         let curr_time = handle.time().to_owned();
         let timestamped_data = TimestampData {
             data,
+            request_id: self.request_ids.as_ref().unwrap().next(),
             start_timestamp: curr_ts,
             last_timestamp: curr_ts,
             total_exec_net_latency: 0
@@ -122,7 +123,9 @@ where
         else if step_timestamp.less_than(&curr_time) {
             panic!("timestamp to advance to is less than current timestamp");
         }
-        self.last_request_timestamp = Some(curr_ts);
+        if let Some(arrival) = self.arrival.as_mut() {
+            arrival.advance(curr_ts);
+        }
         true
     }
 }
@@ -139,11 +142,9 @@ where
         Box::new(stream)
     }
 
-    fn build_and_register_output(&mut self, scope: &mut dyn crate::node::GenericPipelineScope, output_idx: usize) -> Box<dyn GenericStream> {
+    fn build_and_register_output(&mut self, scope: &mut dyn crate::node::GenericPipelineScope, config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>, output_idx: usize) -> Box<dyn GenericStream> {
         let scope = scope.as_any_mut().downcast_mut::<PipelineScope<A, T>>().unwrap();
-        let (handle, stream) = scope.new_input();
-        self.handle = Some(handle);
-        let stream: Box<dyn GenericStream> = Box::new(stream);
+        let stream = self.build_stream(scope, config);
         self.register_pipeline_output(&stream, scope, output_idx);
         stream
     }
@@ -0,0 +1,466 @@
+//! Long-running input source fed by a TCP / HTTP listener
+use std::any::Any;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::marker::PhantomData;
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use serde::{Serialize, Deserialize};
+use timely::communication::RelayConnectAllocate;
+use timely::order::TotalOrder;
+use timely::progress::Timestamp;
+use timely::progress::timestamp::Refines;
+use timely::{Data, ExchangeData};
+use timely::dataflow::{Scope, ScopeParent, Stream};
+use timely::dataflow::operators::Input;
+use timely::dataflow::InputHandle;
+
+use crate::TimestampData;
+use crate::config::ConfigError;
+use crate::node::GenericStream;
+use crate::static_timely::timely_static_pipeline_scope::PipelineScope;
+
+use super::{GenericScope, ExchangeGenericInputFeeder};
+use super::{GenericInputFeeder, RequestId, RequestIds};
+
+
+/// Default upper bound (in bytes) of a request received by a listener
+pub const DEFAULT_MAX_REQUEST_BYTES: usize = 16 << 20;
+/// Default upper bound of the connections a listener serves at the same time
+pub const DEFAULT_MAX_CONNECTIONS: usize = 1024;
+
+/// Framing of the requests received by a listener
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IngestFormat {
+    /// One request per line (e.g., newline-delimited JSON)
+    Lines,
+    /// Each request is prefixed by its length (4 bytes, big endian)
+    LengthPrefixed,
+    /// One request per HTTP/1.1 `POST` body, answered with the assigned request id
+    Http,
+}
+
+/// Decode a JSON request, e.g., for `IngestFormat::Lines` or `IngestFormat::Http`
+pub fn decode_json<D: DeserializeOwned>(bytes: &[u8]) -> Result<D, String> {
+    serde_json::from_slice(bytes).map_err(|err| err.to_string())
+}
+
+// a decoded request, with its id and when it is received
+struct Ingested<D> {
+    request_id: RequestId,
+    data: D,
+    timestamp: i64,
+}
+
+/// Input source fed by the requests received on a local TCP listener, it never completes.
+/// Each timely worker listens on the configured port plus its index.
+/// The requests are decoded and assigned ids by the connection threads,
+/// the worker emits them as `(request_id, data)` and advances the timestamp every epoch interval
+/// (overridden by `epoch_interval` in the operator config).
+/// Requests larger than `max_request_bytes` close their connection and
+/// at most `max_connections` connections are served at the same time (both overridden in the operator config).
+pub struct ListenerInputSource<T: Timestamp, D: Data, F, L, S: Scope>
+where
+    F: Fn(&[u8]) -> Result<D, String> + Send + Sync + 'static,
+    L: FnMut(&T) -> T + 'static,
+    S: ScopeParent<Timestamp = T> + 'static
+{
+    addr: SocketAddr,
+    format: IngestFormat,
+    decode: Arc<F>,
+    advance_logic: L,
+    // in nanoseconds
+    epoch_interval: i64,
+    last_epoch_timestamp: i64,
+    max_request_bytes: usize,
+    max_connections: usize,
+    receiver: Option<Receiver<Ingested<D>>>,
+    handle: Option<InputHandle<T, TimestampData<(RequestId, D)>>>,
+    phantom_scope: PhantomData<S>
+}
+
+impl<T: Timestamp + TotalOrder, D: Data + Send, F, L, S> ListenerInputSource<T, D, F, L, S>
+where
+    F: Fn(&[u8]) -> Result<D, String> + Send + Sync + 'static,
+    L: FnMut(&T) -> T + 'static,
+    S: Scope + ScopeParent<Timestamp = T> + 'static
+{
+    pub fn new<A: ToSocketAddrs>(addr: A, format: IngestFormat, decode: F, epoch_interval: Duration, advance_logic: L) -> Self {
+        let addr = addr.to_socket_addrs().expect("invalid listener address")
+            .next().expect("invalid listener address");
+        ListenerInputSource {
+            addr,
+            format,
+            decode: Arc::new(decode),
+            advance_logic,
+            epoch_interval: epoch_interval.as_nanos() as i64,
+            last_epoch_timestamp: 0,
+            max_request_bytes: DEFAULT_MAX_REQUEST_BYTES,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            receiver: None,
+            handle: None,
+            phantom_scope: PhantomData
+        }
+    }
+
+    fn start_listener(&mut self, worker_index: usize, worker_peers: usize, config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>) {
+        if let Some(config) = config {
+            if let Some(epoch_interval) = config.get("epoch_interval").and_then(|val| val.downcast_ref::<i64>()).copied() {
+                self.epoch_interval = epoch_interval;
+            }
+            if let Some(max_request_bytes) = config.get("max_request_bytes").and_then(|val| val.downcast_ref::<usize>()).copied() {
+                self.max_request_bytes = max_request_bytes;
+            }
+            if let Some(max_connections) = config.get("max_connections").and_then(|val| val.downcast_ref::<usize>()).copied() {
+                self.max_connections = max_connections;
+            }
+        }
+        let addr = worker_listener_addr(self.addr, worker_index).unwrap_or_else(|err| panic!("{}", err));
+        let listener = TcpListener::bind(addr).unwrap_or_else(|err| panic!("failed to listen on {}: {}", addr, err));
+        let (sender, receiver) = channel();
+        let request_ids = Arc::new(RequestIds::new(worker_index, worker_peers));
+        let format = self.format;
+        let decode = self.decode.clone();
+        let (max_request_bytes, max_connections) = (self.max_request_bytes, self.max_connections);
+        // number of connections being served, only incremented by the listener thread
+        let connections = Arc::new(AtomicUsize::new(0));
+        thread::Builder::new()
+            .name(format!("listener-{}", addr))
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    let mut stream = match stream {
+                        Ok(stream) => stream,
+                        Err(err) => {
+                            eprintln!("failed to accept connection on {}: {}", addr, err);
+                            continue;
+                        }
+                    };
+                    if connections.load(Ordering::SeqCst) >= max_connections {
+                        eprintln!("rejected connection on {}: {} connections are served already", addr, max_connections);
+                        if format == IngestFormat::Http {
+                            let _ = write_http_response(&mut stream, "503 Service Unavailable", "{\"error\":\"too many connections\"}");
+                        }
+                        continue;
+                    }
+                    connections.fetch_add(1, Ordering::SeqCst);
+                    let (sender, request_ids, decode, connections) = (sender.clone(), request_ids.clone(), decode.clone(), connections.clone());
+                    thread::spawn(move || {
+                        let peer = stream.peer_addr().ok();
+                        if let Err(err) = serve_connection(stream, format, max_request_bytes, &*decode, &request_ids, &sender) {
+                            eprintln!("connection from {:?} closed: {}", peer, err);
+                        }
+                        connections.fetch_sub(1, Ordering::SeqCst);
+                    });
+                }
+            })
+            .expect("failed to spawn listener thread");
+        self.receiver = Some(receiver);
+        self.last_epoch_timestamp = Utc::now().timestamp_nanos();
+    }
+}
+
+impl<T: Timestamp + TotalOrder, D: Data + Send, F, L, S> GenericInputFeeder for ListenerInputSource<T, D, F, L, S>
+where
+    F: Fn(&[u8]) -> Result<D, String> + Send + Sync + 'static,
+    L: FnMut(&T) -> T + 'static,
+    S: Scope + ScopeParent<Timestamp = T> + 'static
+{
+    fn build_stream(&mut self, scope: &mut dyn GenericScope, config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>) -> Box<dyn GenericStream> {
+        let scope = scope.as_any_mut().downcast_mut::<S>().unwrap();
+        self.start_listener(scope.index(), scope.peers(), config);
+        let (handle, stream) = scope.new_input();
+        self.handle = Some(handle);
+        Box::new(stream)
+    }
+
+    fn step(&mut self) -> bool {
+        let handle = self.handle.as_mut().unwrap();
+        let receiver = self.receiver.as_ref().unwrap();
+        loop {
+            match receiver.try_recv() {
+                Ok(ingested) => {
+                    let timestamped_data = TimestampData {
+                        data: (ingested.request_id, ingested.data),
+                        request_id: ingested.request_id,
+                        start_timestamp: ingested.timestamp,
+                        last_timestamp: ingested.timestamp,
+                        total_exec_net_latency: 0
+                    };
+                    handle.send(timestamped_data);
+                },
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => panic!("listener on {} stopped", self.addr),
+            }
+        }
+        let curr_ts = Utc::now().timestamp_nanos();
+        if curr_ts - self.last_epoch_timestamp >= self.epoch_interval {
+            let curr_time = handle.time().to_owned();
+            let step_timestamp = (self.advance_logic)(&curr_time);
+            if curr_time.less_than(&step_timestamp) {
+                handle.advance_to(step_timestamp);
+            }
+            else if step_timestamp.less_than(&curr_time) {
+                panic!("timestamp to advance to is less than current timestamp");
+            }
+            self.last_epoch_timestamp = curr_ts;
+        }
+        true
+    }
+}
+
+impl<T, D: ExchangeData, F, L, A> ExchangeGenericInputFeeder for ListenerInputSource<T, D, F, L, PipelineScope<A, T>>
+where
+    T: Timestamp+ Refines<()> + TotalOrder,
+    F: Fn(&[u8]) -> Result<D, String> + Send + Sync + 'static,
+    L: FnMut(&T) -> T + 'static,
+    A: RelayConnectAllocate + 'static,
+{
+    fn acquire_from_input_pipeline(&self, scope: &mut dyn crate::node::GenericPipelineScope, input_idx: usize) -> Box<dyn GenericStream> {
+        let scope = scope.as_any_mut().downcast_mut::<PipelineScope<A, T>>().unwrap();
+        let stream = scope.acquire_pipeline_input::<TimestampData<(RequestId, D)>>(input_idx);
+        Box::new(stream)
+    }
+
+    fn build_and_register_output(&mut self, scope: &mut dyn crate::node::GenericPipelineScope, config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>, output_idx: usize) -> Box<dyn GenericStream> {
+        let scope = scope.as_any_mut().downcast_mut::<PipelineScope<A, T>>().unwrap();
+        let stream = self.build_stream(scope, config);
+        self.register_pipeline_output(&stream, scope, output_idx);
+        stream
+    }
+
+    fn register_pipeline_output(&self, stream: &Box<dyn GenericStream>, scope: &mut dyn crate::node::GenericPipelineScope, output_idx: usize) {
+        let scope = scope.as_any_mut().downcast_mut::<PipelineScope<A, T>>().unwrap();
+        let stream = stream.as_any().downcast_ref::<Stream<PipelineScope<A, T>, TimestampData<(RequestId, D)>>>().unwrap();
+        scope.register_pipeline_output_balanced_exchange(stream, output_idx);
+    }
+}
+
+// each timely worker listens on the configured port plus its index
+fn worker_listener_addr(mut addr: SocketAddr, worker_index: usize) -> Result<SocketAddr, ConfigError> {
+    let port = u16::try_from(worker_index).ok()
+        .and_then(|worker_index| addr.port().checked_add(worker_index))
+        .ok_or_else(|| ConfigError::Invalid(format!("listener port {} plus worker index {} exceeds the port range", addr.port(), worker_index)))?;
+    addr.set_port(port);
+    Ok(addr)
+}
+
+fn request_too_large(max_request_bytes: usize) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("request exceeds {} bytes", max_request_bytes))
+}
+
+// receive, decode and forward the requests of a connection until it is closed
+fn serve_connection<D, F>(stream: TcpStream, format: IngestFormat, max_request_bytes: usize, decode: &F, request_ids: &RequestIds, sender: &Sender<Ingested<D>>) -> io::Result<()>
+where
+    F: Fn(&[u8]) -> Result<D, String>
+{
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    loop {
+        let frame = match format {
+            IngestFormat::Lines => read_line(&mut reader, max_request_bytes)?,
+            IngestFormat::LengthPrefixed => read_length_prefixed(&mut reader, max_request_bytes)?,
+            IngestFormat::Http => match read_http_request(&mut reader, max_request_bytes) {
+                Err(err) if err.kind() == io::ErrorKind::InvalidData => {
+                    // the rest of the request is not read, the connection is closed
+                    write_http_response(&mut writer, "413 Payload Too Large", &serde_json::json!({"error": err.to_string()}).to_string())?;
+                    return Err(err);
+                },
+                result => match result? {
+                Some(HttpRequest::Post(body)) => Some(body),
+                Some(HttpRequest::Invalid(status)) => {
+                    write_http_response(&mut writer, status, &format!("{{\"error\":\"{}\"}}", status))?;
+                    continue;
+                },
+                None => None,
+                },
+            },
+        };
+        let frame = match frame {
+            Some(frame) => frame,
+            None => return Ok(()),
+        };
+        let timestamp = Utc::now().timestamp_nanos();
+        match decode(&frame) {
+            Ok(data) => {
+                let request_id = request_ids.next();
+                if sender.send(Ingested { request_id, data, timestamp }).is_err() {
+                    // the worker has finished
+                    return Ok(());
+                }
+                if format == IngestFormat::Http {
+                    write_http_response(&mut writer, "200 OK", &format!("{{\"request_id\":{}}}", request_id))?;
+                }
+            },
+            Err(err) => {
+                if format == IngestFormat::Http {
+                    write_http_response(&mut writer, "400 Bad Request", &serde_json::json!({"error": err}).to_string())?;
+                }
+                else {
+                    eprintln!("failed to decode request: {}", err);
+                }
+            }
+        }
+    }
+}
+
+// reads a line of at most `max_bytes` bytes (besides the line break)
+fn read_bounded_line<R: BufRead>(reader: &mut R, line: &mut Vec<u8>, max_bytes: usize) -> io::Result<usize> {
+    let read = reader.take(max_bytes as u64 + 2).read_until(b'\n', line)?;
+    let len = line.strip_suffix(b"\n").map_or(line.len(), |line| line.strip_suffix(b"\r").unwrap_or(line).len());
+    if len > max_bytes {
+        return Err(request_too_large(max_bytes));
+    }
+    Ok(read)
+}
+
+fn read_line<R: BufRead>(reader: &mut R, max_bytes: usize) -> io::Result<Option<Vec<u8>>> {
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if read_bounded_line(reader, &mut line, max_bytes)? == 0 {
+            return Ok(None);
+        }
+        let len = line.iter().rposition(|byte| !byte.is_ascii_whitespace()).map_or(0, |pos| pos + 1);
+        line.truncate(len);
+        // skip the empty lines
+        if !line.is_empty() {
+            return Ok(Some(line));
+        }
+    }
+}
+
+fn read_length_prefixed<R: Read>(reader: &mut R, max_bytes: usize) -> io::Result<Option<Vec<u8>>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {},
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err),
+    }
+    let len = u32::from_be_bytes(len) as usize;
+    if len > max_bytes {
+        return Err(request_too_large(max_bytes));
+    }
+    let mut frame = vec![0u8; len];
+    reader.read_exact(&mut frame)?;
+    Ok(Some(frame))
+}
+
+enum HttpRequest {
+    Post(Vec<u8>),
+    // the request is answered with the status
+    Invalid(&'static str),
+}
+
+// only `POST` requests with a `Content-Length` are accepted, the connections are kept alive,
+// the request line, each header and the body are bounded by `max_bytes`
+fn read_http_request<R: BufRead>(reader: &mut R, max_bytes: usize) -> io::Result<Option<HttpRequest>> {
+    let mut request_line = Vec::new();
+    if read_bounded_line(reader, &mut request_line, max_bytes)? == 0 {
+        return Ok(None);
+    }
+    let request_line = String::from_utf8_lossy(&request_line);
+    let mut content_length = None;
+    loop {
+        let mut header = Vec::new();
+        if read_bounded_line(reader, &mut header, max_bytes)? == 0 {
+            return Ok(None);
+        }
+        let header = String::from_utf8_lossy(&header);
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let method = request_line.split_whitespace().next().unwrap_or_default();
+    if content_length.unwrap_or(0) > max_bytes {
+        return Err(request_too_large(max_bytes));
+    }
+    let mut body = vec![0u8; content_length.unwrap_or(0)];
+    reader.read_exact(&mut body)?;
+    if method != "POST" {
+        Ok(Some(HttpRequest::Invalid("405 Method Not Allowed")))
+    }
+    else if content_length.is_none() {
+        Ok(Some(HttpRequest::Invalid("411 Length Required")))
+    }
+    else {
+        Ok(Some(HttpRequest::Post(body)))
+    }
+}
+
+fn write_http_response<W: Write>(writer: &mut W, status: &str, body: &str) -> io::Result<()> {
+    write!(writer, "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}", status, body.len(), body)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use std::io::ErrorKind;
+    use std::net::SocketAddr;
+
+    use super::{read_http_request, read_length_prefixed, read_line, worker_listener_addr, HttpRequest, DEFAULT_MAX_REQUEST_BYTES};
+
+    #[test]
+    fn read_frames() {
+        let mut reader = Cursor::new(b"{\"a\":1}\r\n\n{\"a\":2}".to_vec());
+        assert_eq!(read_line(&mut reader, DEFAULT_MAX_REQUEST_BYTES).unwrap(), Some(b"{\"a\":1}".to_vec()));
+        assert_eq!(read_line(&mut reader, DEFAULT_MAX_REQUEST_BYTES).unwrap(), Some(b"{\"a\":2}".to_vec()));
+        assert_eq!(read_line(&mut reader, DEFAULT_MAX_REQUEST_BYTES).unwrap(), None);
+
+        let mut reader = Cursor::new([&3u32.to_be_bytes()[..], b"abc"].concat());
+        assert_eq!(read_length_prefixed(&mut reader, DEFAULT_MAX_REQUEST_BYTES).unwrap(), Some(b"abc".to_vec()));
+        assert_eq!(read_length_prefixed(&mut reader, DEFAULT_MAX_REQUEST_BYTES).unwrap(), None);
+    }
+
+    #[test]
+    fn reject_large_requests() {
+        let mut reader = Cursor::new(b"abc\r\nabcd\n".to_vec());
+        assert_eq!(read_line(&mut reader, 3).unwrap(), Some(b"abc".to_vec()));
+        assert_eq!(read_line(&mut reader, 3).unwrap_err().kind(), ErrorKind::InvalidData);
+
+        // the frame is not allocated
+        let mut reader = Cursor::new(u32::MAX.to_be_bytes().to_vec());
+        assert_eq!(read_length_prefixed(&mut reader, 3).unwrap_err().kind(), ErrorKind::InvalidData);
+
+        let mut reader = Cursor::new(b"POST / HTTP/1.1\r\nContent-Length: 4294967295\r\n\r\n".to_vec());
+        assert!(matches!(read_http_request(&mut reader, 1024), Err(err) if err.kind() == ErrorKind::InvalidData));
+        let mut reader = Cursor::new(b"POST / HTTP/1.1\r\nX-Padding: abcdefghijklmnopqrstuvwxyz\r\n\r\n".to_vec());
+        assert!(matches!(read_http_request(&mut reader, 16), Err(err) if err.kind() == ErrorKind::InvalidData));
+    }
+
+    #[test]
+    fn listener_port_overflow() {
+        let addr: SocketAddr = "127.0.0.1:65530".parse().unwrap();
+        assert_eq!(worker_listener_addr(addr, 5).unwrap().port(), 65535);
+        assert!(worker_listener_addr(addr, 6).is_err());
+        assert!(worker_listener_addr(addr, 1 << 16).is_err());
+    }
+
+    #[test]
+    fn read_http_requests() {
+        let requests = "POST / HTTP/1.1\r\nContent-Length: 7\r\n\r\n{\"a\":1}GET / HTTP/1.1\r\nContent-Length: 0\r\n\r\n";
+        let mut reader = Cursor::new(requests.as_bytes().to_vec());
+        match read_http_request(&mut reader, DEFAULT_MAX_REQUEST_BYTES).unwrap() {
+            Some(HttpRequest::Post(body)) => assert_eq!(body, b"{\"a\":1}".to_vec()),
+            _ => panic!("expected a POST request"),
+        }
+        assert!(matches!(read_http_request(&mut reader, DEFAULT_MAX_REQUEST_BYTES).unwrap(), Some(HttpRequest::Invalid("405 Method Not Allowed"))));
+        assert!(read_http_request(&mut reader, DEFAULT_MAX_REQUEST_BYTES).unwrap().is_none());
+    }
+}
@@ -1,6 +1,7 @@
 use std::any::Any;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use timely::dataflow::Scope;
 
@@ -11,12 +12,18 @@ pub mod closure;
 pub mod distributed_contained;
 pub mod buffered_distributed_contained;
 pub mod buffered_contained;
+pub mod arrival;
+pub mod listener;
+pub mod request;
 
 pub use contained::ContainedInputSource;
 pub use closure::ClosureInputSource;
 pub use distributed_contained::WorkerDistributedContainedInputSource;
 pub use buffered_contained::BufferedContainedInputSource;
 pub use buffered_distributed_contained::BufferedWorkerDistributedContainedInputSource;
+pub use arrival::ArrivalModel;
+pub use listener::{decode_json, IngestFormat, ListenerInputSource};
+pub use request::RequestInputSource;
 
 pub trait GenericScope {
     fn as_any(&self) -> &dyn Any;
@@ -28,6 +35,31 @@ impl<S: Scope + 'static> GenericScope for S {
     fn as_any_mut(&mut self) -> &mut dyn Any{ self }
 }
 
+/// Identifier of a request, assigned by the input source and carried along the dataflow path
+pub type RequestId = u64;
+
+// assigns the request ids of an input source on a timely worker,
+// i.e., the n-th request gets `n * worker_peers + worker_index`
+pub(crate) struct RequestIds {
+    next: AtomicU64,
+    worker_index: u64,
+    worker_peers: u64,
+}
+
+impl RequestIds {
+    pub(crate) fn new(worker_index: usize, worker_peers: usize) -> Self {
+        RequestIds {
+            next: AtomicU64::new(0),
+            worker_index: worker_index as u64,
+            worker_peers: worker_peers as u64,
+        }
+    }
+
+    pub(crate) fn next(&self) -> RequestId {
+        self.next.fetch_add(1, Ordering::SeqCst) * self.worker_peers + self.worker_index
+    }
+}
+
 pub trait GenericInputFeeder {
     fn build_stream(&mut self, scope: &mut dyn GenericScope, config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>) -> Box<dyn GenericStream>;
     fn step(&mut self) -> bool;
@@ -35,6 +67,6 @@ pub trait GenericInputFeeder {
 
 pub trait ExchangeGenericInputFeeder: GenericInputFeeder {
     fn acquire_from_input_pipeline(&self, scope: &mut dyn GenericPipelineScope, input_idx: usize) -> Box<dyn GenericStream>;
-    fn build_and_register_output(&mut self, scope: &mut dyn GenericPipelineScope, output_idx: usize) -> Box<dyn GenericStream>;
+    fn build_and_register_output(&mut self, scope: &mut dyn GenericPipelineScope, config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>, output_idx: usize) -> Box<dyn GenericStream>;
     fn register_pipeline_output(&self, stream: &Box<dyn GenericStream>, scope: &mut dyn GenericPipelineScope, output_idx: usize);
 }
\ No newline at end of file
@@ -0,0 +1,142 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use timely::communication::RelayConnectAllocate;
+use timely::order::TotalOrder;
+use timely::progress::Timestamp;
+use timely::progress::timestamp::Refines;
+use timely::{Data, ExchangeData};
+use timely::dataflow::{Scope, ScopeParent, Stream};
+use timely::dataflow::operators::Input;
+use timely::dataflow::InputHandle;
+
+use crate::TimestampData;
+use crate::node::GenericStream;
+use crate::serving::RequestQueue;
+use crate::static_timely::timely_static_pipeline_scope::PipelineScope;
+
+use super::{GenericScope, ExchangeGenericInputFeeder};
+use super::GenericInputFeeder;
+use super::RequestId;
+
+/// Input source fed by the requests submitted through a `RequestClient`,
+/// emits `(request_id, data)` (with the submission timestamp as `start_timestamp`)
+/// and advances the timestamp every epoch interval (overridden by `epoch_interval` in the operator config).
+/// It completes once all the clients are dropped and their requests are emitted.
+pub struct RequestInputSource<T: Timestamp, D: Data, L, S: Scope>
+where
+    L: FnMut(&T) -> T + 'static,
+    S: ScopeParent<Timestamp = T> + 'static
+{
+    requests: Arc<RequestQueue<D>>,
+    advance_logic: L,
+    // in nanoseconds
+    epoch_interval: i64,
+    last_epoch_timestamp: i64,
+    handle: Option<InputHandle<T, TimestampData<(RequestId, D)>>>,
+    phantom_scope: PhantomData<S>
+}
+
+impl<T: Timestamp + TotalOrder, D: Data, L, S> RequestInputSource<T, D, L, S>
+where
+    L: FnMut(&T) -> T + 'static,
+    S: Scope + ScopeParent<Timestamp = T> + 'static
+{
+    pub(crate) fn new(requests: Arc<RequestQueue<D>>, epoch_interval: Duration, advance_logic: L) -> Self {
+        RequestInputSource {
+            requests,
+            advance_logic,
+            epoch_interval: epoch_interval.as_nanos() as i64,
+            last_epoch_timestamp: 0,
+            handle: None,
+            phantom_scope: PhantomData
+        }
+    }
+
+    fn configure(&mut self, config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>) {
+        if let Some(epoch_interval) = config.and_then(|config| config.get("epoch_interval").and_then(|val| val.downcast_ref::<i64>()).copied()) {
+            self.epoch_interval = epoch_interval;
+        }
+        self.last_epoch_timestamp = Utc::now().timestamp_nanos();
+    }
+}
+
+impl<T: Timestamp + TotalOrder, D: Data, L, S> GenericInputFeeder for RequestInputSource<T, D, L, S>
+where
+    L: FnMut(&T) -> T + 'static,
+    S: Scope + ScopeParent<Timestamp = T> + 'static
+{
+    fn build_stream(&mut self, scope: &mut dyn GenericScope, config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>) -> Box<dyn GenericStream> {
+        self.configure(config);
+        let scope = scope.as_any_mut().downcast_mut::<S>().unwrap();
+        let (handle, stream) = scope.new_input();
+        self.handle = Some(handle);
+        Box::new(stream)
+    }
+
+    fn step(&mut self) -> bool {
+        if self.handle.is_none() {
+            return false;
+        }
+        // check before taking the requests, so that no request submitted in between is left behind
+        let closed = self.requests.is_closed();
+        let handle = self.handle.as_mut().unwrap();
+        while let Some((request_id, data, timestamp)) = self.requests.pop() {
+            let timestamped_data = TimestampData {
+                data: (request_id, data),
+                request_id,
+                start_timestamp: timestamp,
+                last_timestamp: timestamp,
+                total_exec_net_latency: 0
+            };
+            handle.send(timestamped_data);
+        }
+        if closed {
+            self.handle = None;
+            return false;
+        }
+        let curr_ts = Utc::now().timestamp_nanos();
+        if curr_ts - self.last_epoch_timestamp >= self.epoch_interval {
+            let curr_time = handle.time().to_owned();
+            let step_timestamp = (self.advance_logic)(&curr_time);
+            if curr_time.less_than(&step_timestamp) {
+                handle.advance_to(step_timestamp);
+            }
+            else if step_timestamp.less_than(&curr_time) {
+                panic!("timestamp to advance to is less than current timestamp");
+            }
+            self.last_epoch_timestamp = curr_ts;
+        }
+        true
+    }
+}
+
+impl<T, D: ExchangeData, L, A> ExchangeGenericInputFeeder for RequestInputSource<T, D, L, PipelineScope<A, T>>
+where
+    T: Timestamp+ Refines<()> + TotalOrder,
+    L: FnMut(&T) -> T + 'static,
+    A: RelayConnectAllocate + 'static,
+{
+    fn acquire_from_input_pipeline(&self, scope: &mut dyn crate::node::GenericPipelineScope, input_idx: usize) -> Box<dyn GenericStream> {
+        let scope = scope.as_any_mut().downcast_mut::<PipelineScope<A, T>>().unwrap();
+        let stream = scope.acquire_pipeline_input::<TimestampData<(RequestId, D)>>(input_idx);
+        Box::new(stream)
+    }
+
+    fn build_and_register_output(&mut self, scope: &mut dyn crate::node::GenericPipelineScope, config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>, output_idx: usize) -> Box<dyn GenericStream> {
+        let scope = scope.as_any_mut().downcast_mut::<PipelineScope<A, T>>().unwrap();
+        let stream = self.build_stream(scope, config);
+        self.register_pipeline_output(&stream, scope, output_idx);
+        stream
+    }
+
+    fn register_pipeline_output(&self, stream: &Box<dyn GenericStream>, scope: &mut dyn crate::node::GenericPipelineScope, output_idx: usize) {
+        let scope = scope.as_any_mut().downcast_mut::<PipelineScope<A, T>>().unwrap();
+        let stream = stream.as_any().downcast_ref::<Stream<PipelineScope<A, T>, TimestampData<(RequestId, D)>>>().unwrap();
+        scope.register_pipeline_output_balanced_exchange(stream, output_idx);
+    }
+}
@@ -10,20 +10,38 @@ pub mod execute;
 pub mod config;
 pub mod utils;
 pub mod metrics;
+pub mod serving;
+pub mod report;
+pub mod trace;
+#[cfg(test)]
+mod test_utils;
 
 pub use builder::{PipelineGraphBuilder, GraphBuilder};
 pub use config::{PipelineConfigGUID, ExecutionConfigGUID};
 pub use config::{PipelineConfig, ExecutionConfig};
+pub use config::{DeploymentSpec, MetricsEndpointConfig};
+pub use trace::{TracingConfig, TraceFormat};
+pub use timely::communication::{ConnectionConfig, FlowControlConfig, LostWorkersReport, RelayFlowControlReport, ReplayReport};
+pub use utils::PipelineIoError;
+pub use graph::{GraphConnections, GraphTopology, OperatorDescription, OperatorKind};
 pub use handle::Handle;
 pub use handle::{Map, MapLocal};
+pub use node::{DeadLetter, BatchSizeMismatch, RetryPolicy};
 pub use handle::{Filter, FilterLocal};
 pub use handle::{Join, JoinLocal};
+pub use node::OuterJoinMode;
 pub use handle::{Aggregate, AggregateLocal};
 pub use handle::{Inspect, InspectLocal};
 pub use handle::Exchange;
+pub use handle::Respond;
+pub use handle::{Sink, SinkLocal};
+pub use node::{FileSink, SinkFormat};
+pub use node::{Window, WindowBounds, LateData, EpochTimestamp};
 
-pub use execute::{pipeline_worker_execute, pipeline_relay_execute};
+pub use execute::{pipeline_worker_execute, pipeline_relay_execute, ExecuteError, RelayStats};
 pub use execute::{local_execute, local_execute_thread, local_execute_process};
+pub use execute::{local_launch, LaunchConfig, LaunchReport};
+pub use input::{ArrivalModel, IngestFormat, RequestId, decode_json};
 
 #[cfg(feature = "bincode")]
 use serde::{Serialize, Deserialize};
@@ -39,6 +57,9 @@ use timely::communication::{MessageTimestamp, MessageLatency};
 pub struct TimestampData<D>{
     /// Data packet
     pub data: D,
+    /// Id of the request related to this data, assigned by the input source
+    /// and kept along the dataflow path (the one of the earliest request for joins and aggregates)
+    pub request_id: RequestId,
     /// Timestamp that the request related to this data arrives
     /// i.e., starts processing
     /// timestamp at the source
@@ -2,7 +2,10 @@ use std::cell::RefCell;
 use std::collections::{VecDeque, HashMap, BTreeMap};
 use std::rc::Rc;
 use std::ops::Deref;
+use std::any::Any;
+use std::sync::Arc;
 
+use hdrhistogram::Histogram;
 use serde::{Serialize, Deserialize};
 use statrs::statistics::{OrderStatistics, Median, Min, Max, Distribution};
 use statrs::statistics::Data as StatData;
@@ -10,13 +13,67 @@ use statrs::statistics::Data as StatData;
 pub struct MetricsLogger {
     // op global id -> logger
     pub(crate) throughput_loggers: HashMap<usize, ThroughputLogger>,
+    pub(crate) input_throughput_loggers: HashMap<usize, ThroughputLogger>,
     pub(crate) execution_latency_loggers: HashMap<usize, LatencyLogger>,
     pub(crate) edge_latency_loggers: HashMap<usize, LatencyLogger>,
     pub(crate) path_latency_loggers: HashMap<usize, LatencyLogger>,
-    pub(crate) jct_loggers: HashMap<usize, JCTLogger>
+    pub(crate) jct_loggers: HashMap<usize, JCTLogger>,
+    pub(crate) slo_loggers: HashMap<usize, SloLogger>,
+    pub(crate) state_loggers: HashMap<usize, StateLogger>,
+    pub(crate) error_loggers: HashMap<usize, ErrorLogger>,
+    pub(crate) batch_loggers: HashMap<usize, BatchLogger>
 }
 
 
+impl MetricsLogger {
+    /// Merge the metrics of the worker threads of a process (once the threads are joined):
+    /// latencies are pooled, throughputs and counters are summed, and the job completion times span all threads
+    pub(crate) fn merge(loggers: Vec<MetricsLogger>) -> MetricsLogger {
+        let mut merged = MetricsLogger {
+            throughput_loggers: HashMap::new(),
+            input_throughput_loggers: HashMap::new(),
+            execution_latency_loggers: HashMap::new(),
+            edge_latency_loggers: HashMap::new(),
+            path_latency_loggers: HashMap::new(),
+            jct_loggers: HashMap::new(),
+            slo_loggers: HashMap::new(),
+            state_loggers: HashMap::new(),
+            error_loggers: HashMap::new(),
+            batch_loggers: HashMap::new()
+        };
+        for logger in loggers {
+            merge_into(&mut merged.throughput_loggers, logger.throughput_loggers, ThroughputLogger::merge);
+            merge_into(&mut merged.input_throughput_loggers, logger.input_throughput_loggers, ThroughputLogger::merge);
+            merge_into(&mut merged.execution_latency_loggers, logger.execution_latency_loggers, LatencyLogger::merge);
+            merge_into(&mut merged.edge_latency_loggers, logger.edge_latency_loggers, LatencyLogger::merge);
+            merge_into(&mut merged.path_latency_loggers, logger.path_latency_loggers, LatencyLogger::merge);
+            merge_into(&mut merged.jct_loggers, logger.jct_loggers, JCTLogger::merge);
+            merge_into(&mut merged.slo_loggers, logger.slo_loggers, SloLogger::merge);
+            merge_into(&mut merged.state_loggers, logger.state_loggers, StateLogger::merge);
+            merge_into(&mut merged.error_loggers, logger.error_loggers, ErrorLogger::merge);
+            merge_into(&mut merged.batch_loggers, logger.batch_loggers, BatchLogger::merge);
+        }
+        merged
+    }
+}
+
+fn merge_into<L, F: Fn(&L, &L)>(merged: &mut HashMap<usize, L>, loggers: HashMap<usize, L>, merge: F) {
+    for (gid, logger) in loggers {
+        match merged.get(&gid) {
+            Some(merged_logger) => merge(merged_logger, &logger),
+            None => { merged.insert(gid, logger); },
+        }
+    }
+}
+
+fn min_timestamp(x: Option<i64>, y: Option<i64>) -> Option<i64> {
+    x.into_iter().chain(y).min()
+}
+
+fn max_timestamp(x: Option<i64>, y: Option<i64>) -> Option<i64> {
+    x.into_iter().chain(y).max()
+}
+
 #[derive(Debug, Clone)]
 /// Metrics for an operator
 #[derive(Serialize, Deserialize)]
@@ -26,6 +83,10 @@ pub struct OperatorMetricsStats {
     pub throughput: Option<BTreeMap<String, f64>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub overall_throughput: Option<f64>,
+    /// Overall rate (in #req/s) of the requests received,
+    /// only for operators whose outputs are not one-to-one with their inputs (joins and aggregates)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_throughput: Option<f64>,
     /// execution latency, edge latency, path latency
     /// in milliseconds
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -34,7 +95,28 @@ pub struct OperatorMetricsStats {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub operator_jct: Option<f64>,
     /// Dataflow path job completion time (in seconds)
-    pub path_jct: Option<f64>
+    pub path_jct: Option<f64>,
+    /// Number of requests dropped since their latency already exceeded the SLO
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dropped: Option<usize>,
+    /// Requests finished within the SLO in #req/s
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goodput: Option<f64>,
+    /// Number of unmatched keys evicted from the state of a join
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evicted_keys: Option<usize>,
+    /// Number of keys still waiting for a match (joins) or for their remaining data (aggregates)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending_keys: Option<usize>,
+    /// Number of requests whose processing failed (routed to the dead-letter output)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub errors: Option<usize>,
+    /// Number of attempts retried after a retryable error
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retries: Option<usize>,
+    /// Histogram of the processed batch sizes (batch size -> #batches)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_sizes: Option<BTreeMap<usize, usize>>
 }
 
 /// Metrics for all logged operators
@@ -64,6 +146,193 @@ impl<T> Deref for RcWrapper<T> {
     }
 }
 
+/// Default number of significant digits of the latency histograms
+pub const DEFAULT_LATENCY_PRECISION: u8 = 3;
+
+/// Reported latency percentiles
+pub const LATENCY_PERCENTILES: [(&str, f64); 7] = [("P10", 10.0), ("P50", 50.0), ("P75", 75.0), ("P90", 90.0), ("P95", 95.0), ("P99", 99.0), ("P999", 99.9)];
+
+/// Serializable latency histogram: the highest latency (in nanoseconds) of each recorded bucket and its count
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyBuckets {
+    pub precision: u8,
+    pub buckets: Vec<(u64, u64)>,
+}
+
+/// Latencies (in nanoseconds) recorded into an HDR histogram with `precision` significant digits (0 to 5),
+/// the memory is bounded by the precision and the range of the latencies rather than the number of samples.
+/// The raw samples are only kept if enabled (e.g., to dump them to the latency logs)
+#[derive(Debug, Clone)]
+pub struct LatencyHistogram {
+    histogram: Histogram<u64>,
+    samples: Option<Vec<i64>>,
+}
+
+impl LatencyHistogram {
+    pub fn new(precision: u8, keep_samples: bool) -> Self {
+        // resized to the largest latency recorded
+        let histogram = Histogram::new(precision).unwrap_or_else(|err| panic!("invalid latency precision {}: {:?}", precision, err));
+        LatencyHistogram {
+            histogram,
+            samples: if keep_samples { Some(Vec::new()) } else { None },
+        }
+    }
+
+    pub fn record(&mut self, latency: i64) {
+        // negative latencies (e.g., clock skew across machines) are recorded as 0
+        self.record_n(std::cmp::max(latency, 0) as u64, 1);
+        if let Some(samples) = self.samples.as_mut() {
+            samples.push(latency);
+        }
+    }
+
+    fn record_n(&mut self, value: u64, count: u64) {
+        // resized to the value, clamped if it cannot be resized
+        if self.histogram.record_n(value, count).is_err() {
+            self.histogram.saturating_record_n(value, count);
+        }
+    }
+
+    pub fn len(&self) -> u64 {
+        self.histogram.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.histogram.is_empty()
+    }
+
+    /// Latency at the percentile (0 to 100), within the precision of the histogram
+    pub fn percentile(&self, percentile: f64) -> u64 {
+        self.histogram.value_at_percentile(percentile)
+    }
+
+    pub fn samples(&self) -> Option<&[i64]> {
+        self.samples.as_deref()
+    }
+
+    /// Percentiles (in milliseconds) of `LATENCY_PERCENTILES`, None if no latency is recorded
+    pub fn percentiles_ms(&self) -> Option<BTreeMap<String, f64>> {
+        if self.is_empty() {
+            None
+        }
+        else {
+            let mut stats = BTreeMap::new();
+            for (name, p) in LATENCY_PERCENTILES {
+                stats.insert(String::from(name), self.percentile(p) as f64 / 1e6_f64);
+            }
+            Some(stats)
+        }
+    }
+
+    /// Recorded buckets of the histogram, to be merged with the histograms of other workers (see `from_buckets`)
+    pub fn buckets(&self) -> LatencyBuckets {
+        LatencyBuckets {
+            precision: self.histogram.sigfig(),
+            buckets: self.histogram.iter_recorded().map(|bucket| (bucket.value_iterated_to(), bucket.count_at_value())).collect(),
+        }
+    }
+
+    pub fn from_buckets(buckets: &LatencyBuckets) -> Self {
+        let mut latencies = LatencyHistogram::new(buckets.precision, false);
+        for (value, count) in buckets.buckets.iter() {
+            latencies.record_n(*value, *count);
+        }
+        latencies
+    }
+
+    /// Add the latencies of another histogram, the raw samples are only kept if both keep them
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        if self.histogram.add(&other.histogram).is_err() {
+            // a histogram of lower precision cannot hold the other one, record its values one by one
+            for value in other.histogram.iter_recorded() {
+                self.record_n(value.value_iterated_to(), value.count_at_value());
+            }
+        }
+        match (self.samples.as_mut(), other.samples.as_ref()) {
+            (Some(samples), Some(other_samples)) => samples.extend_from_slice(other_samples),
+            _ => self.samples = None,
+        }
+    }
+}
+
+impl Default for LatencyHistogram {
+    fn default() -> Self {
+        LatencyHistogram::new(DEFAULT_LATENCY_PRECISION, false)
+    }
+}
+
+const METRIC_KEEP_LAST_N: Option<usize> = None;
+const THROUGHPUT_WINDOW_SIZE: usize = 10;
+const WARMUP_ITERS: usize = 20;
+
+/// Windowed throughput of the requests counted by an operator,
+/// with the same warmup and windows as the throughput computed inline by the map / filter operators.
+/// Clones share the same counters, so a clone can be moved into the operator logic
+#[derive(Clone, Default)]
+pub(crate) struct ThroughputMeter {
+    warmup_count: Rc<RefCell<usize>>,
+    warmup_start_timestamp: Rc<RefCell<Option<i64>>>,
+    warmup_throughput: Rc<RefCell<Option<f64>>>,
+    window_count: Rc<RefCell<usize>>,
+    window_start_timestamp: Rc<RefCell<i64>>,
+    throughput: Rc<RefCell<VecDeque<f64>>>,
+    // Timestamp when wramup is finished
+    warmed_timestamp: Rc<RefCell<Option<i64>>>,
+    // Total number of requests counted after warmup
+    total_warmed_count: Rc<RefCell<i64>>,
+    overall_throughput: Rc<RefCell<Option<f64>>>
+}
+
+impl ThroughputMeter {
+    /// Count a request at `timestamp` (in nanoseconds)
+    pub(crate) fn record(&self, timestamp: i64) {
+        let rate = |count: f64, since: i64| if timestamp > since { Some(count / ((timestamp - since) as f64 / 1e9_f64)) } else { None };
+        if self.warmup_start_timestamp.borrow().is_none() {
+            *self.warmup_count.borrow_mut() = 0;
+            *self.warmup_start_timestamp.borrow_mut() = Some(timestamp);
+        }
+        else if *self.warmup_count.borrow() < WARMUP_ITERS {
+            *self.warmup_count.borrow_mut() += 1;
+            if *self.warmup_count.borrow() == WARMUP_ITERS {
+                *self.window_start_timestamp.borrow_mut() = timestamp;
+                *self.warmed_timestamp.borrow_mut() = Some(timestamp);
+                *self.total_warmed_count.borrow_mut() = 0;
+            }
+            let warmup_start_timestamp = self.warmup_start_timestamp.borrow().unwrap();
+            if let Some(tp) = rate(*self.warmup_count.borrow() as f64, warmup_start_timestamp) {
+                *self.warmup_throughput.borrow_mut() = Some(tp);
+            }
+        }
+        else {
+            *self.window_count.borrow_mut() += 1;
+            if *self.window_count.borrow() >= THROUGHPUT_WINDOW_SIZE {
+                let window_start_timestamp = *self.window_start_timestamp.borrow();
+                if let Some(tp) = rate(*self.window_count.borrow() as f64, window_start_timestamp) {
+                    self.throughput.borrow_mut().push_front(tp);
+                    if let Some(keep_n) = METRIC_KEEP_LAST_N {
+                        self.throughput.borrow_mut().truncate(keep_n);
+                    }
+                }
+                *self.window_count.borrow_mut() = 0;
+                *self.window_start_timestamp.borrow_mut() = timestamp;
+            }
+            *self.total_warmed_count.borrow_mut() += 1;
+            let warmed_timestamp = self.warmed_timestamp.borrow().unwrap();
+            if let Some(tp) = rate(*self.total_warmed_count.borrow() as f64, warmed_timestamp) {
+                *self.overall_throughput.borrow_mut() = Some(tp);
+            }
+        }
+    }
+
+    pub(crate) fn logger(&self) -> ThroughputLogger {
+        ThroughputLogger {
+            throughput: RcWrapper::new(self.throughput.clone()),
+            warmup_throughput: RcWrapper::new(self.warmup_throughput.clone()),
+            overall_throughput: RcWrapper::new(self.overall_throughput.clone())
+        }
+    }
+}
+
 pub struct ThroughputLogger {
     pub(crate) throughput: RcWrapper<RefCell<VecDeque<f64>>>,
     pub(crate) warmup_throughput: RcWrapper<RefCell<Option<f64>>>,
@@ -71,7 +340,7 @@ pub struct ThroughputLogger {
 }
 
 pub struct LatencyLogger {
-    pub(crate) latencies: RcWrapper<RefCell<VecDeque<i64>>>
+    pub(crate) latencies: RcWrapper<RefCell<LatencyHistogram>>
 }
 
 pub struct JCTLogger {
@@ -80,27 +349,52 @@ pub struct JCTLogger {
     pub(crate) op_end_timestamp: RcWrapper<RefCell<Option<i64>>>
 }
 
+pub struct SloLogger {
+    pub(crate) dropped: RcWrapper<RefCell<usize>>,
+    pub(crate) within_slo: RcWrapper<RefCell<usize>>,
+    pub(crate) op_start_timestamp: RcWrapper<RefCell<Option<i64>>>,
+    pub(crate) op_end_timestamp: RcWrapper<RefCell<Option<i64>>>
+}
+
+pub struct StateLogger {
+    pub(crate) evicted: RcWrapper<RefCell<usize>>,
+    pub(crate) pending: RcWrapper<RefCell<usize>>
+}
+
+pub struct ErrorLogger {
+    pub(crate) errors: RcWrapper<RefCell<usize>>,
+    pub(crate) retries: RcWrapper<RefCell<usize>>
+}
+
+pub struct BatchLogger {
+    pub(crate) batch_sizes: RcWrapper<RefCell<BTreeMap<usize, usize>>>
+}
+
 impl LatencyLogger {
+    /// Return the latency percentiles in milliseconds
     pub fn compute_latency(&self) -> Option<BTreeMap<String, f64>> {
-        if self.latencies.borrow().is_empty() {
-            None
-        }
-        else {
-            let latency_data = self.latencies.borrow().iter().map(|x| *x as f64).collect::<Vec<_>>();
-            let mut latency_data = StatData::new(latency_data);
-            let thresholds = [10, 50, 75, 90, 95, 99, 999];
-            let mut stats = BTreeMap::new();
-            for p in thresholds {
-                let lat = latency_data.percentile(p);
-                stats.insert(format!("P{}", p), lat / 1e6_f64);
-            }  
-        Some(stats)
-        }
+        self.latencies.borrow().percentiles_ms()
+    }
+
+    /// Return the recorded buckets of the latency histogram
+    pub fn get_latency_buckets(&self) -> LatencyBuckets {
+        self.latencies.borrow().buckets()
+    }
+
+    /// Return the raw latencies (in nanoseconds), None if the samples are not kept (see `LatencyHistogram`)
+    pub fn get_all_latencies(&self) -> Option<Vec<i64>> {
+        self.latencies.borrow().samples().map(|samples| samples.to_vec())
     }
 
-    pub fn get_all_latencies(&self) -> Vec<i64> {
-        let latency_data = self.latencies.borrow().clone().into_iter().collect::<Vec<_>>();
-        latency_data
+    /// Set the precision and whether to keep the raw samples from `latency_precision` and `keep_latency_samples`
+    /// in the operator config, before any latency is recorded
+    pub(crate) fn configure(&self, config: Option<&HashMap<String, Arc<dyn Any + Send + Sync>>>) {
+        let precision = config.and_then(|config| config.get("latency_precision").and_then(|val| val.downcast_ref::<u8>()).copied());
+        let keep_samples = config.and_then(|config| config.get("keep_latency_samples").and_then(|val| val.downcast_ref::<bool>()).copied());
+        let mut latencies = self.latencies.borrow_mut();
+        if latencies.is_empty() && (precision.is_some() || keep_samples.is_some()) {
+            *latencies = LatencyHistogram::new(precision.unwrap_or(DEFAULT_LATENCY_PRECISION), keep_samples.unwrap_or(false));
+        }
     }
 }
 
@@ -117,7 +411,7 @@ impl ThroughputLogger {
             }
         }
         else {
-            let throughput_data = self.throughput.borrow().iter().map(|x| *x).collect::<Vec<_>>();
+            let throughput_data = self.throughput.borrow().iter().copied().collect::<Vec<_>>();
             let mut throughput_data = StatData::new(throughput_data);
             let max_tp = throughput_data.max();
             let min_tp = throughput_data.min();
@@ -138,8 +432,7 @@ impl ThroughputLogger {
     }
 
     pub fn compute_overall_throughput(&self) -> Option<f64> {
-        let overall_throughput = self.overall_throughput.borrow().clone();
-        overall_throughput
+        *self.overall_throughput.borrow()
     }
 }
 
@@ -168,4 +461,255 @@ impl JCTLogger {
             Some((end_ts - start_ts) as f64 / 1e9_f64)
         }
     }
-}
\ No newline at end of file
+}
+
+impl SloLogger {
+    /// Return the number of requests dropped before processing
+    pub fn compute_dropped(&self) -> usize {
+        *self.dropped.borrow()
+    }
+
+    /// Return goodput in #req/s
+    /// (requests finished within the SLO, from when the first request is received at this operator to the last request finished processing)
+    pub fn compute_goodput(&self) -> Option<f64> {
+        if self.op_start_timestamp.borrow().is_none() || self.op_end_timestamp.borrow().is_none() {
+            None
+        }
+        else {
+            let start_ts = self.op_start_timestamp.borrow().unwrap();
+            let end_ts = self.op_end_timestamp.borrow().unwrap();
+            if end_ts <= start_ts {
+                None
+            }
+            else {
+                Some(*self.within_slo.borrow() as f64 / ((end_ts - start_ts) as f64 / 1e9_f64))
+            }
+        }
+    }
+}
+
+impl StateLogger {
+    /// Return the number of unmatched keys evicted (on timeout or once the other input completes)
+    pub fn compute_evicted(&self) -> usize {
+        *self.evicted.borrow()
+    }
+
+    /// Return the number of keys waiting for a match (or for the rest of their data)
+    pub fn compute_pending(&self) -> usize {
+        *self.pending.borrow()
+    }
+}
+
+impl ErrorLogger {
+    /// Return the number of failed requests
+    pub fn compute_errors(&self) -> usize {
+        *self.errors.borrow()
+    }
+
+    /// Return the number of retried attempts
+    pub fn compute_retries(&self) -> usize {
+        *self.retries.borrow()
+    }
+}
+
+impl BatchLogger {
+    /// Return the histogram of the batch sizes, None if no batch is processed
+    pub fn compute_batch_sizes(&self) -> Option<BTreeMap<usize, usize>> {
+        if self.batch_sizes.borrow().is_empty() {
+            None
+        }
+        else {
+            Some(self.batch_sizes.borrow().clone())
+        }
+    }
+}
+
+
+impl ThroughputLogger {
+    fn merge(&self, other: &ThroughputLogger) {
+        // the throughputs of the windows (most recent first) are summed over the threads
+        let mut throughput = self.throughput.borrow_mut();
+        for (idx, tp) in other.throughput.borrow().iter().enumerate() {
+            match throughput.get_mut(idx) {
+                Some(merged_tp) => *merged_tp += tp,
+                None => throughput.push_back(*tp),
+            }
+        }
+        for (merged, other) in [(&self.warmup_throughput, &other.warmup_throughput), (&self.overall_throughput, &other.overall_throughput)] {
+            let other = *other.borrow();
+            let mut merged = merged.borrow_mut();
+            *merged = match (*merged, other) {
+                (Some(x), Some(y)) => Some(x + y),
+                (x, y) => x.or(y),
+            };
+        }
+    }
+}
+
+impl LatencyLogger {
+    fn merge(&self, other: &LatencyLogger) {
+        self.latencies.borrow_mut().merge(&other.latencies.borrow());
+    }
+}
+
+impl JCTLogger {
+    fn merge(&self, other: &JCTLogger) {
+        let data_start_timestamp = min_timestamp(*self.data_start_timestamp.borrow(), *other.data_start_timestamp.borrow());
+        let op_start_timestamp = min_timestamp(*self.op_start_timestamp.borrow(), *other.op_start_timestamp.borrow());
+        let op_end_timestamp = max_timestamp(*self.op_end_timestamp.borrow(), *other.op_end_timestamp.borrow());
+        *self.data_start_timestamp.borrow_mut() = data_start_timestamp;
+        *self.op_start_timestamp.borrow_mut() = op_start_timestamp;
+        *self.op_end_timestamp.borrow_mut() = op_end_timestamp;
+    }
+}
+
+impl SloLogger {
+    fn merge(&self, other: &SloLogger) {
+        *self.dropped.borrow_mut() += *other.dropped.borrow();
+        *self.within_slo.borrow_mut() += *other.within_slo.borrow();
+        let op_start_timestamp = min_timestamp(*self.op_start_timestamp.borrow(), *other.op_start_timestamp.borrow());
+        let op_end_timestamp = max_timestamp(*self.op_end_timestamp.borrow(), *other.op_end_timestamp.borrow());
+        *self.op_start_timestamp.borrow_mut() = op_start_timestamp;
+        *self.op_end_timestamp.borrow_mut() = op_end_timestamp;
+    }
+}
+
+impl StateLogger {
+    fn merge(&self, other: &StateLogger) {
+        *self.evicted.borrow_mut() += *other.evicted.borrow();
+        *self.pending.borrow_mut() += *other.pending.borrow();
+    }
+}
+
+impl ErrorLogger {
+    fn merge(&self, other: &ErrorLogger) {
+        *self.errors.borrow_mut() += *other.errors.borrow();
+        *self.retries.borrow_mut() += *other.retries.borrow();
+    }
+}
+
+impl BatchLogger {
+    fn merge(&self, other: &BatchLogger) {
+        let mut batch_sizes = self.batch_sizes.borrow_mut();
+        for (batch_size, count) in other.batch_sizes.borrow().iter() {
+            *batch_sizes.entry(*batch_size).or_insert(0) += count;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::collections::{BTreeMap, HashMap};
+    use std::rc::Rc;
+
+    use super::{LatencyHistogram, LatencyLogger, RcWrapper, ThroughputMeter, WARMUP_ITERS, THROUGHPUT_WINDOW_SIZE};
+    use super::{MetricsLogger, ThroughputLogger, JCTLogger, SloLogger, StateLogger, ErrorLogger, BatchLogger};
+
+    fn shared<X: Send>(x: X) -> RcWrapper<RefCell<X>> {
+        RcWrapper::new(Rc::new(RefCell::new(x)))
+    }
+
+    /// Metrics of a worker thread, for an operator (GUID 0) running on all threads
+    /// and an operator (GUID `thread + 1`) running on this thread only
+    fn thread_metrics(thread: usize, throughput: f64, op_start_ts: i64, op_end_ts: i64) -> MetricsLogger {
+        let throughput_logger = || ThroughputLogger {
+            throughput: shared(vec![throughput, throughput / 2.0].into()),
+            warmup_throughput: shared(Some(throughput)),
+            overall_throughput: shared(Some(throughput)),
+        };
+        MetricsLogger {
+            throughput_loggers: HashMap::from([(0, throughput_logger()), (thread + 1, throughput_logger())]),
+            input_throughput_loggers: HashMap::from([(0, throughput_logger())]),
+            execution_latency_loggers: HashMap::new(),
+            edge_latency_loggers: HashMap::new(),
+            path_latency_loggers: HashMap::new(),
+            jct_loggers: HashMap::from([(0, JCTLogger {
+                data_start_timestamp: shared(Some(op_start_ts - 1)),
+                op_start_timestamp: shared(Some(op_start_ts)),
+                op_end_timestamp: shared(Some(op_end_ts))
+            })]),
+            slo_loggers: HashMap::from([(0, SloLogger {
+                dropped: shared(thread + 1),
+                within_slo: shared(10),
+                op_start_timestamp: shared(Some(op_start_ts)),
+                op_end_timestamp: shared(Some(op_end_ts))
+            })]),
+            state_loggers: HashMap::from([(0, StateLogger { evicted: shared(thread + 1), pending: shared(2) })]),
+            error_loggers: HashMap::from([(0, ErrorLogger { errors: shared(thread + 1), retries: shared(3) })]),
+            batch_loggers: HashMap::from([(0, BatchLogger { batch_sizes: shared(BTreeMap::from([(4, 1), (thread + 1, 1)])) })])
+        }
+    }
+
+    #[test]
+    fn latency_percentiles_and_merge() {
+        let mut histogram = LatencyHistogram::new(3, false);
+        (1..=1000).for_each(|ms| histogram.record(ms * 1000000));
+        assert_eq!(histogram.len(), 1000);
+        assert!(histogram.samples().is_none());
+        // within 3 significant digits
+        let p50 = histogram.percentile(50.0) as f64;
+        assert!((p50 - 500e6).abs() <= 500e6 * 1e-3);
+        // the rank may round up to the next sample
+        let p999 = histogram.percentile(99.9) as f64;
+        assert!((p999 - 999e6).abs() <= 1e6 + 999e6 * 1e-3);
+
+        let logger = LatencyLogger { latencies: RcWrapper::new(Rc::new(RefCell::new(LatencyHistogram::new(3, true)))) };
+        logger.latencies.borrow_mut().record(-1);
+        assert_eq!(logger.get_all_latencies(), Some(vec![-1]));
+        let other = LatencyLogger { latencies: RcWrapper::new(Rc::new(RefCell::new(histogram))) };
+        logger.merge(&other);
+        let latencies = logger.compute_latency().unwrap();
+        assert_eq!(logger.latencies.borrow().len(), 1001);
+        assert!((latencies["P99"] - 990.0).abs() <= 1.0);
+        // the other worker does not keep the samples
+        assert!(logger.get_all_latencies().is_none());
+    }
+
+    #[test]
+    fn throughput_meter_windows() {
+        let meter = ThroughputMeter::default();
+        let other = ThroughputMeter::default();
+        // one request per millisecond
+        for ms in 0..=(WARMUP_ITERS + 2 * THROUGHPUT_WINDOW_SIZE) as i64 {
+            meter.record(ms * 1000000);
+            other.record(ms * 1000000);
+        }
+        let logger = meter.logger();
+        assert_eq!(logger.throughput.borrow().len(), 2);
+        assert!((logger.compute_throughput().unwrap()["Mean"] - 1000.0).abs() < 1e-6);
+        assert!((logger.compute_overall_throughput().unwrap() - 1000.0).abs() < 1e-6);
+        logger.merge(&other.logger());
+        assert!((logger.compute_overall_throughput().unwrap() - 2000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn merge_thread_metrics() {
+        let merged = MetricsLogger::merge(vec![
+            thread_metrics(0, 100.0, 1_000_000_000, 3_000_000_000),
+            thread_metrics(1, 50.0, 2_000_000_000, 5_000_000_000),
+        ]);
+        let throughput = merged.throughput_loggers.get(&0).unwrap();
+        assert_eq!(*throughput.throughput.borrow(), vec![150.0, 75.0]);
+        assert_eq!(throughput.compute_overall_throughput(), Some(150.0));
+        assert_eq!(*throughput.warmup_throughput.borrow(), Some(150.0));
+        // the operators running on a single thread are kept as is
+        assert_eq!(merged.throughput_loggers.get(&2).unwrap().compute_overall_throughput(), Some(50.0));
+        assert_eq!(merged.input_throughput_loggers.get(&0).unwrap().compute_overall_throughput(), Some(150.0));
+
+        // the job completion times span both threads
+        let jct = merged.jct_loggers.get(&0).unwrap();
+        assert_eq!(jct.compute_operator_job_completion_time(), Some(4.0));
+        assert_eq!(jct.compute_path_job_completion_time(), Some(4.0 + 1e-9));
+
+        let slo = merged.slo_loggers.get(&0).unwrap();
+        assert_eq!(slo.compute_dropped(), 3);
+        assert_eq!(slo.compute_goodput(), Some(5.0));
+        let state = merged.state_loggers.get(&0).unwrap();
+        assert_eq!((state.compute_evicted(), state.compute_pending()), (3, 4));
+        let errors = merged.error_loggers.get(&0).unwrap();
+        assert_eq!((errors.compute_errors(), errors.compute_retries()), (3, 6));
+        let batch_sizes = merged.batch_loggers.get(&0).unwrap().compute_batch_sizes().unwrap();
+        assert_eq!(batch_sizes, BTreeMap::from([(1, 1), (2, 1), (4, 2)]));
+    }
+}
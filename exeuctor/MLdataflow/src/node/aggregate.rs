@@ -1,6 +1,6 @@
 use std::any::Any;
 use std::cell::RefCell;
-use std::collections::{HashMap, VecDeque};
+use std::collections::HashMap;
 use std::hash::Hash;
 use std::marker::PhantomData;
 use std::rc::Rc;
@@ -16,20 +16,19 @@ use timely::dataflow::{Stream, Scope};
 use timely::dataflow::operators::Operator;
 
 use crate::TimestampData;
-use crate::metrics::{LatencyLogger, RcWrapper, JCTLogger};
+use crate::metrics::{LatencyHistogram, LatencyLogger, RcWrapper, JCTLogger, ThroughputLogger, ThroughputMeter, StateLogger};
 use crate::static_timely::timely_static_pipeline_scope::PipelineScope;
+use crate::trace::{SpanKind, Tracer};
 
 use super::{LocalOpBuilder, ExchangeOpBuilder};
 use super::GenericStream;
 use super::GenericPipelineScope;
 
-const METRIC_KEEP_LAST_N: Option<usize> = None;
-
 // EXTEREMLY IMPORTANT!
 // NOTE: for aggregation / incremental aggregation operators, we have no concept of "latency"
 // for this operator and its consecutive operators, we only have the concept of JCT (job completion time) 
-
-// TODO: implement JCT logger for these aggregate operators
+// The execution latency of these operators is the time-to-complete of a key,
+// from when its first data is received to when its aggregation is emitted
 
 /// Aggregate data with the same key
 /// Input a stream of data type D1
@@ -49,11 +48,17 @@ where
     aggregate_logic: Option<L>,
     phantom: PhantomData<(D1, D2, K)>,
     phantom_scope: PhantomData<S>,
-    edge_latencies: Rc<RefCell<VecDeque<i64>>>,
-    path_latencies: Rc<RefCell<VecDeque<i64>>>,
+    edge_latencies: Rc<RefCell<LatencyHistogram>>,
+    path_latencies: Rc<RefCell<LatencyHistogram>>,
     data_start_timestamp: Rc<RefCell<Option<i64>>>,
     start_timestamp: Rc<RefCell<Option<i64>>>,
-    end_timestamp: Rc<RefCell<Option<i64>>>
+    end_timestamp: Rc<RefCell<Option<i64>>>,
+    // time from the first data of a key received to its aggregation emitted
+    execution_latencies: Rc<RefCell<LatencyHistogram>>,
+    input_throughput: ThroughputMeter,
+    throughput: ThroughputMeter,
+    // #keys with buffered data
+    pending_count: Rc<RefCell<usize>>
 }
 
 impl<D1: Data, D2: Data, K, L, H, S> AggregateNode<D1, D2, K, L, H, S>
@@ -70,11 +75,15 @@ where
             aggregate_logic: Some(agg_logic),
             phantom: PhantomData,
             phantom_scope: PhantomData,
-            edge_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            path_latencies: Rc::new(RefCell::new(VecDeque::new())),
+            edge_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            path_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
             data_start_timestamp: Rc::new(RefCell::new(None)),
             start_timestamp: Rc::new(RefCell::new(None)),
-            end_timestamp: Rc::new(RefCell::new(None))
+            end_timestamp: Rc::new(RefCell::new(None)),
+            execution_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            input_throughput: ThroughputMeter::default(),
+            throughput: ThroughputMeter::default(),
+            pending_count: Rc::new(RefCell::new(0))
         }
     }
 }
@@ -97,6 +106,7 @@ where
         else { None };
         let reset_timestamp = reset_timestamp.unwrap_or(false);
 
+        let tracer = Tracer::from_config(&config);
         let stream_in = streams[0];
         let stream_in = stream_in.as_any().downcast_ref::<Stream<S, TimestampData<D1>>>().unwrap();
 
@@ -105,6 +115,10 @@ where
         let data_start_timestamp = self.data_start_timestamp.clone();
         let start_timestamp = self.start_timestamp.clone();
         let end_timestamp = self.end_timestamp.clone();
+        let execution_latency_metrics = self.execution_latencies.clone();
+        let input_throughput = self.input_throughput.clone();
+        let throughput = self.throughput.clone();
+        let pending_count = self.pending_count.clone();
 
         let key_map_logic = self.key_map_logic.take().unwrap();
         let mut aggregate_logic = self.aggregate_logic.take().unwrap();
@@ -116,12 +130,15 @@ where
                 input.for_each(|time, data| {
                     data.swap(&mut vector);
                     let mut session = output.session(&time);
+                    let received_ts = Utc::now().timestamp_nanos();
                     for data_point in vector.drain(..) {
+                        input_throughput.record(received_ts);
                         let (key, total_num) = (key_map_logic)(&data_point.data);
-                        buffer.entry(key.clone()).or_insert(Vec::new()).push(data_point);
-                        if buffer.get(&key).unwrap().len() == total_num {
-                            let all_data_points_with_k = buffer.remove(&key).unwrap();
+                        buffer.entry(key.clone()).or_insert_with(|| (received_ts, Vec::new())).1.push(data_point);
+                        if buffer.get(&key).unwrap().1.len() == total_num {
+                            let (buffered_since, all_data_points_with_k) = buffer.remove(&key).unwrap();
                             let min_start_ts = all_data_points_with_k.iter().map(|x| x.start_timestamp).min().unwrap();
+                            let request_id = all_data_points_with_k.iter().min_by_key(|x| x.start_timestamp).unwrap().request_id;
                             let min_last_ts = all_data_points_with_k.iter().map(|x| x.last_timestamp).min().unwrap();
                             let all_data_points_raw = all_data_points_with_k.into_iter().map(|x| x.data).collect();
                             let aggregation_result = (aggregate_logic)(all_data_points_raw);
@@ -138,16 +155,18 @@ where
                                 *start_timestamp.borrow_mut() = Some(curr_ts);
                             }
                             *end_timestamp.borrow_mut() = Some(curr_ts);
-                            edge_latency_metrics.borrow_mut().push_front(curr_ts - min_last_ts);
-                            path_latency_metrics.borrow_mut().push_front(curr_ts - min_start_ts);
-                
-                            if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                                edge_latency_metrics.borrow_mut().truncate(keep_n);
-                                path_latency_metrics.borrow_mut().truncate(keep_n);
+                            execution_latency_metrics.borrow_mut().record(curr_ts - buffered_since);
+                            if let Some(tracer) = &tracer {
+                                tracer.trace_span(request_id, SpanKind::Wait, buffered_since, curr_ts);
                             }
+                            edge_latency_metrics.borrow_mut().record(curr_ts - min_last_ts);
+                            path_latency_metrics.borrow_mut().record(curr_ts - min_start_ts);
+                            throughput.record(curr_ts);
+                
                             session.give(if reset_timestamp { 
                                 TimestampData {
                                     data: aggregation_result,
+                                    request_id,
                                     start_timestamp: curr_ts,
                                     last_timestamp: curr_ts,
                                     total_exec_net_latency: 0
@@ -155,6 +174,7 @@ where
                             } else {
                                 TimestampData {
                                     data: aggregation_result,
+                                    request_id,
                                     start_timestamp: min_start_ts,
                                     last_timestamp: curr_ts,
                                     total_exec_net_latency: curr_ts - min_start_ts
@@ -163,17 +183,25 @@ where
                         }
                     }
                 });
+                *pending_count.borrow_mut() = buffer.len();
             }
         });
         Box::new(stream_out)
     }
 
-    fn get_throughput_logger(&self) -> Option<crate::metrics::ThroughputLogger> {
-        None
+    fn get_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.throughput.logger())
+    }
+
+    fn get_input_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.input_throughput.logger())
     }
 
     fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger> {
-        None
+        let logger = LatencyLogger {
+            latencies: RcWrapper::new(self.execution_latencies.clone())
+        };
+        Some(logger)
     }
 
     fn get_flow_edge_latency_logger(&self) -> Option<LatencyLogger> {
@@ -198,6 +226,15 @@ where
         };
         Some(logger)
     }
+
+    fn get_state_logger(&self) -> Option<StateLogger> {
+        let logger = StateLogger {
+            // the data of a key is buffered until the key completes, never evicted
+            evicted: RcWrapper::new(Rc::new(RefCell::new(0))),
+            pending: RcWrapper::new(self.pending_count.clone())
+        };
+        Some(logger)
+    }
 }
 
 
@@ -246,11 +283,17 @@ where
     aggregate_logic: Option<L>,
     phantom: PhantomData<(D1, D2, K)>,
     phantom_scope: PhantomData<S>,
-    edge_latencies: Rc<RefCell<VecDeque<i64>>>,
-    path_latencies: Rc<RefCell<VecDeque<i64>>>,
+    edge_latencies: Rc<RefCell<LatencyHistogram>>,
+    path_latencies: Rc<RefCell<LatencyHistogram>>,
     data_start_timestamp: Rc<RefCell<Option<i64>>>,
     start_timestamp: Rc<RefCell<Option<i64>>>,
-    end_timestamp: Rc<RefCell<Option<i64>>>    
+    end_timestamp: Rc<RefCell<Option<i64>>>,
+    // time from the first data of a key received to its aggregation emitted
+    execution_latencies: Rc<RefCell<LatencyHistogram>>,
+    input_throughput: ThroughputMeter,
+    throughput: ThroughputMeter,
+    // #keys with buffered data
+    pending_count: Rc<RefCell<usize>>
 }
 
 impl<D1: Data, D2: Data, K, L, H, S> TimestampAggregateNode<D1, D2, K, L, H, S>
@@ -267,11 +310,15 @@ where
             aggregate_logic: Some(agg_logic),
             phantom: PhantomData,
             phantom_scope: PhantomData,
-            edge_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            path_latencies: Rc::new(RefCell::new(VecDeque::new())),
+            edge_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            path_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
             data_start_timestamp: Rc::new(RefCell::new(None)),
             start_timestamp: Rc::new(RefCell::new(None)),
-            end_timestamp: Rc::new(RefCell::new(None))
+            end_timestamp: Rc::new(RefCell::new(None)),
+            execution_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            input_throughput: ThroughputMeter::default(),
+            throughput: ThroughputMeter::default(),
+            pending_count: Rc::new(RefCell::new(0))
         }
     }
 }
@@ -294,6 +341,7 @@ where
         else { None };
         let reset_timestamp = reset_timestamp.unwrap_or(false);        
 
+        let tracer = Tracer::from_config(&config);
         let stream_in = streams[0];
         let stream_in = stream_in.as_any().downcast_ref::<Stream<S, TimestampData<D1>>>().unwrap();
 
@@ -308,14 +356,20 @@ where
         let data_start_timestamp = self.data_start_timestamp.clone();
         let start_timestamp = self.start_timestamp.clone();
         let end_timestamp = self.end_timestamp.clone();
+        let execution_latency_metrics = self.execution_latencies.clone();
+        let input_throughput = self.input_throughput.clone();
+        let throughput = self.throughput.clone();
+        let pending_count = self.pending_count.clone();
     
         let stream_out = stream_in.unary_notify(Pipeline, "Aggregate", vec![], move |input, output, notificator| {
             input.for_each(|time, data| {
                 data.swap(&mut vector);
+                let received_ts = Utc::now().timestamp_nanos();
                 let agg_time = aggregate_buffers.entry(time.time().clone()).or_insert_with(HashMap::new);
                 for data_point in vector.drain(..) {
+                    input_throughput.record(received_ts);
                     let key = (key_map_logic)(&data_point.data);
-                    agg_time.entry(key).or_insert(Vec::new()).push(data_point);
+                    agg_time.entry(key).or_insert_with(|| (received_ts, Vec::new())).1.push(data_point);
                 }
                 notificator.notify_at(time.retain());
             });
@@ -323,8 +377,9 @@ where
             notificator.for_each(|time, _, _| {
                 if let Some(agg_time) = aggregate_buffers.remove(time.time()) {
                     let mut session = output.session(&time);
-                    for (_key, data_points) in agg_time {
+                    for (_key, (buffered_since, data_points)) in agg_time {
                         let min_start_ts = data_points.iter().map(|x| x.start_timestamp).min().unwrap();
+                        let request_id = data_points.iter().min_by_key(|x| x.start_timestamp).unwrap().request_id;
                         let min_last_ts = data_points.iter().map(|x| x.last_timestamp).min().unwrap();
                         let data_points_raw = data_points.into_iter().map(|x| x.data).collect();
                         let aggregated = (aggregate_logic)(data_points_raw);
@@ -341,16 +396,18 @@ where
                             *start_timestamp.borrow_mut() = Some(curr_ts);
                         }
                         *end_timestamp.borrow_mut() = Some(curr_ts);                
-                        edge_latency_metrics.borrow_mut().push_front(curr_ts - min_last_ts);
-                        path_latency_metrics.borrow_mut().push_front(curr_ts - min_start_ts);
-                        
-                        if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                            edge_latency_metrics.borrow_mut().truncate(keep_n);
-                            path_latency_metrics.borrow_mut().truncate(keep_n);
+                        execution_latency_metrics.borrow_mut().record(curr_ts - buffered_since);
+                        if let Some(tracer) = &tracer {
+                            tracer.trace_span(request_id, SpanKind::Wait, buffered_since, curr_ts);
                         }
+                        edge_latency_metrics.borrow_mut().record(curr_ts - min_last_ts);
+                        path_latency_metrics.borrow_mut().record(curr_ts - min_start_ts);
+                        throughput.record(curr_ts);
+                        
                         session.give(if reset_timestamp {
                             TimestampData {
                                 data: aggregated,
+                                request_id,
                                 start_timestamp: curr_ts,
                                 last_timestamp: curr_ts,
                                 total_exec_net_latency: 0
@@ -358,6 +415,7 @@ where
                         } else {
                             TimestampData {
                                 data: aggregated,
+                                request_id,
                                 start_timestamp: min_start_ts,
                                 last_timestamp: curr_ts,
                                 total_exec_net_latency: curr_ts - min_start_ts
@@ -366,16 +424,24 @@ where
                     }
                 }
             });
+            *pending_count.borrow_mut() = aggregate_buffers.values().map(|agg_time| agg_time.len()).sum();
         });
         Box::new(stream_out)
     }
 
-    fn get_throughput_logger(&self) -> Option<crate::metrics::ThroughputLogger> {
-        None
+    fn get_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.throughput.logger())
+    }
+
+    fn get_input_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.input_throughput.logger())
     }
 
     fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger> {
-        None
+        let logger = LatencyLogger {
+            latencies: RcWrapper::new(self.execution_latencies.clone())
+        };
+        Some(logger)
     }
 
     fn get_flow_edge_latency_logger(&self) -> Option<LatencyLogger> {
@@ -400,6 +466,15 @@ where
         };
         Some(logger)
     }
+
+    fn get_state_logger(&self) -> Option<StateLogger> {
+        let logger = StateLogger {
+            // the data of a key is buffered until the key completes, never evicted
+            evicted: RcWrapper::new(Rc::new(RefCell::new(0))),
+            pending: RcWrapper::new(self.pending_count.clone())
+        };
+        Some(logger)
+    }
 }
 
 impl<D1: Data, D2: ExchangeData, K, L, H, A, T> ExchangeOpBuilder for TimestampAggregateNode<D1, D2, K, L, H, PipelineScope<A, T>>
@@ -454,11 +529,17 @@ where
     emit_logic: Option<E>,
     phantom: PhantomData<(D, Di, R, K)>,
     phantom_scope: PhantomData<S>,
-    edge_latencies: Rc<RefCell<VecDeque<i64>>>,
-    path_latencies: Rc<RefCell<VecDeque<i64>>>,
+    edge_latencies: Rc<RefCell<LatencyHistogram>>,
+    path_latencies: Rc<RefCell<LatencyHistogram>>,
     data_start_timestamp: Rc<RefCell<Option<i64>>>,
     start_timestamp: Rc<RefCell<Option<i64>>>,
-    end_timestamp: Rc<RefCell<Option<i64>>>    
+    end_timestamp: Rc<RefCell<Option<i64>>>,
+    // time from the first data of a key received to its aggregation emitted
+    execution_latencies: Rc<RefCell<LatencyHistogram>>,
+    input_throughput: ThroughputMeter,
+    throughput: ThroughputMeter,
+    // #keys with buffered data
+    pending_count: Rc<RefCell<usize>>
 }
 
 impl<D: Data, Di: Default + 'static, R: Data, K, H, F, E, S> IncrementalAggregateNode<D, Di, R, K, H, F, E, S>
@@ -477,11 +558,15 @@ where
             emit_logic: Some(emit_logic),
             phantom: PhantomData,
             phantom_scope: PhantomData,
-            edge_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            path_latencies: Rc::new(RefCell::new(VecDeque::new())),
+            edge_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            path_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
             data_start_timestamp: Rc::new(RefCell::new(None)),
             start_timestamp: Rc::new(RefCell::new(None)),
-            end_timestamp: Rc::new(RefCell::new(None)),                   
+            end_timestamp: Rc::new(RefCell::new(None)),
+            execution_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            input_throughput: ThroughputMeter::default(),
+            throughput: ThroughputMeter::default(),
+            pending_count: Rc::new(RefCell::new(0))
         }
     }
 }
@@ -505,6 +590,7 @@ where
         else { None };
         let reset_timestamp = reset_timestamp.unwrap_or(false);
 
+        let tracer = Tracer::from_config(&config);
         let stream_in = streams[0];
         let stream_in = stream_in.as_any().downcast_ref::<Stream<S, TimestampData<D>>>().unwrap();
 
@@ -517,6 +603,10 @@ where
         let data_start_timestamp = self.data_start_timestamp.clone();
         let start_timestamp = self.start_timestamp.clone();
         let end_timestamp = self.end_timestamp.clone();
+        let execution_latency_metrics = self.execution_latencies.clone();
+        let input_throughput = self.input_throughput.clone();
+        let throughput = self.throughput.clone();
+        let pending_count = self.pending_count.clone();
 
         let stream_out = stream_in.unary(Pipeline, "IncrementalAggregate", |_capability, _info| {
             let mut aggregates = HashMap::new();
@@ -525,13 +615,19 @@ where
                 input.for_each(|time, data| {
                     data.swap(&mut vector);
                     let mut session = output.session(&time);
+                    let received_ts = Utc::now().timestamp_nanos();
                     for data_point in vector.drain(..) {
+                        input_throughput.record(received_ts);
                         let key = (hash_logic)(&data_point.data);
-                        let (agg_intermediate, min_start_ts, min_last_ts) = aggregates.entry(key.clone()).or_insert((Default::default(), data_point.start_timestamp, data_point.last_timestamp));
-                        *min_start_ts = std::cmp::min(data_point.start_timestamp, *min_start_ts);
+                        let (agg_intermediate, request_id, min_start_ts, min_last_ts, _) = aggregates.entry(key.clone())
+                            .or_insert((Default::default(), data_point.request_id, data_point.start_timestamp, data_point.last_timestamp, received_ts));
+                        if data_point.start_timestamp < *min_start_ts {
+                            *request_id = data_point.request_id;
+                            *min_start_ts = data_point.start_timestamp;
+                        }
                         *min_last_ts = std::cmp::min(data_point.last_timestamp, *min_last_ts);
                         if (fold_logic)(&key, data_point.data, agg_intermediate) {
-                            let (completed_intermediate, min_start_ts, min_last_ts) = aggregates.remove(&key).unwrap();
+                            let (completed_intermediate, request_id, min_start_ts, min_last_ts, buffered_since) = aggregates.remove(&key).unwrap();
                             let result = (emit_logic)(key, completed_intermediate);
                             let curr_ts = Utc::now().timestamp_nanos();
 
@@ -546,16 +642,18 @@ where
                                 *start_timestamp.borrow_mut() = Some(curr_ts);
                             }
                             *end_timestamp.borrow_mut() = Some(curr_ts);
-                            edge_latency_metrics.borrow_mut().push_front(curr_ts - min_last_ts);
-                            path_latency_metrics.borrow_mut().push_front(curr_ts - min_start_ts);
+                            execution_latency_metrics.borrow_mut().record(curr_ts - buffered_since);
+                            if let Some(tracer) = &tracer {
+                                tracer.trace_span(request_id, SpanKind::Wait, buffered_since, curr_ts);
+                            }
+                            edge_latency_metrics.borrow_mut().record(curr_ts - min_last_ts);
+                            path_latency_metrics.borrow_mut().record(curr_ts - min_start_ts);
+                            throughput.record(curr_ts);
                 
-                            if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                                edge_latency_metrics.borrow_mut().truncate(keep_n);
-                                path_latency_metrics.borrow_mut().truncate(keep_n);
-                            }                            
                             session.give(if reset_timestamp {
                                 TimestampData {
                                     data: result,
+                                    request_id,
                                     start_timestamp: curr_ts,
                                     last_timestamp: curr_ts,
                                     total_exec_net_latency: 0
@@ -563,6 +661,7 @@ where
                             } else {
                                 TimestampData {
                                     data: result,
+                                    request_id,
                                     start_timestamp: min_start_ts,
                                     last_timestamp: curr_ts,
                                     total_exec_net_latency: curr_ts - min_start_ts
@@ -572,17 +671,25 @@ where
 
                     }
                 });
+                *pending_count.borrow_mut() = aggregates.len();
             }
         });
         Box::new(stream_out)
     }
 
-    fn get_throughput_logger(&self) -> Option<crate::metrics::ThroughputLogger> {
-        None
+    fn get_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.throughput.logger())
+    }
+
+    fn get_input_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.input_throughput.logger())
     }
 
     fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger> {
-        None
+        let logger = LatencyLogger {
+            latencies: RcWrapper::new(self.execution_latencies.clone())
+        };
+        Some(logger)
     }
 
     fn get_flow_edge_latency_logger(&self) -> Option<LatencyLogger> {
@@ -607,6 +714,15 @@ where
         };
         Some(logger)
     }
+
+    fn get_state_logger(&self) -> Option<StateLogger> {
+        let logger = StateLogger {
+            // the data of a key is buffered until the key completes, never evicted
+            evicted: RcWrapper::new(Rc::new(RefCell::new(0))),
+            pending: RcWrapper::new(self.pending_count.clone())
+        };
+        Some(logger)
+    }
 }
 
 impl<D: Data, Di: Default + 'static, R: ExchangeData, K, H, F, E, A, T> ExchangeOpBuilder for IncrementalAggregateNode<D, Di, R, K, H, F, E, PipelineScope<A, T>>
@@ -659,11 +775,17 @@ where
     emit_logic: Option<E>,
     phantom: PhantomData<(D, Di, R, K)>,
     phantom_scope: PhantomData<S>,
-    edge_latencies: Rc<RefCell<VecDeque<i64>>>,
-    path_latencies: Rc<RefCell<VecDeque<i64>>>,
+    edge_latencies: Rc<RefCell<LatencyHistogram>>,
+    path_latencies: Rc<RefCell<LatencyHistogram>>,
     data_start_timestamp: Rc<RefCell<Option<i64>>>,
     start_timestamp: Rc<RefCell<Option<i64>>>,
-    end_timestamp: Rc<RefCell<Option<i64>>>    
+    end_timestamp: Rc<RefCell<Option<i64>>>,
+    // time from the first data of a key received to its aggregation emitted
+    execution_latencies: Rc<RefCell<LatencyHistogram>>,
+    input_throughput: ThroughputMeter,
+    throughput: ThroughputMeter,
+    // #keys with buffered data
+    pending_count: Rc<RefCell<usize>>
 }
 
 impl<D: Data, Di: Default + 'static, R: Data, K, H, F, E, S> TimestampIncrementalAggregateNode<D, Di, R, K, H, F, E, S>
@@ -682,11 +804,15 @@ where
             emit_logic: Some(emit_logic),
             phantom: PhantomData,
             phantom_scope: PhantomData,
-            edge_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            path_latencies: Rc::new(RefCell::new(VecDeque::new())),
+            edge_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            path_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
             data_start_timestamp: Rc::new(RefCell::new(None)),
             start_timestamp: Rc::new(RefCell::new(None)),
-            end_timestamp: Rc::new(RefCell::new(None))            
+            end_timestamp: Rc::new(RefCell::new(None)),
+            execution_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            input_throughput: ThroughputMeter::default(),
+            throughput: ThroughputMeter::default(),
+            pending_count: Rc::new(RefCell::new(0))
         }
     }
 }
@@ -710,6 +836,7 @@ where
         else { None };
         let reset_timestamp = reset_timestamp.unwrap_or(false);
 
+        let tracer = Tracer::from_config(&config);
         let stream_in = streams[0];
         let stream_in = stream_in.as_any().downcast_ref::<Stream<S, TimestampData<D>>>().unwrap();
 
@@ -725,15 +852,25 @@ where
         let data_start_timestamp = self.data_start_timestamp.clone();
         let start_timestamp = self.start_timestamp.clone();
         let end_timestamp = self.end_timestamp.clone();
+        let execution_latency_metrics = self.execution_latencies.clone();
+        let input_throughput = self.input_throughput.clone();
+        let throughput = self.throughput.clone();
+        let pending_count = self.pending_count.clone();
 
         let stream_out = stream_in.unary_notify(Pipeline, "TimestampIncrementalAggregate", vec![], move |input, output, notificator| {
             input.for_each(|time, data| {
                 data.swap(&mut vector);
+                let received_ts = Utc::now().timestamp_nanos();
                 let agg_time = aggregates.entry(time.time().clone()).or_insert_with(HashMap::new);
                 for data_point in vector.drain(..) {
+                    input_throughput.record(received_ts);
                     let key = (hash_logic)(&data_point.data);
-                    let (intermediate, min_start_ts,min_last_ts) = agg_time.entry(key.clone()).or_insert((Default::default(), data_point.start_timestamp, data_point.last_timestamp));
-                    *min_start_ts = std::cmp::min(data_point.start_timestamp, *min_start_ts);
+                    let (intermediate, request_id, min_start_ts, min_last_ts, _) = agg_time.entry(key.clone())
+                        .or_insert((Default::default(), data_point.request_id, data_point.start_timestamp, data_point.last_timestamp, received_ts));
+                    if data_point.start_timestamp < *min_start_ts {
+                        *request_id = data_point.request_id;
+                        *min_start_ts = data_point.start_timestamp;
+                    }
                     *min_last_ts = std::cmp::min(data_point.last_timestamp, *min_last_ts);
                     (fold_logic)(&key, data_point.data, intermediate);
                 }
@@ -743,7 +880,7 @@ where
             notificator.for_each(|time, _, _| {
                 if let Some(agg_time) = aggregates.remove(time.time()) {
                     let mut session = output.session(&time);
-                    for (key, (intermediate, min_start_ts, min_last_ts)) in agg_time {
+                    for (key, (intermediate, request_id, min_start_ts, min_last_ts, buffered_since)) in agg_time {
                         let result = (emit_logic)(key, intermediate);
                         let curr_ts =  Utc::now().timestamp_nanos();
 
@@ -758,16 +895,18 @@ where
                             *start_timestamp.borrow_mut() = Some(curr_ts);
                         }
                         *end_timestamp.borrow_mut() = Some(curr_ts);
-                        edge_latency_metrics.borrow_mut().push_front(curr_ts - min_last_ts);
-                        path_latency_metrics.borrow_mut().push_front(curr_ts - min_start_ts);
+                        execution_latency_metrics.borrow_mut().record(curr_ts - buffered_since);
+                        if let Some(tracer) = &tracer {
+                            tracer.trace_span(request_id, SpanKind::Wait, buffered_since, curr_ts);
+                        }
+                        edge_latency_metrics.borrow_mut().record(curr_ts - min_last_ts);
+                        path_latency_metrics.borrow_mut().record(curr_ts - min_start_ts);
+                        throughput.record(curr_ts);
             
-                        if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                            edge_latency_metrics.borrow_mut().truncate(keep_n);
-                            path_latency_metrics.borrow_mut().truncate(keep_n);
-                        }                        
                         session.give(if reset_timestamp {
                             TimestampData {
                                 data: result,
+                                request_id,
                                 start_timestamp: curr_ts,
                                 last_timestamp: curr_ts,
                                 total_exec_net_latency: 0
@@ -775,6 +914,7 @@ where
                         } else {
                             TimestampData {
                                 data: result,
+                                request_id,
                                 start_timestamp: min_start_ts,
                                 last_timestamp: curr_ts,
                                 total_exec_net_latency: curr_ts - min_start_ts
@@ -783,16 +923,24 @@ where
                     }
                 }
             });
+            *pending_count.borrow_mut() = aggregates.values().map(|agg_time| agg_time.len()).sum();
         });
         Box::new(stream_out)
     }
 
-    fn get_throughput_logger(&self) -> Option<crate::metrics::ThroughputLogger> {
-        None
+    fn get_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.throughput.logger())
+    }
+
+    fn get_input_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.input_throughput.logger())
     }
 
     fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger> {
-        None
+        let logger = LatencyLogger {
+            latencies: RcWrapper::new(self.execution_latencies.clone())
+        };
+        Some(logger)
     }
 
     fn get_flow_edge_latency_logger(&self) -> Option<LatencyLogger> {
@@ -817,6 +965,15 @@ where
         };
         Some(logger)
     }
+
+    fn get_state_logger(&self) -> Option<StateLogger> {
+        let logger = StateLogger {
+            // the data of a key is buffered until the key completes, never evicted
+            evicted: RcWrapper::new(Rc::new(RefCell::new(0))),
+            pending: RcWrapper::new(self.pending_count.clone())
+        };
+        Some(logger)
+    }
 }
 
 impl<D: Data, Di: Default + 'static, R: ExchangeData, K, H, F, E, A, T> ExchangeOpBuilder for TimestampIncrementalAggregateNode<D, Di, R, K, H, F, E, PipelineScope<A, T>>
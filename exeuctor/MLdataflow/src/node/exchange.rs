@@ -1,8 +1,11 @@
 use std::any::Any;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::marker::PhantomData;
+use std::rc::Rc;
 use std::sync::Arc;
 
+use chrono::Utc;
 use timely::communication::RelayConnectAllocate;
 use timely::progress::Timestamp;
 use timely::progress::timestamp::Refines;
@@ -11,7 +14,9 @@ use timely::ExchangeData;
 use timely::dataflow::operators::Exchange;
 
 use crate::TimestampData;
+use crate::metrics::{LatencyHistogram, LatencyLogger, RcWrapper, JCTLogger, ThroughputLogger, ThroughputMeter};
 use crate::static_timely::timely_static_pipeline_scope::PipelineScope;
+use crate::trace::{SpanKind, Tracer};
 use crate::operators_timely::Map;
 
 use super::{LocalOpBuilder, ExchangeOpBuilder};
@@ -20,6 +25,7 @@ use super::GenericPipelineScope;
 
 /// Exchange
 /// Exchange records between workers within a pipeline
+/// The metrics are logged by the sending worker, the execution latency is the time spent in the routing logic
 pub struct ExchangeNode<D, L, S> 
 where
     L: Fn(&D) -> u64 + 'static,
@@ -28,7 +34,14 @@ where
     prev_index: usize,
     logic: Option<L>,
     phantom: PhantomData<D>,
-    phantom_scope: PhantomData<S>
+    phantom_scope: PhantomData<S>,
+    execution_latencies: Rc<RefCell<LatencyHistogram>>,
+    edge_latencies: Rc<RefCell<LatencyHistogram>>,
+    path_latencies: Rc<RefCell<LatencyHistogram>>,
+    data_start_timestamp: Rc<RefCell<Option<i64>>>,
+    start_timestamp: Rc<RefCell<Option<i64>>>,
+    end_timestamp: Rc<RefCell<Option<i64>>>,
+    throughput: ThroughputMeter
 }
 
 impl<D, L, S> ExchangeNode<D, L, S>
@@ -41,7 +54,14 @@ where
             prev_index,
             logic: Some(logic),
             phantom: PhantomData,
-            phantom_scope: PhantomData
+            phantom_scope: PhantomData,
+            execution_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            edge_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            path_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            data_start_timestamp: Rc::new(RefCell::new(None)),
+            start_timestamp: Rc::new(RefCell::new(None)),
+            end_timestamp: Rc::new(RefCell::new(None)),
+            throughput: ThroughputMeter::default()
         }
     }
 }
@@ -60,39 +80,92 @@ where
             config.get("simulate_network_latency").and_then(|val| val.downcast_ref::<i64>()).map(|val| *val)
         }
         else { None };
+        let tracer = Tracer::from_config(&config);
+        let routing_tracer = tracer.clone();
 
         let stream_in = streams[0];
         let stream_in = stream_in.as_any().downcast_ref::<Stream<S, TimestampData<D>>>().unwrap();
+
+        let execution_latency_metrics = self.execution_latencies.clone();
+        let edge_latency_metrics = self.edge_latencies.clone();
+        let path_latency_metrics = self.path_latencies.clone();
+        let data_start_timestamp = self.data_start_timestamp.clone();
+        let start_timestamp = self.start_timestamp.clone();
+        let end_timestamp = self.end_timestamp.clone();
+        let throughput = self.throughput.clone();
+
         let stream_in = stream_in.map_in_place(move |x, mut net_lat| {
             if let Some(sim_net_lat) = sim_network_latency {
                 net_lat = sim_net_lat;
             }
             if net_lat < 0 { net_lat = 0 }    
-            x.total_exec_net_latency += net_lat
+            x.total_exec_net_latency += net_lat;
+
+            let curr_ts = Utc::now().timestamp_nanos();
+            if let Some(tracer) = &tracer {
+                tracer.trace_arrival(x.request_id, x.last_timestamp, net_lat, curr_ts);
+            }
+            edge_latency_metrics.borrow_mut().record(net_lat);
+            path_latency_metrics.borrow_mut().record(x.total_exec_net_latency);
+            let data_min_start_ts = if let Some(data_min_start_ts) = data_start_timestamp.borrow().as_ref().copied() {
+                std::cmp::min(data_min_start_ts, x.start_timestamp)
+            }
+            else {
+                x.start_timestamp
+            };
+            *data_start_timestamp.borrow_mut() = Some(data_min_start_ts);
+            if start_timestamp.borrow().is_none() {
+                *start_timestamp.borrow_mut() = Some(curr_ts);
+            }
+            *end_timestamp.borrow_mut() = Some(curr_ts);
+            throughput.record(curr_ts);
         });
         let logic = self.logic.take().unwrap();
-        let stream_out = stream_in.exchange(move |x| (logic)(&x.data));
+        let stream_out = stream_in.exchange(move |x| {
+            let op_start_ts = Utc::now().timestamp_nanos();
+            let route = (logic)(&x.data);
+            let op_finish_ts = Utc::now().timestamp_nanos();
+            execution_latency_metrics.borrow_mut().record(op_finish_ts - op_start_ts);
+            if let Some(tracer) = &routing_tracer {
+                tracer.trace_span(x.request_id, SpanKind::Execute, op_start_ts, op_finish_ts);
+            }
+            route
+        });
         Box::new(stream_out)
     }
 
-    fn get_throughput_logger(&self) -> Option<crate::metrics::ThroughputLogger> {
-        None
+    fn get_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.throughput.logger())
     }
 
-    fn get_flow_compute_latency_logger(&self) -> Option<crate::metrics::LatencyLogger> {
-        None
+    fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger> {
+        let logger = LatencyLogger {
+            latencies: RcWrapper::new(self.execution_latencies.clone())
+        };
+        Some(logger)
     }
 
-    fn get_flow_edge_latency_logger(&self) -> Option<crate::metrics::LatencyLogger> {
-        None
+    fn get_flow_edge_latency_logger(&self) -> Option<LatencyLogger> {
+        let logger = LatencyLogger {
+            latencies: RcWrapper::new(self.edge_latencies.clone())
+        };
+        Some(logger)
     }
 
-    fn get_flow_path_latency_logger(&self) -> Option<crate::metrics::LatencyLogger> {
-        None
+    fn get_flow_path_latency_logger(&self) -> Option<LatencyLogger> {
+        let logger = LatencyLogger {
+            latencies: RcWrapper::new(self.path_latencies.clone())
+        };
+        Some(logger)
     }
 
-    fn get_jct_logger(&self) -> Option<crate::metrics::JCTLogger> {
-        None
+    fn get_jct_logger(&self) -> Option<JCTLogger> {
+        let logger = JCTLogger {
+            data_start_timestamp: RcWrapper::new(self.data_start_timestamp.clone()),
+            op_start_timestamp: RcWrapper::new(self.start_timestamp.clone()),
+            op_end_timestamp: RcWrapper::new(self.end_timestamp.clone())
+        };
+        Some(logger)
     }
 }
 
@@ -14,8 +14,9 @@ use timely::dataflow::{Stream, Scope};
 use timely::dataflow::operators::Map;
 
 use crate::TimestampData;
-use crate::metrics::{LatencyLogger, JCTLogger, ThroughputLogger, RcWrapper};
+use crate::metrics::{LatencyHistogram, LatencyLogger, JCTLogger, ThroughputLogger, RcWrapper};
 use crate::static_timely::timely_static_pipeline_scope::PipelineScope;
+use crate::trace::Tracer;
 use crate::operators_timely::Filter;
 
 use super::{LocalOpBuilder, ExchangeOpBuilder};
@@ -45,9 +46,9 @@ where
     window_count: Rc<RefCell<usize>>,
     window_start_timestamp: Rc<RefCell<i64>>,
     throughput: Rc<RefCell<VecDeque<f64>>>,    
-    execution_latencies: Rc<RefCell<VecDeque<i64>>>,
-    edge_latencies: Rc<RefCell<VecDeque<i64>>>,
-    path_latencies: Rc<RefCell<VecDeque<i64>>>,
+    execution_latencies: Rc<RefCell<LatencyHistogram>>,
+    edge_latencies: Rc<RefCell<LatencyHistogram>>,
+    path_latencies: Rc<RefCell<LatencyHistogram>>,
     // Timestamp when wramup is finished
     warmed_timestamp: Rc<RefCell<Option<i64>>>,
     // Total number of requests processed after warmup
@@ -76,9 +77,9 @@ where
             window_count: Rc::new(RefCell::new(0)),
             window_start_timestamp: Rc::new(RefCell::new(0)),
             throughput: Rc::new(RefCell::new(VecDeque::new())),
-            execution_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            edge_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            path_latencies: Rc::new(RefCell::new(VecDeque::new())),
+            execution_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            edge_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            path_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
             warmed_timestamp: Rc::new(RefCell::new(None)),
             total_warmed_count: Rc::new(RefCell::new(0)),
             overall_throughput: Rc::new(RefCell::new(None))            
@@ -99,6 +100,7 @@ where
         }
         else { None };
         let reset_timestamp = reset_timestamp.unwrap_or(false);
+        let tracer = Tracer::from_config(&config);
 
         let stream_in = streams[0];
         let stream_in = stream_in.as_any().downcast_ref::<Stream<S, TimestampData<D>>>().unwrap();
@@ -132,19 +134,17 @@ where
             let op_start_ts = Utc::now().timestamp_nanos();
             let predicate = (logic)(&x.data);
             let op_finish_ts = Utc::now().timestamp_nanos();
+            if let Some(tracer) = &tracer {
+                tracer.trace_execution(x.request_id, x.last_timestamp, net_lat, op_start_ts, op_finish_ts);
+            }
 
             let exec_lat = op_finish_ts - op_start_ts;
-            execution_latency_metrics.borrow_mut().push_front(exec_lat);
-            // edge_latency_metrics.borrow_mut().push_front(op_finish_ts - x.last_timestamp);
-            // path_latency_metrics.borrow_mut().push_front(op_finish_ts - x.start_timestamp);
-            edge_latency_metrics.borrow_mut().push_front(exec_lat + net_lat);
-            path_latency_metrics.borrow_mut().push_front(exec_lat + net_lat + x.total_exec_net_latency);
+            execution_latency_metrics.borrow_mut().record(exec_lat);
+            // edge_latency_metrics.borrow_mut().record(op_finish_ts - x.last_timestamp);
+            // path_latency_metrics.borrow_mut().record(op_finish_ts - x.start_timestamp);
+            edge_latency_metrics.borrow_mut().record(exec_lat + net_lat);
+            path_latency_metrics.borrow_mut().record(exec_lat + net_lat + x.total_exec_net_latency);
 
-            if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                execution_latency_metrics.borrow_mut().truncate(keep_n);
-                edge_latency_metrics.borrow_mut().truncate(keep_n);
-                path_latency_metrics.borrow_mut().truncate(keep_n);
-            }
             if warmup_start_timestamp.borrow().is_none() {
                 *warmup_count.borrow_mut() = 0;
                 *start_timestamp.borrow_mut() = Some(op_start_ts);
@@ -185,6 +185,7 @@ where
             if reset_timestamp {
                 TimestampData {
                     data: x.data,
+                    request_id: x.request_id,
                     start_timestamp: curr_ts,
                     last_timestamp: curr_ts,
                     total_exec_net_latency: 0
@@ -193,6 +194,7 @@ where
             else {
                 TimestampData {
                     data: x.data,
+                    request_id: x.request_id,
                     start_timestamp: x.start_timestamp,
                     last_timestamp: curr_ts,
                     total_exec_net_latency: x.total_exec_net_latency + exec_net_lat
@@ -1,10 +1,11 @@
 use std::any::Any;
 use std::rc::Rc;
 use std::cell::RefCell;
-use std::collections::{HashMap, VecDeque};
+use std::collections::HashMap;
 use std::hash::Hash;
 use std::marker::PhantomData;
 use std::sync::Arc;
+use std::time::Duration;
 
 use chrono::Utc;
 use timely::communication::{RelayConnectAllocate, MessageLatency};
@@ -13,24 +14,26 @@ use timely::progress::timestamp::Refines;
 use timely::dataflow::channels::pact::Pipeline;
 use timely::{Data, ExchangeData};
 use timely::dataflow::{Stream, Scope, ScopeParent};
-use timely::dataflow::operators::Operator;
+use timely::dataflow::operators::{Capability, Operator};
+use timely::scheduling::Activator;
 
-use crate::TimestampData;
-use crate::metrics::{LatencyLogger, RcWrapper, JCTLogger};
+use crate::{RequestId, TimestampData};
+use crate::metrics::{LatencyHistogram, LatencyLogger, RcWrapper, JCTLogger, StateLogger, ThroughputLogger, ThroughputMeter};
 use crate::static_timely::timely_static_pipeline_scope::PipelineScope;
+use crate::trace::{SpanKind, Tracer};
 
 use super::{LocalOpBuilder, ExchangeOpBuilder};
 use super::GenericStream;
 use super::GenericPipelineScope;
 
-const METRIC_KEEP_LAST_N: Option<usize> = None;
-
 /// Inner join
 /// key_map_left, key_map_right are hash functions
 /// that maps the data on the left stream
 /// and the data on the right stream into a key of type K
 /// These two hash functions also provide the total number of items
 /// on the left/right stream that correspond with this key
+/// The data of a key is evicted if it is still unmatched after `join_ttl` (in nanoseconds, operator config),
+/// and the data of an input is evicted once the other input completes
 pub struct JoinNode<D1, D2, K, H1, H2, S>
 where
     H1: Fn(&D1) -> (K, usize) + 'static,
@@ -43,11 +46,19 @@ where
     key_map_right: Option<H2>,
     phantom: PhantomData<(D1, D2, K)>,
     phantom_scope: PhantomData<S>,
-    edge_latencies: Rc<RefCell<VecDeque<i64>>>,
-    path_latencies: Rc<RefCell<VecDeque<i64>>>,
+    edge_latencies: Rc<RefCell<LatencyHistogram>>,
+    path_latencies: Rc<RefCell<LatencyHistogram>>,
     data_start_timestamp: Rc<RefCell<Option<i64>>>,
     start_timestamp: Rc<RefCell<Option<i64>>>,
-    end_timestamp: Rc<RefCell<Option<i64>>>
+    // time from the first data of a matched pair received to the pair matched
+    execution_latencies: Rc<RefCell<LatencyHistogram>>,
+    input_throughput: ThroughputMeter,
+    throughput: ThroughputMeter,
+    end_timestamp: Rc<RefCell<Option<i64>>>,
+    // #keys evicted before all their data arrives
+    evicted_count: Rc<RefCell<usize>>,
+    // #keys with buffered data
+    pending_count: Rc<RefCell<usize>>
 }
 
 
@@ -66,11 +77,16 @@ where
             key_map_right: Some(right_hash_logic),
             phantom: PhantomData,
             phantom_scope: PhantomData,
-            edge_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            path_latencies: Rc::new(RefCell::new(VecDeque::new())),
+            edge_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            path_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
             data_start_timestamp: Rc::new(RefCell::new(None)),
             start_timestamp: Rc::new(RefCell::new(None)),
-            end_timestamp: Rc::new(RefCell::new(None))   
+            execution_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            input_throughput: ThroughputMeter::default(),
+            throughput: ThroughputMeter::default(),
+            end_timestamp: Rc::new(RefCell::new(None)),
+            evicted_count: Rc::new(RefCell::new(0)),
+            pending_count: Rc::new(RefCell::new(0))
         }
     }
 }
@@ -97,7 +113,12 @@ where
             config.get("simulate_network_latency").and_then(|val| val.downcast_ref::<i64>()).map(|val| *val)
         }
         else { None };
+        let join_ttl = if let Some(config) = &config {
+            config.get("join_ttl").and_then(|val| val.downcast_ref::<i64>()).copied()
+        }
+        else { None };
 
+        let tracer = Tracer::from_config(&config);
         let stream_left = streams[0];
         let stream_right = streams[1];
         let stream_left = stream_left.as_any().downcast_ref::<Stream<S, TimestampData<D1>>>().unwrap();
@@ -110,24 +131,36 @@ where
         let path_latency_metrics = self.path_latencies.clone();
         let data_start_timestamp = self.data_start_timestamp.clone();
         let start_timestamp = self.start_timestamp.clone();
+        let execution_latency_metrics = self.execution_latencies.clone();
+        let input_throughput = self.input_throughput.clone();
+        let throughput = self.throughput.clone();
         let end_timestamp = self.end_timestamp.clone();
+        let evicted_count = self.evicted_count.clone();
+        let pending_count = self.pending_count.clone();
         
-        let stream_out = stream_left.binary(
+        let scope = stream_left.scope();
+        let stream_out = stream_left.binary_frontier(
             stream_right,
             Pipeline,
             Pipeline,
             "InnerJoin",
-            |_capability, _info| {
-                let mut map_left: HashMap<K, Vec<(TimestampData<D1>, MessageLatency)>> = HashMap::new();
-                let mut map_right: HashMap<K, Vec<(TimestampData<D2>, MessageLatency)>> = HashMap::new();
+            |_capability, info| {
+                let activator = scope.activator_for(&info.address[..]);
+                let mut map_left: HashMap<K, Vec<(TimestampData<D1>, MessageLatency, i64)>> = HashMap::new();
+                let mut map_right: HashMap<K, Vec<(TimestampData<D2>, MessageLatency, i64)>> = HashMap::new();
 
                 let mut vector_left = Vec::new();
                 let mut vector_right = Vec::new();
 
                 let mut key_count_left = HashMap::new();
                 let mut key_count_right = HashMap::new();
+                // when the data of each key is first buffered
+                let mut buffered_since: HashMap<K, i64> = HashMap::new();
 
                 move |input_left, input_right, output| {
+                    // the data of an input can no longer be matched once the other input completes
+                    let left_done = input_left.frontier().frontier().is_empty();
+                    let right_done = input_right.frontier().frontier().is_empty();
                     input_left.for_each_with_latency(|time, data, net_lat| {
                         let mut left_net_lat = if let Some(lat) = net_lat { lat }
                         else { 0 };
@@ -137,21 +170,24 @@ where
                         if left_net_lat < 0 { left_net_lat = 0 }
                         data.swap(&mut vector_left);
                         let mut session = output.session(&time);
+                        let received_ts = Utc::now().timestamp_nanos();
                         for data_point_left in vector_left.drain(..) {
+                            input_throughput.record(received_ts);
                             let (key, total_num_key) = (key_map_left)(&data_point_left.data);
                             key_count_left.entry(key.clone()).or_insert(total_num_key);
                             if let Some(values) = map_right.get(&key) {
-                                for (val_right, right_net_lat) in values.iter() {
+                                for (val_right, right_net_lat, buffered_ts) in values.iter() {
                                     let min_start_ts = std::cmp::min(data_point_left.start_timestamp, val_right.start_timestamp);
+                                    let request_id = earliest_request_id(&data_point_left, val_right);
                                     // let min_last_ts = std::cmp::min(data_point_left.last_timestamp, val_right.last_timestamp);
                                     let curr_ts = Utc::now().timestamp_nanos();
 
                                     let max_net_lat = std::cmp::max(left_net_lat, *right_net_lat);
                                     let max_total_exec_net_lat = std::cmp::max(data_point_left.total_exec_net_latency + left_net_lat, val_right.total_exec_net_latency + *right_net_lat);
-                                    // edge_latency_metrics.borrow_mut().push_front(curr_ts - min_last_ts);
-                                    // path_latency_metrics.borrow_mut().push_front(curr_ts - min_start_ts);
-                                    edge_latency_metrics.borrow_mut().push_front(max_net_lat);
-                                    path_latency_metrics.borrow_mut().push_front(max_total_exec_net_lat);
+                                    // edge_latency_metrics.borrow_mut().record(curr_ts - min_last_ts);
+                                    // path_latency_metrics.borrow_mut().record(curr_ts - min_start_ts);
+                                    edge_latency_metrics.borrow_mut().record(max_net_lat);
+                                    path_latency_metrics.borrow_mut().record(max_total_exec_net_lat);
                                     let data_min_start_ts = if let Some(data_min_start_ts) = data_start_timestamp.borrow().as_ref().copied() {
                                         std::cmp::min(data_min_start_ts, min_start_ts)
                                     }   
@@ -163,14 +199,18 @@ where
                                         *start_timestamp.borrow_mut() = Some(curr_ts);
                                     }
                                     *end_timestamp.borrow_mut() = Some(curr_ts);
-                                    
-                                    if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                                        edge_latency_metrics.borrow_mut().truncate(keep_n);
-                                        path_latency_metrics.borrow_mut().truncate(keep_n);
+                                    execution_latency_metrics.borrow_mut().record(curr_ts - buffered_ts);
+                                    if let Some(tracer) = &tracer {
+                                        tracer.trace_arrival(request_id, val_right.last_timestamp, *right_net_lat, *buffered_ts);
+                                        tracer.trace_span(request_id, SpanKind::Wait, *buffered_ts, curr_ts);
+                                        tracer.trace_arrival(request_id, data_point_left.last_timestamp, left_net_lat, received_ts);
                                     }
+                                    throughput.record(curr_ts);
+                                    
                                     let output_data = if reset_timestamp { 
                                         TimestampData {
                                             data: (data_point_left.data.clone(), val_right.data.clone()),
+                                            request_id,
                                             start_timestamp: curr_ts,
                                             last_timestamp: curr_ts,
                                             total_exec_net_latency: 0,
@@ -179,6 +219,7 @@ where
                                     else {
                                         TimestampData {
                                             data: (data_point_left.data.clone(), val_right.data.clone()),
+                                            request_id,
                                             start_timestamp: min_start_ts,
                                             last_timestamp: curr_ts,
                                             total_exec_net_latency: max_total_exec_net_lat,
@@ -187,12 +228,22 @@ where
                                     session.give(output_data);
                                 }
                             }
-                            map_left.entry(key.clone()).or_insert(Vec::new()).push((data_point_left, left_net_lat));
+                            if right_done {
+                                if !buffered_since.contains_key(&key) {
+                                    key_count_left.remove(&key);
+                                }
+                                continue;
+                            }
+                            map_left.entry(key.clone()).or_insert(Vec::new()).push((data_point_left, left_net_lat, received_ts));
+                            buffered_since.entry(key.clone()).or_insert(received_ts);
                             if let Some(total_elements_right) = key_count_right.get(&key).copied() {
                                 if (Some(total_num_key) == map_left.get(&key).map(|x| x.len())) &&
                                     (Some(total_elements_right) == map_right.get(&key).map(|x| x.len())) {
                                     map_left.remove(&key);
                                     map_right.remove(&key);
+                                    key_count_left.remove(&key);
+                                    key_count_right.remove(&key);
+                                    buffered_since.remove(&key);
                                 }
                             }
                         }
@@ -207,21 +258,24 @@ where
                         if right_net_lat < 0 { right_net_lat = 0 }
                         data.swap(&mut vector_right);
                         let mut session = output.session(&time);
+                        let received_ts = Utc::now().timestamp_nanos();
                         for data_point_right in vector_right.drain(..) {
+                            input_throughput.record(received_ts);
                             let (key, total_num_key) = (key_map_right)(&data_point_right.data);
                             key_count_right.entry(key.clone()).or_insert(total_num_key);
                             if let Some(values) = map_left.get(&key) {
-                                for (val_left, left_net_lat) in values.iter() {
+                                for (val_left, left_net_lat, buffered_ts) in values.iter() {
                                     let min_start_ts = std::cmp::min(val_left.start_timestamp, data_point_right.start_timestamp);
+                                    let request_id = earliest_request_id(val_left, &data_point_right);
                                     // let min_last_ts = std::cmp::min(val_left.last_timestamp, data_point_right.last_timestamp);
                                     let curr_ts = Utc::now().timestamp_nanos();
                                     
                                     let max_net_lat = std::cmp::max(*left_net_lat, right_net_lat);
                                     let max_total_exec_net_lat = std::cmp::max(val_left.total_exec_net_latency + *left_net_lat, data_point_right.total_exec_net_latency + right_net_lat);
-                                    // edge_latency_metrics.borrow_mut().push_front(curr_ts - min_last_ts);
-                                    // path_latency_metrics.borrow_mut().push_front(curr_ts - min_start_ts);
-                                    edge_latency_metrics.borrow_mut().push_front(max_net_lat);
-                                    path_latency_metrics.borrow_mut().push_front(max_total_exec_net_lat);
+                                    // edge_latency_metrics.borrow_mut().record(curr_ts - min_last_ts);
+                                    // path_latency_metrics.borrow_mut().record(curr_ts - min_start_ts);
+                                    edge_latency_metrics.borrow_mut().record(max_net_lat);
+                                    path_latency_metrics.borrow_mut().record(max_total_exec_net_lat);
                                     let data_min_start_ts = if let Some(data_min_start_ts) = data_start_timestamp.borrow().as_ref().copied() {
                                         std::cmp::min(data_min_start_ts, min_start_ts)
                                     }   
@@ -233,14 +287,18 @@ where
                                         *start_timestamp.borrow_mut() = Some(curr_ts);
                                     }
                                     *end_timestamp.borrow_mut() = Some(curr_ts);
-                                    
-                                    if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                                        edge_latency_metrics.borrow_mut().truncate(keep_n);
-                                        path_latency_metrics.borrow_mut().truncate(keep_n);
+                                    execution_latency_metrics.borrow_mut().record(curr_ts - buffered_ts);
+                                    if let Some(tracer) = &tracer {
+                                        tracer.trace_arrival(request_id, val_left.last_timestamp, *left_net_lat, *buffered_ts);
+                                        tracer.trace_span(request_id, SpanKind::Wait, *buffered_ts, curr_ts);
+                                        tracer.trace_arrival(request_id, data_point_right.last_timestamp, right_net_lat, received_ts);
                                     }
+                                    throughput.record(curr_ts);
+                                    
                                     let output_data = if reset_timestamp { 
                                         TimestampData {
                                             data: (val_left.data.clone(), data_point_right.data.clone()),
+                                            request_id,
                                             start_timestamp: curr_ts,
                                             last_timestamp: curr_ts,
                                             total_exec_net_latency: 0,
@@ -249,6 +307,7 @@ where
                                     else {
                                         TimestampData {
                                             data: (val_left.data.clone(), data_point_right.data.clone()),
+                                            request_id,
                                             start_timestamp: min_start_ts,
                                             last_timestamp: curr_ts,
                                             total_exec_net_latency: max_total_exec_net_lat,
@@ -257,27 +316,67 @@ where
                                     session.give(output_data);
                                 }
                             }
-                            map_right.entry(key.clone()).or_insert(Vec::new()).push((data_point_right, right_net_lat));
+                            if left_done {
+                                if !buffered_since.contains_key(&key) {
+                                    key_count_right.remove(&key);
+                                }
+                                continue;
+                            }
+                            map_right.entry(key.clone()).or_insert(Vec::new()).push((data_point_right, right_net_lat, received_ts));
+                            buffered_since.entry(key.clone()).or_insert(received_ts);
                             if let Some(total_elements_left) = key_count_left.get(&key).copied() {
                                 if (Some(total_num_key) == map_right.get(&key).map(|x| x.len())) &&
                                     (Some(total_elements_left) == map_left.get(&key).map(|x| x.len())) {
                                     map_left.remove(&key);
                                     map_right.remove(&key);
+                                    key_count_left.remove(&key);
+                                    key_count_right.remove(&key);
+                                    buffered_since.remove(&key);
                                 }
                             }
                         }
-                    })
+                    });
+
+                    if left_done {
+                        map_right.clear();
+                    }
+                    if right_done {
+                        map_left.clear();
+                    }
+                    let curr_ts = Utc::now().timestamp_nanos();
+                    buffered_since.retain(|key, since| {
+                        let expired = matches!(join_ttl, Some(ttl) if curr_ts - *since > ttl);
+                        let buffered = map_left.contains_key(key) || map_right.contains_key(key);
+                        if expired || !buffered {
+                            map_left.remove(key);
+                            map_right.remove(key);
+                            key_count_left.remove(key);
+                            key_count_right.remove(key);
+                            *evicted_count.borrow_mut() += 1;
+                            return false;
+                        }
+                        true
+                    });
+                    *pending_count.borrow_mut() = buffered_since.len();
+                    activate_at_expiry(&activator, buffered_since.values().min().copied(), join_ttl, curr_ts);
                 }
         });
         Box::new(stream_out)
     }
 
-    fn get_throughput_logger(&self) -> Option<crate::metrics::ThroughputLogger> {
-        None
+    fn get_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.throughput.logger())
+    }
+
+    fn get_input_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.input_throughput.logger())
     }
 
     fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger> {
-        None
+        let logger = LatencyLogger {
+            latencies: RcWrapper::new(self.execution_latencies.clone())
+        };
+        Some(logger)
     }
 
     fn get_flow_edge_latency_logger(&self) -> Option<LatencyLogger> {
@@ -302,6 +401,14 @@ where
         };
         Some(logger)
     }
+
+    fn get_state_logger(&self) -> Option<StateLogger> {
+        let logger = StateLogger {
+            evicted: RcWrapper::new(self.evicted_count.clone()),
+            pending: RcWrapper::new(self.pending_count.clone())
+        };
+        Some(logger)
+    }
 }
 
 
@@ -351,10 +458,14 @@ where
     key_map_right: Option<H2>,
     phantom: PhantomData<(D1, D2, K)>,
     phantom_scope: PhantomData<S>,
-    edge_latencies: Rc<RefCell<VecDeque<i64>>>,
-    path_latencies: Rc<RefCell<VecDeque<i64>>>,
+    edge_latencies: Rc<RefCell<LatencyHistogram>>,
+    path_latencies: Rc<RefCell<LatencyHistogram>>,
     data_start_timestamp: Rc<RefCell<Option<i64>>>,
     start_timestamp: Rc<RefCell<Option<i64>>>,
+    // time from the first data of a matched pair received to the pair matched
+    execution_latencies: Rc<RefCell<LatencyHistogram>>,
+    input_throughput: ThroughputMeter,
+    throughput: ThroughputMeter,
     end_timestamp: Rc<RefCell<Option<i64>>>    
 }
 
@@ -373,10 +484,13 @@ where
             key_map_right: Some(right_hash_logic),
             phantom: PhantomData,
             phantom_scope: PhantomData,
-            edge_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            path_latencies: Rc::new(RefCell::new(VecDeque::new())),
+            edge_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            path_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
             data_start_timestamp: Rc::new(RefCell::new(None)),
             start_timestamp: Rc::new(RefCell::new(None)),
+            execution_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            input_throughput: ThroughputMeter::default(),
+            throughput: ThroughputMeter::default(),
             end_timestamp: Rc::new(RefCell::new(None))            
         }
     }
@@ -404,6 +518,7 @@ where
         }
         else { None };        
         
+        let tracer = Tracer::from_config(&config);
         let stream_left = streams[0];
         let stream_right = streams[1];
         let stream_left = stream_left.as_any().downcast_ref::<Stream<S, TimestampData<D1>>>().unwrap();
@@ -412,8 +527,8 @@ where
         let key_map_left = self.key_map_left.take().unwrap();
         let key_map_right = self.key_map_right.take().unwrap();
 
-        let mut left_buffers: HashMap<<S as ScopeParent>::Timestamp, HashMap<K, Vec<(TimestampData<D1>, MessageLatency)>>> = HashMap::new();
-        let mut right_buffers: HashMap<<S as ScopeParent>::Timestamp, HashMap<K, Vec<(TimestampData<D2>, MessageLatency)>>> = HashMap::new();
+        let mut left_buffers: HashMap<<S as ScopeParent>::Timestamp, HashMap<K, Vec<(TimestampData<D1>, MessageLatency, i64)>>> = HashMap::new();
+        let mut right_buffers: HashMap<<S as ScopeParent>::Timestamp, HashMap<K, Vec<(TimestampData<D2>, MessageLatency, i64)>>> = HashMap::new();
 
         let mut vector_left = Vec::new();
         let mut vector_right = Vec::new();
@@ -422,6 +537,9 @@ where
         let path_latency_metrics = self.path_latencies.clone();
         let data_start_timestamp = self.data_start_timestamp.clone();
         let start_timestamp = self.start_timestamp.clone();
+        let execution_latency_metrics = self.execution_latencies.clone();
+        let input_throughput = self.input_throughput.clone();
+        let throughput = self.throughput.clone();
         let end_timestamp = self.end_timestamp.clone();
 
         let stream_out = stream_left.binary_notify(
@@ -442,20 +560,23 @@ where
                     let mut session = output.session(&time);
                     let map_left = left_buffers.entry(time.time().clone()).or_insert_with(HashMap::new);
                     let map_right = right_buffers.entry(time.time().clone()).or_insert_with(HashMap::new);
+                    let received_ts = Utc::now().timestamp_nanos();
                     for data_point_left in vector_left.drain(..) {
+                        input_throughput.record(received_ts);
                         let key = (key_map_left)(&data_point_left.data);
                         if let Some(values) = map_right.get(&key) {
-                            for (val_right, right_net_lat) in values.iter() {
+                            for (val_right, right_net_lat, buffered_ts) in values.iter() {
                                 let min_start_ts = std::cmp::min(data_point_left.start_timestamp, val_right.start_timestamp);
+                                let request_id = earliest_request_id(&data_point_left, val_right);
                                 // let min_last_ts = std::cmp::min(data_point_left.last_timestamp, val_right.last_timestamp);
                                 let curr_ts = Utc::now().timestamp_nanos();
 
                                 let max_net_lat = std::cmp::max(left_net_lat, *right_net_lat);
                                 let max_total_exec_net_lat = std::cmp::max(data_point_left.total_exec_net_latency + left_net_lat, val_right.total_exec_net_latency + *right_net_lat);
-                                // edge_latency_metrics.borrow_mut().push_front(curr_ts - min_last_ts);
-                                // path_latency_metrics.borrow_mut().push_front(curr_ts - min_start_ts);
-                                edge_latency_metrics.borrow_mut().push_front(max_net_lat);
-                                path_latency_metrics.borrow_mut().push_front(max_total_exec_net_lat);
+                                // edge_latency_metrics.borrow_mut().record(curr_ts - min_last_ts);
+                                // path_latency_metrics.borrow_mut().record(curr_ts - min_start_ts);
+                                edge_latency_metrics.borrow_mut().record(max_net_lat);
+                                path_latency_metrics.borrow_mut().record(max_total_exec_net_lat);
                                 let data_min_start_ts = if let Some(data_min_start_ts) = data_start_timestamp.borrow().as_ref().copied() {
                                     std::cmp::min(data_min_start_ts, min_start_ts)
                                 }   
@@ -467,14 +588,18 @@ where
                                     *start_timestamp.borrow_mut() = Some(curr_ts);
                                 }
                                 *end_timestamp.borrow_mut() = Some(curr_ts);
-
-                                if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                                    edge_latency_metrics.borrow_mut().truncate(keep_n);
-                                    path_latency_metrics.borrow_mut().truncate(keep_n);
+                                execution_latency_metrics.borrow_mut().record(curr_ts - buffered_ts);
+                                if let Some(tracer) = &tracer {
+                                    tracer.trace_arrival(request_id, val_right.last_timestamp, *right_net_lat, *buffered_ts);
+                                    tracer.trace_span(request_id, SpanKind::Wait, *buffered_ts, curr_ts);
+                                    tracer.trace_arrival(request_id, data_point_left.last_timestamp, left_net_lat, received_ts);
                                 }
+                                throughput.record(curr_ts);
+
                                 let output_data = if reset_timestamp { 
                                     TimestampData {
                                         data: (data_point_left.data.clone(), val_right.data.clone()),
+                                        request_id,
                                         start_timestamp: curr_ts,
                                         last_timestamp: curr_ts,
                                         total_exec_net_latency: 0,
@@ -483,6 +608,7 @@ where
                                 else {
                                     TimestampData {
                                         data: (data_point_left.data.clone(), val_right.data.clone()),
+                                        request_id,
                                         start_timestamp: min_start_ts,
                                         last_timestamp: curr_ts,
                                         total_exec_net_latency: max_total_exec_net_lat,
@@ -491,7 +617,7 @@ where
                                 session.give(output_data);
                             }
                         }
-                        map_left.entry(key).or_insert(Vec::new()).push((data_point_left, left_net_lat));
+                        map_left.entry(key).or_insert(Vec::new()).push((data_point_left, left_net_lat, received_ts));
                     }
                     notificator.notify_at(time.retain());
                 });
@@ -507,20 +633,23 @@ where
                     let mut session = output.session(&time);
                     let map_left = left_buffers.entry(time.time().clone()).or_insert_with(HashMap::new);
                     let map_right = right_buffers.entry(time.time().clone()).or_insert_with(HashMap::new);
+                    let received_ts = Utc::now().timestamp_nanos();
                     for data_point_right in vector_right.drain(..) {
+                        input_throughput.record(received_ts);
                         let key = (key_map_right)(&data_point_right.data);
                         if let Some(values) = map_left.get(&key) {
-                            for (val_left, left_net_lat) in values.iter() {
+                            for (val_left, left_net_lat, buffered_ts) in values.iter() {
                                 let min_start_ts = std::cmp::min(val_left.start_timestamp, data_point_right.start_timestamp);
+                                let request_id = earliest_request_id(val_left, &data_point_right);
                                 // let min_last_ts = std::cmp::min(val_left.last_timestamp, data_point_right.last_timestamp);
                                 let curr_ts = Utc::now().timestamp_nanos();
                                 
                                 let max_net_lat = std::cmp::max(*left_net_lat, right_net_lat);
                                 let max_total_exec_net_lat = std::cmp::max(val_left.total_exec_net_latency + *left_net_lat, data_point_right.total_exec_net_latency + right_net_lat);
-                                // edge_latency_metrics.borrow_mut().push_front(curr_ts - min_last_ts);
-                                // path_latency_metrics.borrow_mut().push_front(curr_ts - min_start_ts);
-                                edge_latency_metrics.borrow_mut().push_front(max_net_lat);
-                                path_latency_metrics.borrow_mut().push_front(max_total_exec_net_lat);
+                                // edge_latency_metrics.borrow_mut().record(curr_ts - min_last_ts);
+                                // path_latency_metrics.borrow_mut().record(curr_ts - min_start_ts);
+                                edge_latency_metrics.borrow_mut().record(max_net_lat);
+                                path_latency_metrics.borrow_mut().record(max_total_exec_net_lat);
                                 let data_min_start_ts = if let Some(data_min_start_ts) = data_start_timestamp.borrow().as_ref().copied() {
                                     std::cmp::min(data_min_start_ts, min_start_ts)
                                 }   
@@ -532,14 +661,18 @@ where
                                     *start_timestamp.borrow_mut() = Some(curr_ts);
                                 }
                                 *end_timestamp.borrow_mut() = Some(curr_ts);
+                                execution_latency_metrics.borrow_mut().record(curr_ts - buffered_ts);
+                                if let Some(tracer) = &tracer {
+                                    tracer.trace_arrival(request_id, val_left.last_timestamp, *left_net_lat, *buffered_ts);
+                                    tracer.trace_span(request_id, SpanKind::Wait, *buffered_ts, curr_ts);
+                                    tracer.trace_arrival(request_id, data_point_right.last_timestamp, right_net_lat, received_ts);
+                                }
+                                throughput.record(curr_ts);
                                 
-                                if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                                    edge_latency_metrics.borrow_mut().truncate(keep_n);
-                                    path_latency_metrics.borrow_mut().truncate(keep_n);
-                                }                                
                                 let output_data = if reset_timestamp { 
                                     TimestampData {
                                         data: (val_left.data.clone(), data_point_right.data.clone()),
+                                        request_id,
                                         start_timestamp: curr_ts,
                                         last_timestamp: curr_ts,
                                         total_exec_net_latency: 0,
@@ -548,6 +681,7 @@ where
                                 else {
                                     TimestampData {
                                         data: (val_left.data.clone(), data_point_right.data.clone()),
+                                        request_id,
                                         start_timestamp: min_start_ts,
                                         last_timestamp: curr_ts,
                                         total_exec_net_latency: max_total_exec_net_lat,
@@ -556,7 +690,7 @@ where
                                 session.give(output_data);
                             }
                         }
-                        map_right.entry(key).or_insert(Vec::new()).push((data_point_right, right_net_lat));
+                        map_right.entry(key).or_insert(Vec::new()).push((data_point_right, right_net_lat, received_ts));
                     }
                     notificator.notify_at(time.retain());
                 });
@@ -570,12 +704,19 @@ where
         Box::new(stream_out)
     }
 
-    fn get_throughput_logger(&self) -> Option<crate::metrics::ThroughputLogger> {
-        None
+    fn get_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.throughput.logger())
+    }
+
+    fn get_input_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.input_throughput.logger())
     }
 
     fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger> {
-        None
+        let logger = LatencyLogger {
+            latencies: RcWrapper::new(self.execution_latencies.clone())
+        };
+        Some(logger)
     }
 
     fn get_flow_edge_latency_logger(&self) -> Option<LatencyLogger> {
@@ -635,6 +776,8 @@ where
 /// and a single item with the key `k` in the right stream
 /// Output a single tuple for each key
 /// key_map_left, key_map_right are hash functions that maps the data into key
+/// The data is evicted if it is still unmatched after `join_ttl` (in nanoseconds, operator config),
+/// and the data of an input is evicted once the other input completes
 pub struct SingleItemJoinNode<D1, D2, K, H1, H2, S>
 where
     H1: Fn(&D1) -> K + 'static,
@@ -647,11 +790,19 @@ where
     key_map_right: Option<H2>,
     phantom: PhantomData<(D1, D2, K)>,
     phantom_scope: PhantomData<S>,
-    edge_latencies: Rc<RefCell<VecDeque<i64>>>,
-    path_latencies: Rc<RefCell<VecDeque<i64>>>,
+    edge_latencies: Rc<RefCell<LatencyHistogram>>,
+    path_latencies: Rc<RefCell<LatencyHistogram>>,
     data_start_timestamp: Rc<RefCell<Option<i64>>>,
     start_timestamp: Rc<RefCell<Option<i64>>>,
-    end_timestamp: Rc<RefCell<Option<i64>>>       
+    // time from the first data of a matched pair received to the pair matched
+    execution_latencies: Rc<RefCell<LatencyHistogram>>,
+    input_throughput: ThroughputMeter,
+    throughput: ThroughputMeter,
+    end_timestamp: Rc<RefCell<Option<i64>>>,
+    // #keys evicted after waiting without a match
+    evicted_count: Rc<RefCell<usize>>,
+    // #keys waiting for a match
+    pending_count: Rc<RefCell<usize>>
 }
 
 
@@ -670,11 +821,16 @@ where
             key_map_right: Some(right_hash_logic),
             phantom: PhantomData,
             phantom_scope: PhantomData,
-            edge_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            path_latencies: Rc::new(RefCell::new(VecDeque::new())),
+            edge_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            path_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
             data_start_timestamp: Rc::new(RefCell::new(None)),
             start_timestamp: Rc::new(RefCell::new(None)),
-            end_timestamp: Rc::new(RefCell::new(None))                                
+            execution_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            input_throughput: ThroughputMeter::default(),
+            throughput: ThroughputMeter::default(),
+            end_timestamp: Rc::new(RefCell::new(None)),
+            evicted_count: Rc::new(RefCell::new(0)),
+            pending_count: Rc::new(RefCell::new(0))
         }
     }
 }
@@ -700,7 +856,12 @@ where
             config.get("simulate_network_latency").and_then(|val| val.downcast_ref::<i64>()).map(|val| *val)
         }
         else { None };
+        let join_ttl = if let Some(config) = &config {
+            config.get("join_ttl").and_then(|val| val.downcast_ref::<i64>()).copied()
+        }
+        else { None };
 
+        let tracer = Tracer::from_config(&config);
         let stream_left = streams[0];
         let stream_right = streams[1];
         let stream_left = stream_left.as_any().downcast_ref::<Stream<S, TimestampData<D1>>>().unwrap();
@@ -713,21 +874,32 @@ where
         let path_latency_metrics = self.path_latencies.clone();
         let data_start_timestamp = self.data_start_timestamp.clone();
         let start_timestamp = self.start_timestamp.clone();
+        let execution_latency_metrics = self.execution_latencies.clone();
+        let input_throughput = self.input_throughput.clone();
+        let throughput = self.throughput.clone();
         let end_timestamp = self.end_timestamp.clone();
+        let evicted_count = self.evicted_count.clone();
+        let pending_count = self.pending_count.clone();
 
-        let stream_out = stream_left.binary(
+        let scope = stream_left.scope();
+        let stream_out = stream_left.binary_frontier(
             stream_right,
             Pipeline,
             Pipeline,
             "SingleItemInnerJoin",
-            |_capability, _info| {
-                let mut map_left: HashMap<K, (TimestampData<D1>, MessageLatency)> = HashMap::new();
-                let mut map_right: HashMap<K, (TimestampData<D2>, MessageLatency)> = HashMap::new();
+            |_capability, info| {
+                let activator = scope.activator_for(&info.address[..]);
+                // data, network latency and when the data is buffered
+                let mut map_left: HashMap<K, (TimestampData<D1>, MessageLatency, i64)> = HashMap::new();
+                let mut map_right: HashMap<K, (TimestampData<D2>, MessageLatency, i64)> = HashMap::new();
 
                 let mut vector_left = Vec::new();
                 let mut vector_right = Vec::new();
 
                 move |input_left, input_right, output| {
+                    // the data of an input can no longer be matched once the other input completes
+                    let left_done = input_left.frontier().frontier().is_empty();
+                    let right_done = input_right.frontier().frontier().is_empty();
                     input_left.for_each_with_latency(|time, data, net_lat| {
                         let mut left_net_lat = if let Some(lat) = net_lat { lat }
                         else { 0 };
@@ -737,19 +909,22 @@ where
                         if left_net_lat < 0 { left_net_lat = 0 }
                         data.swap(&mut vector_left);
                         let mut session = output.session(&time);
+                        let received_ts = Utc::now().timestamp_nanos();
                         for data_point_left in vector_left.drain(..) {
+                            input_throughput.record(received_ts);
                             let key = (key_map_left)(&data_point_left.data);
-                            if let Some((data_point_right, right_net_lat)) = map_right.remove(&key) {
+                            if let Some((data_point_right, right_net_lat, buffered_ts)) = map_right.remove(&key) {
                                 let min_start_ts = std::cmp::min(data_point_left.start_timestamp, data_point_right.start_timestamp);
+                                let request_id = earliest_request_id(&data_point_left, &data_point_right);
                                 // let min_last_ts = std::cmp::min(data_point_left.last_timestamp, data_point_right.last_timestamp);
                                 let curr_ts = Utc::now().timestamp_nanos();
 
                                 let max_net_lat = std::cmp::max(left_net_lat, right_net_lat);
                                 let max_total_exec_net_lat = std::cmp::max(data_point_left.total_exec_net_latency + left_net_lat, data_point_right.total_exec_net_latency + right_net_lat);
-                                // edge_latency_metrics.borrow_mut().push_front(curr_ts - min_last_ts);
-                                // path_latency_metrics.borrow_mut().push_front(curr_ts - min_start_ts);
-                                edge_latency_metrics.borrow_mut().push_front(max_net_lat);
-                                path_latency_metrics.borrow_mut().push_front(max_total_exec_net_lat);
+                                // edge_latency_metrics.borrow_mut().record(curr_ts - min_last_ts);
+                                // path_latency_metrics.borrow_mut().record(curr_ts - min_start_ts);
+                                edge_latency_metrics.borrow_mut().record(max_net_lat);
+                                path_latency_metrics.borrow_mut().record(max_total_exec_net_lat);
                                 let data_min_start_ts = if let Some(data_min_start_ts) = data_start_timestamp.borrow().as_ref().copied() {
                                     std::cmp::min(data_min_start_ts, min_start_ts)
                                 }   
@@ -761,14 +936,18 @@ where
                                     *start_timestamp.borrow_mut() = Some(curr_ts);
                                 }
                                 *end_timestamp.borrow_mut() = Some(curr_ts);
-                                                    
-                                if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                                    edge_latency_metrics.borrow_mut().truncate(keep_n);
-                                    path_latency_metrics.borrow_mut().truncate(keep_n);
+                                execution_latency_metrics.borrow_mut().record(curr_ts - buffered_ts);
+                                if let Some(tracer) = &tracer {
+                                    tracer.trace_arrival(request_id, data_point_right.last_timestamp, right_net_lat, buffered_ts);
+                                    tracer.trace_span(request_id, SpanKind::Wait, buffered_ts, curr_ts);
+                                    tracer.trace_arrival(request_id, data_point_left.last_timestamp, left_net_lat, received_ts);
                                 }
+                                throughput.record(curr_ts);
+                                                    
                                 let output_data = if reset_timestamp { 
                                     TimestampData {
                                         data: (data_point_left.data, data_point_right.data),
+                                        request_id,
                                         start_timestamp: curr_ts,
                                         last_timestamp: curr_ts,
                                         total_exec_net_latency: 0,
@@ -777,14 +956,15 @@ where
                                 else {
                                     TimestampData {
                                         data: (data_point_left.data, data_point_right.data),
+                                        request_id,
                                         start_timestamp: min_start_ts,
                                         last_timestamp: curr_ts,
                                         total_exec_net_latency: max_total_exec_net_lat,
                                     }  
                                 };
                                 session.give(output_data);
-                            } else {
-                                map_left.insert(key, (data_point_left, left_net_lat));
+                            } else if !right_done {
+                                map_left.insert(key, (data_point_left, left_net_lat, received_ts));
                             }
                         }
                     });
@@ -798,19 +978,22 @@ where
                         if right_net_lat < 0 { right_net_lat = 0 }
                         data.swap(&mut vector_right);
                         let mut session = output.session(&time);
+                        let received_ts = Utc::now().timestamp_nanos();
                         for data_point_right in vector_right.drain(..) {
+                            input_throughput.record(received_ts);
                             let key = (key_map_right)(&data_point_right.data);
-                            if let Some((data_point_left, left_net_lat)) = map_left.remove(&key) {
+                            if let Some((data_point_left, left_net_lat, buffered_ts)) = map_left.remove(&key) {
                                 let min_start_ts = std::cmp::min(data_point_left.start_timestamp, data_point_right.start_timestamp);
+                                let request_id = earliest_request_id(&data_point_left, &data_point_right);
                                 // let min_last_ts = std::cmp::min(data_point_left.last_timestamp, data_point_right.last_timestamp);
                                 let curr_ts = Utc::now().timestamp_nanos();
 
                                 let max_net_lat = std::cmp::max(left_net_lat, right_net_lat);
                                 let max_total_exec_net_lat = std::cmp::max(data_point_left.total_exec_net_latency + left_net_lat, data_point_right.total_exec_net_latency + right_net_lat);
-                                // edge_latency_metrics.borrow_mut().push_front(curr_ts - min_last_ts);
-                                // path_latency_metrics.borrow_mut().push_front(curr_ts - min_start_ts);
-                                edge_latency_metrics.borrow_mut().push_front(max_net_lat);
-                                path_latency_metrics.borrow_mut().push_front(max_total_exec_net_lat);
+                                // edge_latency_metrics.borrow_mut().record(curr_ts - min_last_ts);
+                                // path_latency_metrics.borrow_mut().record(curr_ts - min_start_ts);
+                                edge_latency_metrics.borrow_mut().record(max_net_lat);
+                                path_latency_metrics.borrow_mut().record(max_total_exec_net_lat);
                                 let data_min_start_ts = if let Some(data_min_start_ts) = data_start_timestamp.borrow().as_ref().copied() {
                                     std::cmp::min(data_min_start_ts, min_start_ts)
                                 }   
@@ -822,14 +1005,18 @@ where
                                     *start_timestamp.borrow_mut() = Some(curr_ts);
                                 }
                                 *end_timestamp.borrow_mut() = Some(curr_ts);
-                                                    
-                                if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                                    edge_latency_metrics.borrow_mut().truncate(keep_n);
-                                    path_latency_metrics.borrow_mut().truncate(keep_n);
+                                execution_latency_metrics.borrow_mut().record(curr_ts - buffered_ts);
+                                if let Some(tracer) = &tracer {
+                                    tracer.trace_arrival(request_id, data_point_left.last_timestamp, left_net_lat, buffered_ts);
+                                    tracer.trace_span(request_id, SpanKind::Wait, buffered_ts, curr_ts);
+                                    tracer.trace_arrival(request_id, data_point_right.last_timestamp, right_net_lat, received_ts);
                                 }
+                                throughput.record(curr_ts);
+                                                    
                                 let output_data = if reset_timestamp { 
                                     TimestampData {
                                         data: (data_point_left.data, data_point_right.data),
+                                        request_id,
                                         start_timestamp: curr_ts,
                                         last_timestamp: curr_ts,
                                         total_exec_net_latency: 0,
@@ -838,28 +1025,47 @@ where
                                 else {
                                     TimestampData {
                                         data: (data_point_left.data, data_point_right.data),
+                                        request_id,
                                         start_timestamp: min_start_ts,
                                         last_timestamp: curr_ts,
                                         total_exec_net_latency: max_total_exec_net_lat,
                                     }  
                                 };
                                 session.give(output_data);
-                            } else {
-                                map_right.insert(key, (data_point_right, right_net_lat));
+                            } else if !left_done {
+                                map_right.insert(key, (data_point_right, right_net_lat, received_ts));
                             }
                         }
-                    })
+                    });
+
+                    let curr_ts = Utc::now().timestamp_nanos();
+                    let expired = |since: i64| matches!(join_ttl, Some(ttl) if curr_ts - since > ttl);
+                    let num_buffered = map_left.len() + map_right.len();
+                    map_left.retain(|_, (_, _, since)| !right_done && !expired(*since));
+                    map_right.retain(|_, (_, _, since)| !left_done && !expired(*since));
+                    *evicted_count.borrow_mut() += num_buffered - map_left.len() - map_right.len();
+                    *pending_count.borrow_mut() = map_left.len() + map_right.len();
+                    let earliest_buffered_ts = map_left.values().map(|(_, _, since)| *since)
+                        .chain(map_right.values().map(|(_, _, since)| *since)).min();
+                    activate_at_expiry(&activator, earliest_buffered_ts, join_ttl, curr_ts);
                 }
         });
         Box::new(stream_out)
     }
 
-    fn get_throughput_logger(&self) -> Option<crate::metrics::ThroughputLogger> {
-        None
+    fn get_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.throughput.logger())
+    }
+
+    fn get_input_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.input_throughput.logger())
     }
 
     fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger> {
-        None
+        let logger = LatencyLogger {
+            latencies: RcWrapper::new(self.execution_latencies.clone())
+        };
+        Some(logger)
     }
 
     fn get_flow_edge_latency_logger(&self) -> Option<LatencyLogger> {
@@ -884,6 +1090,14 @@ where
         };
         Some(logger)
     }
+
+    fn get_state_logger(&self) -> Option<StateLogger> {
+        let logger = StateLogger {
+            evicted: RcWrapper::new(self.evicted_count.clone()),
+            pending: RcWrapper::new(self.pending_count.clone())
+        };
+        Some(logger)
+    }
 }
 
 impl<D1: ExchangeData, D2: ExchangeData, K, H1, H2, A, T> ExchangeOpBuilder for SingleItemJoinNode<D1, D2, K, H1, H2, PipelineScope<A, T>>
@@ -913,6 +1127,393 @@ where
     }
 }
 
+/// Which unmatched data an outer join emits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OuterJoinMode {
+    /// Emit the unmatched data of the left stream as `(Some(left), None)`
+    Left,
+    /// Emit the unmatched data of the right stream as `(None, Some(right))`
+    Right,
+    /// Emit the unmatched data of both streams
+    Full,
+}
+
+impl OuterJoinMode {
+    fn emits_left(&self) -> bool {
+        matches!(self, OuterJoinMode::Left | OuterJoinMode::Full)
+    }
+
+    fn emits_right(&self) -> bool {
+        matches!(self, OuterJoinMode::Right | OuterJoinMode::Full)
+    }
+}
+
+// data, network latency, when the data is buffered and the capability to emit it if unmatched
+type OuterJoinState<K, D, T> = HashMap<K, (TimestampData<D>, MessageLatency, i64, Capability<T>)>;
+
+/// Single item outer join
+/// There will be at most a single item with the key `k` in the left stream,
+/// and at most a single item with the key `k` in the right stream
+/// Output `(Some(left), Some(right))` for each matched key,
+/// an item still unmatched after the timeout (overridden by `join_ttl` in nanoseconds, operator config)
+/// or once the other input completes is emitted with `None` on the other side if the mode includes its side,
+/// and evicted otherwise
+/// key_map_left, key_map_right are hash functions that maps the data into key
+pub struct OuterJoinNode<D1, D2, K, H1, H2, S>
+where
+    H1: Fn(&D1) -> K + 'static,
+    H2: Fn(&D2) -> K + 'static,
+    S: Scope + 'static
+{
+    left_index: usize,
+    right_index: usize,
+    mode: OuterJoinMode,
+    // in nanoseconds
+    timeout: i64,
+    key_map_left: Option<H1>,
+    key_map_right: Option<H2>,
+    phantom: PhantomData<(D1, D2, K)>,
+    phantom_scope: PhantomData<S>,
+    edge_latencies: Rc<RefCell<LatencyHistogram>>,
+    path_latencies: Rc<RefCell<LatencyHistogram>>,
+    data_start_timestamp: Rc<RefCell<Option<i64>>>,
+    start_timestamp: Rc<RefCell<Option<i64>>>,
+    // time from the first data of a matched pair received to the pair matched
+    execution_latencies: Rc<RefCell<LatencyHistogram>>,
+    input_throughput: ThroughputMeter,
+    throughput: ThroughputMeter,
+    end_timestamp: Rc<RefCell<Option<i64>>>,
+    // #keys emitted or evicted after waiting without a match
+    evicted_count: Rc<RefCell<usize>>,
+    // #keys waiting for a match
+    pending_count: Rc<RefCell<usize>>
+}
+
+impl<D1: Data, D2: Data, K, H1, H2, S> OuterJoinNode<D1, D2, K, H1, H2, S>
+where
+    K: Clone + Eq + Hash + 'static,
+    H1: Fn(&D1) -> K + 'static,
+    H2: Fn(&D2) -> K + 'static,
+    S: Scope + 'static
+{
+    pub(crate) fn new(left_index: usize, right_index: usize, left_hash_logic: H1, right_hash_logic: H2, mode: OuterJoinMode, timeout: Duration) -> Self {
+        OuterJoinNode {
+            left_index,
+            right_index,
+            mode,
+            timeout: timeout.as_nanos() as i64,
+            key_map_left: Some(left_hash_logic),
+            key_map_right: Some(right_hash_logic),
+            phantom: PhantomData,
+            phantom_scope: PhantomData,
+            edge_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            path_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            data_start_timestamp: Rc::new(RefCell::new(None)),
+            start_timestamp: Rc::new(RefCell::new(None)),
+            execution_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            input_throughput: ThroughputMeter::default(),
+            throughput: ThroughputMeter::default(),
+            end_timestamp: Rc::new(RefCell::new(None)),
+            evicted_count: Rc::new(RefCell::new(0)),
+            pending_count: Rc::new(RefCell::new(0))
+        }
+    }
+}
+
+impl<D1: Data, D2: Data, K, H1, H2, S> LocalOpBuilder for OuterJoinNode<D1, D2, K, H1, H2, S>
+where
+    K: Clone + Eq + Hash + 'static,
+    H1: Fn(&D1) -> K + 'static,
+    H2: Fn(&D2) -> K + 'static,
+    S: Scope + 'static
+{
+    fn required_prev_nodes(&self) -> Vec<usize> {
+        vec![self.left_index, self.right_index]
+    }
+
+    fn build(&mut self, streams: &[&Box<dyn GenericStream>], config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>) -> Box<dyn GenericStream> {
+        let reset_timestamp = if let Some(config) = &config {
+            config.get("reset_timestamp").and_then(|val| val.downcast_ref::<bool>()).copied()
+        }
+        else { None };
+        let reset_timestamp = reset_timestamp.unwrap_or(false);
+        let sim_network_latency = if let Some(config) = &config {
+            config.get("simulate_network_latency").and_then(|val| val.downcast_ref::<i64>()).copied()
+        }
+        else { None };
+        let timeout = if let Some(config) = &config {
+            config.get("join_ttl").and_then(|val| val.downcast_ref::<i64>()).copied()
+        }
+        else { None };
+        let timeout = timeout.unwrap_or(self.timeout);
+        let mode = self.mode;
+
+        // the joined requests are only traced without resetting their timestamps, as the outer join timestamps the output when emitting it
+        let tracer = Tracer::from_config(&config);
+        let stream_left = streams[0];
+        let stream_right = streams[1];
+        let stream_left = stream_left.as_any().downcast_ref::<Stream<S, TimestampData<D1>>>().unwrap();
+        let stream_right = stream_right.as_any().downcast_ref::<Stream<S, TimestampData<D2>>>().unwrap();
+
+        let key_map_left = self.key_map_left.take().unwrap();
+        let key_map_right = self.key_map_right.take().unwrap();
+
+        let edge_latency_metrics = self.edge_latencies.clone();
+        let path_latency_metrics = self.path_latencies.clone();
+        let data_start_timestamp = self.data_start_timestamp.clone();
+        let start_timestamp = self.start_timestamp.clone();
+        let execution_latency_metrics = self.execution_latencies.clone();
+        let input_throughput = self.input_throughput.clone();
+        let throughput = self.throughput.clone();
+        let end_timestamp = self.end_timestamp.clone();
+        let evicted_count = self.evicted_count.clone();
+        let pending_count = self.pending_count.clone();
+
+        let scope = stream_left.scope();
+        let stream_out = stream_left.binary_frontier(
+            stream_right,
+            Pipeline,
+            Pipeline,
+            "SingleItemOuterJoin",
+            |_capability, info| {
+                let activator = scope.activator_for(&info.address[..]);
+                let mut map_left: OuterJoinState<K, D1, S::Timestamp> = HashMap::new();
+                let mut map_right: OuterJoinState<K, D2, S::Timestamp> = HashMap::new();
+
+                let mut vector_left = Vec::new();
+                let mut vector_right = Vec::new();
+
+                // log the metrics and timestamp the output
+                let output_data = move |data: (Option<D1>, Option<D2>), request_id: RequestId, min_start_ts: i64, max_net_lat: i64, max_total_exec_net_lat: i64| {
+                    let curr_ts = Utc::now().timestamp_nanos();
+                    edge_latency_metrics.borrow_mut().record(max_net_lat);
+                    path_latency_metrics.borrow_mut().record(max_total_exec_net_lat);
+                    let data_min_start_ts = if let Some(data_min_start_ts) = data_start_timestamp.borrow().as_ref().copied() {
+                        std::cmp::min(data_min_start_ts, min_start_ts)
+                    }
+                    else {
+                        min_start_ts
+                    };
+                    *data_start_timestamp.borrow_mut() = Some(data_min_start_ts);
+                    if start_timestamp.borrow().is_none() {
+                        *start_timestamp.borrow_mut() = Some(curr_ts);
+                    }
+                    *end_timestamp.borrow_mut() = Some(curr_ts);
+                    throughput.record(curr_ts);
+
+                    if reset_timestamp {
+                        TimestampData {
+                            data,
+                            request_id,
+                            start_timestamp: curr_ts,
+                            last_timestamp: curr_ts,
+                            total_exec_net_latency: 0,
+                        }
+                    }
+                    else {
+                        TimestampData {
+                            data,
+                            request_id,
+                            start_timestamp: min_start_ts,
+                            last_timestamp: curr_ts,
+                            total_exec_net_latency: max_total_exec_net_lat,
+                        }
+                    }
+                };
+
+                move |input_left, input_right, output| {
+                    // the data of an input can no longer be matched once the other input completes
+                    let left_done = input_left.frontier().frontier().is_empty();
+                    let right_done = input_right.frontier().frontier().is_empty();
+
+                    input_left.for_each_with_latency(|time, data, net_lat| {
+                        let mut left_net_lat = net_lat.unwrap_or(0);
+                        if let Some(sim_net_lat) = sim_network_latency {
+                            left_net_lat = sim_net_lat;
+                        }
+                        if left_net_lat < 0 { left_net_lat = 0 }
+                        data.swap(&mut vector_left);
+                        let mut session = output.session(&time);
+                        let received_ts = Utc::now().timestamp_nanos();
+                        for data_point_left in vector_left.drain(..) {
+                            input_throughput.record(received_ts);
+                            let key = (key_map_left)(&data_point_left.data);
+                            if let Some((data_point_right, right_net_lat, buffered_ts, _)) = map_right.remove(&key) {
+                                execution_latency_metrics.borrow_mut().record(received_ts - buffered_ts);
+                                let min_start_ts = std::cmp::min(data_point_left.start_timestamp, data_point_right.start_timestamp);
+                                let request_id = earliest_request_id(&data_point_left, &data_point_right);
+                                let max_net_lat = std::cmp::max(left_net_lat, right_net_lat);
+                                let max_total_exec_net_lat = std::cmp::max(data_point_left.total_exec_net_latency + left_net_lat, data_point_right.total_exec_net_latency + right_net_lat);
+                                if let Some(tracer) = &tracer {
+                                    tracer.trace_arrival(request_id, data_point_right.last_timestamp, right_net_lat, buffered_ts);
+                                    tracer.trace_span(request_id, SpanKind::Wait, buffered_ts, received_ts);
+                                    tracer.trace_arrival(request_id, data_point_left.last_timestamp, left_net_lat, received_ts);
+                                }
+                                session.give((output_data)((Some(data_point_left.data), Some(data_point_right.data)), request_id, min_start_ts, max_net_lat, max_total_exec_net_lat));
+                            } else if right_done {
+                                if mode.emits_left() {
+                                    let total_exec_net_lat = data_point_left.total_exec_net_latency + left_net_lat;
+                                    session.give((output_data)((Some(data_point_left.data), None), data_point_left.request_id, data_point_left.start_timestamp, left_net_lat, total_exec_net_lat));
+                                }
+                            } else {
+                                map_left.insert(key, (data_point_left, left_net_lat, received_ts, time.delayed(time.time())));
+                            }
+                        }
+                    });
+
+                    input_right.for_each_with_latency(|time, data, net_lat| {
+                        let mut right_net_lat = net_lat.unwrap_or(0);
+                        if let Some(sim_net_lat) = sim_network_latency {
+                            right_net_lat = sim_net_lat;
+                        }
+                        if right_net_lat < 0 { right_net_lat = 0 }
+                        data.swap(&mut vector_right);
+                        let mut session = output.session(&time);
+                        let received_ts = Utc::now().timestamp_nanos();
+                        for data_point_right in vector_right.drain(..) {
+                            input_throughput.record(received_ts);
+                            let key = (key_map_right)(&data_point_right.data);
+                            if let Some((data_point_left, left_net_lat, buffered_ts, _)) = map_left.remove(&key) {
+                                execution_latency_metrics.borrow_mut().record(received_ts - buffered_ts);
+                                let min_start_ts = std::cmp::min(data_point_left.start_timestamp, data_point_right.start_timestamp);
+                                let request_id = earliest_request_id(&data_point_left, &data_point_right);
+                                let max_net_lat = std::cmp::max(left_net_lat, right_net_lat);
+                                let max_total_exec_net_lat = std::cmp::max(data_point_left.total_exec_net_latency + left_net_lat, data_point_right.total_exec_net_latency + right_net_lat);
+                                if let Some(tracer) = &tracer {
+                                    tracer.trace_arrival(request_id, data_point_left.last_timestamp, left_net_lat, buffered_ts);
+                                    tracer.trace_span(request_id, SpanKind::Wait, buffered_ts, received_ts);
+                                    tracer.trace_arrival(request_id, data_point_right.last_timestamp, right_net_lat, received_ts);
+                                }
+                                session.give((output_data)((Some(data_point_left.data), Some(data_point_right.data)), request_id, min_start_ts, max_net_lat, max_total_exec_net_lat));
+                            } else if left_done {
+                                if mode.emits_right() {
+                                    let total_exec_net_lat = data_point_right.total_exec_net_latency + right_net_lat;
+                                    session.give((output_data)((None, Some(data_point_right.data)), data_point_right.request_id, data_point_right.start_timestamp, right_net_lat, total_exec_net_lat));
+                                }
+                            } else {
+                                map_right.insert(key, (data_point_right, right_net_lat, received_ts, time.delayed(time.time())));
+                            }
+                        }
+                    });
+
+                    let curr_ts = Utc::now().timestamp_nanos();
+                    let expired_left = map_left.iter().filter(|(_, (_, _, since, _))| right_done || curr_ts - since > timeout)
+                        .map(|(key, _)| key.clone()).collect::<Vec<_>>();
+                    for key in expired_left {
+                        let (data_point_left, left_net_lat, _, capability) = map_left.remove(&key).unwrap();
+                        *evicted_count.borrow_mut() += 1;
+                        if mode.emits_left() {
+                            let total_exec_net_lat = data_point_left.total_exec_net_latency + left_net_lat;
+                            output.session(&capability).give((output_data)((Some(data_point_left.data), None), data_point_left.request_id, data_point_left.start_timestamp, left_net_lat, total_exec_net_lat));
+                        }
+                    }
+                    let expired_right = map_right.iter().filter(|(_, (_, _, since, _))| left_done || curr_ts - since > timeout)
+                        .map(|(key, _)| key.clone()).collect::<Vec<_>>();
+                    for key in expired_right {
+                        let (data_point_right, right_net_lat, _, capability) = map_right.remove(&key).unwrap();
+                        *evicted_count.borrow_mut() += 1;
+                        if mode.emits_right() {
+                            let total_exec_net_lat = data_point_right.total_exec_net_latency + right_net_lat;
+                            output.session(&capability).give((output_data)((None, Some(data_point_right.data)), data_point_right.request_id, data_point_right.start_timestamp, right_net_lat, total_exec_net_lat));
+                        }
+                    }
+                    *pending_count.borrow_mut() = map_left.len() + map_right.len();
+                    let earliest_buffered_ts = map_left.values().map(|(_, _, since, _)| *since)
+                        .chain(map_right.values().map(|(_, _, since, _)| *since)).min();
+                    activate_at_expiry(&activator, earliest_buffered_ts, Some(timeout), curr_ts);
+                }
+        });
+        Box::new(stream_out)
+    }
+
+    fn get_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.throughput.logger())
+    }
+
+    fn get_input_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.input_throughput.logger())
+    }
+
+    fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger> {
+        let logger = LatencyLogger {
+            latencies: RcWrapper::new(self.execution_latencies.clone())
+        };
+        Some(logger)
+    }
+
+    fn get_flow_edge_latency_logger(&self) -> Option<LatencyLogger> {
+        let logger = LatencyLogger {
+            latencies: RcWrapper::new(self.edge_latencies.clone())
+        };
+        Some(logger)
+    }
+
+    fn get_flow_path_latency_logger(&self) -> Option<LatencyLogger> {
+        let logger = LatencyLogger {
+            latencies: RcWrapper::new(self.path_latencies.clone())
+        };
+        Some(logger)
+    }
+
+    fn get_jct_logger(&self) -> Option<JCTLogger> {
+        let logger = JCTLogger {
+            data_start_timestamp: RcWrapper::new(self.data_start_timestamp.clone()),
+            op_start_timestamp: RcWrapper::new(self.start_timestamp.clone()),
+            op_end_timestamp: RcWrapper::new(self.end_timestamp.clone())
+        };
+        Some(logger)
+    }
+
+    fn get_state_logger(&self) -> Option<StateLogger> {
+        let logger = StateLogger {
+            evicted: RcWrapper::new(self.evicted_count.clone()),
+            pending: RcWrapper::new(self.pending_count.clone())
+        };
+        Some(logger)
+    }
+}
+
+impl<D1: ExchangeData, D2: ExchangeData, K, H1, H2, A, T> ExchangeOpBuilder for OuterJoinNode<D1, D2, K, H1, H2, PipelineScope<A, T>>
+where
+    K: Clone + Eq + Hash + 'static,
+    H1: Fn(&D1) -> K + 'static,
+    H2: Fn(&D2) -> K + 'static,
+    A: RelayConnectAllocate + 'static,
+    T: Timestamp+Refines<()>
+{
+    fn acquire_from_input_pipeline(&self, scope: &mut dyn GenericPipelineScope, input_idx: usize) -> Box<dyn GenericStream> {
+        let scope = scope.as_any_mut().downcast_mut::<PipelineScope<A, T>>().unwrap();
+        let stream = scope.acquire_pipeline_input::<TimestampData<(Option<D1>, Option<D2>)>>(input_idx);
+        Box::new(stream)
+    }
+
+    fn build_and_register_output(&mut self, streams: &[&Box<dyn GenericStream>], config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>, scope: &mut dyn GenericPipelineScope, output_idx: usize) -> Box<dyn GenericStream> {
+        let stream = (self as &mut dyn LocalOpBuilder).build(streams, config);
+        self.register_pipeline_output(&stream, scope, output_idx);
+        stream
+    }
+
+    fn register_pipeline_output(&self, stream: &Box<dyn GenericStream>, scope: &mut dyn GenericPipelineScope, output_idx: usize) {
+        let scope = scope.as_any_mut().downcast_mut::<PipelineScope<A, T>>().unwrap();
+        let stream = stream.as_any().downcast_ref::<Stream<PipelineScope<A, T>, TimestampData<(Option<D1>, Option<D2>)>>>().unwrap();
+        scope.register_pipeline_output_balanced_exchange(stream, output_idx);
+    }
+}
+
+/// Schedule the join again once the earliest buffered data (buffered at `earliest_buffered_ts`) expires,
+/// as the expiry is only checked when the operator runs
+fn activate_at_expiry(activator: &Activator, earliest_buffered_ts: Option<i64>, ttl: Option<i64>, curr_ts: i64) {
+    if let (Some(since), Some(ttl)) = (earliest_buffered_ts, ttl) {
+        let remaining = std::cmp::max(since + ttl - curr_ts, 0) as u64;
+        activator.activate_after(Duration::from_nanos(remaining + 1));
+    }
+}
+
+/// Id of the joined request that started first
+fn earliest_request_id<D1, D2>(left: &TimestampData<D1>, right: &TimestampData<D2>) -> RequestId {
+    if left.start_timestamp <= right.start_timestamp { left.request_id } else { right.request_id }
+}
+
 /// Timestamped single item join
 /// There will be a single item with the key `k` in the left stream,
 /// and a single item with the key `k` in the right stream at each timestamp
@@ -930,10 +1531,14 @@ where
     key_map_right: Option<H2>,
     phantom: PhantomData<(D1, D2, K)>,
     phantom_scope: PhantomData<S>,
-    edge_latencies: Rc<RefCell<VecDeque<i64>>>,
-    path_latencies: Rc<RefCell<VecDeque<i64>>>,
+    edge_latencies: Rc<RefCell<LatencyHistogram>>,
+    path_latencies: Rc<RefCell<LatencyHistogram>>,
     data_start_timestamp: Rc<RefCell<Option<i64>>>,
     start_timestamp: Rc<RefCell<Option<i64>>>,
+    // time from the first data of a matched pair received to the pair matched
+    execution_latencies: Rc<RefCell<LatencyHistogram>>,
+    input_throughput: ThroughputMeter,
+    throughput: ThroughputMeter,
     end_timestamp: Rc<RefCell<Option<i64>>>    
 }
 
@@ -953,10 +1558,13 @@ where
             key_map_right: Some(right_hash_logic),
             phantom: PhantomData,
             phantom_scope: PhantomData,
-            edge_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            path_latencies: Rc::new(RefCell::new(VecDeque::new())),
+            edge_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            path_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
             data_start_timestamp: Rc::new(RefCell::new(None)),
             start_timestamp: Rc::new(RefCell::new(None)),
+            execution_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            input_throughput: ThroughputMeter::default(),
+            throughput: ThroughputMeter::default(),
             end_timestamp: Rc::new(RefCell::new(None))               
         }
     }
@@ -984,6 +1592,7 @@ where
         }
         else { None };
 
+        let tracer = Tracer::from_config(&config);
         let stream_left = streams[0];
         let stream_right = streams[1];
         let stream_left = stream_left.as_any().downcast_ref::<Stream<S, TimestampData<D1>>>().unwrap();
@@ -992,8 +1601,8 @@ where
         let key_map_left = self.key_map_left.take().unwrap();
         let key_map_right = self.key_map_right.take().unwrap();
 
-        let mut left_buffers: HashMap<<S as ScopeParent>::Timestamp, HashMap<K, (TimestampData<D1>, MessageLatency)>> = HashMap::new();
-        let mut right_buffers: HashMap<<S as ScopeParent>::Timestamp, HashMap<K, (TimestampData<D2>, MessageLatency)>> = HashMap::new();
+        let mut left_buffers: HashMap<<S as ScopeParent>::Timestamp, HashMap<K, (TimestampData<D1>, MessageLatency, i64)>> = HashMap::new();
+        let mut right_buffers: HashMap<<S as ScopeParent>::Timestamp, HashMap<K, (TimestampData<D2>, MessageLatency, i64)>> = HashMap::new();
 
         let mut vector_left = Vec::new();
         let mut vector_right = Vec::new();
@@ -1002,6 +1611,9 @@ where
         let path_latency_metrics = self.path_latencies.clone();
         let data_start_timestamp = self.data_start_timestamp.clone();
         let start_timestamp = self.start_timestamp.clone();
+        let execution_latency_metrics = self.execution_latencies.clone();
+        let input_throughput = self.input_throughput.clone();
+        let throughput = self.throughput.clone();
         let end_timestamp = self.end_timestamp.clone();
 
         let stream_out = stream_left.binary_notify(
@@ -1022,19 +1634,22 @@ where
                     let mut session = output.session(&time);
                     let map_left = left_buffers.entry(time.time().clone()).or_insert_with(HashMap::new);
                     let map_right = right_buffers.entry(time.time().clone()).or_insert_with(HashMap::new);
+                    let received_ts = Utc::now().timestamp_nanos();
                     for data_point_left in vector_left.drain(..) {
+                        input_throughput.record(received_ts);
                         let key = (key_map_left)(&data_point_left.data);
-                        if let Some((data_point_right, right_net_lat)) = map_right.remove(&key) {
+                        if let Some((data_point_right, right_net_lat, buffered_ts)) = map_right.remove(&key) {
                             let min_start_ts = std::cmp::min(data_point_left.start_timestamp, data_point_right.start_timestamp);
+                            let request_id = earliest_request_id(&data_point_left, &data_point_right);
                             // let min_last_ts = std::cmp::min(data_point_left.last_timestamp, data_point_right.last_timestamp);
                             let curr_ts = Utc::now().timestamp_nanos();
 
                             let max_net_lat = std::cmp::max(left_net_lat, right_net_lat);
                             let max_total_exec_net_lat = std::cmp::max(data_point_left.total_exec_net_latency + left_net_lat, data_point_right.total_exec_net_latency + right_net_lat);
-                            // edge_latency_metrics.borrow_mut().push_front(curr_ts - min_last_ts);
-                            // path_latency_metrics.borrow_mut().push_front(curr_ts - min_start_ts);
-                            edge_latency_metrics.borrow_mut().push_front(max_net_lat);
-                            path_latency_metrics.borrow_mut().push_front(max_total_exec_net_lat);
+                            // edge_latency_metrics.borrow_mut().record(curr_ts - min_last_ts);
+                            // path_latency_metrics.borrow_mut().record(curr_ts - min_start_ts);
+                            edge_latency_metrics.borrow_mut().record(max_net_lat);
+                            path_latency_metrics.borrow_mut().record(max_total_exec_net_lat);
                             let data_min_start_ts = if let Some(data_min_start_ts) = data_start_timestamp.borrow().as_ref().copied() {
                                 std::cmp::min(data_min_start_ts, min_start_ts)
                             }   
@@ -1046,14 +1661,18 @@ where
                                 *start_timestamp.borrow_mut() = Some(curr_ts);
                             }
                             *end_timestamp.borrow_mut() = Some(curr_ts);
-                                            
-                            if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                                edge_latency_metrics.borrow_mut().truncate(keep_n);
-                                path_latency_metrics.borrow_mut().truncate(keep_n);
+                            execution_latency_metrics.borrow_mut().record(curr_ts - buffered_ts);
+                            if let Some(tracer) = &tracer {
+                                tracer.trace_arrival(request_id, data_point_right.last_timestamp, right_net_lat, buffered_ts);
+                                tracer.trace_span(request_id, SpanKind::Wait, buffered_ts, curr_ts);
+                                tracer.trace_arrival(request_id, data_point_left.last_timestamp, left_net_lat, received_ts);
                             }
+                            throughput.record(curr_ts);
+                                            
                             let output_data = if reset_timestamp { 
                                 TimestampData {
                                     data: (data_point_left.data, data_point_right.data),
+                                    request_id,
                                     start_timestamp: curr_ts,
                                     last_timestamp: curr_ts,
                                     total_exec_net_latency: 0,
@@ -1062,6 +1681,7 @@ where
                             else {
                                 TimestampData {
                                     data: (data_point_left.data, data_point_right.data),
+                                    request_id,
                                     start_timestamp: min_start_ts,
                                     last_timestamp: curr_ts,
                                     total_exec_net_latency: max_total_exec_net_lat,
@@ -1069,7 +1689,7 @@ where
                             };
                             session.give(output_data);
                         } else {
-                            map_left.insert(key, (data_point_left, left_net_lat));
+                            map_left.insert(key, (data_point_left, left_net_lat, received_ts));
                         }
                     }
                     notificator.notify_at(time.retain());
@@ -1086,19 +1706,22 @@ where
                     let mut session = output.session(&time);
                     let map_left = left_buffers.entry(time.time().clone()).or_insert_with(HashMap::new);
                     let map_right = right_buffers.entry(time.time().clone()).or_insert_with(HashMap::new);
+                    let received_ts = Utc::now().timestamp_nanos();
                     for data_point_right in vector_right.drain(..) {
+                        input_throughput.record(received_ts);
                         let key = (key_map_right)(&data_point_right.data);
-                        if let Some((data_point_left, left_net_lat)) = map_left.remove(&key) {
+                        if let Some((data_point_left, left_net_lat, buffered_ts)) = map_left.remove(&key) {
                             let min_start_ts = std::cmp::min(data_point_left.start_timestamp, data_point_right.start_timestamp);
+                            let request_id = earliest_request_id(&data_point_left, &data_point_right);
                             // let min_last_ts = std::cmp::min(data_point_left.last_timestamp, data_point_right.last_timestamp);
                             let curr_ts = Utc::now().timestamp_nanos();
 
                             let max_net_lat = std::cmp::max(left_net_lat, right_net_lat);
                             let max_total_exec_net_lat = std::cmp::max(data_point_left.total_exec_net_latency + left_net_lat, data_point_right.total_exec_net_latency + right_net_lat);
-                            // edge_latency_metrics.borrow_mut().push_front(curr_ts - min_last_ts);
-                            // path_latency_metrics.borrow_mut().push_front(curr_ts - min_start_ts);
-                            edge_latency_metrics.borrow_mut().push_front(max_net_lat);
-                            path_latency_metrics.borrow_mut().push_front(max_total_exec_net_lat);
+                            // edge_latency_metrics.borrow_mut().record(curr_ts - min_last_ts);
+                            // path_latency_metrics.borrow_mut().record(curr_ts - min_start_ts);
+                            edge_latency_metrics.borrow_mut().record(max_net_lat);
+                            path_latency_metrics.borrow_mut().record(max_total_exec_net_lat);
                             let data_min_start_ts = if let Some(data_min_start_ts) = data_start_timestamp.borrow().as_ref().copied() {
                                 std::cmp::min(data_min_start_ts, min_start_ts)
                             }   
@@ -1110,14 +1733,18 @@ where
                                 *start_timestamp.borrow_mut() = Some(curr_ts);
                             }
                             *end_timestamp.borrow_mut() = Some(curr_ts);
-
-                            if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                                edge_latency_metrics.borrow_mut().truncate(keep_n);
-                                path_latency_metrics.borrow_mut().truncate(keep_n);
+                            execution_latency_metrics.borrow_mut().record(curr_ts - buffered_ts);
+                            if let Some(tracer) = &tracer {
+                                tracer.trace_arrival(request_id, data_point_left.last_timestamp, left_net_lat, buffered_ts);
+                                tracer.trace_span(request_id, SpanKind::Wait, buffered_ts, curr_ts);
+                                tracer.trace_arrival(request_id, data_point_right.last_timestamp, right_net_lat, received_ts);
                             }
+                            throughput.record(curr_ts);
+
                             let output_data = if reset_timestamp { 
                                 TimestampData {
                                     data: (data_point_left.data, data_point_right.data),
+                                    request_id,
                                     start_timestamp: curr_ts,
                                     last_timestamp: curr_ts,
                                     total_exec_net_latency: 0,
@@ -1126,6 +1753,7 @@ where
                             else {
                                 TimestampData {
                                     data: (data_point_left.data, data_point_right.data),
+                                    request_id,
                                     start_timestamp: min_start_ts,
                                     last_timestamp: curr_ts,
                                     total_exec_net_latency: max_total_exec_net_lat,
@@ -1133,7 +1761,7 @@ where
                             };
                             session.give(output_data);
                         } else {
-                            map_right.insert(key, (data_point_right, right_net_lat));
+                            map_right.insert(key, (data_point_right, right_net_lat, received_ts));
                         }
                     }
                     notificator.notify_at(time.retain());
@@ -1149,12 +1777,19 @@ where
         Box::new(stream_out)
     }
 
-    fn get_throughput_logger(&self) -> Option<crate::metrics::ThroughputLogger> {
-        None
+    fn get_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.throughput.logger())
+    }
+
+    fn get_input_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.input_throughput.logger())
     }
 
     fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger> {
-        None
+        let logger = LatencyLogger {
+            latencies: RcWrapper::new(self.execution_latencies.clone())
+        };
+        Some(logger)
     }
 
     fn get_flow_edge_latency_logger(&self) -> Option<LatencyLogger> {
@@ -1207,3 +1842,132 @@ where
         scope.register_pipeline_output_balanced_exchange(stream, output_idx);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+    use std::collections::{HashMap, VecDeque};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use timely::dataflow::{InputHandle, Stream};
+    use timely::dataflow::operators::{Input, Inspect};
+
+    use crate::TimestampData;
+    use crate::builder::GraphBuilder;
+    use crate::execute::local_execute_thread;
+    use crate::metrics::StateLogger;
+    use crate::node::{GenericStream, LocalOpBuilder};
+    use crate::static_timely::timely_static_execute::execute_directly;
+    use crate::static_timely::timely_static_scope::Child;
+    use crate::test_utils::{ThreadWorker, step_until, timestamped};
+    use crate::{InspectLocal, JoinLocal};
+
+    use super::{JoinNode, OuterJoinMode, OuterJoinNode};
+
+    // joins 0..6 on the left with 3..8 on the right
+    fn outer_join(mode: OuterJoinMode) -> Vec<(Option<usize>, Option<usize>)> {
+        let outputs = Arc::new(Mutex::new(Vec::new()));
+        let outputs_cloned = outputs.clone();
+        local_execute_thread(move |builder: &mut GraphBuilder<usize>| {
+            let outputs = outputs_cloned.clone();
+            let left = builder.new_input_from_source(VecDeque::from((0..6).collect::<Vec<usize>>()), |_, t| *t + 1, "Left");
+            let right = builder.new_input_from_source(VecDeque::from((3..8).collect::<Vec<usize>>()), |_, t| *t + 1, "Right");
+            left.outer_join_local(&right, |x| *x, |x| *x, mode, Duration::from_secs(60), "OuterJoin")
+                .inspect_local(move |x| outputs.lock().unwrap().push(*x), "Inspect");
+        });
+        let mut outputs = outputs.lock().unwrap().clone();
+        outputs.sort();
+        outputs
+    }
+
+    #[test]
+    fn outer_join_modes() {
+        let matched = (3..6).map(|x| (Some(x), Some(x)));
+        assert_eq!(outer_join(OuterJoinMode::Left), (0..3).map(|x| (Some(x), None)).chain(matched.clone()).collect::<Vec<_>>());
+        assert_eq!(outer_join(OuterJoinMode::Right), (6..8).map(|x| (None, Some(x))).chain(matched.clone()).collect::<Vec<_>>());
+        // unmatched data is emitted once the other input completes
+        let full = outer_join(OuterJoinMode::Full);
+        assert_eq!(full.len(), 8);
+        assert_eq!(full.iter().filter(|(left, right)| left.is_some() && right.is_some()).count(), 3);
+    }
+
+    #[test]
+    fn outer_join_timeout() {
+        execute_directly(|worker| {
+            let outputs = Arc::new(Mutex::new(Vec::new()));
+            let outputs_cloned = outputs.clone();
+            let (mut left, mut right, logger): (InputHandle<usize, _>, InputHandle<usize, _>, StateLogger) = worker.dataflow(|scope| {
+                let (left, stream_left) = scope.new_input::<TimestampData<usize>>();
+                let (right, stream_right) = scope.new_input::<TimestampData<usize>>();
+                let mut node = OuterJoinNode::<_, _, _, _, _, Child<ThreadWorker, usize>>::new(0, 1, |x: &usize| *x, |x: &usize| *x, OuterJoinMode::Left, Duration::from_millis(50));
+                let stream_left: Box<dyn GenericStream> = Box::new(stream_left);
+                let stream_right: Box<dyn GenericStream> = Box::new(stream_right);
+                let stream_out = node.build(&[&stream_left, &stream_right], None);
+                stream_out.as_any().downcast_ref::<Stream<Child<ThreadWorker, usize>, TimestampData<(Option<usize>, Option<usize>)>>>().unwrap()
+                    .inspect(move |x| outputs_cloned.lock().unwrap().push(x.data));
+                (left, right, node.get_state_logger().unwrap())
+            });
+            left.send(timestamped(1));
+            right.send(timestamped(2));
+            left.advance_to(1);
+            right.advance_to(1);
+            // the unmatched data is emitted on timeout without any further input
+            step_until(worker, "the join did not expire its buffered data", || outputs.lock().unwrap().len() == 1);
+            assert_eq!(*outputs.lock().unwrap(), vec![(Some(1), None)]);
+            assert_eq!(logger.compute_evicted(), 2);
+            assert_eq!(logger.compute_pending(), 0);
+        });
+    }
+
+    #[test]
+    fn join_ttl_eviction() {
+        execute_directly(|worker| {
+            let (mut left, mut right, logger): (InputHandle<usize, _>, InputHandle<usize, _>, StateLogger) = worker.dataflow(|scope| {
+                let (left, stream_left) = scope.new_input::<TimestampData<usize>>();
+                let (right, stream_right) = scope.new_input::<TimestampData<usize>>();
+                let mut node = JoinNode::<_, _, _, _, _, Child<ThreadWorker, usize>>::new(0, 1, |x: &usize| (*x, 1), |x: &usize| (*x, 1));
+                let mut config: HashMap<String, Arc<dyn Any + Send + Sync>> = HashMap::new();
+                config.insert("join_ttl".to_string(), Arc::new(Duration::from_millis(50).as_nanos() as i64));
+                let stream_left: Box<dyn GenericStream> = Box::new(stream_left);
+                let stream_right: Box<dyn GenericStream> = Box::new(stream_right);
+                node.build(&[&stream_left, &stream_right], Some(config));
+                (left, right, node.get_state_logger().unwrap())
+            });
+            left.send(timestamped(1));
+            left.send(timestamped(2));
+            right.send(timestamped(3));
+            left.advance_to(1);
+            right.advance_to(1);
+            step_until(worker, "the join did not buffer its data", || logger.compute_pending() == 3);
+            // the unmatched keys are evicted on timeout without any further input
+            step_until(worker, "the join did not expire its buffered data", || logger.compute_evicted() == 3);
+            assert_eq!(logger.compute_pending(), 0);
+        });
+    }
+    #[test]
+    fn carry_earliest_request_id() {
+        execute_directly(|worker| {
+            let outputs = Arc::new(Mutex::new(Vec::new()));
+            let outputs_cloned = outputs.clone();
+            let (mut left, mut right): (InputHandle<usize, _>, InputHandle<usize, _>) = worker.dataflow(|scope| {
+                let (left, stream_left) = scope.new_input::<TimestampData<usize>>();
+                let (right, stream_right) = scope.new_input::<TimestampData<usize>>();
+                let mut node = JoinNode::<_, _, _, _, _, Child<ThreadWorker, usize>>::new(0, 1, |x: &usize| (*x, 1), |x: &usize| (*x, 1));
+                let stream_left: Box<dyn GenericStream> = Box::new(stream_left);
+                let stream_right: Box<dyn GenericStream> = Box::new(stream_right);
+                let stream_out = node.build(&[&stream_left, &stream_right], None);
+                stream_out.as_any().downcast_ref::<Stream<Child<ThreadWorker, usize>, TimestampData<(usize, usize)>>>().unwrap()
+                    .inspect(move |x| outputs_cloned.lock().unwrap().push((x.request_id, x.start_timestamp)));
+                (left, right)
+            });
+            // the right request started first
+            left.send(TimestampData { request_id: 10, start_timestamp: 2000, last_timestamp: 2000, ..timestamped(1) });
+            right.send(TimestampData { request_id: 20, start_timestamp: 1000, last_timestamp: 1000, ..timestamped(1) });
+            drop(left);
+            drop(right);
+            while worker.step() { }
+            assert_eq!(*outputs.lock().unwrap(), vec![(20, 1000)]);
+        });
+    }
+}
@@ -1,28 +1,41 @@
 use std::any::Any;
 use std::cell::RefCell;
-use std::collections::{VecDeque, HashMap};
+use std::collections::{VecDeque, HashMap, BTreeMap};
+use std::fmt;
 use std::marker::PhantomData;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 
 use chrono::Utc;
+#[cfg(feature = "bincode")]
+use serde::{Serialize, Deserialize};
+#[cfg(not(feature = "bincode"))]
+use abomonation::Abomonation;
 
 
-use timely::communication::RelayConnectAllocate;
+use timely::communication::{MessageLatency, RelayConnectAllocate};
 use timely::progress::Timestamp;
 use timely::progress::timestamp::Refines;
 use timely::{Data, ExchangeData};
 use timely::dataflow::{Stream, Scope};
+use timely::dataflow::operators::Partition;
 
 use crate::TimestampData;
+use crate::metrics::BatchLogger;
 use crate::metrics::JCTLogger;
+use crate::metrics::LatencyHistogram;
 use crate::metrics::LatencyLogger;
 use crate::metrics::RcWrapper;
+use crate::metrics::SloLogger;
+use crate::metrics::ErrorLogger;
 use crate::metrics::ThroughputLogger;
+use crate::metrics::ThroughputMeter;
 use crate::static_timely::timely_static_pipeline_scope::PipelineScope;
+use crate::trace::Tracer;
 use crate::operators_timely::Map;
 
-use super::{LocalOpBuilder, ExchangeOpBuilder};
+use super::{LocalOpBuilder, ExchangeOpBuilder, RetryPolicy};
 use super::GenericStream;
 use super::GenericPipelineScope;
 
@@ -33,17 +46,9 @@ const WARMUP_ITERS: usize = 20;
 const DEFAULT_BATCH_SIZE: usize = 64;
 const DEFAULT_BUFFER_SIZE: usize = 512;
 
-/// Map
-/// Consumes each element of the stream and yields a new element.
-pub struct MapNode<D1, D2, L, S> 
-where
-    L: FnMut(D1) -> D2 + 'static,
-    S: Scope + 'static 
-{
-    prev_index: usize,
-    logic: Option<L>,
-    phantom: PhantomData<(D1, D2)>,
-    phantom_scope: PhantomData<S>,
+/// Metrics of a map operator, shared with its dataflow operator
+#[derive(Clone)]
+struct MapMetrics {
     // the minimal start timestamp of received requests
     data_start_timestamp: Rc<RefCell<Option<i64>>>,
     // the timestamp that first request arrived
@@ -63,30 +68,28 @@ where
     // throughput in #reqs/sec
     throughput: Rc<RefCell<VecDeque<f64>>>,    
     // operator execution latency in milliseconds
-    execution_latencies: Rc<RefCell<VecDeque<i64>>>,
+    execution_latencies: Rc<RefCell<LatencyHistogram>>,
     // operator execution latency + delay since last operator emits the output
-    edge_latencies: Rc<RefCell<VecDeque<i64>>>,
+    edge_latencies: Rc<RefCell<LatencyHistogram>>,
     // latency that this operation 
-    path_latencies: Rc<RefCell<VecDeque<i64>>>,
+    path_latencies: Rc<RefCell<LatencyHistogram>>,
     // Timestamp when wramup is finished
     warmed_timestamp: Rc<RefCell<Option<i64>>>,
     // Total number of requests processed after warmup
     total_warmed_count: Rc<RefCell<i64>>,
     // Overall throughput (no window)
-    overall_throughput: Rc<RefCell<Option<f64>>>
+    overall_throughput: Rc<RefCell<Option<f64>>>,
+    // latency SLO (in nanoseconds) read from the operator config, requests exceeding it are dropped
+    latency_slo: Option<i64>,
+    // #requests dropped since their latency already exceeds the SLO
+    dropped_count: Rc<RefCell<usize>>,
+    // #requests finished within the SLO
+    within_slo_count: Rc<RefCell<usize>>
 }
 
-impl<D1, D2, L, S> MapNode<D1, D2, L, S>
-where
-    L: FnMut(D1) -> D2 + 'static,
-    S: Scope + 'static 
-{
-    pub fn new(prev_index: usize, logic: L) -> Self {
-        MapNode {
-            prev_index,
-            logic: Some(logic),
-            phantom: PhantomData,
-            phantom_scope: PhantomData,
+impl MapMetrics {
+    fn new() -> Self {
+        MapMetrics {
             data_start_timestamp: Rc::new(RefCell::new(None)),
             start_timestamp: Rc::new(RefCell::new(None)),
             end_timestamp: Rc::new(RefCell::new(None)),
@@ -96,12 +99,167 @@ where
             window_count: Rc::new(RefCell::new(0)),
             window_start_timestamp: Rc::new(RefCell::new(0)),
             throughput: Rc::new(RefCell::new(VecDeque::new())),
-            execution_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            edge_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            path_latencies: Rc::new(RefCell::new(VecDeque::new())),
+            execution_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            edge_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            path_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
             warmed_timestamp: Rc::new(RefCell::new(None)),
             total_warmed_count: Rc::new(RefCell::new(0)),
-            overall_throughput: Rc::new(RefCell::new(None))
+            overall_throughput: Rc::new(RefCell::new(None)),
+            latency_slo: None,
+            dropped_count: Rc::new(RefCell::new(0)),
+            within_slo_count: Rc::new(RefCell::new(0))
+        }
+    }
+
+    // reads the latency SLO from the operator config
+    fn configure(&mut self, config: &Option<HashMap<String, Arc<dyn Any + Send + Sync>>>) {
+        self.latency_slo = if let Some(config) = config {
+            config.get("latency_slo").and_then(|val| val.downcast_ref::<i64>()).copied()
+        }
+        else { None };
+    }
+
+    // drops the request if its latency `latency` already exceeds the SLO
+    fn drop_over_slo(&self, latency: i64) -> bool {
+        match self.latency_slo {
+            Some(slo) if latency > slo => {
+                *self.dropped_count.borrow_mut() += 1;
+                true
+            },
+            _ => false
+        }
+    }
+
+    fn record_within_slo(&self, latency: i64) {
+        if let Some(slo) = self.latency_slo {
+            if latency <= slo {
+                *self.within_slo_count.borrow_mut() += 1;
+            }
+        }
+    }
+
+    // records a request started at `data_start_ts`, processed from `op_start_ts` to `op_finish_ts`
+    fn record(&self, data_start_ts: i64, net_lat: MessageLatency, total_exec_net_latency: i64, op_start_ts: i64, op_finish_ts: i64) {
+        let data_min_start_ts = if let Some(data_min_start_ts) = self.data_start_timestamp.borrow().as_ref().copied() {
+            std::cmp::min(data_min_start_ts, data_start_ts)
+        }
+        else {
+            data_start_ts
+        };
+        *self.data_start_timestamp.borrow_mut() = Some(data_min_start_ts);
+
+        let exec_lat = op_finish_ts - op_start_ts;
+        self.execution_latencies.borrow_mut().record(exec_lat);
+        // self.edge_latencies.borrow_mut().record(op_finish_ts - x.last_timestamp);
+        // self.path_latencies.borrow_mut().record(op_finish_ts - x.start_timestamp);
+        self.edge_latencies.borrow_mut().record(exec_lat + net_lat);
+        self.path_latencies.borrow_mut().record(exec_lat + net_lat + total_exec_net_latency);
+
+        if self.warmup_start_timestamp.borrow().is_none() {
+            *self.warmup_count.borrow_mut() = 0;
+            *self.start_timestamp.borrow_mut() = Some(op_start_ts);
+            *self.warmup_start_timestamp.borrow_mut() = Some(op_finish_ts);
+        }
+        else if *self.warmup_count.borrow() < WARMUP_ITERS {
+            *self.warmup_count.borrow_mut() += 1;
+            if *self.warmup_count.borrow() == WARMUP_ITERS {
+                *self.window_start_timestamp.borrow_mut() = op_finish_ts;
+                *self.warmed_timestamp.borrow_mut() = Some(op_finish_ts);
+                *self.total_warmed_count.borrow_mut() = 0;
+            }
+            *self.warmup_throughput.borrow_mut() = Some(
+                *self.warmup_count.borrow() as f64 / ((op_finish_ts - self.warmup_start_timestamp.borrow().unwrap()) as f64 / 1e9_f64)
+            );
+        }
+        else {
+            *self.window_count.borrow_mut() += 1;
+            if *self.window_count.borrow() >= THROUGHPUT_WINDOW_SIZE {
+                let ts = Utc::now().timestamp_nanos();
+                let tp = *self.window_count.borrow() as f64 / ((ts - *self.window_start_timestamp.borrow()) as f64 / 1e9_f64);
+                self.throughput.borrow_mut().push_front(tp);
+                if let Some(keep_n) = METRIC_KEEP_LAST_N {
+                    self.throughput.borrow_mut().truncate(keep_n);
+                }
+                *self.window_count.borrow_mut() = 0;
+                *self.window_start_timestamp.borrow_mut() = ts;
+            }
+            *self.total_warmed_count.borrow_mut() += 1;
+            *self.overall_throughput.borrow_mut() = Some(*self.total_warmed_count.borrow() as f64 / ((op_finish_ts - *self.warmed_timestamp.borrow().as_ref().unwrap()) as f64 / 1e9_f64))
+        }
+        *self.end_timestamp.borrow_mut() = Some(op_finish_ts);
+    }
+
+    fn throughput_logger(&self) -> ThroughputLogger {
+        ThroughputLogger {
+            throughput: RcWrapper::new(self.throughput.clone()),
+            warmup_throughput: RcWrapper::new(self.warmup_throughput.clone()),
+            overall_throughput: RcWrapper::new(self.overall_throughput.clone())
+        }
+    }
+
+    fn compute_latency_logger(&self) -> LatencyLogger {
+        LatencyLogger {
+            latencies: RcWrapper::new(self.execution_latencies.clone())
+        }
+    }
+
+    fn edge_latency_logger(&self) -> LatencyLogger {
+        LatencyLogger {
+            latencies: RcWrapper::new(self.edge_latencies.clone())
+        }
+    }
+
+    fn path_latency_logger(&self) -> LatencyLogger {
+        LatencyLogger {
+            latencies: RcWrapper::new(self.path_latencies.clone())
+        }
+    }
+
+    fn jct_logger(&self) -> JCTLogger {
+        JCTLogger {
+            data_start_timestamp: RcWrapper::new(self.data_start_timestamp.clone()),
+            op_start_timestamp: RcWrapper::new(self.start_timestamp.clone()),
+            op_end_timestamp: RcWrapper::new(self.end_timestamp.clone())
+        }
+    }
+
+    fn slo_logger(&self) -> Option<SloLogger> {
+        // only reported with a latency SLO
+        self.latency_slo.map(|_| SloLogger {
+            dropped: RcWrapper::new(self.dropped_count.clone()),
+            within_slo: RcWrapper::new(self.within_slo_count.clone()),
+            op_start_timestamp: RcWrapper::new(self.start_timestamp.clone()),
+            op_end_timestamp: RcWrapper::new(self.end_timestamp.clone())
+        })
+    }
+}
+
+/// Map
+/// Consumes each element of the stream and yields a new element.
+pub struct MapNode<D1, D2, L, S> 
+where
+    L: FnMut(D1) -> D2 + 'static,
+    S: Scope + 'static 
+{
+    prev_index: usize,
+    logic: Option<L>,
+    phantom: PhantomData<(D1, D2)>,
+    phantom_scope: PhantomData<S>,
+    metrics: MapMetrics
+}
+
+impl<D1, D2, L, S> MapNode<D1, D2, L, S>
+where
+    L: FnMut(D1) -> D2 + 'static,
+    S: Scope + 'static 
+{
+    pub fn new(prev_index: usize, logic: L) -> Self {
+        MapNode {
+            prev_index,
+            logic: Some(logic),
+            phantom: PhantomData,
+            phantom_scope: PhantomData,
+            metrics: MapMetrics::new()
         }
     }
 }
@@ -125,147 +283,79 @@ where
             config.get("simulate_network_latency").and_then(|val| val.downcast_ref::<i64>()).map(|val| *val)
         }
         else { None };
+        self.metrics.configure(&config);
 
+        let tracer = Tracer::from_config(&config);
         let stream_in = streams[0];
         let stream_in = stream_in.as_any().downcast_ref::<Stream<S, TimestampData<D1>>>().unwrap();
         let mut logic = self.logic.take().unwrap();
 
-        let execution_latency_metrics = self.execution_latencies.clone();
-        let edge_latency_metrics = self.edge_latencies.clone();
-        let path_latency_metrics = self.path_latencies.clone();
-        let window_start_timestamp = self.window_start_timestamp.clone();
-        let window_count = self.window_count.clone();
-        let throughput_metrics = self.throughput.clone();
-        let warmup_start_timestamp = self.warmup_start_timestamp.clone();
-        let warmup_count = self.warmup_count.clone();
-        let warmup_throughput = self.warmup_throughput.clone();
-        let data_start_timestamp = self.data_start_timestamp.clone();
-        let start_timestamp = self.start_timestamp.clone();
-        let end_timestamp = self.end_timestamp.clone();
-        let warmed_timestamp = self.warmed_timestamp.clone();
-        let total_warmed_count = self.total_warmed_count.clone();
-        let overall_throughput = self.overall_throughput.clone();
-
-        // TODO: drop request that latency already exceeds SLO
-        let stream_out = stream_in.map(move |x, mut net_lat| {
+        let metrics = self.metrics.clone();
+
+        let stream_out = stream_in.filter_map(move |x, mut net_lat| {
             if let Some(sim_net_lat) = sim_network_latency {
                 net_lat = sim_net_lat;
             }
             if net_lat < 0 { net_lat = 0 }
-            
-            let data_min_start_ts = if let Some(data_min_start_ts) = data_start_timestamp.borrow().as_ref().copied() {
-                std::cmp::min(data_min_start_ts, x.start_timestamp)
-            }   
-            else {
-                x.start_timestamp
-            };
-            *data_start_timestamp.borrow_mut() = Some(data_min_start_ts);
+            // drop request that latency already exceeds SLO
+            if metrics.drop_over_slo(x.total_exec_net_latency + net_lat) {
+                return None;
+            }
 
             let op_start_ts = Utc::now().timestamp_nanos();
             let mapped_data = (logic)(x.data);
             let op_finish_ts = Utc::now().timestamp_nanos();
+            if let Some(tracer) = &tracer {
+                tracer.trace_execution(x.request_id, x.last_timestamp, net_lat, op_start_ts, op_finish_ts);
+            }
 
+            metrics.record(x.start_timestamp, net_lat, x.total_exec_net_latency, op_start_ts, op_finish_ts);
             let exec_lat = op_finish_ts - op_start_ts;
-            execution_latency_metrics.borrow_mut().push_front(exec_lat);
-            // edge_latency_metrics.borrow_mut().push_front(op_finish_ts - x.last_timestamp);
-            // path_latency_metrics.borrow_mut().push_front(op_finish_ts - x.start_timestamp);
-            edge_latency_metrics.borrow_mut().push_front(exec_lat + net_lat);
-            path_latency_metrics.borrow_mut().push_front(exec_lat + net_lat + x.total_exec_net_latency);
-
-            if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                execution_latency_metrics.borrow_mut().truncate(keep_n);
-                edge_latency_metrics.borrow_mut().truncate(keep_n);
-                path_latency_metrics.borrow_mut().truncate(keep_n);
-            }
-            if warmup_start_timestamp.borrow().is_none() {
-                *warmup_count.borrow_mut() = 0;
-                *start_timestamp.borrow_mut() = Some(op_start_ts);
-                *warmup_start_timestamp.borrow_mut() = Some(op_finish_ts);
-            }
-            else if *warmup_count.borrow() < WARMUP_ITERS {
-                *warmup_count.borrow_mut() += 1;
-                if *warmup_count.borrow() == WARMUP_ITERS {
-                    *window_start_timestamp.borrow_mut() = op_finish_ts;
-                    *warmed_timestamp.borrow_mut() = Some(op_finish_ts);
-                    *total_warmed_count.borrow_mut() = 0;
-                }
-                *warmup_throughput.borrow_mut() = Some(
-                    *warmup_count.borrow() as f64 / ((op_finish_ts - warmup_start_timestamp.borrow().unwrap()) as f64 / 1e9_f64)
-                );
-            }
-            else {
-                *window_count.borrow_mut() += 1;
-                if *window_count.borrow() >= THROUGHPUT_WINDOW_SIZE {
-                    let ts = Utc::now().timestamp_nanos();
-                    let tp = *window_count.borrow() as f64 / ((ts - *window_start_timestamp.borrow()) as f64 / 1e9_f64);
-                    throughput_metrics.borrow_mut().push_front(tp);
-                    if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                        throughput_metrics.borrow_mut().truncate(keep_n);
-                    }
-                    *window_count.borrow_mut() = 0;
-                    *window_start_timestamp.borrow_mut() = ts;
-                }
-                *total_warmed_count.borrow_mut() += 1;
-                *overall_throughput.borrow_mut() = Some(*total_warmed_count.borrow() as f64 / ((op_finish_ts - *warmed_timestamp.borrow().as_ref().unwrap()) as f64 / 1e9_f64))
-            }
-            *end_timestamp.borrow_mut() = Some(op_finish_ts);
+            metrics.record_within_slo(exec_lat + net_lat + x.total_exec_net_latency);
             if reset_timestamp {
-                TimestampData {
+                Some(TimestampData {
                     data: mapped_data,
+                    request_id: x.request_id,
                     start_timestamp: op_finish_ts,
                     last_timestamp: op_finish_ts,
                     total_exec_net_latency: 0
-                }                
+                })                
             }
             else {
-                TimestampData {
+                Some(TimestampData {
                     data: mapped_data,
+                    request_id: x.request_id,
                     start_timestamp: x.start_timestamp,
                     last_timestamp: op_finish_ts,
                     total_exec_net_latency: exec_lat + net_lat + x.total_exec_net_latency
-                }
+                })
             }
         });
         Box::new(stream_out)
     }
 
     fn get_throughput_logger(&self) -> Option<ThroughputLogger> {
-        let logger = ThroughputLogger {
-            throughput: RcWrapper::new(self.throughput.clone()),
-            warmup_throughput: RcWrapper::new(self.warmup_throughput.clone()),
-            overall_throughput: RcWrapper::new(self.overall_throughput.clone())
-        };
-        Some(logger)
+        Some(self.metrics.throughput_logger())
     }
 
     fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger> {
-        let logger = LatencyLogger {
-            latencies: RcWrapper::new(self.execution_latencies.clone())
-        };
-        Some(logger)
+        Some(self.metrics.compute_latency_logger())
     }
 
     fn get_flow_edge_latency_logger(&self) -> Option<LatencyLogger> {
-        let logger = LatencyLogger {
-            latencies: RcWrapper::new(self.edge_latencies.clone())
-        };
-        Some(logger)
+        Some(self.metrics.edge_latency_logger())
     }
 
     fn get_flow_path_latency_logger(&self) -> Option<LatencyLogger> {
-        let logger = LatencyLogger {
-            latencies: RcWrapper::new(self.path_latencies.clone())
-        };
-        Some(logger)
+        Some(self.metrics.path_latency_logger())
     }
 
     fn get_jct_logger(&self) -> Option<JCTLogger> {
-        let logger = JCTLogger {
-            data_start_timestamp: RcWrapper::new(self.data_start_timestamp.clone()),
-            op_start_timestamp: RcWrapper::new(self.start_timestamp.clone()),
-            op_end_timestamp: RcWrapper::new(self.end_timestamp.clone())
-        };
-        Some(logger)
+        Some(self.metrics.jct_logger())
+    }
+
+    fn get_slo_logger(&self) -> Option<SloLogger> {
+        self.metrics.slo_logger()
     }
 }
 
@@ -306,36 +396,7 @@ where
     logic: Option<L>,
     phantom: PhantomData<(D, I::Item)>,
     phantom_scope: PhantomData<S>,
-    // the minimal start timestamp of received requests
-    data_start_timestamp: Rc<RefCell<Option<i64>>>,    
-    // the timestamp that first request arrived
-    start_timestamp: Rc<RefCell<Option<i64>>>,
-    // the timestamp that the last received request finished processing
-    end_timestamp: Rc<RefCell<Option<i64>>>,
-    // number of warmup requests received
-    warmup_count: Rc<RefCell<usize>>,
-    // the timestamp that the first request finished processing
-    warmup_start_timestamp: Rc<RefCell<Option<i64>>>,
-    // the throughput in the warmup period (will be computed as long as there are >=2 requests)
-    warmup_throughput: Rc<RefCell<Option<f64>>>,
-    // #requests processed in current window
-    window_count: Rc<RefCell<usize>>,
-    // the timestamp that current window starts
-    window_start_timestamp: Rc<RefCell<i64>>,
-    // throughput in #reqs/sec
-    throughput: Rc<RefCell<VecDeque<f64>>>,    
-    // operator execution latency in milliseconds
-    execution_latencies: Rc<RefCell<VecDeque<i64>>>,
-    // operator execution latency + delay since last operator emits the output
-    edge_latencies: Rc<RefCell<VecDeque<i64>>>,
-    // latency that this operation 
-    path_latencies: Rc<RefCell<VecDeque<i64>>>,
-    // Timestamp when wramup is finished
-    warmed_timestamp: Rc<RefCell<Option<i64>>>,
-    // Total number of requests processed after warmup
-    total_warmed_count: Rc<RefCell<i64>>,
-    // Overall throughput (no window)
-    overall_throughput: Rc<RefCell<Option<f64>>>    
+    metrics: MapMetrics
 }
 
 impl<D: Data, I: IntoIterator + 'static, L, S> FlatMapNode<D, I, L, S>
@@ -350,21 +411,7 @@ where
             logic: Some(logic),
             phantom: PhantomData,
             phantom_scope: PhantomData,
-            data_start_timestamp: Rc::new(RefCell::new(None)),
-            start_timestamp: Rc::new(RefCell::new(None)),
-            end_timestamp: Rc::new(RefCell::new(None)),
-            warmup_count: Rc::new(RefCell::new(0)),
-            warmup_start_timestamp: Rc::new(RefCell::new(None)),
-            warmup_throughput: Rc::new(RefCell::new(None)),
-            window_count: Rc::new(RefCell::new(0)),
-            window_start_timestamp: Rc::new(RefCell::new(0)),
-            throughput: Rc::new(RefCell::new(VecDeque::new())),
-            execution_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            edge_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            path_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            warmed_timestamp: Rc::new(RefCell::new(None)),
-            total_warmed_count: Rc::new(RefCell::new(0)),
-            overall_throughput: Rc::new(RefCell::new(None))            
+            metrics: MapMetrics::new()
         }
     }
 }
@@ -389,95 +436,43 @@ where
             config.get("simulate_network_latency").and_then(|val| val.downcast_ref::<i64>()).map(|val| *val)
         }
         else { None };        
+        self.metrics.configure(&config);
 
+        let tracer = Tracer::from_config(&config);
         let stream_in = streams[0];
         let stream_in = stream_in.as_any().downcast_ref::<Stream<S, TimestampData<D>>>().unwrap();
         let mut logic = self.logic.take().unwrap();
 
-        let execution_latency_metrics = self.execution_latencies.clone();
-        let edge_latency_metrics = self.edge_latencies.clone();
-        let path_latency_metrics = self.path_latencies.clone();
-        let window_start_timestamp = self.window_start_timestamp.clone();
-        let window_count = self.window_count.clone();
-        let throughput_metrics = self.throughput.clone();
-        let warmup_start_timestamp = self.warmup_start_timestamp.clone();
-        let warmup_count = self.warmup_count.clone();
-        let warmup_throughput = self.warmup_throughput.clone();
-        let data_start_timestamp = self.data_start_timestamp.clone();
-        let start_timestamp = self.start_timestamp.clone();
-        let end_timestamp = self.end_timestamp.clone();
-        let warmed_timestamp = self.warmed_timestamp.clone();
-        let total_warmed_count = self.total_warmed_count.clone();
-        let overall_throughput = self.overall_throughput.clone();
+        let metrics = self.metrics.clone();
 
         let stream_out = stream_in.flat_map(move |x, mut net_lat| {      
             if let Some(sim_net_lat) = sim_network_latency {
                 net_lat = sim_net_lat;
             }
             if net_lat < 0 { net_lat = 0 }
+            // drop request that latency already exceeds SLO
+            if metrics.drop_over_slo(x.total_exec_net_latency + net_lat) {
+                return None.into_iter().flatten();
+            }
 
-            let data_min_start_ts = if let Some(data_min_start_ts) = data_start_timestamp.borrow().as_ref().copied() {
-                std::cmp::min(data_min_start_ts, x.start_timestamp)
-            }   
-            else {
-                x.start_timestamp
-            };
-            *data_start_timestamp.borrow_mut() = Some(data_min_start_ts);
-
+            let x_request_id = x.request_id;
             let x_start_timestamp = x.start_timestamp;
             let x_total_exec_net_lat = x.total_exec_net_latency;
             let op_start_ts = Utc::now().timestamp_nanos();
             let mapped_iter = (logic)(x.data);
             let op_finish_ts = Utc::now().timestamp_nanos();
+            if let Some(tracer) = &tracer {
+                tracer.trace_execution(x_request_id, x.last_timestamp, net_lat, op_start_ts, op_finish_ts);
+            }
             
+            metrics.record(x_start_timestamp, net_lat, x_total_exec_net_lat, op_start_ts, op_finish_ts);
             let exec_lat = op_finish_ts - op_start_ts;
-            execution_latency_metrics.borrow_mut().push_front(exec_lat);
-            // edge_latency_metrics.borrow_mut().push_front(op_finish_ts - x.last_timestamp);
-            // path_latency_metrics.borrow_mut().push_front(op_finish_ts - x.start_timestamp);
-            edge_latency_metrics.borrow_mut().push_front(exec_lat + net_lat);
-            path_latency_metrics.borrow_mut().push_front(exec_lat + net_lat + x.total_exec_net_latency);
-
-            if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                execution_latency_metrics.borrow_mut().truncate(keep_n);
-                edge_latency_metrics.borrow_mut().truncate(keep_n);
-                path_latency_metrics.borrow_mut().truncate(keep_n);
-            }
-            if warmup_start_timestamp.borrow().is_none() {
-                *warmup_count.borrow_mut() = 0;
-                *start_timestamp.borrow_mut() = Some(op_start_ts);
-                *warmup_start_timestamp.borrow_mut() = Some(op_finish_ts);
-            }
-            else if *warmup_count.borrow() < WARMUP_ITERS {
-                *warmup_count.borrow_mut() += 1;
-                if *warmup_count.borrow() == WARMUP_ITERS {
-                    *window_start_timestamp.borrow_mut() = op_finish_ts;
-                    *warmed_timestamp.borrow_mut() = Some(op_finish_ts);
-                    *total_warmed_count.borrow_mut() = 0;
-                }
-                *warmup_throughput.borrow_mut() = Some(
-                    *warmup_count.borrow() as f64 / ((op_finish_ts - warmup_start_timestamp.borrow().unwrap()) as f64 / 1e9_f64)
-                );
-            }
-            else {
-                *window_count.borrow_mut() += 1;
-                if *window_count.borrow() >= THROUGHPUT_WINDOW_SIZE {
-                    let ts = Utc::now().timestamp_nanos();
-                    let tp = *window_count.borrow() as f64 / ((ts - *window_start_timestamp.borrow()) as f64 / 1e9_f64);
-                    throughput_metrics.borrow_mut().push_front(tp);
-                    if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                        throughput_metrics.borrow_mut().truncate(keep_n);
-                    }
-                    *window_count.borrow_mut() = 0;
-                    *window_start_timestamp.borrow_mut() = ts;
-                }
-                *total_warmed_count.borrow_mut() += 1;
-                *overall_throughput.borrow_mut() = Some(*total_warmed_count.borrow() as f64 / ((op_finish_ts - *warmed_timestamp.borrow().as_ref().unwrap()) as f64 / 1e9_f64))
-            }
-            *end_timestamp.borrow_mut() = Some(op_finish_ts);
+            metrics.record_within_slo(exec_lat + net_lat + x_total_exec_net_lat);
             let mapped_iter_timestamped = mapped_iter.into_iter().map(move |item| {
                 if reset_timestamp {
                     TimestampData {
                         data: item,
+                        request_id: x_request_id,
                         start_timestamp: x_start_timestamp,
                         last_timestamp: op_finish_ts,
                         total_exec_net_latency: exec_lat + net_lat + x_total_exec_net_lat,
@@ -486,54 +481,40 @@ where
                 else { 
                     TimestampData {
                         data: item,
+                        request_id: x_request_id,
                         start_timestamp: x_start_timestamp,
                         last_timestamp: op_finish_ts,
                         total_exec_net_latency: exec_lat + net_lat + x_total_exec_net_lat,
                     }
                 }
             });
-            mapped_iter_timestamped
+            Some(mapped_iter_timestamped).into_iter().flatten()
         });
         Box::new(stream_out)
     }
 
     fn get_throughput_logger(&self) -> Option<ThroughputLogger> {
-        let logger = ThroughputLogger {
-            throughput: RcWrapper::new(self.throughput.clone()),
-            warmup_throughput: RcWrapper::new(self.warmup_throughput.clone()),
-            overall_throughput: RcWrapper::new(self.overall_throughput.clone())
-        };
-        Some(logger)
+        Some(self.metrics.throughput_logger())
     }
 
     fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger> {
-        let logger = LatencyLogger {
-            latencies: RcWrapper::new(self.execution_latencies.clone())
-        };
-        Some(logger)
+        Some(self.metrics.compute_latency_logger())
     }
 
     fn get_flow_edge_latency_logger(&self) -> Option<LatencyLogger> {
-        let logger = LatencyLogger {
-            latencies: RcWrapper::new(self.edge_latencies.clone())
-        };
-        Some(logger)
+        Some(self.metrics.edge_latency_logger())
     }
 
     fn get_flow_path_latency_logger(&self) -> Option<LatencyLogger> {
-        let logger = LatencyLogger {
-            latencies: RcWrapper::new(self.path_latencies.clone())
-        };
-        Some(logger)
+        Some(self.metrics.path_latency_logger())
     }
 
     fn get_jct_logger(&self) -> Option<JCTLogger> {
-        let logger = JCTLogger {
-            data_start_timestamp: RcWrapper::new(self.data_start_timestamp.clone()),
-            op_start_timestamp: RcWrapper::new(self.start_timestamp.clone()),
-            op_end_timestamp: RcWrapper::new(self.end_timestamp.clone())
-        };
-        Some(logger)
+        Some(self.metrics.jct_logger())
+    }
+
+    fn get_slo_logger(&self) -> Option<SloLogger> {
+        self.metrics.slo_logger()
     }
 }
 
@@ -546,7 +527,365 @@ where
 {
     fn acquire_from_input_pipeline(&self, scope: &mut dyn GenericPipelineScope, input_idx: usize) -> Box<dyn GenericStream> {
         let scope = scope.as_any_mut().downcast_mut::<PipelineScope<A, T>>().unwrap();
-        let stream = scope.acquire_pipeline_input::<TimestampData<I::Item>>(input_idx);
+        let stream = scope.acquire_pipeline_input::<TimestampData<I::Item>>(input_idx);
+        Box::new(stream)
+    }
+
+    fn build_and_register_output(&mut self, streams: &[&Box<dyn GenericStream>], config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>, scope: &mut dyn GenericPipelineScope, output_idx: usize) -> Box<dyn GenericStream> {
+        let stream = (self as &mut dyn LocalOpBuilder).build(streams, config);
+        self.register_pipeline_output(&stream, scope, output_idx);
+        stream
+    }
+
+    fn register_pipeline_output(&self, stream: &Box<dyn GenericStream>, scope: &mut dyn GenericPipelineScope, output_idx: usize) {
+        let scope = scope.as_any_mut().downcast_mut::<PipelineScope<A, T>>().unwrap();
+        let stream = stream.as_any().downcast_ref::<Stream<PipelineScope<A, T>, TimestampData<I::Item>>>().unwrap();
+        scope.register_pipeline_output_balanced_exchange(stream, output_idx);
+    }
+}
+
+
+/// Input of a fallible operator whose processing failed
+#[derive(Debug, Clone)]
+#[cfg_attr(feature="bincode", derive(Serialize, Deserialize))]
+pub struct DeadLetter<D, E> {
+    /// Name of the failed operator
+    pub operator: String,
+    /// Original input (including the request id for requests from a request / listener input)
+    pub input: D,
+    pub error: E,
+}
+
+#[cfg(not(feature = "bincode"))]
+impl<D: Abomonation, E: Abomonation> Abomonation for DeadLetter<D, E> {
+    unsafe fn entomb<W: std::io::Write>(&self, write: &mut W) -> std::io::Result<()> {
+        self.operator.entomb(write)?;
+        self.input.entomb(write)?;
+        self.error.entomb(write)
+    }
+
+    unsafe fn exhume<'b>(&mut self, bytes: &'b mut [u8]) -> Option<&'b mut [u8]> {
+        let bytes = self.operator.exhume(bytes)?;
+        let bytes = self.input.exhume(bytes)?;
+        self.error.exhume(bytes)
+    }
+
+    fn extent(&self) -> usize {
+        self.operator.extent() + self.input.extent() + self.error.extent()
+    }
+}
+
+type Routed<D, D2, E> = Result<TimestampData<D2>, TimestampData<DeadLetter<D, E>>>;
+
+type DeadLetterStream = Rc<RefCell<Option<Box<dyn GenericStream>>>>;
+
+// output stream and dead-letter stream of a fallible operator
+type SplitStreams<S, D, D2, E> = (Stream<S, TimestampData<D2>>, Stream<S, TimestampData<DeadLetter<D, E>>>);
+
+// split the routed stream into the output stream and the dead-letter stream
+fn split_dead_letters<S: Scope, D: Data, D2: Data, E: Data>(routed: &Stream<S, Routed<D, D2, E>>) -> SplitStreams<S, D, D2, E> {
+    let mut parts = routed.partition(2, |x| match x {
+        Ok(_) => (0, x),
+        Err(_) => (1, x)
+    });
+    let dead_letters = parts.pop().unwrap().filter_map(|x, _| x.err());
+    let stream_out = parts.pop().unwrap().filter_map(|x, _| x.ok());
+    (stream_out, dead_letters)
+}
+
+/// Fallible flat map
+/// Consumes each element of the stream and yields some number of new elements,
+/// or routes the element to the dead-letter output (see `DeadLetterNode`) if the logic still fails after the retries.
+pub struct TryMapNode<D, I, E, L, S>
+where
+    I: IntoIterator,
+    L: FnMut(&D) -> Result<I, E> + 'static,
+    S: Scope + 'static
+{
+    prev_index: usize,
+    name: String,
+    logic: Option<L>,
+    // dead-letter stream, taken by the dead-letter operator
+    dead_letters: DeadLetterStream,
+    phantom: PhantomData<(D, I::Item, E)>,
+    phantom_scope: PhantomData<S>,
+    metrics: MapMetrics,
+    // #requests routed to the dead-letter output
+    error_count: Rc<RefCell<usize>>,
+    retry: RetryPolicy<E>,
+    // #attempts retried
+    retry_count: Rc<RefCell<usize>>
+}
+
+impl<D: Data, I: IntoIterator + 'static, E: Data, L, S> TryMapNode<D, I, E, L, S>
+where
+    L: FnMut(&D) -> Result<I, E> + 'static,
+    S: Scope + 'static,
+    I::Item: Data
+{
+    pub fn new(prev_index: usize, name: String, logic: L, retry: RetryPolicy<E>) -> Self {
+        TryMapNode {
+            prev_index,
+            name,
+            logic: Some(logic),
+            dead_letters: Rc::new(RefCell::new(None)),
+            phantom: PhantomData,
+            phantom_scope: PhantomData,
+            metrics: MapMetrics::new(),
+            error_count: Rc::new(RefCell::new(0)),
+            retry,
+            retry_count: Rc::new(RefCell::new(0))
+        }
+    }
+
+    /// Dead-letter operator of this operator
+    pub fn dead_letter_node(&self, index: usize) -> DeadLetterNode<D, E, S> {
+        DeadLetterNode::new(index, self.dead_letters.clone())
+    }
+}
+
+impl<D: Data, I: IntoIterator + 'static, E: Data, L, S> LocalOpBuilder for TryMapNode<D, I, E, L, S>
+where
+    L: FnMut(&D) -> Result<I, E> + 'static,
+    S: Scope + 'static,
+    I::Item: Data
+{
+    fn required_prev_nodes(&self) -> Vec<usize> {
+        vec![self.prev_index]
+    }
+
+    fn build(&mut self, streams: &[&Box<dyn GenericStream>], config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>) -> Box<dyn GenericStream> {
+        let reset_timestamp = if let Some(config) = &config {
+            config.get("reset_timestamp").and_then(|val| val.downcast_ref::<bool>()).copied()
+        }
+        else { None };
+        let reset_timestamp = reset_timestamp.unwrap_or(false);
+        let sim_network_latency = if let Some(config) = &config {
+            config.get("simulate_network_latency").and_then(|val| val.downcast_ref::<i64>()).copied()
+        }
+        else { None };
+        self.metrics.configure(&config);
+        if let Some(config) = &config {
+            self.retry.configure(config);
+        }
+
+        let tracer = Tracer::from_config(&config);
+        let stream_in = streams[0];
+        let stream_in = stream_in.as_any().downcast_ref::<Stream<S, TimestampData<D>>>().unwrap();
+        let mut logic = self.logic.take().unwrap();
+        let name = self.name.clone();
+
+        let metrics = self.metrics.clone();
+        let error_count = self.error_count.clone();
+        let retry = self.retry.clone();
+        let retry_count = self.retry_count.clone();
+
+        let routed = stream_in.flat_map(move |x, mut net_lat| {
+            if let Some(sim_net_lat) = sim_network_latency {
+                net_lat = sim_net_lat;
+            }
+            if net_lat < 0 { net_lat = 0 }
+            // drop request that latency already exceeds SLO
+            if metrics.drop_over_slo(x.total_exec_net_latency + net_lat) {
+                return Vec::new();
+            }
+
+            let op_start_ts = Utc::now().timestamp_nanos();
+            let result = retry.call(&mut logic, &x.data, &retry_count);
+            let op_finish_ts = Utc::now().timestamp_nanos();
+
+            metrics.record(x.start_timestamp, net_lat, x.total_exec_net_latency, op_start_ts, op_finish_ts);
+            let exec_lat = op_finish_ts - op_start_ts;
+
+            let (start_ts, total_exec_net_lat) = if reset_timestamp {
+                (op_finish_ts, 0)
+            }
+            else {
+                (x.start_timestamp, exec_lat + net_lat + x.total_exec_net_latency)
+            };
+            if let Some(tracer) = &tracer {
+                tracer.trace_execution(x.request_id, x.last_timestamp, net_lat, op_start_ts, op_finish_ts);
+            }
+            match result {
+                Ok(items) => {
+                    metrics.record_within_slo(exec_lat + net_lat + x.total_exec_net_latency);
+                    items.into_iter().map(|item| Ok(TimestampData {
+                        data: item,
+                        request_id: x.request_id,
+                        start_timestamp: start_ts,
+                        last_timestamp: op_finish_ts,
+                        total_exec_net_latency: total_exec_net_lat
+                    })).collect::<Vec<Routed<D, I::Item, E>>>()
+                },
+                Err(error) => {
+                    *error_count.borrow_mut() += 1;
+                    let dead_letter = DeadLetter {
+                        operator: name.clone(),
+                        input: x.data,
+                        error
+                    };
+                    vec![Err(TimestampData {
+                        data: dead_letter,
+                        request_id: x.request_id,
+                        start_timestamp: start_ts,
+                        last_timestamp: op_finish_ts,
+                        total_exec_net_latency: total_exec_net_lat
+                    })]
+                }
+            }
+        });
+        let (stream_out, dead_letters) = split_dead_letters(&routed);
+        *self.dead_letters.borrow_mut() = Some(Box::new(dead_letters));
+        Box::new(stream_out)
+    }
+
+    fn get_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.metrics.throughput_logger())
+    }
+
+    fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger> {
+        Some(self.metrics.compute_latency_logger())
+    }
+
+    fn get_flow_edge_latency_logger(&self) -> Option<LatencyLogger> {
+        Some(self.metrics.edge_latency_logger())
+    }
+
+    fn get_flow_path_latency_logger(&self) -> Option<LatencyLogger> {
+        Some(self.metrics.path_latency_logger())
+    }
+
+    fn get_jct_logger(&self) -> Option<JCTLogger> {
+        Some(self.metrics.jct_logger())
+    }
+
+    fn get_slo_logger(&self) -> Option<SloLogger> {
+        self.metrics.slo_logger()
+    }
+
+    fn get_error_logger(&self) -> Option<ErrorLogger> {
+        let logger = ErrorLogger {
+            errors: RcWrapper::new(self.error_count.clone()),
+            retries: RcWrapper::new(self.retry_count.clone())
+        };
+        Some(logger)
+    }
+}
+
+impl<D: Data, I: IntoIterator + 'static, E: Data, L, A, T> ExchangeOpBuilder for TryMapNode<D, I, E, L, PipelineScope<A, T>>
+where
+    L: FnMut(&D) -> Result<I, E> + 'static,
+    I::Item: ExchangeData,
+    A: RelayConnectAllocate + 'static,
+    T: Timestamp+Refines<()>
+{
+    fn acquire_from_input_pipeline(&self, scope: &mut dyn GenericPipelineScope, input_idx: usize) -> Box<dyn GenericStream> {
+        let scope = scope.as_any_mut().downcast_mut::<PipelineScope<A, T>>().unwrap();
+        let stream = scope.acquire_pipeline_input::<TimestampData<I::Item>>(input_idx);
+        Box::new(stream)
+    }
+
+    fn build_and_register_output(&mut self, streams: &[&Box<dyn GenericStream>], config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>, scope: &mut dyn GenericPipelineScope, output_idx: usize) -> Box<dyn GenericStream> {
+        let stream = (self as &mut dyn LocalOpBuilder).build(streams, config);
+        self.register_pipeline_output(&stream, scope, output_idx);
+        stream
+    }
+
+    fn register_pipeline_output(&self, stream: &Box<dyn GenericStream>, scope: &mut dyn GenericPipelineScope, output_idx: usize) {
+        let scope = scope.as_any_mut().downcast_mut::<PipelineScope<A, T>>().unwrap();
+        let stream = stream.as_any().downcast_ref::<Stream<PipelineScope<A, T>, TimestampData<I::Item>>>().unwrap();
+        scope.register_pipeline_output_balanced_exchange(stream, output_idx);
+    }
+}
+
+/// Error of a batch for which the logic of a `TryBatchedMapNode` does not yield one output per input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchSizeMismatch {
+    pub inputs: usize,
+    pub outputs: usize,
+}
+
+impl fmt::Display for BatchSizeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "batch logic yields {} outputs for {} inputs", self.outputs, self.inputs)
+    }
+}
+
+impl From<BatchSizeMismatch> for String {
+    fn from(err: BatchSizeMismatch) -> Self {
+        err.to_string()
+    }
+}
+
+/// Dead-letter output of a fallible operator (`TryMapNode`),
+/// it must be assigned to the same pipeline as the fallible operator
+pub struct DeadLetterNode<D, E, S>
+where
+    S: Scope + 'static
+{
+    prev_index: usize,
+    dead_letters: DeadLetterStream,
+    phantom: PhantomData<(D, E)>,
+    phantom_scope: PhantomData<S>
+}
+
+impl<D, E, S> DeadLetterNode<D, E, S>
+where
+    S: Scope + 'static
+{
+    fn new(prev_index: usize, dead_letters: DeadLetterStream) -> Self {
+        DeadLetterNode {
+            prev_index,
+            dead_letters,
+            phantom: PhantomData,
+            phantom_scope: PhantomData
+        }
+    }
+}
+
+impl<D: Data, E: Data, S> LocalOpBuilder for DeadLetterNode<D, E, S>
+where
+    S: Scope + 'static
+{
+    fn required_prev_nodes(&self) -> Vec<usize> {
+        vec![self.prev_index]
+    }
+
+    fn build(&mut self, _streams: &[&Box<dyn GenericStream>], _config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>) -> Box<dyn GenericStream> {
+        // the fallible operator is built first, since it has a smaller index
+        self.dead_letters.borrow_mut().take()
+            .expect("dead-letter operator must be assigned to the same pipeline as its fallible operator")
+    }
+
+    fn get_throughput_logger(&self) -> Option<ThroughputLogger> {
+        None
+    }
+
+    fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger> {
+        None
+    }
+
+    fn get_flow_edge_latency_logger(&self) -> Option<LatencyLogger> {
+        None
+    }
+
+    fn get_flow_path_latency_logger(&self) -> Option<LatencyLogger> {
+        None
+    }
+
+    fn get_jct_logger(&self) -> Option<JCTLogger> {
+        None
+    }
+}
+
+impl<D: ExchangeData, E: ExchangeData, A, T> ExchangeOpBuilder for DeadLetterNode<D, E, PipelineScope<A, T>>
+where
+    A: RelayConnectAllocate + 'static,
+    T: Timestamp+Refines<()>
+{
+    fn acquire_from_input_pipeline(&self, scope: &mut dyn GenericPipelineScope, input_idx: usize) -> Box<dyn GenericStream> {
+        let scope = scope.as_any_mut().downcast_mut::<PipelineScope<A, T>>().unwrap();
+        let stream = scope.acquire_pipeline_input::<TimestampData<DeadLetter<D, E>>>(input_idx);
         Box::new(stream)
     }
 
@@ -558,14 +897,18 @@ where
 
     fn register_pipeline_output(&self, stream: &Box<dyn GenericStream>, scope: &mut dyn GenericPipelineScope, output_idx: usize) {
         let scope = scope.as_any_mut().downcast_mut::<PipelineScope<A, T>>().unwrap();
-        let stream = stream.as_any().downcast_ref::<Stream<PipelineScope<A, T>, TimestampData<I::Item>>>().unwrap();
+        let stream = stream.as_any().downcast_ref::<Stream<PipelineScope<A, T>, TimestampData<DeadLetter<D, E>>>>().unwrap();
         scope.register_pipeline_output_balanced_exchange(stream, output_idx);
     }
 }
 
+type BucketKey<D> = Rc<dyn Fn(&D) -> usize>;
 
 /// Batched map
 /// Consumes each element of the stream and yields a new element.
+/// Batches of `batch_size` (in the operator config) are formed from the buffered elements of a timestamp,
+/// with `max_batch_wait` (in nanoseconds) or a bucket key (see `new_bucketed`) the batches are formed dynamically
+/// across timestamps (see `dynamic_batched_map`), `batch_size` being the maximum batch size.
 pub struct BatchedMapNode<D1, D2, I2: IntoIterator<Item=D2>, L, S> 
 where
     L: FnMut(Vec<D1>) -> I2 + 'static,
@@ -575,36 +918,13 @@ where
     logic: Option<L>,
     phantom: PhantomData<(D1, D2)>,
     phantom_scope: PhantomData<S>,
-    // the minimal start timestamp of received requests
-    data_start_timestamp: Rc<RefCell<Option<i64>>>,    
-    // the timestamp that first request arrived
-    start_timestamp: Rc<RefCell<Option<i64>>>,
-    // the timestamp that the last received request finished processing
-    end_timestamp: Rc<RefCell<Option<i64>>>,
-    // number of warmup requests received
-    warmup_count: Rc<RefCell<usize>>,
-    // the timestamp that the first request finished processing
-    warmup_start_timestamp: Rc<RefCell<Option<i64>>>,
-    // the throughput in the warmup period (will be computed as long as there are >=2 requests)
-    warmup_throughput: Rc<RefCell<Option<f64>>>,
-    // #requests processed in current window
-    window_count: Rc<RefCell<usize>>,
-    // the timestamp that current window starts
-    window_start_timestamp: Rc<RefCell<i64>>,
-    // throughput in #reqs/sec
-    throughput: Rc<RefCell<VecDeque<f64>>>,    
-    // operator execution latency in milliseconds
-    execution_latencies: Rc<RefCell<VecDeque<i64>>>,
-    // operator execution latency + delay since last operator emits the output
-    edge_latencies: Rc<RefCell<VecDeque<i64>>>,
-    // latency that this operation 
-    path_latencies: Rc<RefCell<VecDeque<i64>>>,
-    // Timestamp when wramup is finished
-    warmed_timestamp: Rc<RefCell<Option<i64>>>,
-    // Total number of requests processed after warmup
-    total_warmed_count: Rc<RefCell<i64>>,
-    // Overall throughput (no window)
-    overall_throughput: Rc<RefCell<Option<f64>>>
+    metrics: MapMetrics,
+    // bucket key of the inputs, only the inputs of the same bucket are batched together
+    bucket: Option<BucketKey<D1>>,
+    // batch size -> #batches
+    batch_sizes: Rc<RefCell<BTreeMap<usize, usize>>>,
+    // inputs handed to the batch logic, including the ones dropped over the SLO
+    input_throughput: ThroughputMeter
 }
 
 
@@ -619,23 +939,19 @@ where
             logic: Some(logic),
             phantom: PhantomData,
             phantom_scope: PhantomData,
-            data_start_timestamp: Rc::new(RefCell::new(None)),
-            start_timestamp: Rc::new(RefCell::new(None)),
-            end_timestamp: Rc::new(RefCell::new(None)),
-            warmup_count: Rc::new(RefCell::new(0)),
-            warmup_start_timestamp: Rc::new(RefCell::new(None)),
-            warmup_throughput: Rc::new(RefCell::new(None)),
-            window_count: Rc::new(RefCell::new(0)),
-            window_start_timestamp: Rc::new(RefCell::new(0)),
-            throughput: Rc::new(RefCell::new(VecDeque::new())),
-            execution_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            edge_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            path_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            warmed_timestamp: Rc::new(RefCell::new(None)),
-            total_warmed_count: Rc::new(RefCell::new(0)),
-            overall_throughput: Rc::new(RefCell::new(None))
+            metrics: MapMetrics::new(),
+            bucket: None,
+            batch_sizes: Rc::new(RefCell::new(BTreeMap::new())),
+            input_throughput: ThroughputMeter::default()
         }
     }
+
+    /// Batch the inputs dynamically, only the inputs with the same bucket key (e.g., the input size) are batched together
+    pub fn new_bucketed<B: Fn(&D1) -> usize + 'static>(prev_index: usize, logic: L, bucket: B) -> Self {
+        let mut node = Self::new(prev_index, logic);
+        node.bucket = Some(Rc::new(bucket));
+        node
+    }
 }
 
 impl<D1: Data, D2: Data, I2: IntoIterator<Item=D2>, L, S> LocalOpBuilder for BatchedMapNode<D1, D2, I2, L, S>
@@ -657,102 +973,69 @@ where
             config.get("simulate_network_latency").and_then(|val| val.downcast_ref::<i64>()).map(|val| *val)
         }
         else { None };
+        self.metrics.configure(&config);
 
         let batch_size = if let Some(config) = &config {
             config.get("batch_size").and_then(|val| val.downcast_ref::<usize>()).map(|val| *val)
         }
         else { None };
         let batch_size = batch_size.unwrap_or(DEFAULT_BATCH_SIZE);
+        let max_batch_wait = config.as_ref().and_then(|config| config.get("max_batch_wait").and_then(|val| val.downcast_ref::<i64>()).copied())
+            .map(|max_batch_wait| Duration::from_nanos(max_batch_wait as u64));
 
+        let tracer = Tracer::from_config(&config);
         let stream_in = streams[0];
         let stream_in = stream_in.as_any().downcast_ref::<Stream<S, TimestampData<D1>>>().unwrap();
         let mut logic = self.logic.take().unwrap();
 
-        let execution_latency_metrics = self.execution_latencies.clone();
-        let edge_latency_metrics = self.edge_latencies.clone();
-        let path_latency_metrics = self.path_latencies.clone();
-        let window_start_timestamp = self.window_start_timestamp.clone();
-        let window_count = self.window_count.clone();
-        let throughput_metrics = self.throughput.clone();
-        let warmup_start_timestamp = self.warmup_start_timestamp.clone();
-        let warmup_count = self.warmup_count.clone();
-        let warmup_throughput = self.warmup_throughput.clone();
-        let data_start_timestamp = self.data_start_timestamp.clone();
-        let start_timestamp = self.start_timestamp.clone();
-        let end_timestamp = self.end_timestamp.clone();
-        let warmed_timestamp = self.warmed_timestamp.clone();
-        let total_warmed_count = self.total_warmed_count.clone();
-        let overall_throughput = self.overall_throughput.clone();
-
-        let stream_out = stream_in.batched_map(batch_size,move |data| {
+        let metrics = self.metrics.clone();
+        let batch_sizes = self.batch_sizes.clone();
+        let input_throughput = self.input_throughput.clone();
+
+        let batch_logic = move |mut data: Vec<(TimestampData<D1>, MessageLatency)>| {
+            let received_ts = Utc::now().timestamp_nanos();
+            for _ in data.iter() {
+                input_throughput.record(received_ts);
+            }
+            // drop requests that latency already exceeds SLO, before the batch is processed
+            data.retain(|(x, lat)| {
+                let net_lat = if let Some(sim_net_lat) = sim_network_latency { sim_net_lat } else { std::cmp::max(0, *lat) };
+                !metrics.drop_over_slo(x.total_exec_net_latency + net_lat)
+            });
+            if data.is_empty() {
+                return Vec::new().into_iter();
+            }
+            let num_inputs = data.len();
+            *batch_sizes.borrow_mut().entry(num_inputs).or_insert(0) += 1;
             let all_latency = data.iter().map(|(_x, lat)| { 
                 if let Some(sim_net_lat) = sim_network_latency { sim_net_lat } else { std::cmp::max(0, *lat) }
             }).collect::<Vec<_>>();
+            let all_request_ids = data.iter().map(|(x, _lat)| x.request_id).collect::<Vec<_>>();
             let all_start_ts = data.iter().map(|(x, _lat)| x.start_timestamp).collect::<Vec<_>>();
             let all_total_lat = data.iter().map(|(x, _lat)| x.total_exec_net_latency).collect::<Vec<_>>();
-
-            let data_min_start_ts = if let Some(data_min_start_ts) = data_start_timestamp.borrow().as_ref().copied() {
-                std::cmp::min(data_min_start_ts, *all_start_ts.iter().min().unwrap())
-            }   
-            else {
-                *all_start_ts.iter().min().unwrap()
-            };
-            *data_start_timestamp.borrow_mut() = Some(data_min_start_ts);
+            let all_last_ts = data.iter().map(|(x, _lat)| x.last_timestamp).collect::<Vec<_>>();
 
             let input_vec = data.into_iter().map(|(x, _lat)| x.data).collect();
             let op_start_ts = Utc::now().timestamp_nanos();
             let mapped_data = (logic)(input_vec).into_iter();
             let op_finish_ts = Utc::now().timestamp_nanos();
+            if let Some(tracer) = &tracer {
+                for ((request_id, last_ts), net_lat) in all_request_ids.iter().zip(all_last_ts.iter()).zip(all_latency.iter()) {
+                    tracer.trace_execution(*request_id, *last_ts, *net_lat, op_start_ts, op_finish_ts);
+                }
+            }
             let mut processed_count = 0;
 
-            let output_data = mapped_data.zip(all_latency).zip(all_start_ts).zip(all_total_lat).map(|(((x_out, net_lat), start_ts), total_exec_net_lat)| {
+            let output_data = mapped_data.zip(all_request_ids).zip(all_latency).zip(all_start_ts).zip(all_total_lat).map(|((((x_out, request_id), net_lat), start_ts), total_exec_net_lat)| {
                 // batch execution latency
+                metrics.record(start_ts, net_lat, total_exec_net_lat, op_start_ts, op_finish_ts);
                 let exec_lat = op_finish_ts - op_start_ts;
-                execution_latency_metrics.borrow_mut().push_front(exec_lat);
-                edge_latency_metrics.borrow_mut().push_front(exec_lat + net_lat);
-                path_latency_metrics.borrow_mut().push_front(exec_lat + net_lat + total_exec_net_lat);
-
-                if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                    execution_latency_metrics.borrow_mut().truncate(keep_n);
-                    edge_latency_metrics.borrow_mut().truncate(keep_n);
-                    path_latency_metrics.borrow_mut().truncate(keep_n);
-                }
-                if warmup_start_timestamp.borrow().is_none() {
-                    *warmup_count.borrow_mut() = 0;
-                    *start_timestamp.borrow_mut() = Some(op_start_ts);
-                    *warmup_start_timestamp.borrow_mut() = Some(op_finish_ts);
-                }
-                else if *warmup_count.borrow() < WARMUP_ITERS {
-                    *warmup_count.borrow_mut() += 1;
-                    if *warmup_count.borrow() == WARMUP_ITERS {
-                        *window_start_timestamp.borrow_mut() = op_finish_ts;
-                        *warmed_timestamp.borrow_mut() = Some(op_finish_ts);
-                        *total_warmed_count.borrow_mut() = 0;
-                    }
-                    *warmup_throughput.borrow_mut() = Some(
-                        *warmup_count.borrow() as f64 / ((op_finish_ts - warmup_start_timestamp.borrow().unwrap()) as f64 / 1e9_f64)
-                    );
-                }
-                else {
-                    *window_count.borrow_mut() += 1;
-                    if *window_count.borrow() >= THROUGHPUT_WINDOW_SIZE {
-                        let ts = Utc::now().timestamp_nanos();
-                        let tp = *window_count.borrow() as f64 / ((ts - *window_start_timestamp.borrow()) as f64 / 1e9_f64);
-                        throughput_metrics.borrow_mut().push_front(tp);
-                        if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                            throughput_metrics.borrow_mut().truncate(keep_n);
-                        }
-                        *window_count.borrow_mut() = 0;
-                        *window_start_timestamp.borrow_mut() = ts;
-                    }
-                    *total_warmed_count.borrow_mut() += 1;
-                    *overall_throughput.borrow_mut() = Some(*total_warmed_count.borrow() as f64 / ((op_finish_ts - *warmed_timestamp.borrow().as_ref().unwrap()) as f64 / 1e9_f64))
-                }
-                *end_timestamp.borrow_mut() = Some(op_finish_ts);
+                metrics.record_within_slo(exec_lat + net_lat + total_exec_net_lat);
                 processed_count += 1;
                 if reset_timestamp {
                     TimestampData {
                         data: x_out,
+                        request_id,
                         start_timestamp: op_finish_ts,
                         last_timestamp: op_finish_ts,
                         total_exec_net_latency: 0
@@ -761,53 +1044,58 @@ where
                 else {
                     TimestampData {
                         data: x_out,
+                        request_id,
                         start_timestamp: start_ts,
                         last_timestamp: op_finish_ts,
                         total_exec_net_latency: exec_lat + net_lat + total_exec_net_lat
                     }
                 }
             }).collect::<Vec<_>>();
-            assert_eq!(batch_size, processed_count, "output batch size is not equal to the input batch size");
+            assert_eq!(num_inputs, processed_count, "output batch size is not equal to the input batch size");
             output_data.into_iter()
-        });
+        };
+        let stream_out = if max_batch_wait.is_some() || self.bucket.is_some() {
+            let bucket = self.bucket.clone();
+            let key = move |x: &TimestampData<D1>| bucket.as_ref().map_or(0, |bucket| bucket(&x.data));
+            stream_in.dynamic_batched_map(batch_size, max_batch_wait, key, batch_logic)
+        }
+        else {
+            stream_in.batched_map(batch_size, batch_logic)
+        };
         Box::new(stream_out)
     }
 
     fn get_throughput_logger(&self) -> Option<ThroughputLogger> {
-        let logger = ThroughputLogger {
-            throughput: RcWrapper::new(self.throughput.clone()),
-            warmup_throughput: RcWrapper::new(self.warmup_throughput.clone()),
-            overall_throughput: RcWrapper::new(self.overall_throughput.clone())
-        };
-        Some(logger)
+        Some(self.metrics.throughput_logger())
     }
 
     fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger> {
-        let logger = LatencyLogger {
-            latencies: RcWrapper::new(self.execution_latencies.clone())
-        };
-        Some(logger)
+        Some(self.metrics.compute_latency_logger())
     }
 
     fn get_flow_edge_latency_logger(&self) -> Option<LatencyLogger> {
-        let logger = LatencyLogger {
-            latencies: RcWrapper::new(self.edge_latencies.clone())
-        };
-        Some(logger)
+        Some(self.metrics.edge_latency_logger())
     }
 
     fn get_flow_path_latency_logger(&self) -> Option<LatencyLogger> {
-        let logger = LatencyLogger {
-            latencies: RcWrapper::new(self.path_latencies.clone())
-        };
-        Some(logger)
+        Some(self.metrics.path_latency_logger())
     }
 
     fn get_jct_logger(&self) -> Option<JCTLogger> {
-        let logger = JCTLogger {
-            data_start_timestamp: RcWrapper::new(self.data_start_timestamp.clone()),
-            op_start_timestamp: RcWrapper::new(self.start_timestamp.clone()),
-            op_end_timestamp: RcWrapper::new(self.end_timestamp.clone())
+        Some(self.metrics.jct_logger())
+    }
+
+    fn get_slo_logger(&self) -> Option<SloLogger> {
+        self.metrics.slo_logger()
+    }
+
+    fn get_input_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.input_throughput.logger())
+    }
+
+    fn get_batch_logger(&self) -> Option<BatchLogger> {
+        let logger = BatchLogger {
+            batch_sizes: RcWrapper::new(self.batch_sizes.clone())
         };
         Some(logger)
     }
@@ -838,6 +1126,161 @@ where
     }
 }
 
+// output of the fallible batch logic for an input, the dead letter if the batch still fails after the retries
+type TryOutput<D, D2, E> = Result<D2, DeadLetter<D, E>>;
+
+type BatchLogic<D, D2, E> = Box<dyn FnMut(Vec<D>) -> Vec<TryOutput<D, D2, E>>>;
+
+type TryBatchedMapInner<D, D2, E, S> = BatchedMapNode<D, TryOutput<D, D2, E>, Vec<TryOutput<D, D2, E>>, BatchLogic<D, D2, E>, S>;
+
+/// Fallible batched map
+/// Consumes a batch of elements and yields a new element for each of them,
+/// or routes the whole batch to the dead-letter output (see `DeadLetterNode`) if the logic still fails after the retries
+/// or does not yield one output per input (`BatchSizeMismatch`).
+pub struct TryBatchedMapNode<D, D2, E, S>
+where
+    D: Data,
+    D2: Data,
+    E: Data,
+    S: Scope + 'static
+{
+    inner: TryBatchedMapInner<D, D2, E, S>,
+    // shared with the logic, so that it can be configured when the operator is built
+    retry: Rc<RefCell<RetryPolicy<E>>>,
+    // dead-letter stream, taken by the dead-letter operator
+    dead_letters: DeadLetterStream,
+    // #requests routed to the dead-letter output
+    error_count: Rc<RefCell<usize>>,
+    // #attempts retried
+    retry_count: Rc<RefCell<usize>>
+}
+
+impl<D: Data, D2: Data, E: Data, S> TryBatchedMapNode<D, D2, E, S>
+where
+    S: Scope + 'static
+{
+    pub fn new<I2, L>(prev_index: usize, name: String, mut logic: L, retry: RetryPolicy<E>) -> Self
+    where
+        I2: IntoIterator<Item=D2>,
+        L: FnMut(&[D]) -> Result<I2, E> + 'static,
+        E: From<BatchSizeMismatch>
+    {
+        let retry = Rc::new(RefCell::new(retry));
+        let error_count = Rc::new(RefCell::new(0));
+        let retry_count = Rc::new(RefCell::new(0));
+        let retry_cloned = retry.clone();
+        let error_count_cloned = error_count.clone();
+        let retry_count_cloned = retry_count.clone();
+        let batch_logic: BatchLogic<D, D2, E> = Box::new(move |batch: Vec<D>| {
+            let result = retry_cloned.borrow().call(&mut logic, &batch[..], &retry_count_cloned).and_then(|outputs| {
+                let outputs = outputs.into_iter().collect::<Vec<_>>();
+                if outputs.len() == batch.len() {
+                    Ok(outputs)
+                }
+                else {
+                    Err(E::from(BatchSizeMismatch { inputs: batch.len(), outputs: outputs.len() }))
+                }
+            });
+            match result {
+                Ok(outputs) => outputs.into_iter().map(Ok).collect(),
+                Err(error) => {
+                    *error_count_cloned.borrow_mut() += batch.len();
+                    batch.into_iter().map(|input| Err(DeadLetter {
+                        operator: name.clone(),
+                        input,
+                        error: error.clone()
+                    })).collect()
+                }
+            }
+        });
+        TryBatchedMapNode {
+            inner: BatchedMapNode::new(prev_index, batch_logic),
+            retry,
+            dead_letters: Rc::new(RefCell::new(None)),
+            error_count,
+            retry_count
+        }
+    }
+
+    /// Dead-letter operator of this operator
+    pub fn dead_letter_node(&self, index: usize) -> DeadLetterNode<D, E, S> {
+        DeadLetterNode::new(index, self.dead_letters.clone())
+    }
+}
+
+impl<D: Data, D2: Data, E: Data, S> LocalOpBuilder for TryBatchedMapNode<D, D2, E, S>
+where
+    S: Scope + 'static
+{
+    fn required_prev_nodes(&self) -> Vec<usize> { self.inner.required_prev_nodes() }
+
+    fn build(&mut self, streams: &[&Box<dyn GenericStream>], config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>) -> Box<dyn GenericStream> {
+        if let Some(config) = &config {
+            self.retry.borrow_mut().configure(config);
+        }
+        let stream = self.inner.build(streams, config);
+        let stream = stream.as_any().downcast_ref::<Stream<S, TimestampData<TryOutput<D, D2, E>>>>().unwrap();
+        let routed = stream.filter_map(|x, _| {
+            let TimestampData { data, request_id, start_timestamp, last_timestamp, total_exec_net_latency } = x;
+            Some(match data {
+                Ok(data) => Ok(TimestampData { data, request_id, start_timestamp, last_timestamp, total_exec_net_latency }),
+                Err(data) => Err(TimestampData { data, request_id, start_timestamp, last_timestamp, total_exec_net_latency })
+            })
+        });
+        let (stream_out, dead_letters) = split_dead_letters(&routed);
+        *self.dead_letters.borrow_mut() = Some(Box::new(dead_letters));
+        Box::new(stream_out)
+    }
+
+    fn get_throughput_logger(&self) -> Option<ThroughputLogger> { self.inner.get_throughput_logger() }
+
+    fn get_input_throughput_logger(&self) -> Option<ThroughputLogger> { self.inner.get_input_throughput_logger() }
+
+    fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger> { self.inner.get_flow_compute_latency_logger() }
+
+    fn get_flow_edge_latency_logger(&self) -> Option<LatencyLogger> { self.inner.get_flow_edge_latency_logger() }
+
+    fn get_flow_path_latency_logger(&self) -> Option<LatencyLogger> { self.inner.get_flow_path_latency_logger() }
+
+    fn get_jct_logger(&self) -> Option<JCTLogger> { self.inner.get_jct_logger() }
+
+    fn get_slo_logger(&self) -> Option<SloLogger> { self.inner.get_slo_logger() }
+
+    fn get_batch_logger(&self) -> Option<BatchLogger> { self.inner.get_batch_logger() }
+
+    fn get_error_logger(&self) -> Option<ErrorLogger> {
+        let logger = ErrorLogger {
+            errors: RcWrapper::new(self.error_count.clone()),
+            retries: RcWrapper::new(self.retry_count.clone())
+        };
+        Some(logger)
+    }
+}
+
+impl<D: ExchangeData, D2: ExchangeData, E: ExchangeData, A, T> ExchangeOpBuilder for TryBatchedMapNode<D, D2, E, PipelineScope<A, T>>
+where
+    A: RelayConnectAllocate + 'static,
+    T: Timestamp+Refines<()>
+{
+    fn acquire_from_input_pipeline(&self, scope: &mut dyn GenericPipelineScope, input_idx: usize) -> Box<dyn GenericStream> {
+        let scope = scope.as_any_mut().downcast_mut::<PipelineScope<A, T>>().unwrap();
+        let stream = scope.acquire_pipeline_input::<TimestampData<D2>>(input_idx);
+        Box::new(stream)
+    }
+
+    fn build_and_register_output(&mut self, streams: &[&Box<dyn GenericStream>], config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>, scope: &mut dyn GenericPipelineScope, output_idx: usize) -> Box<dyn GenericStream> {
+        let stream = (self as &mut dyn LocalOpBuilder).build(streams, config);
+        self.register_pipeline_output(&stream, scope, output_idx);
+        stream
+    }
+
+    fn register_pipeline_output(&self, stream: &Box<dyn GenericStream>, scope: &mut dyn GenericPipelineScope, output_idx: usize) {
+        let scope = scope.as_any_mut().downcast_mut::<PipelineScope<A, T>>().unwrap();
+        let stream = stream.as_any().downcast_ref::<Stream<PipelineScope<A, T>, TimestampData<D2>>>().unwrap();
+        scope.register_pipeline_output_balanced_exchange(stream, output_idx);
+    }
+}
+
 /// Buffered map
 pub struct BufferedMapNode<D1, D2, L, S> 
 where
@@ -848,36 +1291,7 @@ where
     logic: Option<L>,
     phantom: PhantomData<(D1, D2)>,
     phantom_scope: PhantomData<S>,
-    // the minimal start timestamp of received requests
-    data_start_timestamp: Rc<RefCell<Option<i64>>>,    
-    // the timestamp that first request arrived
-    start_timestamp: Rc<RefCell<Option<i64>>>,
-    // the timestamp that the last received request finished processing
-    end_timestamp: Rc<RefCell<Option<i64>>>,
-    // number of warmup requests received
-    warmup_count: Rc<RefCell<usize>>,
-    // the timestamp that the first request finished processing
-    warmup_start_timestamp: Rc<RefCell<Option<i64>>>,
-    // the throughput in the warmup period (will be computed as long as there are >=2 requests)
-    warmup_throughput: Rc<RefCell<Option<f64>>>,
-    // #requests processed in current window
-    window_count: Rc<RefCell<usize>>,
-    // the timestamp that current window starts
-    window_start_timestamp: Rc<RefCell<i64>>,
-    // throughput in #reqs/sec
-    throughput: Rc<RefCell<VecDeque<f64>>>,    
-    // operator execution latency in milliseconds
-    execution_latencies: Rc<RefCell<VecDeque<i64>>>,
-    // operator execution latency + delay since last operator emits the output
-    edge_latencies: Rc<RefCell<VecDeque<i64>>>,
-    // latency that this operation 
-    path_latencies: Rc<RefCell<VecDeque<i64>>>,
-    // Timestamp when wramup is finished
-    warmed_timestamp: Rc<RefCell<Option<i64>>>,
-    // Total number of requests processed after warmup
-    total_warmed_count: Rc<RefCell<i64>>,
-    // Overall throughput (no window)
-    overall_throughput: Rc<RefCell<Option<f64>>>
+    metrics: MapMetrics
 }
 
 impl<D1, D2, L, S> BufferedMapNode<D1, D2, L, S>
@@ -891,21 +1305,7 @@ where
             logic: Some(logic),
             phantom: PhantomData,
             phantom_scope: PhantomData,
-            data_start_timestamp: Rc::new(RefCell::new(None)),
-            start_timestamp: Rc::new(RefCell::new(None)),
-            end_timestamp: Rc::new(RefCell::new(None)),
-            warmup_count: Rc::new(RefCell::new(0)),
-            warmup_start_timestamp: Rc::new(RefCell::new(None)),
-            warmup_throughput: Rc::new(RefCell::new(None)),
-            window_count: Rc::new(RefCell::new(0)),
-            window_start_timestamp: Rc::new(RefCell::new(0)),
-            throughput: Rc::new(RefCell::new(VecDeque::new())),
-            execution_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            edge_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            path_latencies: Rc::new(RefCell::new(VecDeque::new())),
-            warmed_timestamp: Rc::new(RefCell::new(None)),
-            total_warmed_count: Rc::new(RefCell::new(0)),
-            overall_throughput: Rc::new(RefCell::new(None))
+            metrics: MapMetrics::new()
         }
     }
 }
@@ -929,7 +1329,9 @@ where
             config.get("simulate_network_latency").and_then(|val| val.downcast_ref::<i64>()).map(|val| *val)
         }
         else { None };
+        self.metrics.configure(&config);
 
+        let tracer = Tracer::from_config(&config);
         let buffer_size = if let Some(config) = config {
             config.get("buffer_size").and_then(|val| val.downcast_ref::<usize>()).map(|val| *val)
         }
@@ -940,139 +1342,72 @@ where
         let stream_in = stream_in.as_any().downcast_ref::<Stream<S, TimestampData<D1>>>().unwrap();
         let mut logic = self.logic.take().unwrap();
 
-        let execution_latency_metrics = self.execution_latencies.clone();
-        let edge_latency_metrics = self.edge_latencies.clone();
-        let path_latency_metrics = self.path_latencies.clone();
-        let window_start_timestamp = self.window_start_timestamp.clone();
-        let window_count = self.window_count.clone();
-        let throughput_metrics = self.throughput.clone();
-        let warmup_start_timestamp = self.warmup_start_timestamp.clone();
-        let warmup_count = self.warmup_count.clone();
-        let warmup_throughput = self.warmup_throughput.clone();
-        let data_start_timestamp = self.data_start_timestamp.clone();
-        let start_timestamp = self.start_timestamp.clone();
-        let end_timestamp = self.end_timestamp.clone();
-        let warmed_timestamp = self.warmed_timestamp.clone();
-        let total_warmed_count = self.total_warmed_count.clone();
-        let overall_throughput = self.overall_throughput.clone();
-
-        let stream_out = stream_in.buffered_map(buffer_size, move |x, mut net_lat| {
+        let metrics = self.metrics.clone();
+
+        let stream_out = stream_in.buffered_filter_map(buffer_size, move |x, mut net_lat| {
             if let Some(sim_net_lat) = sim_network_latency {
                 net_lat = sim_net_lat;
             }
             if net_lat < 0 { net_lat = 0 }
-
-            let data_min_start_ts = if let Some(data_min_start_ts) = data_start_timestamp.borrow().as_ref().copied() {
-                std::cmp::min(data_min_start_ts, x.start_timestamp)
-            }   
-            else {
-                x.start_timestamp
-            };
-            *data_start_timestamp.borrow_mut() = Some(data_min_start_ts);
+            // drop request that latency already exceeds SLO
+            if metrics.drop_over_slo(x.total_exec_net_latency + net_lat) {
+                return None;
+            }
 
             let op_start_ts = Utc::now().timestamp_nanos();
             let mapped_data = (logic)(x.data);
             let op_finish_ts = Utc::now().timestamp_nanos();
+            if let Some(tracer) = &tracer {
+                tracer.trace_execution(x.request_id, x.last_timestamp, net_lat, op_start_ts, op_finish_ts);
+            }
 
+            metrics.record(x.start_timestamp, net_lat, x.total_exec_net_latency, op_start_ts, op_finish_ts);
             let exec_lat = op_finish_ts - op_start_ts;
-            execution_latency_metrics.borrow_mut().push_front(exec_lat);
-            edge_latency_metrics.borrow_mut().push_front(exec_lat + net_lat);
-            path_latency_metrics.borrow_mut().push_front(exec_lat + net_lat + x.total_exec_net_latency);
-
-            if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                execution_latency_metrics.borrow_mut().truncate(keep_n);
-                edge_latency_metrics.borrow_mut().truncate(keep_n);
-                path_latency_metrics.borrow_mut().truncate(keep_n);
-            }
-            if warmup_start_timestamp.borrow().is_none() {
-                *warmup_count.borrow_mut() = 0;
-                *start_timestamp.borrow_mut() = Some(op_start_ts);
-                *warmup_start_timestamp.borrow_mut() = Some(op_finish_ts);
-            }
-            else if *warmup_count.borrow() < WARMUP_ITERS {
-                *warmup_count.borrow_mut() += 1;
-                if *warmup_count.borrow() == WARMUP_ITERS {
-                    *window_start_timestamp.borrow_mut() = op_finish_ts;
-                    *warmed_timestamp.borrow_mut() = Some(op_finish_ts);
-                    *total_warmed_count.borrow_mut() = 0;
-                }
-                *warmup_throughput.borrow_mut() = Some(
-                    *warmup_count.borrow() as f64 / ((op_finish_ts - warmup_start_timestamp.borrow().unwrap()) as f64 / 1e9_f64)
-                );
-            }
-            else {
-                *window_count.borrow_mut() += 1;
-                if *window_count.borrow() >= THROUGHPUT_WINDOW_SIZE {
-                    let ts = Utc::now().timestamp_nanos();
-                    let tp = *window_count.borrow() as f64 / ((ts - *window_start_timestamp.borrow()) as f64 / 1e9_f64);
-                    throughput_metrics.borrow_mut().push_front(tp);
-                    if let Some(keep_n) = METRIC_KEEP_LAST_N {
-                        throughput_metrics.borrow_mut().truncate(keep_n);
-                    }
-                    *window_count.borrow_mut() = 0;
-                    *window_start_timestamp.borrow_mut() = ts;
-                }
-                *total_warmed_count.borrow_mut() += 1;
-                *overall_throughput.borrow_mut() = Some(*total_warmed_count.borrow() as f64 / ((op_finish_ts - *warmed_timestamp.borrow().as_ref().unwrap()) as f64 / 1e9_f64))
-            }
-            *end_timestamp.borrow_mut() = Some(op_finish_ts);
+            metrics.record_within_slo(exec_lat + net_lat + x.total_exec_net_latency);
             if reset_timestamp {
-                TimestampData {
+                Some(TimestampData {
                     data: mapped_data,
+                    request_id: x.request_id,
                     start_timestamp: op_finish_ts,
                     last_timestamp: op_finish_ts,
                     total_exec_net_latency: 0,
-                }
+                })
             }
             else {
-                TimestampData {
+                Some(TimestampData {
                     data: mapped_data,
+                    request_id: x.request_id,
                     start_timestamp: x.start_timestamp,
                     last_timestamp: op_finish_ts,
                     total_exec_net_latency: exec_lat + net_lat + x.total_exec_net_latency
-                }
+                })
             }
         });
         Box::new(stream_out)
     }
 
     fn get_throughput_logger(&self) -> Option<ThroughputLogger> {
-        let logger = ThroughputLogger {
-            throughput: RcWrapper::new(self.throughput.clone()),
-            warmup_throughput: RcWrapper::new(self.warmup_throughput.clone()),
-            overall_throughput: RcWrapper::new(self.overall_throughput.clone())
-        };
-        Some(logger)
+        Some(self.metrics.throughput_logger())
     }
 
     fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger> {
-        let logger = LatencyLogger {
-            latencies: RcWrapper::new(self.execution_latencies.clone())
-        };
-        Some(logger)
+        Some(self.metrics.compute_latency_logger())
     }
 
     fn get_flow_edge_latency_logger(&self) -> Option<LatencyLogger> {
-        let logger = LatencyLogger {
-            latencies: RcWrapper::new(self.edge_latencies.clone())
-        };
-        Some(logger)
+        Some(self.metrics.edge_latency_logger())
     }
 
     fn get_flow_path_latency_logger(&self) -> Option<LatencyLogger> {
-        let logger = LatencyLogger {
-            latencies: RcWrapper::new(self.path_latencies.clone())
-        };
-        Some(logger)
+        Some(self.metrics.path_latency_logger())
     }
 
     fn get_jct_logger(&self) -> Option<JCTLogger> {
-        let logger = JCTLogger {
-            data_start_timestamp: RcWrapper::new(self.data_start_timestamp.clone()),
-            op_start_timestamp: RcWrapper::new(self.start_timestamp.clone()),
-            op_end_timestamp: RcWrapper::new(self.end_timestamp.clone())
-        };
-        Some(logger)
+        Some(self.metrics.jct_logger())
+    }
+
+    fn get_slo_logger(&self) -> Option<SloLogger> {
+        self.metrics.slo_logger()
     }
 }
 
@@ -1099,4 +1434,191 @@ where
         let stream = stream.as_any().downcast_ref::<Stream<PipelineScope<A, T>, TimestampData<D2>>>().unwrap();
         scope.register_pipeline_output_balanced_exchange(stream, output_idx);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+    use std::collections::{HashMap, VecDeque};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use timely::dataflow::{InputHandle, Stream};
+    use timely::dataflow::operators::{Input, Inspect};
+
+    use crate::TimestampData;
+    use crate::builder::GraphBuilder;
+    use crate::execute::local_execute_thread;
+    use crate::node::{GenericStream, LocalOpBuilder};
+    use crate::static_timely::timely_static_execute::execute_directly;
+    use crate::static_timely::timely_static_scope::Child;
+    use crate::test_utils::ThreadWorker;
+    use crate::{InspectLocal, MapLocal, RetryPolicy};
+
+    use super::{BatchSizeMismatch, DeadLetter, MapNode, TryBatchedMapNode};
+
+    #[test]
+    fn drop_requests_over_slo() {
+        execute_directly(|worker| {
+            let outputs = Arc::new(Mutex::new(Vec::new()));
+            let outputs_cloned = outputs.clone();
+            let (mut input, slo_logger): (InputHandle<usize, _>, _) = worker.dataflow(|scope| {
+                let (input, stream) = scope.new_input::<TimestampData<usize>>();
+                let mut node = MapNode::<_, _, _, Child<ThreadWorker, usize>>::new(0, |x: usize| x * 2);
+                let mut config: HashMap<String, Arc<dyn Any + Send + Sync>> = HashMap::new();
+                config.insert(String::from("latency_slo"), Arc::new(1_000_000_000i64));
+                let stream: Box<dyn GenericStream> = Box::new(stream);
+                let stream_out = node.build(&[&stream], Some(config));
+                stream_out.as_any().downcast_ref::<Stream<Child<ThreadWorker, usize>, TimestampData<usize>>>().unwrap()
+                    .inspect(move |x| outputs_cloned.lock().unwrap().push(x.data));
+                (input, node.get_slo_logger().unwrap())
+            });
+            let curr_ts = chrono::Utc::now().timestamp_nanos();
+            // the second request already spent 2s upstream, over the 1s SLO
+            for (data, total_exec_net_latency) in [(1, 0), (2, 2_000_000_000), (3, 0)] {
+                input.send(TimestampData { data, request_id: data as u64, start_timestamp: curr_ts, last_timestamp: curr_ts, total_exec_net_latency });
+            }
+            drop(input);
+            while worker.step() { }
+            assert_eq!(*outputs.lock().unwrap(), vec![2, 6]);
+            assert_eq!(slo_logger.compute_dropped(), 1);
+        });
+    }
+
+    #[test]
+    fn count_batched_inputs_over_slo() {
+        execute_directly(|worker| {
+            let (mut input, throughput_logger, input_throughput_logger): (InputHandle<usize, _>, _, _) = worker.dataflow(|scope| {
+                let (input, stream) = scope.new_input::<TimestampData<usize>>();
+                let mut node = TryBatchedMapNode::<_, _, _, Child<ThreadWorker, usize>>::new(0, String::from("TryBatchMap"), |batch: &[usize]| {
+                    Ok::<_, String>(batch.to_vec())
+                }, RetryPolicy::no_retry());
+                let mut config: HashMap<String, Arc<dyn Any + Send + Sync>> = HashMap::new();
+                config.insert(String::from("latency_slo"), Arc::new(1_000_000_000i64));
+                let stream: Box<dyn GenericStream> = Box::new(stream);
+                node.build(&[&stream], Some(config));
+                (input, node.get_throughput_logger().unwrap(), node.get_input_throughput_logger().unwrap())
+            });
+            // all the requests already spent 2s upstream, over the 1s SLO
+            for data in 0..5 {
+                let curr_ts = chrono::Utc::now().timestamp_nanos();
+                input.send(TimestampData { data, request_id: data as u64, start_timestamp: curr_ts, last_timestamp: curr_ts, total_exec_net_latency: 2_000_000_000 });
+                input.advance_to(data + 1);
+                worker.step();
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            drop(input);
+            while worker.step() { }
+            assert!(throughput_logger.compute_throughput().is_none());
+            assert!(input_throughput_logger.compute_throughput().is_some());
+        });
+    }
+
+    #[test]
+    fn route_errors_to_dead_letters() {
+        let outputs = Arc::new(Mutex::new(Vec::new()));
+        let dead_letters = Arc::new(Mutex::new(Vec::new()));
+        let outputs_cloned = outputs.clone();
+        let dead_letters_cloned = dead_letters.clone();
+        local_execute_thread(move |builder: &mut GraphBuilder<usize>| {
+            let outputs = outputs_cloned.clone();
+            let dead_letters = dead_letters_cloned.clone();
+            let (doubled, failed) = builder.new_input_from_source(VecDeque::from((0..6).collect::<Vec<usize>>()), |_, t| *t + 1, "Input")
+                .try_map_local(|x| if x % 2 == 0 { Ok(x * 2) } else { Err(format!("odd input {}", x)) }, "TryMap");
+            doubled.inspect_local(move |x| outputs.lock().unwrap().push(*x), "InspectOutputs");
+            failed.inspect_local(move |x| dead_letters.lock().unwrap().push((x.operator.clone(), x.input, x.error.clone())), "InspectDeadLetters");
+        });
+        assert_eq!(*outputs.lock().unwrap(), vec![0, 4, 8]);
+        let dead_letters = dead_letters.lock().unwrap();
+        assert_eq!(dead_letters.iter().map(|x| x.1).collect::<Vec<_>>(), vec![1, 3, 5]);
+        assert_eq!(dead_letters[0], (String::from("TryMap"), 1, String::from("odd input 1")));
+    }
+
+    #[cfg(not(feature = "bincode"))]
+    #[test]
+    fn encode_dead_letters() {
+        let dead_letter = DeadLetter { operator: String::from("TryMap"), input: (3usize, String::from("input")), error: String::from("odd input") };
+        let mut bytes = Vec::new();
+        unsafe { abomonation::encode(&dead_letter, &mut bytes).unwrap(); }
+        let (decoded, remaining) = unsafe { abomonation::decode::<DeadLetter<(usize, String), String>>(&mut bytes) }.unwrap();
+        assert!(remaining.is_empty());
+        assert_eq!((decoded.operator.as_str(), &decoded.input, decoded.error.as_str()), ("TryMap", &(3, String::from("input")), "odd input"));
+    }
+
+    #[test]
+    fn retry_failed_batches() {
+        let outputs = Arc::new(Mutex::new(Vec::new()));
+        let dead_letters = Arc::new(Mutex::new(Vec::new()));
+        let outputs_cloned = outputs.clone();
+        let dead_letters_cloned = dead_letters.clone();
+        local_execute_thread(move |builder: &mut GraphBuilder<usize>| {
+            let outputs = outputs_cloned.clone();
+            let dead_letters = dead_letters_cloned.clone();
+            // every batch fails transiently once, the batches with a 3 always fail
+            let mut failed_once = false;
+            let retry = RetryPolicy::new(3).with_backoff(Duration::from_millis(1), Duration::from_millis(2))
+                .retry_if(|err: &String| err == "transient");
+            let (doubled, failed) = builder.new_input_from_source(VecDeque::from((0..6).collect::<Vec<usize>>()), |_, t| *t + 1, "Input")
+                .retry_batch_map_local(move |batch: &[usize]| {
+                    if batch.contains(&3) {
+                        return Err(String::from("fatal"));
+                    }
+                    failed_once = !failed_once;
+                    if failed_once { Err(String::from("transient")) } else { Ok(batch.iter().map(|x| x * 2).collect::<Vec<_>>()) }
+                }, retry, "RetryBatchMap");
+            doubled.inspect_local(move |x| outputs.lock().unwrap().push(*x), "InspectOutputs");
+            failed.inspect_local(move |x| dead_letters.lock().unwrap().push((x.input, x.error.clone())), "InspectDeadLetters");
+        });
+        let dead_letters = dead_letters.lock().unwrap();
+        assert!(dead_letters.contains(&(3, String::from("fatal"))));
+        assert!(dead_letters.iter().all(|x| x.1 == "fatal"));
+        let mut processed = outputs.lock().unwrap().iter().map(|x| x / 2).chain(dead_letters.iter().map(|x| x.0)).collect::<Vec<_>>();
+        processed.sort_unstable();
+        assert_eq!(processed, (0..6).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn route_mismatched_batches() {
+        let outputs = Arc::new(Mutex::new(Vec::new()));
+        let dead_letters = Arc::new(Mutex::new(Vec::new()));
+        let outputs_cloned = outputs.clone();
+        let dead_letters_cloned = dead_letters.clone();
+        local_execute_thread(move |builder: &mut GraphBuilder<usize>| {
+            let outputs = outputs_cloned.clone();
+            let dead_letters = dead_letters_cloned.clone();
+            // the batches with a 3 lose their last output
+            let (doubled, failed) = builder.new_input_from_source(VecDeque::from((0..6).collect::<Vec<usize>>()), |_, t| *t + 1, "Input")
+                .retry_batch_map_local(|batch: &[usize]| {
+                    let outputs = batch.iter().map(|x| x * 2);
+                    let len = if batch.contains(&3) { batch.len() - 1 } else { batch.len() };
+                    Ok::<_, String>(outputs.take(len).collect::<Vec<_>>())
+                }, RetryPolicy::no_retry(), "TryBatchMap");
+            doubled.inspect_local(move |x| outputs.lock().unwrap().push(*x), "InspectOutputs");
+            failed.inspect_local(move |x| dead_letters.lock().unwrap().push((x.input, x.error.clone())), "InspectDeadLetters");
+        });
+        let dead_letters = dead_letters.lock().unwrap();
+        let error = String::from(BatchSizeMismatch { inputs: 1, outputs: 0 });
+        assert_eq!(*dead_letters, vec![(3, error)]);
+        let mut outputs = outputs.lock().unwrap().clone();
+        outputs.sort_unstable();
+        assert_eq!(outputs, vec![0, 2, 4, 8, 10]);
+    }
+
+    #[test]
+    fn batch_by_bucket() {
+        let outputs = Arc::new(Mutex::new(Vec::new()));
+        let outputs_cloned = outputs.clone();
+        local_execute_thread(move |builder: &mut GraphBuilder<usize>| {
+            let outputs = outputs_cloned.clone();
+            builder.new_input_from_source(VecDeque::from((0..10).collect::<Vec<usize>>()), |x, t| *t + x % 3, "Input")
+                .bucketed_batch_map_local(|batch: Vec<usize>| {
+                    assert!(batch.iter().all(|x| x % 2 == batch[0] % 2), "batch mixes buckets: {:?}", batch);
+                    batch.into_iter().map(|x| x * 2).collect::<Vec<_>>()
+                }, |x| x % 2, "BucketedBatchMap")
+                .inspect_local(move |x| outputs.lock().unwrap().push(*x), "Inspect");
+        });
+        let mut outputs = outputs.lock().unwrap().clone();
+        outputs.sort_unstable();
+        assert_eq!(outputs, (0..10).map(|x| x * 2).collect::<Vec<_>>());
+    }
+}
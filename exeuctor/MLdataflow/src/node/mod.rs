@@ -9,7 +9,7 @@ use timely::progress::Timestamp;
 use timely::progress::timestamp::Refines;
 
 use crate::input::GenericScope;
-use crate::metrics::{LatencyLogger, ThroughputLogger, JCTLogger};
+use crate::metrics::{LatencyLogger, ThroughputLogger, JCTLogger, SloLogger, StateLogger, ErrorLogger, BatchLogger};
 use crate::static_timely::timely_static_pipeline_scope::PipelineScope;
 
 pub mod map;
@@ -17,16 +17,22 @@ pub mod filter;
 pub mod inspect;
 pub mod join;
 pub mod aggregate;
+pub mod window;
+pub mod retry;
 pub mod exchange;
 pub mod union;
+pub mod sink;
 
-pub use map::{MapNode, FlatMapNode, BatchedMapNode, BufferedMapNode};
+pub use map::{MapNode, FlatMapNode, BatchedMapNode, BufferedMapNode, TryMapNode, TryBatchedMapNode, DeadLetter, BatchSizeMismatch};
 pub use filter::FilterNode;
 pub use inspect::InspectNode;
-pub use join::{JoinNode, TimestampJoinNode, SingleItemJoinNode, TimestampSingleItemJoinNode};
+pub use join::{JoinNode, TimestampJoinNode, SingleItemJoinNode, TimestampSingleItemJoinNode, OuterJoinNode, OuterJoinMode};
 pub use aggregate::{AggregateNode, TimestampAggregateNode, IncrementalAggregateNode, TimestampIncrementalAggregateNode};
+pub use window::{WindowAggregateNode, Window, WindowBounds, LateData, EpochTimestamp};
+pub use retry::RetryPolicy;
 pub use exchange::ExchangeNode;
 pub use union::UnionNode;
+pub use sink::{FileSink, SinkFormat, SinkNode};
 
 /// Generic operator builder
 /// The operator can takes in multiple input streams,
@@ -38,6 +44,8 @@ pub trait LocalOpBuilder {
     fn build(&mut self, streams: &[&Box<dyn GenericStream>], config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>) -> Box<dyn GenericStream>;
     /// get throguhput metrics (avg, max, median, ...)
     fn get_throughput_logger(&self) -> Option<ThroughputLogger>;
+    /// get the throughput of the received requests (only for joins, aggregates and batched maps, whose outputs are not one-to-one with their inputs)
+    fn get_input_throughput_logger(&self) -> Option<ThroughputLogger> { None }
     /// get the op's execution (compute) latency metrics (P50, P90, P99, ...)
     fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger>;
     /// get the latency from the last op emitted the output to current op finishes execution
@@ -47,6 +55,14 @@ pub trait LocalOpBuilder {
     fn get_flow_path_latency_logger(&self) -> Option<LatencyLogger>;
     /// get job completion time logger
     fn get_jct_logger(&self) -> Option<JCTLogger>;
+    /// get the dropped requests and goodput logger (only for operators with a latency SLO)
+    fn get_slo_logger(&self) -> Option<SloLogger> { None }
+    /// get the evicted and pending keys logger (only for joins and aggregates)
+    fn get_state_logger(&self) -> Option<StateLogger> { None }
+    /// get the failed requests logger (only for fallible operators)
+    fn get_error_logger(&self) -> Option<ErrorLogger> { None }
+    /// get the batch size histogram logger (only for batched operators)
+    fn get_batch_logger(&self) -> Option<BatchLogger> { None }
 }
 
 /// Builder for operators that emit outputs which can be sent across network,
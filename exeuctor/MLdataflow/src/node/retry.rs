@@ -0,0 +1,136 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(10);
+const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Retry policy of a fallible operator (e.g., `retry_map`),
+/// the logic is re-invoked until it succeeds, fails with a non-retryable error, or runs out of attempts.
+/// The backoff between attempts doubles from `initial_backoff` up to `max_backoff`,
+/// the timely worker is blocked while backing off.
+/// The attempts and backoff are overridden by `retry_max_attempts`, `retry_backoff` and `retry_max_backoff`
+/// (in nanoseconds) in the operator config
+pub struct RetryPolicy<E> {
+    pub max_attempts: usize,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    retryable: Arc<dyn Fn(&E) -> bool + Send + Sync>,
+}
+
+impl<E> RetryPolicy<E> {
+    /// Retry all the errors, up to `max_attempts` attempts in total
+    pub fn new(max_attempts: usize) -> Self {
+        assert!(max_attempts > 0, "max attempts must be positive");
+        RetryPolicy {
+            max_attempts,
+            initial_backoff: DEFAULT_INITIAL_BACKOFF,
+            max_backoff: DEFAULT_MAX_BACKOFF,
+            retryable: Arc::new(|_| true),
+        }
+    }
+
+    /// Invoke the logic once
+    pub fn no_retry() -> Self {
+        Self::new(1)
+    }
+
+    pub fn with_backoff(mut self, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Only retry the errors accepted by the classifier (e.g., OOM or CUDA errors)
+    pub fn retry_if<F: Fn(&E) -> bool + Send + Sync + 'static>(mut self, classifier: F) -> Self {
+        self.retryable = Arc::new(classifier);
+        self
+    }
+
+    pub(crate) fn configure(&mut self, config: &HashMap<String, Arc<dyn Any + Send + Sync>>) {
+        if let Some(max_attempts) = config.get("retry_max_attempts").and_then(|val| val.downcast_ref::<usize>()).copied() {
+            self.max_attempts = max_attempts;
+        }
+        if let Some(backoff) = config.get("retry_backoff").and_then(|val| val.downcast_ref::<i64>()).copied() {
+            self.initial_backoff = Duration::from_nanos(backoff as u64);
+        }
+        if let Some(backoff) = config.get("retry_max_backoff").and_then(|val| val.downcast_ref::<i64>()).copied() {
+            self.max_backoff = Duration::from_nanos(backoff as u64);
+        }
+    }
+
+    /// Invoke the logic on the input according to the policy, counting the retries
+    pub(crate) fn call<D: ?Sized, R, L>(&self, logic: &mut L, input: &D, retries: &RefCell<usize>) -> Result<R, E>
+    where
+        L: FnMut(&D) -> Result<R, E>
+    {
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 1;
+        loop {
+            match logic(input) {
+                Err(err) if attempt < self.max_attempts && (self.retryable)(&err) => {
+                    *retries.borrow_mut() += 1;
+                    attempt += 1;
+                    std::thread::sleep(backoff);
+                    backoff = std::cmp::min(backoff * 2, self.max_backoff);
+                },
+                result => return result,
+            }
+        }
+    }
+}
+
+impl<E> Clone for RetryPolicy<E> {
+    fn clone(&self) -> Self {
+        RetryPolicy {
+            max_attempts: self.max_attempts,
+            initial_backoff: self.initial_backoff,
+            max_backoff: self.max_backoff,
+            retryable: self.retryable.clone(),
+        }
+    }
+}
+
+impl<E> fmt::Debug for RetryPolicy<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::time::Duration;
+
+    use super::RetryPolicy;
+
+    #[test]
+    fn retry_until_success_or_fatal() {
+        let policy = RetryPolicy::new(3).with_backoff(Duration::from_millis(1), Duration::from_millis(2))
+            .retry_if(|err: &String| err.starts_with("CUDA"));
+        let retries = RefCell::new(0);
+
+        // fails twice, then succeeds on the last attempt
+        let mut attempts = 0;
+        let mut flaky = |x: &usize| { attempts += 1; if attempts < 3 { Err(String::from("CUDA error")) } else { Ok(*x) } };
+        assert_eq!(policy.call(&mut flaky, &7, &retries), Ok(7));
+        assert_eq!(*retries.borrow(), 2);
+
+        // not retryable
+        let mut fatal = |_: &usize| -> Result<usize, String> { Err(String::from("corrupt input")) };
+        assert!(policy.call(&mut fatal, &7, &retries).is_err());
+        assert_eq!(*retries.borrow(), 2);
+
+        // runs out of attempts
+        let mut failing = |_: &usize| -> Result<usize, String> { Err(String::from("CUDA error")) };
+        assert!(policy.call(&mut failing, &7, &retries).is_err());
+        assert_eq!(*retries.borrow(), 4);
+    }
+}
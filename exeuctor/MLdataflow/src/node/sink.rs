@@ -0,0 +1,266 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use serde::{Serialize, Deserialize};
+use timely::communication::RelayConnectAllocate;
+use timely::progress::Timestamp;
+use timely::progress::timestamp::Refines;
+use timely::{Data, ExchangeData};
+use timely::dataflow::{Stream, Scope};
+
+use crate::TimestampData;
+use crate::metrics::{LatencyLogger, JCTLogger, ThroughputLogger};
+use crate::static_timely::timely_static_pipeline_scope::PipelineScope;
+
+use super::{LocalOpBuilder, ExchangeOpBuilder, InspectNode};
+use super::GenericStream;
+use super::GenericPipelineScope;
+
+/// File format of a sink
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SinkFormat {
+    /// One JSON record per line
+    Jsonl,
+    /// CSV with a header row, the records must be flat (e.g., structs of scalars)
+    Csv,
+    /// bincode records, each prefixed by its length (4 bytes, big endian)
+    Binary,
+}
+
+impl SinkFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            SinkFormat::Jsonl => "jsonl",
+            SinkFormat::Csv => "csv",
+            SinkFormat::Binary => "bin",
+        }
+    }
+}
+
+// counts the bytes written to the file
+struct CountingWriter {
+    writer: BufWriter<File>,
+    bytes: usize,
+}
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.writer.write(buf)?;
+        self.bytes += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes the records of a timely worker to `{dir}/{name}_{worker_index}_{file_index}.{extension}`,
+/// rotating to the next file once the current one exceeds `max_file_bytes` (if set).
+/// The files are flushed when rotated and when the sink is dropped (i.e., the dataflow completes)
+pub struct FileSink {
+    dir: PathBuf,
+    name: String,
+    format: SinkFormat,
+    worker_index: usize,
+    max_file_bytes: Option<usize>,
+    file_index: usize,
+    writer: Option<CountingWriter>,
+}
+
+impl FileSink {
+    pub fn new(dir: PathBuf, name: String, format: SinkFormat, worker_index: usize, max_file_bytes: Option<usize>) -> Self {
+        fs::create_dir_all(&dir).unwrap_or_else(|err| panic!("failed to create sink directory {}: {}", dir.display(), err));
+        FileSink {
+            dir,
+            name,
+            format,
+            worker_index,
+            max_file_bytes,
+            file_index: 0,
+            writer: None,
+        }
+    }
+
+    /// Path of the current file
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(format!("{}_{}_{}.{}", self.name, self.worker_index, self.file_index, self.format.extension()))
+    }
+
+    pub fn write<D: Serialize>(&mut self, record: &D) {
+        let new_file = self.writer.is_none();
+        if new_file {
+            let path = self.path();
+            let file = File::create(&path).unwrap_or_else(|err| panic!("failed to create sink file {}: {}", path.display(), err));
+            self.writer = Some(CountingWriter { writer: BufWriter::new(file), bytes: 0 });
+        }
+        let bytes = match self.format {
+            SinkFormat::Jsonl => serde_json::to_vec(record).map_err(|err| err.to_string())
+                .map(|mut bytes| { bytes.push(b'\n'); bytes }),
+            // the header row is written at the beginning of each file
+            SinkFormat::Csv => {
+                let mut writer = csv::WriterBuilder::new().has_headers(new_file).from_writer(Vec::new());
+                writer.serialize(record).map_err(|err| err.to_string())
+                    .and_then(|_| writer.into_inner().map_err(|err| err.to_string()))
+            },
+            SinkFormat::Binary => bincode_codec::serialize(record).map_err(|err| err.to_string())
+                .map(|bytes| [&(bytes.len() as u32).to_be_bytes()[..], &bytes[..]].concat()),
+        };
+        let writer = self.writer.as_mut().unwrap();
+        if let Err(err) = bytes.and_then(|bytes| writer.write_all(&bytes).map_err(|err| err.to_string())) {
+            panic!("failed to write to sink file {}: {}", self.path().display(), err);
+        }
+        if matches!(self.max_file_bytes, Some(max_file_bytes) if writer.bytes >= max_file_bytes) {
+            self.flush();
+            self.writer = None;
+            self.file_index += 1;
+        }
+    }
+
+    pub fn flush(&mut self) {
+        if let Some(writer) = self.writer.as_mut() {
+            if let Err(err) = writer.flush() {
+                panic!("failed to flush sink file {}: {}", self.path().display(), err);
+            }
+        }
+    }
+}
+
+impl Drop for FileSink {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+type SinkLogic<D> = Box<dyn FnMut(&D)>;
+
+/// Persist a data stream to files, one set of files per timely worker (see `FileSink`).
+/// The rotation size is taken from `max_file_bytes` in the operator config
+pub struct SinkNode<D, S>
+where
+    D: 'static,
+    S: Scope + 'static,
+{
+    dir: PathBuf,
+    name: String,
+    format: SinkFormat,
+    sink: Rc<RefCell<Option<FileSink>>>,
+    inspect: InspectNode<D, SinkLogic<D>, S>,
+}
+
+impl<D: Data + Serialize, S> SinkNode<D, S>
+where
+    S: Scope + 'static
+{
+    pub fn new(prev_index: usize, dir: PathBuf, name: String, format: SinkFormat) -> Self {
+        let sink: Rc<RefCell<Option<FileSink>>> = Rc::new(RefCell::new(None));
+        let sink_cloned = sink.clone();
+        let logic: SinkLogic<D> = Box::new(move |x: &D| {
+            sink_cloned.borrow_mut().as_mut().unwrap().write(x);
+        });
+        SinkNode {
+            dir,
+            name,
+            format,
+            sink,
+            inspect: InspectNode::new(prev_index, logic),
+        }
+    }
+}
+
+impl<D: Data + Serialize, S> LocalOpBuilder for SinkNode<D, S>
+where
+    S: Scope + 'static
+{
+    fn required_prev_nodes(&self) -> Vec<usize> { self.inspect.required_prev_nodes() }
+
+    fn build(&mut self, streams: &[&Box<dyn GenericStream>], config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>) -> Box<dyn GenericStream> {
+        let stream_in = streams[0].as_any().downcast_ref::<Stream<S, TimestampData<D>>>().unwrap();
+        let worker_index = stream_in.scope().index();
+        let max_file_bytes = config.as_ref().and_then(|config| config.get("max_file_bytes").and_then(|val| val.downcast_ref::<usize>()).copied());
+        *self.sink.borrow_mut() = Some(FileSink::new(self.dir.clone(), self.name.clone(), self.format, worker_index, max_file_bytes));
+        self.inspect.build(streams, config)
+    }
+
+    fn get_throughput_logger(&self) -> Option<ThroughputLogger> { self.inspect.get_throughput_logger() }
+
+    fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger> { self.inspect.get_flow_compute_latency_logger() }
+
+    fn get_flow_edge_latency_logger(&self) -> Option<LatencyLogger> { self.inspect.get_flow_edge_latency_logger() }
+
+    fn get_flow_path_latency_logger(&self) -> Option<LatencyLogger> { self.inspect.get_flow_path_latency_logger() }
+
+    fn get_jct_logger(&self) -> Option<JCTLogger> { self.inspect.get_jct_logger() }
+}
+
+impl<D: ExchangeData + Serialize, A, T> ExchangeOpBuilder for SinkNode<D, PipelineScope<A, T>>
+where
+    A: RelayConnectAllocate + 'static,
+    T: Timestamp+Refines<()>
+{
+    fn acquire_from_input_pipeline(&self, scope: &mut dyn GenericPipelineScope, input_idx: usize) -> Box<dyn GenericStream> {
+        self.inspect.acquire_from_input_pipeline(scope, input_idx)
+    }
+
+    fn build_and_register_output(&mut self, streams: &[&Box<dyn GenericStream>], config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>, scope: &mut dyn GenericPipelineScope, output_idx: usize) -> Box<dyn GenericStream> {
+        let stream = (self as &mut dyn LocalOpBuilder).build(streams, config);
+        self.register_pipeline_output(&stream, scope, output_idx);
+        stream
+    }
+
+    fn register_pipeline_output(&self, stream: &Box<dyn GenericStream>, scope: &mut dyn GenericPipelineScope, output_idx: usize) {
+        self.inspect.register_pipeline_output(stream, scope, output_idx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Read;
+
+    use serde::Serialize;
+
+    use super::{FileSink, SinkFormat};
+
+    #[derive(Serialize)]
+    struct Answer {
+        id: usize,
+        answer: String,
+    }
+
+    #[test]
+    fn write_and_rotate() {
+        let dir = std::env::temp_dir().join(format!("mlflow_sink_test_{}", std::process::id()));
+        let answers = (0..4).map(|id| Answer { id, answer: format!("answer {}", id) }).collect::<Vec<_>>();
+
+        let mut sink = FileSink::new(dir.clone(), String::from("Answers"), SinkFormat::Jsonl, 1, None);
+        answers.iter().for_each(|answer| sink.write(answer));
+        drop(sink);
+        let lines = fs::read_to_string(dir.join("Answers_1_0.jsonl")).unwrap();
+        assert_eq!(lines.lines().next(), Some("{\"id\":0,\"answer\":\"answer 0\"}"));
+        assert_eq!(lines.lines().count(), 4);
+
+        // rotate after every two records
+        let mut sink = FileSink::new(dir.clone(), String::from("Answers"), SinkFormat::Csv, 0, Some(30));
+        answers.iter().for_each(|answer| sink.write(answer));
+        drop(sink);
+        let csv = fs::read_to_string(dir.join("Answers_0_1.csv")).unwrap();
+        assert_eq!(csv, "id,answer\n2,answer 2\n3,answer 3\n");
+
+        let mut sink = FileSink::new(dir.clone(), String::from("Answers"), SinkFormat::Binary, 0, None);
+        sink.write(&answers[0]);
+        drop(sink);
+        let mut bytes = Vec::new();
+        fs::File::open(dir.join("Answers_0_0.bin")).unwrap().read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes[..4], (bytes.len() as u32 - 4).to_be_bytes());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
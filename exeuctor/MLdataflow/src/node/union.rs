@@ -1,8 +1,11 @@
 use std::any::Any;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::marker::PhantomData;
+use std::rc::Rc;
 use std::sync::Arc;
 
+use chrono::Utc;
 use timely::communication::RelayConnectAllocate;
 use timely::progress::Timestamp;
 use timely::progress::timestamp::Refines;
@@ -11,6 +14,7 @@ use timely::{ExchangeData, Data};
 use timely::dataflow::operators::Concatenate;
 
 use crate::TimestampData;
+use crate::metrics::{LatencyHistogram, LatencyLogger, RcWrapper, JCTLogger, ThroughputLogger, ThroughputMeter};
 use crate::static_timely::timely_static_pipeline_scope::PipelineScope;
 use crate::operators_timely::Map;
 
@@ -20,13 +24,20 @@ use super::GenericPipelineScope;
 
 /// Union
 /// Merge the contents of multiple streams
+/// The union has no logic of its own, so it has no execution latency
 pub struct UnionNode<D, S> 
 where
     S: Scope + 'static 
 {
     sources_indices: Vec<usize>,
     phantom: PhantomData<D>,
-    phantom_scope: PhantomData<S>
+    phantom_scope: PhantomData<S>,
+    edge_latencies: Rc<RefCell<LatencyHistogram>>,
+    path_latencies: Rc<RefCell<LatencyHistogram>>,
+    data_start_timestamp: Rc<RefCell<Option<i64>>>,
+    start_timestamp: Rc<RefCell<Option<i64>>>,
+    end_timestamp: Rc<RefCell<Option<i64>>>,
+    throughput: ThroughputMeter
 }
 
 impl<D, S> UnionNode<D, S>
@@ -37,7 +48,13 @@ where
         UnionNode {
             sources_indices: sources.into_iter().collect(),
             phantom: PhantomData,
-            phantom_scope: PhantomData
+            phantom_scope: PhantomData,
+            edge_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            path_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            data_start_timestamp: Rc::new(RefCell::new(None)),
+            start_timestamp: Rc::new(RefCell::new(None)),
+            end_timestamp: Rc::new(RefCell::new(None)),
+            throughput: ThroughputMeter::default()
         }
     }
 }
@@ -61,14 +78,37 @@ where
         ).collect::<Vec<_>>();
 
         let source_streams = source_streams.iter().map(
-            |stream|
+            |stream| {
+                let edge_latency_metrics = self.edge_latencies.clone();
+                let path_latency_metrics = self.path_latencies.clone();
+                let data_start_timestamp = self.data_start_timestamp.clone();
+                let start_timestamp = self.start_timestamp.clone();
+                let end_timestamp = self.end_timestamp.clone();
+                let throughput = self.throughput.clone();
                 stream.map_in_place(move |x, mut net_lat| {
-                if let Some(sim_net_lat) = sim_network_latency {
-                    net_lat = sim_net_lat;
-                }
-                if net_lat < 0 { net_lat = 0 }                    
-                x.total_exec_net_latency += net_lat 
-            })
+                    if let Some(sim_net_lat) = sim_network_latency {
+                        net_lat = sim_net_lat;
+                    }
+                    if net_lat < 0 { net_lat = 0 }                    
+                    x.total_exec_net_latency += net_lat;
+
+                    let curr_ts = Utc::now().timestamp_nanos();
+                    edge_latency_metrics.borrow_mut().record(net_lat);
+                    path_latency_metrics.borrow_mut().record(x.total_exec_net_latency);
+                    let data_min_start_ts = if let Some(data_min_start_ts) = data_start_timestamp.borrow().as_ref().copied() {
+                        std::cmp::min(data_min_start_ts, x.start_timestamp)
+                    }
+                    else {
+                        x.start_timestamp
+                    };
+                    *data_start_timestamp.borrow_mut() = Some(data_min_start_ts);
+                    if start_timestamp.borrow().is_none() {
+                        *start_timestamp.borrow_mut() = Some(curr_ts);
+                    }
+                    *end_timestamp.borrow_mut() = Some(curr_ts);
+                    throughput.record(curr_ts);
+                })
+            }
         ).collect::<Vec<_>>();
         
         let source_clone = source_streams[0].clone();
@@ -77,24 +117,35 @@ where
         Box::new(stream_out)
     }
 
-    fn get_throughput_logger(&self) -> Option<crate::metrics::ThroughputLogger> {
-        None
+    fn get_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.throughput.logger())
     }
 
-    fn get_flow_compute_latency_logger(&self) -> Option<crate::metrics::LatencyLogger> {
+    fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger> {
         None
     }
 
-    fn get_flow_edge_latency_logger(&self) -> Option<crate::metrics::LatencyLogger> {
-        None
+    fn get_flow_edge_latency_logger(&self) -> Option<LatencyLogger> {
+        let logger = LatencyLogger {
+            latencies: RcWrapper::new(self.edge_latencies.clone())
+        };
+        Some(logger)
     }
 
-    fn get_flow_path_latency_logger(&self) -> Option<crate::metrics::LatencyLogger> {
-        None
+    fn get_flow_path_latency_logger(&self) -> Option<LatencyLogger> {
+        let logger = LatencyLogger {
+            latencies: RcWrapper::new(self.path_latencies.clone())
+        };
+        Some(logger)
     }
 
-    fn get_jct_logger(&self) -> Option<crate::metrics::JCTLogger> {
-        None
+    fn get_jct_logger(&self) -> Option<JCTLogger> {
+        let logger = JCTLogger {
+            data_start_timestamp: RcWrapper::new(self.data_start_timestamp.clone()),
+            op_start_timestamp: RcWrapper::new(self.start_timestamp.clone()),
+            op_end_timestamp: RcWrapper::new(self.end_timestamp.clone())
+        };
+        Some(logger)
     }
 }
 
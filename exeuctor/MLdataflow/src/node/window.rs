@@ -0,0 +1,617 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Serialize, Deserialize};
+#[cfg(not(feature = "bincode"))]
+use abomonation::Abomonation;
+use timely::communication::RelayConnectAllocate;
+use timely::order::TotalOrder;
+use timely::progress::Timestamp;
+use timely::progress::timestamp::Refines;
+use timely::dataflow::channels::pact::Pipeline;
+use timely::dataflow::operators::{Capability, Operator};
+use timely::{Data, ExchangeData};
+use timely::dataflow::{Stream, Scope};
+
+use crate::{RequestId, TimestampData};
+use crate::metrics::{LatencyHistogram, LatencyLogger, RcWrapper, JCTLogger, ThroughputLogger, ThroughputMeter};
+use crate::static_timely::timely_static_pipeline_scope::PipelineScope;
+
+use super::{LocalOpBuilder, ExchangeOpBuilder};
+use super::GenericStream;
+use super::GenericPipelineScope;
+
+/// Timestamps that can be windowed by epoch, i.e., mapped to a (non-negative) epoch number
+pub trait EpochTimestamp: Timestamp + TotalOrder {
+    fn epoch(&self) -> u64;
+}
+
+macro_rules! implement_epoch_timestamp {
+    ($($index_type:ty,)*) => (
+        $(
+            impl EpochTimestamp for $index_type {
+                fn epoch(&self) -> u64 { *self as u64 }
+            }
+        )*
+    )
+}
+
+implement_epoch_timestamp!(usize, u64, u32, u16, u8,);
+
+/// What to do with the data of a wall-clock window that is already closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LateData {
+    /// Drop the late data
+    Drop,
+    /// Emit the late data of a window as a separate (late) result of the window
+    Fire,
+}
+
+/// Window of an aggregation, windows of `size` start every `slide` (tumbling if `size == slide`).
+/// Epoch and wall-clock windows are shared by all the keys, while count windows are per key.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Window {
+    /// Windows over the timely epochs, closed once the input frontier passes the end of the window
+    Epoch { size: u64, slide: u64 },
+    /// Windows over the wall-clock time the data enters the dataflow (`start_timestamp`),
+    /// closed once the wall clock passes the end of the window by `allowed_lateness`
+    /// (the operator is scheduled again when the earliest open window is due, even without new input)
+    WallClock { size: Duration, slide: Duration, allowed_lateness: Duration, late_data: LateData },
+    /// Windows over the records of each key, closed once the last record of the window arrives.
+    /// The state of a key is dropped once all its windows are closed (at the start of a window),
+    /// the records of the key are counted from zero again afterwards,
+    /// e.g., the bounds of tumbling count windows are always `[0, size)`
+    Count { size: usize, slide: usize },
+}
+
+impl Window {
+    pub fn tumbling_epochs(size: u64) -> Self {
+        Window::Epoch { size, slide: size }
+    }
+
+    pub fn sliding_epochs(size: u64, slide: u64) -> Self {
+        Window::Epoch { size, slide }
+    }
+
+    pub fn tumbling(size: Duration) -> Self {
+        Window::WallClock { size, slide: size, allowed_lateness: Duration::from_secs(0), late_data: LateData::Drop }
+    }
+
+    pub fn sliding(size: Duration, slide: Duration) -> Self {
+        Window::WallClock { size, slide, allowed_lateness: Duration::from_secs(0), late_data: LateData::Drop }
+    }
+
+    pub fn tumbling_count(size: usize) -> Self {
+        Window::Count { size, slide: size }
+    }
+
+    pub fn sliding_count(size: usize, slide: usize) -> Self {
+        Window::Count { size, slide }
+    }
+
+    /// Keep the wall-clock windows open for `allowed_lateness` after their end,
+    /// and handle the data arriving afterwards with `late_data` (no effect on other windows)
+    pub fn with_lateness(self, allowed_lateness: Duration, late_data: LateData) -> Self {
+        match self {
+            Window::WallClock { size, slide, .. } => Window::WallClock { size, slide, allowed_lateness, late_data },
+            window => window,
+        }
+    }
+
+    /// Size and slide in epochs / nanoseconds / records
+    fn size_and_slide(&self) -> (i64, i64) {
+        let (size, slide) = match self {
+            Window::Epoch { size, slide } => (*size as i64, *slide as i64),
+            Window::WallClock { size, slide, .. } => (size.as_nanos() as i64, slide.as_nanos() as i64),
+            Window::Count { size, slide } => (*size as i64, *slide as i64),
+        };
+        assert!(size > 0 && slide > 0, "window size and slide must be positive, got {:?}", self);
+        (size, slide)
+    }
+}
+
+/// Bounds `[start, end)` of a window, in epochs / nanoseconds / records (of the key, see `Window::Count`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct WindowBounds {
+    pub start: i64,
+    pub end: i64,
+    /// Whether the result only covers the late data of the window
+    pub late: bool,
+}
+
+// plain data without any owned memory
+#[cfg(not(feature = "bincode"))]
+impl Abomonation for WindowBounds { }
+
+// starts of the windows that contain the position
+fn window_starts(position: i64, size: i64, slide: i64) -> impl Iterator<Item = i64> {
+    let first = std::cmp::max((position - size).div_euclid(slide) + 1, 0);
+    let last = position.div_euclid(slide);
+    (first..=last).map(move |k| k * slide)
+}
+
+
+// data of an open window
+struct WindowState<T: Timestamp, K, Di> {
+    // held at the earliest timestamp of the data in the window
+    capability: Capability<T>,
+    // latest timestamp of the data in the window, the results are emitted at this timestamp
+    time: T,
+    // when the first data of the window is received
+    opened_ts: i64,
+    // intermediate result, id of the earliest request, min start timestamp and min last timestamp of each key
+    aggregates: HashMap<K, (Di, RequestId, i64, i64)>,
+}
+
+impl<T: Timestamp + TotalOrder, K: Clone + Hash + Eq, Di: Default> WindowState<T, K, Di> {
+    fn new(capability: Capability<T>, opened_ts: i64) -> Self {
+        let time = capability.time().clone();
+        WindowState { capability, time, opened_ts, aggregates: HashMap::new() }
+    }
+
+    fn fold<D, F>(&mut self, capability: &Capability<T>, key: K, data_point: TimestampData<D>, fold_logic: &F)
+    where
+        F: Fn(&K, D, &mut Di)
+    {
+        if capability.time().less_than(self.capability.time()) {
+            self.capability = capability.clone();
+        }
+        if self.time.less_than(capability.time()) {
+            self.time = capability.time().clone();
+        }
+        let (intermediate, request_id, min_start_ts, min_last_ts) = self.aggregates.entry(key.clone())
+            .or_insert((Default::default(), data_point.request_id, data_point.start_timestamp, data_point.last_timestamp));
+        if data_point.start_timestamp < *min_start_ts {
+            *request_id = data_point.request_id;
+            *min_start_ts = data_point.start_timestamp;
+        }
+        *min_last_ts = std::cmp::min(data_point.last_timestamp, *min_last_ts);
+        (fold_logic)(&key, data_point.data, intermediate);
+    }
+}
+
+type OpenWindows<T, K, Di> = BTreeMap<i64, WindowState<T, K, Di>>;
+// #records and open windows of each key
+type KeyWindows<T, K, Di> = HashMap<K, (i64, OpenWindows<T, K, Di>)>;
+
+// fold the data (received at `received_ts`) into the windows starting at `starts`,
+// the data is cloned for all but the last window
+fn fold_into<T, D, K, Di, F>(windows: &mut OpenWindows<T, K, Di>, starts: Vec<i64>, capability: &Capability<T>, received_ts: i64, key: K, data_point: TimestampData<D>, fold_logic: &F)
+where
+    T: Timestamp + TotalOrder,
+    D: Clone,
+    K: Clone + Hash + Eq,
+    Di: Default,
+    F: Fn(&K, D, &mut Di)
+{
+    let mut starts = starts.into_iter().peekable();
+    while let Some(start) = starts.next() {
+        let state = windows.entry(start).or_insert_with(|| WindowState::new(capability.clone(), received_ts));
+        if starts.peek().is_some() {
+            state.fold(capability, key.clone(), data_point.clone(), fold_logic);
+        }
+        else {
+            state.fold(capability, key, data_point, fold_logic);
+            break;
+        }
+    }
+}
+
+// remove the windows ending no later than `end` (all the windows if `end` is None)
+fn close_windows<T: Timestamp, K, Di>(windows: &mut OpenWindows<T, K, Di>, size: i64, end: Option<i64>, late: bool, closed: &mut Vec<(WindowBounds, WindowState<T, K, Di>)>) {
+    while let Some(start) = windows.keys().next().copied() {
+        if matches!(end, Some(end) if start + size > end) {
+            break;
+        }
+        let state = windows.remove(&start).unwrap();
+        closed.push((WindowBounds { start, end: start + size, late }, state));
+    }
+}
+
+/// Incremental aggregate data with the same key within a window (see `Window`)
+/// Input stream: data of type D
+/// Intermediate value: data of type Di
+/// Emit output stream of data type R
+/// hash_logic hashes the data to a key of type K
+/// fold_logic takes in a reference to the key, the data
+/// and mutate the intermediate aggregate result Di corresponds to that key and window
+/// (the data is folded into each of the overlapping sliding windows)
+/// emit_logic takes the key, the bounds of the window, and the final intermediate result
+/// And outputs data of type R, emitted at the latest timestamp of the data in the window
+/// The windows still open are emitted once the input completes
+/// As for the aggregates, the execution latency is the time from when the first data of a window is received
+/// to when its results are emitted
+pub struct WindowAggregateNode<D, Di, R, K, H, F, E, S>
+where
+    H: Fn(&D) -> K + 'static,
+    F: Fn(&K, D, &mut Di) + 'static,
+    E: Fn(K, WindowBounds, Di) -> R + 'static,
+    S: Scope + 'static
+{
+    prev_index: usize,
+    window: Window,
+    hash_logic: Option<H>,
+    fold_logic: Option<F>,
+    emit_logic: Option<E>,
+    phantom: PhantomData<(D, Di, R, K)>,
+    phantom_scope: PhantomData<S>,
+    edge_latencies: Rc<RefCell<LatencyHistogram>>,
+    path_latencies: Rc<RefCell<LatencyHistogram>>,
+    data_start_timestamp: Rc<RefCell<Option<i64>>>,
+    start_timestamp: Rc<RefCell<Option<i64>>>,
+    end_timestamp: Rc<RefCell<Option<i64>>>,
+    // time from the first data of a window received to its results emitted
+    execution_latencies: Rc<RefCell<LatencyHistogram>>,
+    input_throughput: ThroughputMeter,
+    throughput: ThroughputMeter
+}
+
+impl<D: Data, Di: Default + 'static, R: Data, K, H, F, E, S> WindowAggregateNode<D, Di, R, K, H, F, E, S>
+where
+    K: Clone + Hash + Eq + 'static,
+    H: Fn(&D) -> K + 'static,
+    F: Fn(&K, D, &mut Di) + 'static,
+    E: Fn(K, WindowBounds, Di) -> R + 'static,
+    S: Scope + 'static,
+    S::Timestamp: EpochTimestamp
+{
+    pub fn new(prev_index: usize, window: Window, hash_logic: H, fold_logic: F, emit_logic: E) -> Self {
+        window.size_and_slide();
+        WindowAggregateNode {
+            prev_index,
+            window,
+            hash_logic: Some(hash_logic),
+            fold_logic: Some(fold_logic),
+            emit_logic: Some(emit_logic),
+            phantom: PhantomData,
+            phantom_scope: PhantomData,
+            edge_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            path_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            data_start_timestamp: Rc::new(RefCell::new(None)),
+            start_timestamp: Rc::new(RefCell::new(None)),
+            end_timestamp: Rc::new(RefCell::new(None)),
+            execution_latencies: Rc::new(RefCell::new(LatencyHistogram::default())),
+            input_throughput: ThroughputMeter::default(),
+            throughput: ThroughputMeter::default()
+        }
+    }
+}
+
+impl<D: Data, Di: Default + 'static, R: Data, K, H, F, E, S> LocalOpBuilder for WindowAggregateNode<D, Di, R, K, H, F, E, S>
+where
+    K: Clone + Hash + Eq + 'static,
+    H: Fn(&D) -> K + 'static,
+    F: Fn(&K, D, &mut Di) + 'static,
+    E: Fn(K, WindowBounds, Di) -> R + 'static,
+    S: Scope + 'static,
+    S::Timestamp: EpochTimestamp
+{
+    fn required_prev_nodes(&self) -> Vec<usize> {
+        vec![self.prev_index]
+    }
+
+    fn build(&mut self, streams: &[&Box<dyn GenericStream>], config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>) -> Box<dyn GenericStream> {
+        let reset_timestamp = if let Some(config) = &config {
+            config.get("reset_timestamp").and_then(|val| val.downcast_ref::<bool>()).copied()
+        }
+        else { None };
+        let reset_timestamp = reset_timestamp.unwrap_or(false);
+
+        let stream_in = streams[0];
+        let stream_in = stream_in.as_any().downcast_ref::<Stream<S, TimestampData<D>>>().unwrap();
+
+        let hash_logic = self.hash_logic.take().unwrap();
+        let fold_logic = self.fold_logic.take().unwrap();
+        let emit_logic = self.emit_logic.take().unwrap();
+        let window = self.window.clone();
+        let (size, slide) = window.size_and_slide();
+
+        let edge_latency_metrics = self.edge_latencies.clone();
+        let path_latency_metrics = self.path_latencies.clone();
+        let data_start_timestamp = self.data_start_timestamp.clone();
+        let start_timestamp = self.start_timestamp.clone();
+        let end_timestamp = self.end_timestamp.clone();
+        let execution_latency_metrics = self.execution_latencies.clone();
+        let input_throughput = self.input_throughput.clone();
+        let throughput = self.throughput.clone();
+
+        let scope = stream_in.scope();
+        let stream_out = stream_in.unary_frontier(Pipeline, "WindowAggregate", |_capability, info| {
+            let activator = scope.activator_for(&info.address[..]);
+            // epoch and wall-clock windows, by start
+            let mut windows: OpenWindows<S::Timestamp, K, Di> = BTreeMap::new();
+            // late data of the closed wall-clock windows
+            let mut late_windows: OpenWindows<S::Timestamp, K, Di> = BTreeMap::new();
+            // count windows
+            let mut key_windows: KeyWindows<S::Timestamp, K, Di> = HashMap::new();
+            // wall-clock windows ending no later than the watermark are closed
+            let mut watermark = i64::MIN;
+            let mut vector = Vec::new();
+
+            move |input, output| {
+                let mut closed = Vec::new();
+                input.for_each(|time, data| {
+                    data.swap(&mut vector);
+                    let capability = time.retain();
+                    let received_ts = Utc::now().timestamp_nanos();
+                    for data_point in vector.drain(..) {
+                        input_throughput.record(received_ts);
+                        let key = (hash_logic)(&data_point.data);
+                        match &window {
+                            Window::Epoch { .. } => {
+                                let starts = window_starts(capability.time().epoch() as i64, size, slide).collect();
+                                fold_into(&mut windows, starts, &capability, received_ts, key, data_point, &fold_logic);
+                            },
+                            Window::WallClock { late_data, .. } => {
+                                let (starts, late_starts): (Vec<_>, Vec<_>) = window_starts(data_point.start_timestamp, size, slide)
+                                    .partition(|start| start + size > watermark);
+                                if late_starts.is_empty() || *late_data == LateData::Drop {
+                                    fold_into(&mut windows, starts, &capability, received_ts, key, data_point, &fold_logic);
+                                }
+                                else {
+                                    fold_into(&mut windows, starts, &capability, received_ts, key.clone(), data_point.clone(), &fold_logic);
+                                    fold_into(&mut late_windows, late_starts, &capability, received_ts, key, data_point, &fold_logic);
+                                }
+                            },
+                            Window::Count { .. } => {
+                                let (count, windows) = key_windows.entry(key.clone()).or_insert_with(|| (0, BTreeMap::new()));
+                                let starts = window_starts(*count, size, slide).collect();
+                                *count += 1;
+                                fold_into(windows, starts, &capability, received_ts, key.clone(), data_point, &fold_logic);
+                                close_windows(windows, size, Some(*count), false, &mut closed);
+                                // drop the state of the key once all its windows are closed,
+                                // unless the next record of the key still falls into a gap between the windows
+                                if windows.is_empty() && *count % slide == 0 {
+                                    key_windows.remove(&key);
+                                }
+                            },
+                        }
+                    }
+                });
+
+                // the windows still open are closed once the input completes
+                let frontier = input.frontier().frontier();
+                let input_done = frontier.is_empty();
+                match &window {
+                    Window::Epoch { .. } => {
+                        let end = frontier.iter().next().map(|time| time.epoch() as i64);
+                        close_windows(&mut windows, size, end, false, &mut closed);
+                    },
+                    Window::WallClock { allowed_lateness, .. } => {
+                        let lateness = allowed_lateness.as_nanos() as i64;
+                        let curr_ts = Utc::now().timestamp_nanos();
+                        watermark = std::cmp::max(watermark, curr_ts - lateness);
+                        close_windows(&mut windows, size, if input_done { None } else { Some(watermark) }, false, &mut closed);
+                        close_windows(&mut late_windows, size, None, true, &mut closed);
+                        // schedule the operator again once the earliest open window closes, even without any further input
+                        if let Some(start) = windows.keys().next() {
+                            let remaining = std::cmp::max(start + size + lateness - curr_ts, 0) as u64;
+                            activator.activate_after(Duration::from_nanos(remaining + 1));
+                        }
+                    },
+                    Window::Count { .. } => {
+                        if input_done {
+                            for (_, (_, mut windows)) in key_windows.drain() {
+                                close_windows(&mut windows, size, None, false, &mut closed);
+                            }
+                        }
+                    },
+                }
+
+                for (bounds, state) in closed {
+                    let capability = state.capability.delayed(&state.time);
+                    let mut session = output.session(&capability);
+                    for (key, (intermediate, request_id, min_start_ts, min_last_ts)) in state.aggregates {
+                        let result = (emit_logic)(key, bounds, intermediate);
+                        let curr_ts = Utc::now().timestamp_nanos();
+
+                        let data_min_start_ts = if let Some(data_min_start_ts) = data_start_timestamp.borrow().as_ref().copied() {
+                            std::cmp::min(data_min_start_ts, min_start_ts)
+                        }
+                        else {
+                            min_start_ts
+                        };
+                        *data_start_timestamp.borrow_mut() = Some(data_min_start_ts);
+                        if start_timestamp.borrow().is_none() {
+                            *start_timestamp.borrow_mut() = Some(curr_ts);
+                        }
+                        *end_timestamp.borrow_mut() = Some(curr_ts);
+                        execution_latency_metrics.borrow_mut().record(curr_ts - state.opened_ts);
+                        edge_latency_metrics.borrow_mut().record(curr_ts - min_last_ts);
+                        path_latency_metrics.borrow_mut().record(curr_ts - min_start_ts);
+                        throughput.record(curr_ts);
+
+                        session.give(if reset_timestamp {
+                            TimestampData {
+                                data: result,
+                                request_id,
+                                start_timestamp: curr_ts,
+                                last_timestamp: curr_ts,
+                                total_exec_net_latency: 0
+                            }
+                        } else {
+                            TimestampData {
+                                data: result,
+                                request_id,
+                                start_timestamp: min_start_ts,
+                                last_timestamp: curr_ts,
+                                total_exec_net_latency: curr_ts - min_start_ts
+                            }
+                        });
+                    }
+                }
+            }
+        });
+        Box::new(stream_out)
+    }
+
+    fn get_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.throughput.logger())
+    }
+
+    fn get_input_throughput_logger(&self) -> Option<ThroughputLogger> {
+        Some(self.input_throughput.logger())
+    }
+
+    fn get_flow_compute_latency_logger(&self) -> Option<LatencyLogger> {
+        let logger = LatencyLogger {
+            latencies: RcWrapper::new(self.execution_latencies.clone())
+        };
+        Some(logger)
+    }
+
+    fn get_flow_edge_latency_logger(&self) -> Option<LatencyLogger> {
+        let logger = LatencyLogger {
+            latencies: RcWrapper::new(self.edge_latencies.clone())
+        };
+        Some(logger)
+    }
+
+    fn get_flow_path_latency_logger(&self) -> Option<LatencyLogger> {
+        let logger = LatencyLogger {
+            latencies: RcWrapper::new(self.path_latencies.clone())
+        };
+        Some(logger)
+    }
+
+    fn get_jct_logger(&self) -> Option<JCTLogger> {
+        let logger = JCTLogger {
+            data_start_timestamp: RcWrapper::new(self.data_start_timestamp.clone()),
+            op_start_timestamp: RcWrapper::new(self.start_timestamp.clone()),
+            op_end_timestamp: RcWrapper::new(self.end_timestamp.clone())
+        };
+        Some(logger)
+    }
+}
+
+impl<D: Data, Di: Default + 'static, R: ExchangeData, K, H, F, E, A, T> ExchangeOpBuilder for WindowAggregateNode<D, Di, R, K, H, F, E, PipelineScope<A, T>>
+where
+    K: Clone + Hash + Eq + 'static,
+    H: Fn(&D) -> K + 'static,
+    F: Fn(&K, D, &mut Di) + 'static,
+    E: Fn(K, WindowBounds, Di) -> R + 'static,
+    A: RelayConnectAllocate + 'static,
+    T: EpochTimestamp + Refines<()>
+{
+    fn acquire_from_input_pipeline(&self, scope: &mut dyn GenericPipelineScope, input_idx: usize) -> Box<dyn GenericStream> {
+        let scope = scope.as_any_mut().downcast_mut::<PipelineScope<A, T>>().unwrap();
+        let stream = scope.acquire_pipeline_input::<TimestampData<R>>(input_idx);
+        Box::new(stream)
+    }
+
+    fn build_and_register_output(&mut self, streams: &[&Box<dyn GenericStream>], config: Option<HashMap<String, Arc<dyn Any + Send + Sync>>>, scope: &mut dyn GenericPipelineScope, output_idx: usize) -> Box<dyn GenericStream> {
+        let stream = (self as &mut dyn LocalOpBuilder).build(streams, config);
+        self.register_pipeline_output(&stream, scope, output_idx);
+        stream
+    }
+
+    fn register_pipeline_output(&self, stream: &Box<dyn GenericStream>, scope: &mut dyn GenericPipelineScope, output_idx: usize) {
+        let scope = scope.as_any_mut().downcast_mut::<PipelineScope<A, T>>().unwrap();
+        let stream = stream.as_any().downcast_ref::<Stream<PipelineScope<A, T>, TimestampData<R>>>().unwrap();
+        scope.register_pipeline_output_balanced_exchange(stream, output_idx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use timely::dataflow::{InputHandle, Stream};
+    use timely::dataflow::operators::{Input, Inspect};
+
+    use crate::TimestampData;
+    use crate::builder::GraphBuilder;
+    use crate::execute::local_execute_thread;
+    use crate::node::{GenericStream, LocalOpBuilder};
+    use crate::static_timely::timely_static_execute::execute_directly;
+    use crate::static_timely::timely_static_scope::Child;
+    use crate::test_utils::{ThreadWorker, step_until, timestamped};
+    use crate::{AggregateLocal, InspectLocal};
+
+    use super::{LateData, Window, WindowAggregateNode, WindowBounds, window_starts};
+
+    // sums of the windows over 0..10, one number per epoch, keyed by the parity
+    fn window_sums(window: Window) -> Vec<(usize, i64, i64, usize)> {
+        let sums = Arc::new(Mutex::new(Vec::new()));
+        let sums_cloned = sums.clone();
+        local_execute_thread(move |builder: &mut GraphBuilder<usize>| {
+            let sums = sums_cloned.clone();
+            builder.new_input_from_source(VecDeque::from((0..10).collect::<Vec<usize>>()), |_, t| *t + 1, "Input")
+                .window_aggregate_local(window.clone(), |x| x % 2, |_, x, sum: &mut usize| *sum += x,
+                    |key, bounds: WindowBounds, sum| (key, bounds.start, bounds.end, sum), "Window")
+                .inspect_local(move |x| sums.lock().unwrap().push(*x), "Inspect");
+        });
+        let mut sums = sums.lock().unwrap().clone();
+        sums.sort();
+        sums
+    }
+
+    #[test]
+    fn assign_windows() {
+        assert_eq!(window_starts(5, 4, 4).collect::<Vec<_>>(), vec![4]);
+        assert_eq!(window_starts(5, 4, 2).collect::<Vec<_>>(), vec![2, 4]);
+        assert_eq!(window_starts(1, 4, 2).collect::<Vec<_>>(), vec![0]);
+        // windows with gaps
+        assert_eq!(window_starts(3, 2, 4).count(), 0);
+    }
+
+    #[test]
+    fn epoch_and_count_windows() {
+        assert_eq!(window_sums(Window::tumbling_epochs(4)), vec![
+            (0, 0, 4, 2), (0, 4, 8, 10), (0, 8, 12, 8),
+            (1, 0, 4, 4), (1, 4, 8, 12), (1, 8, 12, 9),
+        ]);
+        let sliding = window_sums(Window::sliding_epochs(4, 2));
+        assert_eq!(sliding.len(), 10);
+        assert!(sliding.contains(&(0, 2, 6, 6)) && sliding.contains(&(1, 6, 10, 16)));
+        // the records of a key are counted from zero once all its windows are closed,
+        // the last window of each key is partial
+        assert_eq!(window_sums(Window::tumbling_count(3)), vec![
+            (0, 0, 3, 6), (0, 0, 3, 14), (1, 0, 3, 9), (1, 0, 3, 16),
+        ]);
+        // the first window of each key is still open when the second one starts
+        assert_eq!(window_sums(Window::sliding_count(4, 2)), vec![
+            (0, 0, 4, 12), (0, 2, 6, 18), (0, 4, 8, 8), (1, 0, 4, 16), (1, 2, 6, 21), (1, 4, 8, 9),
+        ]);
+        // all the data falls into at most two windows, closed once the input completes
+        let wall_clock = window_sums(Window::tumbling(Duration::from_secs(60)));
+        assert_eq!(wall_clock.iter().filter(|x| x.0 == 0).map(|x| x.3).sum::<usize>(), 20);
+        assert_eq!(wall_clock.iter().filter(|x| x.0 == 1).map(|x| x.3).sum::<usize>(), 25);
+    }
+
+    #[test]
+    fn wall_clock_window_closes_while_input_paused() {
+        execute_directly(|worker| {
+            let sums = Arc::new(Mutex::new(Vec::new()));
+            let sums_cloned = sums.clone();
+            let mut input: InputHandle<usize, _> = worker.dataflow(|scope| {
+                let (input, stream) = scope.new_input::<TimestampData<usize>>();
+                let window = Window::tumbling(Duration::from_millis(50)).with_lateness(Duration::from_millis(20), LateData::Drop);
+                let mut node = WindowAggregateNode::<_, _, _, _, _, _, _, Child<ThreadWorker, usize>>::new(0, window,
+                    |_: &usize| 0, |_, x, sum: &mut usize| *sum += x, |_, _, sum| sum);
+                let stream: Box<dyn GenericStream> = Box::new(stream);
+                let stream_out = node.build(&[&stream], None);
+                stream_out.as_any().downcast_ref::<Stream<Child<ThreadWorker, usize>, TimestampData<usize>>>().unwrap()
+                    .inspect(move |x| sums_cloned.lock().unwrap().push(x.data));
+                input
+            });
+            for data in 1..4 {
+                input.send(timestamped(data));
+            }
+            input.advance_to(1);
+            // the window is emitted once the wall clock passes its end, while the input stays open
+            step_until(worker, "the window was not closed", || !sums.lock().unwrap().is_empty());
+            assert_eq!(*sums.lock().unwrap(), vec![6]);
+        });
+    }
+}
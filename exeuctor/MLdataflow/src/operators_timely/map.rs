@@ -2,20 +2,35 @@
 //! Extension methods for `Stream` based on record-by-record transformation.
 
 use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
 
 use timely::Data;
 use timely::communication::MessageLatency;
+use timely::progress::Timestamp;
 use timely::dataflow::{Stream, Scope};
 use timely::dataflow::channels::pact::Pipeline;
+use timely::dataflow::channels::pushers::Tee;
+use timely::dataflow::operators::Capability;
+use timely::dataflow::operators::generic::OutputHandle;
 use timely::dataflow::operators::generic::operator::Operator;
 
+// arrival of the oldest buffered record, buffered records with their timestamps
+type Bucket<T, D> = (Instant, Vec<(T, D, MessageLatency)>);
+
 /// Extension trait for `Stream`.
 pub trait Map<S: Scope, D: Data> {
     fn map<D2: Data, L: FnMut(D, MessageLatency)->D2+'static>(&self, logic: L) -> Stream<S, D2>;
     fn map_in_place<L: FnMut(&mut D, MessageLatency)+'static>(&self, logic: L) -> Stream<S, D>;
+    fn filter_map<D2: Data, L: FnMut(D, MessageLatency)->Option<D2>+'static>(&self, logic: L) -> Stream<S, D2>;
     fn flat_map<I: IntoIterator, L: FnMut(D, MessageLatency)->I+'static>(&self, logic: L) -> Stream<S, I::Item> where I::Item: Data;
     fn batched_map<D2: Data, I2: IntoIterator<Item=D2>, L: FnMut(Vec<(D, MessageLatency)>)->I2+'static>(&self, batch_size: usize, logic: L) -> Stream<S, D2>;
+    /// Batches across timestamps, per bucket key: a batch is processed once it reaches `max_batch_size`,
+    /// once its oldest record waited for `max_wait` (or, without `max_wait`, once all its timestamps are complete),
+    /// or once the input completes. The outputs of a batch are emitted at the latest timestamp of the batch
+    fn dynamic_batched_map<D2: Data, I2: IntoIterator<Item=D2>, K: Hash+Eq+'static, KF: FnMut(&D)->K+'static, L: FnMut(Vec<(D, MessageLatency)>)->I2+'static>(&self, max_batch_size: usize, max_wait: Option<Duration>, key: KF, logic: L) -> Stream<S, D2>;
     fn buffered_map<D2: Data, L: FnMut(D, MessageLatency)->D2+'static>(&self, buffer_size: usize, logic: L) -> Stream<S, D2>;
+    fn buffered_filter_map<D2: Data, L: FnMut(D, MessageLatency)->Option<D2>+'static>(&self, buffer_size: usize, logic: L) -> Stream<S, D2>;
 }
 
 impl<S: Scope, D: Data> Map<S, D> for Stream<S, D> {
@@ -46,6 +61,18 @@ impl<S: Scope, D: Data> Map<S, D> for Stream<S, D> {
         })
     }
   
+    fn filter_map<D2: Data, L: FnMut(D, MessageLatency)->Option<D2>+'static>(&self, mut logic: L) -> Stream<S, D2> {
+        let mut vector = Vec::new();
+        // records mapped to None are dropped
+        self.unary(Pipeline, "FilterMap", move |_,_| move |input, output| {
+            input.for_each_with_latency(|time, data, lat| {
+                let lat = lat.unwrap_or(0);
+                data.swap(&mut vector);
+                output.session(&time).give_iterator(vector.drain(..).filter_map(|x| logic(x, lat)));
+            });
+        })
+    }
+
     fn flat_map<I: IntoIterator, L: FnMut(D, MessageLatency)->I+'static>(&self, mut logic: L) -> Stream<S, I::Item> where I::Item: Data {
         let mut vector = Vec::new();
         // each input record may produce multiple output records
@@ -90,6 +117,64 @@ impl<S: Scope, D: Data> Map<S, D> for Stream<S, D> {
         stream_out     
     }
 
+    fn dynamic_batched_map<D2: Data, I2: IntoIterator<Item=D2>, K: Hash+Eq+'static, KF: FnMut(&D)->K+'static, L: FnMut(Vec<(D, MessageLatency)>)->I2+'static>(&self, max_batch_size: usize, max_wait: Option<Duration>, mut key: KF, mut logic: L) -> Stream<S, D2> {
+        let mut vector = Vec::new();
+        // buffered records per bucket key
+        let mut buckets: HashMap<K, Bucket<S::Timestamp, D>> = HashMap::new();
+        // timestamp -> (capability, #buffered records of the timestamp)
+        let mut capabilities = HashMap::new();
+        let scope = self.scope();
+        let stream_out = self.unary_frontier(Pipeline, "DynamicBatchMap", move |_capability, info| {
+            // reschedules the operator once the oldest batch is due
+            let activator = scope.activator_for(&info.address[..]);
+            move |input, output| {
+                input.for_each_with_latency(|time, data, lat| {
+                    let lat = lat.unwrap_or(0);
+                    data.swap(&mut vector);
+                    let timestamp = time.time().clone();
+                    if !capabilities.contains_key(&timestamp) {
+                        capabilities.insert(timestamp.clone(), (time.retain(), 0));
+                    }
+                    capabilities.get_mut(&timestamp).unwrap().1 += vector.len();
+                    for x in vector.drain(..) {
+                        let (since, batch) = buckets.entry(key(&x)).or_insert_with(|| (Instant::now(), Vec::with_capacity(max_batch_size)));
+                        if batch.is_empty() {
+                            *since = Instant::now();
+                        }
+                        batch.push((timestamp.clone(), x, lat));
+                        if batch.len() >= max_batch_size {
+                            flush_batch(std::mem::take(batch), &mut logic, &mut capabilities, output);
+                        }
+                    }
+                });
+
+                let frontier = input.frontier().frontier();
+                let input_done = frontier.is_empty();
+                let now = Instant::now();
+                let mut next_due: Option<Duration> = None;
+                for (since, batch) in buckets.values_mut().filter(|(_, batch)| !batch.is_empty()) {
+                    let waited = now.duration_since(*since);
+                    let due = match max_wait {
+                        Some(max_wait) => waited >= max_wait,
+                        None => batch.iter().all(|(time, _, _)| !frontier.less_equal(time))
+                    };
+                    if input_done || due {
+                        flush_batch(std::mem::take(batch), &mut logic, &mut capabilities, output);
+                    }
+                    else if let Some(max_wait) = max_wait {
+                        let remaining = max_wait - waited;
+                        next_due = Some(next_due.map_or(remaining, |next_due| std::cmp::min(next_due, remaining)));
+                    }
+                }
+                buckets.retain(|_, (_, batch)| !batch.is_empty());
+                if let Some(next_due) = next_due {
+                    activator.activate_after(next_due);
+                }
+            }
+        });
+        stream_out
+    }
+
     fn buffered_map<D2: Data, L: FnMut(D, MessageLatency)->D2+'static>(&self, buffer_size: usize, mut logic: L) -> Stream<S, D2> {
         let mut vector = Vec::new();
         let mut buffer = HashMap::new();
@@ -119,4 +204,97 @@ impl<S: Scope, D: Data> Map<S, D> for Stream<S, D> {
         });      
         stream_out     
     }
-}
\ No newline at end of file
+
+    fn buffered_filter_map<D2: Data, L: FnMut(D, MessageLatency)->Option<D2>+'static>(&self, buffer_size: usize, mut logic: L) -> Stream<S, D2> {
+        let mut vector = Vec::new();
+        let mut buffer = HashMap::new();
+        let stream_out = self.unary_notify(Pipeline, "BufferFilterMap", None, move |input, output, notificator| {
+            input.for_each_with_latency(|time, data, lat| {
+                let lat = lat.unwrap_or(0);
+                data.swap(&mut vector);
+                // records mapped to None are dropped
+                let timestamp_buffer = buffer.entry(time.time().clone()).or_insert(Vec::with_capacity(buffer_size));
+                for x in vector.drain(..) {
+                    if let Some(mapped) = logic(x, lat) {
+                        timestamp_buffer.push(mapped);
+                        if timestamp_buffer.len() >= buffer_size {
+                            output.session(&time).give_iterator(timestamp_buffer.drain(..))
+                        }
+                    }
+                }
+                notificator.notify_at(time.retain());
+            });
+            notificator.for_each(|time, _, _| {
+                if let Some(timestamp_buffer) = buffer.remove(time.time()) {
+                    if !timestamp_buffer.is_empty() {
+                        output.session(&time).give_iterator(timestamp_buffer.into_iter());
+                    }
+                }
+            });
+        });
+        stream_out
+    }
+}
+
+// processes a batch and emits its outputs at the latest timestamp of the batch,
+// the capabilities of the timestamps without buffered records are released
+fn flush_batch<T, D, D2, I2, L>(batch: Vec<(T, D, MessageLatency)>, logic: &mut L, capabilities: &mut HashMap<T, (Capability<T>, usize)>, output: &mut OutputHandle<T, D2, Tee<T, D2>>)
+where
+    T: Timestamp,
+    D2: Data,
+    I2: IntoIterator<Item=D2>,
+    L: FnMut(Vec<(D, MessageLatency)>)->I2
+{
+    let mut latest: Option<T> = None;
+    let mut data = Vec::with_capacity(batch.len());
+    for (time, x, lat) in batch {
+        if latest.iter().all(|latest| latest.less_equal(&time)) {
+            latest = Some(time.clone());
+        }
+        capabilities.get_mut(&time).unwrap().1 -= 1;
+        data.push((x, lat));
+    }
+    if let Some(latest) = latest {
+        output.session(&capabilities[&latest].0).give_iterator(logic(data).into_iter());
+    }
+    capabilities.retain(|_, (_, count)| *count > 0);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    use timely::dataflow::InputHandle;
+    use timely::dataflow::operators::{Input, Inspect};
+
+    use crate::static_timely::timely_static_execute::execute_directly;
+    use crate::test_utils::step_until;
+
+    use super::Map;
+
+    #[test]
+    fn flush_partial_batch_after_max_wait() {
+        execute_directly(|worker| {
+            let batches = Arc::new(Mutex::new(Vec::new()));
+            let batches_cloned = batches.clone();
+            let max_wait = Duration::from_millis(50);
+            let mut input: InputHandle<usize, usize> = worker.dataflow(|scope| {
+                let (input, stream) = scope.new_input();
+                stream.dynamic_batched_map(10, Some(max_wait), |_| 0, |batch| Some(batch.into_iter().map(|(x, _)| x).collect::<Vec<_>>()))
+                    .inspect(move |batch| batches_cloned.lock().unwrap().push(batch.clone()));
+                input
+            });
+            let start = Instant::now();
+            for x in 0..3 {
+                input.send(x);
+            }
+            // sends the buffered records, the input stays open
+            input.advance_to(1);
+            // with max_wait, only the deadline flushes the batch (not the frontier)
+            step_until(worker, "the partial batch was not flushed", || !batches.lock().unwrap().is_empty());
+            assert!(start.elapsed() >= max_wait, "flushed after {:?}", start.elapsed());
+            assert_eq!(*batches.lock().unwrap(), vec![vec![0, 1, 2]]);
+        });
+    }
+}
@@ -0,0 +1,508 @@
+//! Cluster-wide report of a run: merges the metrics logged by every pipeline worker in the metrics logging directory
+//! (`performance_metrics_p{P}_w{W}.json` and `latency_logs/latency_histograms_p{P}_w{W}.json`)
+//! into per-operator and per-pipeline views, plus the end-to-end latency and JCT of the dataflow.
+//! The report is rendered as JSON, Markdown or HTML (see also the `mlflow_report` binary).
+//!
+//! ```no_run
+//! use mlflow::report::MetricsReport;
+//!
+//! let report = MetricsReport::from_dir("metrics", None).unwrap();
+//! println!("{}", report.to_markdown());
+//! ```
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Serialize, Deserialize};
+
+use crate::graph::GraphTopology;
+use crate::metrics::{LatencyBuckets, LatencyHistogram, MetricsStats};
+
+/// Errors when reading the logged metrics
+#[derive(Debug)]
+pub enum ReportError {
+    Io(PathBuf, std::io::Error),
+    Json(PathBuf, serde_json::Error),
+    /// No `performance_metrics_p{P}_w{W}.json` in the directory
+    NoMetrics(PathBuf),
+}
+
+impl fmt::Display for ReportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportError::Io(path, err) => write!(f, "failed to read {}: {}", path.display(), err),
+            ReportError::Json(path, err) => write!(f, "invalid metrics in {}: {}", path.display(), err),
+            ReportError::NoMetrics(path) => write!(f, "no worker metrics in {}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for ReportError {}
+
+/// Metrics of an operator merged over the workers of its pipeline,
+/// operators with the same name in different pipelines are reported separately
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OperatorReport {
+    pub name: String,
+    pub pipeline: usize,
+    /// Number of workers reporting the operator
+    pub workers: usize,
+    /// Overall throughput (in #req/s) summed over the workers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub throughput: Option<f64>,
+    /// Overall rate (in #req/s) of the requests received by a join or an aggregate, summed over the workers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_throughput: Option<f64>,
+    /// Latency percentiles (in milliseconds) of each kind (operator_execution, dataflow_edge, dataflow_path),
+    /// computed from the merged latency histograms of the workers
+    /// (or the highest percentile reported by a worker if the histograms are not logged)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub latency: BTreeMap<String, BTreeMap<String, f64>>,
+    /// Longest operator job completion time (in seconds) of the workers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operator_jct: Option<f64>,
+    /// Longest dataflow path job completion time (in seconds) of the workers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path_jct: Option<f64>,
+    /// Requests finished within the SLO in #req/s, summed over the workers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub goodput: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dropped: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub evicted_keys: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending_keys: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub errors: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retries: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch_sizes: Option<BTreeMap<usize, usize>>,
+}
+
+/// Metrics of a pipeline over its workers and operators
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PipelineReport {
+    /// Indices of the workers with logged metrics
+    pub workers: Vec<usize>,
+    pub operators: Vec<String>,
+    /// Longest operator job completion time (in seconds) in the pipeline
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jct: Option<f64>,
+    /// Failed requests of all operators in the pipeline
+    pub errors: usize,
+    /// Requests dropped (exceeding the SLO) by all operators in the pipeline
+    pub dropped: usize,
+}
+
+/// Latency from the sources to the end of the dataflow
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EndToEndReport {
+    /// Operators at the end of the dataflow (without outgoing edges)
+    pub sinks: Vec<String>,
+    /// Dataflow path latency percentiles (in milliseconds) pooled over the sinks and their workers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency: Option<BTreeMap<String, f64>>,
+}
+
+/// Metrics of a run merged over all pipelines and workers, serialized with sorted keys to be compared across runs
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MetricsReport {
+    /// Sorted by pipeline and operator name
+    pub operators: Vec<OperatorReport>,
+    pub pipelines: BTreeMap<usize, PipelineReport>,
+    /// Time (in seconds) from the first request launched to the last request processed by any operator
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jct: Option<f64>,
+    /// Only reported with the topology of the dataflow
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_to_end: Option<EndToEndReport>,
+}
+
+// latencies of an operator while merging the workers
+#[derive(Default)]
+struct OperatorLatencies {
+    histograms: BTreeMap<String, LatencyHistogram>,
+    // reported by the workers without logged histograms
+    percentiles: BTreeMap<String, BTreeMap<String, f64>>,
+}
+
+impl OperatorLatencies {
+    fn percentiles(&self, kind: &str) -> Option<BTreeMap<String, f64>> {
+        let merged = self.histograms.get(kind).and_then(|histogram| histogram.percentiles_ms());
+        match (merged, self.percentiles.get(kind)) {
+            (Some(mut merged), Some(reported)) => {
+                for (name, value) in reported {
+                    let merged = merged.entry(name.clone()).or_insert(*value);
+                    *merged = merged.max(*value);
+                }
+                Some(merged)
+            },
+            (merged, reported) => merged.or_else(|| reported.cloned()),
+        }
+    }
+}
+
+fn sum<T: std::ops::Add<Output = T>>(x: Option<T>, y: Option<T>) -> Option<T> {
+    match (x, y) {
+        (Some(x), Some(y)) => Some(x + y),
+        (x, y) => x.or(y),
+    }
+}
+
+fn max(x: Option<f64>, y: Option<f64>) -> Option<f64> {
+    match (x, y) {
+        (Some(x), Some(y)) => Some(x.max(y)),
+        (x, y) => x.or(y),
+    }
+}
+
+// (pipeline index, worker index) of `performance_metrics_p{P}_w{W}.json`
+fn parse_metrics_file_name(name: &str) -> Option<(usize, usize)> {
+    let indices = name.strip_prefix("performance_metrics_p")?.strip_suffix(".json")?;
+    let (pipeline_index, worker_index) = indices.split_once("_w")?;
+    Some((pipeline_index.parse().ok()?, worker_index.parse().ok()?))
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, ReportError> {
+    let json = fs::read_to_string(path).map_err(|err| ReportError::Io(path.to_owned(), err))?;
+    serde_json::from_str(&json).map_err(|err| ReportError::Json(path.to_owned(), err))
+}
+
+impl MetricsReport {
+    /// Merge the metrics logged in `metrics_logging_dir`,
+    /// the end-to-end latency is reported if the topology of the dataflow is provided
+    pub fn from_dir<P: AsRef<Path>>(metrics_logging_dir: P, topology: Option<&GraphTopology>) -> Result<Self, ReportError> {
+        let dir = metrics_logging_dir.as_ref();
+        let entries = fs::read_dir(dir).map_err(|err| ReportError::Io(dir.to_owned(), err))?;
+        let mut workers = BTreeSet::new();
+        for entry in entries {
+            let entry = entry.map_err(|err| ReportError::Io(dir.to_owned(), err))?;
+            if let Some(indices) = entry.file_name().to_str().and_then(parse_metrics_file_name) {
+                workers.insert(indices);
+            }
+        }
+        if workers.is_empty() {
+            return Err(ReportError::NoMetrics(dir.to_owned()));
+        }
+
+        let mut report = MetricsReport::default();
+        // keyed by (pipeline index, operator name)
+        let mut operators: BTreeMap<(usize, String), OperatorReport> = BTreeMap::new();
+        let mut latencies: BTreeMap<(usize, String), OperatorLatencies> = BTreeMap::new();
+        for (pipeline_index, worker_index) in workers {
+            let metrics: MetricsStats = read_json(&dir.join(format!("performance_metrics_p{}_w{}.json", pipeline_index, worker_index)))?;
+            let histograms_path = dir.join(format!("latency_logs/latency_histograms_p{}_w{}.json", pipeline_index, worker_index));
+            let mut histograms: BTreeMap<String, BTreeMap<String, LatencyBuckets>> = if histograms_path.exists() {
+                read_json(&histograms_path)?
+            }
+            else {
+                BTreeMap::new()
+            };
+            report.pipelines.entry(pipeline_index).or_default().workers.push(worker_index);
+            for (op_name, stats) in metrics {
+                let op = operators.entry((pipeline_index, op_name.clone()))
+                    .or_insert_with(|| OperatorReport { name: op_name.clone(), pipeline: pipeline_index, ..Default::default() });
+                op.workers += 1;
+                op.throughput = sum(op.throughput, stats.overall_throughput);
+                op.input_throughput = sum(op.input_throughput, stats.input_throughput);
+                op.operator_jct = max(op.operator_jct, stats.operator_jct);
+                op.path_jct = max(op.path_jct, stats.path_jct);
+                op.goodput = sum(op.goodput, stats.goodput);
+                op.dropped = sum(op.dropped, stats.dropped);
+                op.evicted_keys = sum(op.evicted_keys, stats.evicted_keys);
+                op.pending_keys = sum(op.pending_keys, stats.pending_keys);
+                op.errors = sum(op.errors, stats.errors);
+                op.retries = sum(op.retries, stats.retries);
+                if let Some(batch_sizes) = stats.batch_sizes {
+                    let merged = op.batch_sizes.get_or_insert_with(BTreeMap::new);
+                    for (batch_size, count) in batch_sizes {
+                        *merged.entry(batch_size).or_insert(0) += count;
+                    }
+                }
+                let op_latencies = latencies.entry((pipeline_index, op_name.clone())).or_default();
+                let mut op_histograms = histograms.remove(&op_name).unwrap_or_default();
+                for (kind, percentiles) in stats.latency.unwrap_or_default() {
+                    match op_histograms.remove(&kind) {
+                        Some(buckets) => {
+                            let histogram = LatencyHistogram::from_buckets(&buckets);
+                            match op_latencies.histograms.get_mut(&kind) {
+                                Some(merged) => merged.merge(&histogram),
+                                None => { op_latencies.histograms.insert(kind, histogram); },
+                            }
+                        },
+                        None => {
+                            let merged = op_latencies.percentiles.entry(kind).or_default();
+                            for (name, value) in percentiles {
+                                let merged = merged.entry(name).or_insert(value);
+                                *merged = merged.max(value);
+                            }
+                        },
+                    }
+                }
+            }
+        }
+
+        for (op_key, op_latencies) in latencies.iter() {
+            let op = operators.get_mut(op_key).unwrap();
+            let kinds = op_latencies.histograms.keys().chain(op_latencies.percentiles.keys()).cloned().collect::<BTreeSet<_>>();
+            for kind in kinds {
+                if let Some(percentiles) = op_latencies.percentiles(&kind) {
+                    op.latency.insert(kind, percentiles);
+                }
+            }
+        }
+        report.operators = operators.into_values().collect();
+        for op in report.operators.iter() {
+            let pipeline = report.pipelines.get_mut(&op.pipeline).unwrap();
+            pipeline.operators.push(op.name.clone());
+            pipeline.jct = max(pipeline.jct, op.operator_jct);
+            pipeline.errors += op.errors.unwrap_or(0);
+            pipeline.dropped += op.dropped.unwrap_or(0);
+            report.jct = max(report.jct, op.path_jct);
+        }
+        report.pipelines.values_mut().for_each(|pipeline| pipeline.workers.sort_unstable());
+
+        if let Some(topology) = topology {
+            let sinks = topology.operators.iter()
+                .filter(|op| topology.connections.outgoing_edges.get(&op.id).iter().all(|targets| targets.is_empty()))
+                .map(|op| op.name.clone())
+                .filter(|name| report.operators.iter().any(|op| op.name == *name))
+                .collect::<Vec<_>>();
+            // pooled over the pipelines running a sink
+            let mut pooled = OperatorLatencies::default();
+            let sink_latencies = latencies.iter()
+                .filter(|((_, op_name), _)| sinks.contains(op_name))
+                .map(|(_, op_latencies)| op_latencies);
+            for sink_latencies in sink_latencies {
+                if let Some(histogram) = sink_latencies.histograms.get("dataflow_path") {
+                    match pooled.histograms.get_mut("dataflow_path") {
+                        Some(merged) => merged.merge(histogram),
+                        None => { pooled.histograms.insert(String::from("dataflow_path"), histogram.clone()); },
+                    }
+                }
+                if let Some(percentiles) = sink_latencies.percentiles.get("dataflow_path") {
+                    let merged = pooled.percentiles.entry(String::from("dataflow_path")).or_default();
+                    for (name, value) in percentiles {
+                        let merged = merged.entry(name.clone()).or_insert(*value);
+                        *merged = merged.max(*value);
+                    }
+                }
+            }
+            report.end_to_end = Some(EndToEndReport {
+                latency: pooled.percentiles("dataflow_path"),
+                sinks,
+            });
+        }
+        Ok(report)
+    }
+
+    /// Metrics of the operator `name` in the pipeline `pipeline`
+    pub fn operator(&self, pipeline: usize, name: &str) -> Option<&OperatorReport> {
+        self.operators.iter().find(|op| op.pipeline == pipeline && op.name == name)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    // (title, header, rows) of the summary tables
+    fn tables(&self) -> Vec<(String, Vec<String>, Vec<Vec<String>>)> {
+        let format_f64 = |x: Option<f64>| x.map_or_else(|| String::from("-"), |x| format!("{:.3}", x));
+        let format_usize = |x: Option<usize>| x.map_or_else(|| String::from("-"), |x| x.to_string());
+        let mut tables = Vec::new();
+
+        let percentile_names = ["P50", "P90", "P99", "P999"];
+        if let Some(end_to_end) = &self.end_to_end {
+            let mut header = vec![String::from("Sinks")];
+            header.extend(percentile_names.iter().map(|name| format!("{} (ms)", name)));
+            let mut row = vec![end_to_end.sinks.join(", ")];
+            row.extend(percentile_names.iter().map(|name| format_f64(end_to_end.latency.as_ref().and_then(|latency| latency.get(*name).copied()))));
+            tables.push((String::from("End-to-end latency"), header, vec![row]));
+        }
+
+        let header = ["Pipeline", "Workers", "Operators", "JCT (s)", "Errors", "Dropped"];
+        let rows = self.pipelines.iter().map(|(pipeline_index, pipeline)| vec![
+            pipeline_index.to_string(),
+            pipeline.workers.len().to_string(),
+            pipeline.operators.join(", "),
+            format_f64(pipeline.jct),
+            pipeline.errors.to_string(),
+            pipeline.dropped.to_string(),
+        ]).collect();
+        tables.push((String::from("Pipelines"), header.iter().map(|x| x.to_string()).collect(), rows));
+
+        let header = [
+            "Operator", "Pipeline", "Workers", "Input (req/s)", "Throughput (req/s)",
+            "Execution P50 (ms)", "Execution P99 (ms)", "Path P50 (ms)", "Path P99 (ms)",
+            "Operator JCT (s)", "Path JCT (s)", "Errors", "Dropped",
+        ];
+        let rows = self.operators.iter().map(|op| {
+            let latency = |kind: &str, name: &str| format_f64(op.latency.get(kind).and_then(|latency| latency.get(name).copied()));
+            vec![
+                op.name.clone(),
+                op.pipeline.to_string(),
+                op.workers.to_string(),
+                format_f64(op.input_throughput),
+                format_f64(op.throughput),
+                latency("operator_execution", "P50"),
+                latency("operator_execution", "P99"),
+                latency("dataflow_path", "P50"),
+                latency("dataflow_path", "P99"),
+                format_f64(op.operator_jct),
+                format_f64(op.path_jct),
+                format_usize(op.errors),
+                format_usize(op.dropped),
+            ]
+        }).collect();
+        tables.push((String::from("Operators"), header.iter().map(|x| x.to_string()).collect(), rows));
+        tables
+    }
+
+    fn jct_summary(&self) -> String {
+        match self.jct {
+            Some(jct) => format!("Dataflow JCT: {:.3} s", jct),
+            None => String::from("Dataflow JCT: -"),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("# Metrics report\n\n{}\n", self.jct_summary());
+        for (title, header, rows) in self.tables() {
+            markdown.push_str(&format!("\n## {}\n\n", title));
+            markdown.push_str(&format!("| {} |\n", header.join(" | ")));
+            markdown.push_str(&format!("|{}\n", " --- |".repeat(header.len())));
+            for row in rows {
+                let row = row.iter().map(|cell| cell.replace('|', "\\|")).collect::<Vec<_>>();
+                markdown.push_str(&format!("| {} |\n", row.join(" | ")));
+            }
+        }
+        markdown
+    }
+
+    pub fn to_html(&self) -> String {
+        let escape = |text: &str| text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+        let mut html = String::from("<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Metrics report</title></head>\n<body>\n");
+        html.push_str(&format!("<h1>Metrics report</h1>\n<p>{}</p>\n", self.jct_summary()));
+        for (title, header, rows) in self.tables() {
+            html.push_str(&format!("<h2>{}</h2>\n<table border=\"1\">\n<tr>", escape(&title)));
+            for cell in header {
+                html.push_str(&format!("<th>{}</th>", escape(&cell)));
+            }
+            html.push_str("</tr>\n");
+            for row in rows {
+                html.push_str("<tr>");
+                for cell in row {
+                    html.push_str(&format!("<td>{}</td>", escape(&cell)));
+                }
+                html.push_str("</tr>\n");
+            }
+            html.push_str("</table>\n");
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+    use std::fs;
+
+    use crate::graph::{GraphConnections, GraphTopology, OperatorDescription, OperatorKind};
+    use crate::metrics::{LatencyHistogram, OperatorMetricsStats};
+
+    use super::MetricsReport;
+
+    fn op_metrics(throughput: f64, path_jct: f64, errors: usize) -> OperatorMetricsStats {
+        OperatorMetricsStats {
+            throughput: None,
+            overall_throughput: Some(throughput),
+            input_throughput: None,
+            latency: None,
+            operator_jct: Some(path_jct / 2.0),
+            path_jct: Some(path_jct),
+            dropped: None,
+            goodput: None,
+            evicted_keys: None,
+            pending_keys: None,
+            errors: Some(errors),
+            retries: None,
+            batch_sizes: None,
+        }
+    }
+
+    #[test]
+    fn merge_worker_metrics() {
+        let dir = std::env::temp_dir().join(format!("mlflow_report_test_{}", std::process::id()));
+        fs::create_dir_all(dir.join("latency_logs")).unwrap();
+        // two workers of pipeline 0 processing 1..=100 ms and 101..=200 ms, pipeline 1 without histograms
+        for worker_index in 0..2 {
+            let mut histogram = LatencyHistogram::default();
+            (1..=100).for_each(|ms| histogram.record((worker_index * 100 + ms) * 1000000));
+            let mut stats = op_metrics(10.0, 1.0 + worker_index as f64, 1);
+            let mut latency = BTreeMap::new();
+            latency.insert(String::from("dataflow_path"), histogram.percentiles_ms().unwrap());
+            stats.latency = Some(latency);
+            let metrics = HashMap::from([(String::from("Map"), stats)]);
+            fs::write(dir.join(format!("performance_metrics_p0_w{}.json", worker_index)), serde_json::to_string(&metrics).unwrap()).unwrap();
+            let mut buckets = BTreeMap::new();
+            buckets.insert(String::from("Map"), BTreeMap::from([(String::from("dataflow_path"), histogram.buckets())]));
+            fs::write(dir.join(format!("latency_logs/latency_histograms_p0_w{}.json", worker_index)), serde_json::to_string(&buckets).unwrap()).unwrap();
+        }
+        let metrics = HashMap::from([(String::from("Inspect"), op_metrics(5.0, 4.0, 0))]);
+        fs::write(dir.join("performance_metrics_p1_w2.json"), serde_json::to_string(&metrics).unwrap()).unwrap();
+
+        let topology = GraphTopology {
+            operators: vec![
+                OperatorDescription { id: 0, name: String::from("Map"), kind: OperatorKind::ExchangeCompute, dead_letters_of: None },
+                OperatorDescription { id: 1, name: String::from("Inspect"), kind: OperatorKind::ExchangeCompute, dead_letters_of: None },
+            ],
+            connections: GraphConnections {
+                outgoing_edges: HashMap::from([(0, vec![1])]),
+                incoming_edges: HashMap::from([(1, vec![0])]),
+            },
+        };
+        let report = MetricsReport::from_dir(&dir, Some(&topology)).unwrap();
+        let map = report.operator(0, "Map").unwrap();
+        assert_eq!((map.pipeline, map.workers, map.throughput, map.errors), (0, 2, Some(20.0), Some(2)));
+        // the median of the pooled latencies, not the median of either worker
+        let p50 = map.latency.get("dataflow_path").unwrap().get("P50").unwrap();
+        assert!((p50 - 100.0).abs() < 0.1);
+        assert_eq!(map.path_jct, Some(2.0));
+        assert_eq!(report.pipelines.get(&0).unwrap().workers, vec![0, 1]);
+        assert_eq!(report.pipelines.get(&1).unwrap().operators, vec![String::from("Inspect")]);
+        assert_eq!(report.jct, Some(4.0));
+        let end_to_end = report.end_to_end.as_ref().unwrap();
+        assert_eq!(end_to_end.sinks, vec![String::from("Inspect")]);
+        assert!(end_to_end.latency.is_none());
+
+        assert_eq!(MetricsReport::from_json(&report.to_json()).unwrap(), report);
+        assert!(report.to_markdown().contains("| Map | 0 | 2 | - | 20.000 |"));
+        assert!(report.to_html().contains("<td>Inspect</td>"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn separate_operators_of_pipelines() {
+        let dir = std::env::temp_dir().join(format!("mlflow_report_pipelines_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        // both pipelines run an operator named "Map"
+        for (pipeline_index, throughput) in [(0, 10.0), (1, 5.0)] {
+            let metrics = HashMap::from([(String::from("Map"), op_metrics(throughput, 1.0, pipeline_index))]);
+            fs::write(dir.join(format!("performance_metrics_p{}_w0.json", pipeline_index)), serde_json::to_string(&metrics).unwrap()).unwrap();
+        }
+        let report = MetricsReport::from_dir(&dir, None).unwrap();
+        assert_eq!(report.operators.iter().map(|op| (op.pipeline, op.name.as_str())).collect::<Vec<_>>(), vec![(0, "Map"), (1, "Map")]);
+        let map = report.operator(1, "Map").unwrap();
+        assert_eq!((map.workers, map.throughput, map.errors), (1, Some(5.0), Some(1)));
+        assert_eq!(report.pipelines.get(&0).unwrap().errors, 0);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,331 @@
+//! Request / response API around the pipeline execution:
+//! requests are submitted through a `RequestClient`, fed into the dataflow by a request input
+//! (`PipelineGraphBuilder::new_request_input`), and answered once the terminal operator (`Respond::respond`)
+//! emits the output correlated by the request id.
+//! The input and the terminal operator must be executed in the process of the client.
+//! Requests that are never answered (e.g., dropped by the latency SLO, routed to dead letters, filtered out
+//! or evicted from a join) resolve to `None` once the response deadline (`request_response_with_deadline`) passes.
+//!
+//! ```no_run
+//! use std::time::Duration;
+//! use mlflow::{ExecutionConfig, Map, Respond, PipelineGraphBuilder};
+//! use mlflow::execute::pipeline_worker_execute;
+//! use mlflow::serving::request_response;
+//!
+//! let config = ExecutionConfig::from_deployment_file("deployment.yaml").unwrap();
+//! let (client, endpoint) = request_response::<usize, usize>();
+//! let worker = std::thread::spawn(move || {
+//!     pipeline_worker_execute(move |builder: &mut PipelineGraphBuilder<usize>| {
+//!         let input = builder.new_request_input(&endpoint, Duration::from_millis(10), |t| t + 1, "Input");
+//!         input.map(|(id, x)| (id, x * 2), "Map").respond(&endpoint, "Respond");
+//!     }, &config, 0, 0)
+//! });
+//! let response = client.submit(21).wait().unwrap();
+//! assert_eq!(response.output, 42);
+//! drop(client);
+//! worker.join().unwrap().unwrap();
+//! ```
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+use chrono::Utc;
+
+use crate::input::RequestId;
+
+/// Output of a request
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response<R> {
+    pub request_id: RequestId,
+    pub output: R,
+    /// End-to-end latency (in nanoseconds) from the submission (`start_timestamp`) to the terminal operator emitting the output
+    pub latency: i64,
+}
+
+/// Response to a submitted request, resolved once the terminal operator emits the correlated output
+pub struct PendingResponse<R> {
+    request_id: RequestId,
+    receiver: Receiver<Response<R>>,
+    responses: Arc<PendingResponses<R>>,
+    // the request is given up at this timestamp (in nanoseconds), never if None
+    expires_at: Option<i64>,
+}
+
+impl<R> PendingResponse<R> {
+    pub fn request_id(&self) -> RequestId {
+        self.request_id
+    }
+
+    /// Block until the response is emitted,
+    /// returns `None` if the dataflow finishes or the response deadline passes without answering the request
+    pub fn wait(self) -> Option<Response<R>> {
+        match self.expires_at {
+            // never times out before the deadline
+            Some(_) => self.wait_timeout(Duration::MAX).ok().flatten(),
+            None => self.receiver.recv().ok()
+        }
+    }
+
+    /// Block until the response is emitted or the timeout elapses,
+    /// returns `Err(self)` on timeout
+    pub fn wait_timeout(self, timeout: Duration) -> Result<Option<Response<R>>, Self> {
+        let remaining = self.expires_at.map(|expires_at| Duration::from_nanos(std::cmp::max(expires_at - Utc::now().timestamp_nanos(), 0) as u64));
+        let expiring = matches!(remaining, Some(remaining) if remaining <= timeout);
+        let timeout = if expiring { remaining.unwrap() } else { timeout };
+        match self.receiver.recv_timeout(timeout) {
+            Ok(response) => Ok(Some(response)),
+            Err(RecvTimeoutError::Disconnected) => Ok(None),
+            Err(RecvTimeoutError::Timeout) if expiring => {
+                self.responses.expire(self.request_id);
+                // the output may be emitted right before the request is given up
+                Ok(self.receiver.try_recv().ok())
+            },
+            Err(RecvTimeoutError::Timeout) => Err(self),
+        }
+    }
+}
+
+// requests submitted by the clients, not taken by a request input yet
+pub(crate) struct RequestQueue<D> {
+    // (request id, input, submission timestamp)
+    queue: Mutex<VecDeque<(RequestId, D, i64)>>,
+    next_request_id: AtomicU64,
+    clients: AtomicUsize,
+}
+
+impl<D> RequestQueue<D> {
+    pub(crate) fn pop(&self) -> Option<(RequestId, D, i64)> {
+        self.queue.lock().unwrap().pop_front()
+    }
+
+    /// Whether all the clients are dropped and all the requests are taken
+    pub(crate) fn is_closed(&self) -> bool {
+        self.clients.load(Ordering::SeqCst) == 0 && self.queue.lock().unwrap().is_empty()
+    }
+}
+
+// submission timestamp and sender of the response
+type PendingRequest<R> = (i64, Sender<Response<R>>);
+
+// requests waiting for their outputs
+struct PendingResponses<R> {
+    pending: Mutex<HashMap<RequestId, PendingRequest<R>>>,
+    // (submission timestamp, request id) in the order of submission, only kept with a response deadline
+    submitted: Mutex<VecDeque<(i64, RequestId)>>,
+    // in nanoseconds
+    deadline: Option<i64>,
+    expired: AtomicUsize,
+    endpoints: AtomicUsize,
+}
+
+impl<R> PendingResponses<R> {
+    // give up a request, dropping its sender resolves the response to `None`
+    fn expire(&self, request_id: RequestId) {
+        if self.pending.lock().unwrap().remove(&request_id).is_some() {
+            self.expired.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    // give up the requests submitted more than the deadline ago
+    fn expire_overdue(&self, curr_ts: i64) {
+        if let Some(deadline) = self.deadline {
+            let mut pending = self.pending.lock().unwrap();
+            let mut submitted = self.submitted.lock().unwrap();
+            while let Some((timestamp, request_id)) = submitted.front().copied() {
+                if curr_ts - timestamp < deadline {
+                    break;
+                }
+                submitted.pop_front();
+                if pending.remove(&request_id).is_some() {
+                    self.expired.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        }
+    }
+}
+
+/// Submits requests to the dataflow, the dataflow input completes once all the clients are dropped
+pub struct RequestClient<D, R> {
+    requests: Arc<RequestQueue<D>>,
+    responses: Arc<PendingResponses<R>>,
+}
+
+impl<D, R> RequestClient<D, R> {
+    /// Submit an input, the returned response resolves once the correlated output is emitted
+    pub fn submit(&self, input: D) -> PendingResponse<R> {
+        let request_id = self.requests.next_request_id.fetch_add(1, Ordering::SeqCst);
+        let (sender, receiver) = channel();
+        let timestamp = Utc::now().timestamp_nanos();
+        self.responses.expire_overdue(timestamp);
+        {
+            let mut pending = self.responses.pending.lock().unwrap();
+            // the response is never sent once the dataflow finishes
+            if self.responses.endpoints.load(Ordering::SeqCst) > 0 {
+                pending.insert(request_id, (timestamp, sender));
+                if self.responses.deadline.is_some() {
+                    self.responses.submitted.lock().unwrap().push_back((timestamp, request_id));
+                }
+            }
+        }
+        self.requests.queue.lock().unwrap().push_back((request_id, input, timestamp));
+        PendingResponse {
+            request_id,
+            receiver,
+            responses: self.responses.clone(),
+            expires_at: self.responses.deadline.map(|deadline| timestamp + deadline)
+        }
+    }
+
+    /// Number of requests not answered yet
+    pub fn num_pending(&self) -> usize {
+        self.responses.pending.lock().unwrap().len()
+    }
+
+    /// Number of requests given up after the response deadline passed without an answer
+    pub fn num_expired(&self) -> usize {
+        self.responses.expired.load(Ordering::SeqCst)
+    }
+}
+
+impl<D, R> Clone for RequestClient<D, R> {
+    fn clone(&self) -> Self {
+        self.requests.clients.fetch_add(1, Ordering::SeqCst);
+        RequestClient {
+            requests: self.requests.clone(),
+            responses: self.responses.clone(),
+        }
+    }
+}
+
+impl<D, R> Drop for RequestClient<D, R> {
+    fn drop(&mut self) {
+        self.requests.clients.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Connects the dataflow to the clients, passed to the request input and the terminal operator.
+/// Once all the endpoints are dropped (i.e., the dataflow finishes), the unanswered requests resolve to `None`
+pub struct RequestEndpoint<D, R> {
+    pub(crate) requests: Arc<RequestQueue<D>>,
+    responses: Arc<PendingResponses<R>>,
+}
+
+impl<D, R> RequestEndpoint<D, R> {
+    /// Answer a request, the outputs of requests already answered are ignored
+    pub(crate) fn resolve(&self, request_id: RequestId, output: R) {
+        let curr_ts = Utc::now().timestamp_nanos();
+        self.responses.expire_overdue(curr_ts);
+        let pending = self.responses.pending.lock().unwrap().remove(&request_id);
+        if let Some((timestamp, sender)) = pending {
+            let latency = curr_ts - timestamp;
+            // the client may have dropped the pending response
+            let _ = sender.send(Response { request_id, output, latency });
+        }
+    }
+}
+
+impl<D, R> Clone for RequestEndpoint<D, R> {
+    fn clone(&self) -> Self {
+        self.responses.endpoints.fetch_add(1, Ordering::SeqCst);
+        RequestEndpoint {
+            requests: self.requests.clone(),
+            responses: self.responses.clone(),
+        }
+    }
+}
+
+impl<D, R> Drop for RequestEndpoint<D, R> {
+    fn drop(&mut self) {
+        let mut pending = self.responses.pending.lock().unwrap();
+        if self.responses.endpoints.fetch_sub(1, Ordering::SeqCst) == 1 {
+            pending.clear();
+            self.responses.submitted.lock().unwrap().clear();
+        }
+    }
+}
+
+/// Create a client submitting inputs of type `D` and an endpoint answering them with outputs of type `R`,
+/// the requests wait for their outputs until the dataflow finishes
+pub fn request_response<D, R>() -> (RequestClient<D, R>, RequestEndpoint<D, R>) {
+    new_request_response(None)
+}
+
+/// Create a client and an endpoint (see `request_response`),
+/// where the requests not answered within `deadline` from their submission resolve to `None`
+pub fn request_response_with_deadline<D, R>(deadline: Duration) -> (RequestClient<D, R>, RequestEndpoint<D, R>) {
+    new_request_response(Some(deadline.as_nanos() as i64))
+}
+
+fn new_request_response<D, R>(deadline: Option<i64>) -> (RequestClient<D, R>, RequestEndpoint<D, R>) {
+    let requests = Arc::new(RequestQueue {
+        queue: Mutex::new(VecDeque::new()),
+        next_request_id: AtomicU64::new(0),
+        clients: AtomicUsize::new(1),
+    });
+    let responses = Arc::new(PendingResponses {
+        pending: Mutex::new(HashMap::new()),
+        submitted: Mutex::new(VecDeque::new()),
+        deadline,
+        expired: AtomicUsize::new(0),
+        endpoints: AtomicUsize::new(1),
+    });
+    let client = RequestClient { requests: requests.clone(), responses: responses.clone() };
+    let endpoint = RequestEndpoint { requests, responses };
+    (client, endpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{request_response, request_response_with_deadline};
+
+    #[test]
+    fn resolve_requests() {
+        let (client, endpoint) = request_response::<usize, String>();
+        let first = client.submit(1);
+        let second = client.submit(2);
+        let (request_id, input, _) = endpoint.requests.pop().unwrap();
+        assert_eq!((request_id, input), (first.request_id(), 1));
+        endpoint.resolve(request_id, String::from("one"));
+        endpoint.resolve(request_id, String::from("ignored"));
+        let response = first.wait().unwrap();
+        assert_eq!(response.output, "one");
+        assert!(response.latency >= 0);
+
+        drop(client);
+        assert!(!endpoint.requests.is_closed());
+        endpoint.requests.pop().unwrap();
+        assert!(endpoint.requests.is_closed());
+        // the dataflow finishes without answering the second request
+        drop(endpoint);
+        assert!(second.wait().is_none());
+    }
+
+    #[test]
+    fn expire_unanswered_requests() {
+        let (client, endpoint) = request_response_with_deadline::<usize, usize>(Duration::from_millis(50));
+        // e.g., dropped by the latency SLO, never answered
+        let dropped = client.submit(1);
+        let answered = client.submit(2);
+        endpoint.resolve(answered.request_id(), 4);
+        assert_eq!(answered.wait().unwrap().output, 4);
+
+        let start = Instant::now();
+        assert!(dropped.wait().is_none());
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(client.num_pending(), 0);
+        assert_eq!(client.num_expired(), 1);
+
+        // overdue requests nobody waits for are given up on the next submission
+        let _forgotten = client.submit(3);
+        std::thread::sleep(Duration::from_millis(60));
+        let next = client.submit(4);
+        assert_eq!(client.num_pending(), 1);
+        assert_eq!(client.num_expired(), 2);
+        assert!(next.wait_timeout(Duration::from_millis(1)).is_err());
+        drop(endpoint);
+    }
+}
@@ -102,9 +102,11 @@ where
         });
     }
 
+    let noisy = config.communication.report();
     let (allocators, other) = config.communication.try_build()?;
 
-    let worker_config = config.worker;
+    let mut worker_config = config.worker;
+    worker_config.set(String::from("noisy"), noisy);
     initialize_from(allocators, other, move |allocator| {
 
         let mut worker = Worker::new(worker_config.clone(), allocator);
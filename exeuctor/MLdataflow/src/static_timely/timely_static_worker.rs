@@ -1,7 +1,7 @@
 //! The root of each single-threaded worker.
 
 use std::rc::Rc;
-use std::cell::{RefCell, RefMut};
+use std::cell::{Cell, RefCell, RefMut};
 use std::any::Any;
 use std::time::{Instant, Duration};
 use std::collections::HashMap;
@@ -44,6 +44,9 @@ pub struct Worker<A: Allocate + 'static> {
 
     activations: Rc<RefCell<Activations>>,
     active_dataflows: Vec<usize>,
+    // Whether the completion of the relay input connections has been observed,
+    // the pipeline is activated once more to close the input frontiers.
+    relay_input_completed: Rc<Cell<bool>>,
 
     // Temporary storage for channel identifiers during dataflow construction.
     // These are then associated with a dataflow once constructed.
@@ -101,6 +104,7 @@ impl<A: Allocate + 'static> Worker<A> {
             logging: Rc::new(RefCell::new(timely::logging_core::Registry::new(now.clone(), index))),
             activations: Rc::new(RefCell::new(Activations::new(now.clone()))),
             active_dataflows: Default::default(),
+            relay_input_completed: Default::default(),
             temp_channel_ids:  Default::default(),
         }
     }
@@ -562,6 +566,9 @@ impl<A: RelayConnectAllocate + 'static> RelayConnector for Worker<A>  {
     fn relay_input_pipelines_completed(&self) -> bool {
         self.allocator.borrow().relay_input_pipelines_complete()
     }    
+    fn relay_backpressured(&self) -> bool {
+        self.allocator.borrow().relay_backpressured()
+    }
     fn allocate_relay_channel<T: Data>(&mut self, identifier: usize) -> (Vec<Box<dyn Push<Message<T>>>>, Box<dyn Pull<Message<T>>>) {
         self.allocator.borrow_mut().allocate_channel_to_relay(identifier)
     }
@@ -590,6 +597,12 @@ impl<A: RelayConnectAllocate> Worker<A> {
                 self.activations.borrow_mut().activate(&[0]);
             }
             relay_events.borrow_mut().clear();
+            // the relay connections may close without any further message
+            // (e.g., the upstream timely workers are lost)
+            if !self.relay_input_completed.get() && allocator.relay_input_pipelines_complete() {
+                self.relay_input_completed.set(true);
+                self.activations.borrow_mut().activate(&[0]);
+            }
         }
 
         {   // Process channel events. Activate responders.
@@ -832,6 +845,7 @@ impl<A: Allocate + 'static> Clone for Worker<A> {
             logging: self.logging.clone(),
             activations: self.activations.clone(),
             active_dataflows: Vec::new(),
+            relay_input_completed: self.relay_input_completed.clone(),
             temp_channel_ids: self.temp_channel_ids.clone(),
         }
     }
@@ -0,0 +1,23 @@
+//! Fixtures shared by the operator unit tests
+
+use std::time::{Duration, Instant};
+
+use crate::TimestampData;
+use crate::static_timely::timely_static_worker::Worker;
+
+pub(crate) type ThreadWorker = Worker<timely::communication::allocator::Thread>;
+
+/// Wraps `data` as if it was just received from an input
+pub(crate) fn timestamped<D>(data: D) -> TimestampData<D> {
+    let curr_ts = chrono::Utc::now().timestamp_nanos();
+    TimestampData { data, request_id: 0, start_timestamp: curr_ts, last_timestamp: curr_ts, total_exec_net_latency: 0 }
+}
+
+/// Steps the worker, with its inputs still open, until `done` holds, or panics with `what` after 5 seconds
+pub(crate) fn step_until<F: FnMut() -> bool>(worker: &mut ThreadWorker, what: &str, mut done: F) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !done() {
+        assert!(Instant::now() < deadline, "{}", what);
+        worker.step_or_park(Some(Duration::from_millis(500)));
+    }
+}
@@ -0,0 +1,345 @@
+//! Per-request tracing: the operators record the spans of the sampled requests
+//! (relay hop, queueing at the worker, operator execution, and waiting in the state of joins and aggregates),
+//! which are written by each worker process as Chrome trace-event JSON (`trace_p{P}_w{W}.json`)
+//! or OTLP-JSON (`otlp_p{P}_w{W}.json`), enabled by `TracingConfig`.
+//!
+//! A request is identified by the request id assigned by its input source and kept along the dataflow path
+//! (the one of the earliest request for joins and aggregates).
+//! The relay nodes do not decode the data, the relay hop of a request is derived from the network latency
+//! measured at the receiving operator. Spans recorded by different machines are subject to their clock skew.
+use std::any::Any;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Value};
+
+use crate::input::RequestId;
+
+/// Format of the trace files
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceFormat {
+    /// Chrome trace-event JSON, viewable in `chrome://tracing` or Perfetto (one row per request)
+    #[default]
+    Chrome,
+    /// OTLP-JSON export of OpenTelemetry spans, the trace id of a request is the same across all the workers
+    Otlp,
+}
+
+/// Per-request tracing of the dataflow
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TracingConfig {
+    /// Fraction of the requests traced, in (0, 1].
+    /// The sampling is deterministic on the request id, so all the operators trace the same requests
+    pub sample_rate: f64,
+    #[serde(default)]
+    pub format: TraceFormat,
+    /// Directory of the trace files, defaults to the metrics logging directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dir: Option<PathBuf>,
+}
+
+impl TracingConfig {
+    /// Whether the request is traced
+    pub fn is_sampled(&self, request_id: RequestId) -> bool {
+        if self.sample_rate >= 1.0 {
+            return true;
+        }
+        // uniform in [0, 1) from the 53 high bits of the mixed request id
+        let uniform = (splitmix64(request_id) >> 11) as f64 / (1u64 << 53) as f64;
+        uniform < self.sample_rate
+    }
+
+    /// Path of the trace file of a worker process
+    pub fn trace_path(&self, pipeline_index: usize, worker_index: usize) -> PathBuf {
+        let name = match self.format {
+            TraceFormat::Chrome => format!("trace_p{}_w{}.json", pipeline_index, worker_index),
+            TraceFormat::Otlp => format!("otlp_p{}_w{}.json", pipeline_index, worker_index),
+        };
+        self.dir.clone().unwrap_or_default().join(name)
+    }
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Where a request spent its time at an operator
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpanKind {
+    /// From emitted by the upstream operator until received by the worker (through the relay nodes)
+    Relay,
+    /// From received by the worker until the operator starts executing it
+    Queue,
+    /// Execution of the operator logic
+    Execute,
+    /// Buffered in the state of a join or an aggregate, until matched or completed
+    Wait,
+}
+
+impl SpanKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SpanKind::Relay => "relay",
+            SpanKind::Queue => "queue",
+            SpanKind::Execute => "execute",
+            SpanKind::Wait => "wait",
+        }
+    }
+}
+
+/// Span of a request at an operator (timestamps in nanoseconds)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    pub request_id: RequestId,
+    pub operator: Arc<str>,
+    pub kind: SpanKind,
+    pub start: i64,
+    pub end: i64,
+    /// Index of the timely worker thread
+    pub worker: usize,
+}
+
+/// Spans recorded by the worker threads of a process
+#[derive(Clone, Default)]
+pub(crate) struct TraceCollector {
+    spans: Arc<Mutex<Vec<Span>>>,
+}
+
+impl TraceCollector {
+    /// Tracer of an operator, passed to the operator in its config (under `tracer`)
+    pub(crate) fn tracer(&self, config: &TracingConfig, operator: &str, worker: usize) -> Tracer {
+        Tracer {
+            config: config.clone(),
+            operator: Arc::from(operator),
+            worker,
+            spans: self.spans.clone(),
+        }
+    }
+
+    pub(crate) fn take(&self) -> Vec<Span> {
+        std::mem::take(&mut *self.spans.lock().unwrap())
+    }
+}
+
+/// Records the spans of the sampled requests at an operator
+#[derive(Clone)]
+pub(crate) struct Tracer {
+    config: TracingConfig,
+    operator: Arc<str>,
+    worker: usize,
+    spans: Arc<Mutex<Vec<Span>>>,
+}
+
+impl Tracer {
+    pub(crate) fn from_config(config: &Option<HashMap<String, Arc<dyn Any + Send + Sync>>>) -> Option<Tracer> {
+        config.as_ref().and_then(|config| config.get("tracer")).and_then(|val| val.downcast_ref::<Tracer>()).cloned()
+    }
+
+    /// Record the relay hop (`net_lat` after emitted upstream at `last_ts`) and the queueing of a request,
+    /// until the operator starts executing it at `op_start_ts`
+    pub(crate) fn trace_arrival(&self, request_id: RequestId, last_ts: i64, net_lat: i64, op_start_ts: i64) {
+        if !self.config.is_sampled(request_id) {
+            return;
+        }
+        let received_ts = std::cmp::min(last_ts + std::cmp::max(net_lat, 0), op_start_ts);
+        if net_lat > 0 {
+            self.record(request_id, SpanKind::Relay, last_ts, received_ts);
+        }
+        if op_start_ts > received_ts {
+            self.record(request_id, SpanKind::Queue, received_ts, op_start_ts);
+        }
+    }
+
+    /// Record the arrival and the execution of a request
+    pub(crate) fn trace_execution(&self, request_id: RequestId, last_ts: i64, net_lat: i64, op_start_ts: i64, op_finish_ts: i64) {
+        self.trace_arrival(request_id, last_ts, net_lat, op_start_ts);
+        self.trace_span(request_id, SpanKind::Execute, op_start_ts, op_finish_ts);
+    }
+
+    /// Record a span of a request (e.g., waiting in the state of a join or an aggregate)
+    pub(crate) fn trace_span(&self, request_id: RequestId, kind: SpanKind, start: i64, end: i64) {
+        if self.config.is_sampled(request_id) {
+            self.record(request_id, kind, start, end);
+        }
+    }
+
+    fn record(&self, request_id: RequestId, kind: SpanKind, start: i64, end: i64) {
+        self.spans.lock().unwrap().push(Span {
+            request_id,
+            operator: self.operator.clone(),
+            kind,
+            start,
+            end: std::cmp::max(start, end),
+            worker: self.worker,
+        });
+    }
+}
+
+fn span_name(span: &Span) -> String {
+    format!("{} {}", span.operator, span.kind.as_str())
+}
+
+/// Chrome trace-event JSON of the spans, each request is a row (thread) of the pipeline (process)
+pub fn to_chrome_trace(spans: &[Span], pipeline_index: usize) -> Value {
+    let mut request_ids = spans.iter().map(|span| span.request_id).collect::<Vec<_>>();
+    request_ids.sort_unstable();
+    request_ids.dedup();
+
+    let mut events = Vec::with_capacity(spans.len() + request_ids.len() + 1);
+    events.push(json!({
+        "name": "process_name", "ph": "M", "pid": pipeline_index,
+        "args": {"name": format!("pipeline {}", pipeline_index)}
+    }));
+    for (row, request_id) in request_ids.iter().enumerate() {
+        events.push(json!({
+            "name": "thread_name", "ph": "M", "pid": pipeline_index, "tid": row,
+            "args": {"name": format!("request {}", request_id)}
+        }));
+    }
+    for span in spans {
+        let row = request_ids.binary_search(&span.request_id).unwrap();
+        events.push(json!({
+            "name": span_name(span),
+            "cat": span.kind.as_str(),
+            "ph": "X",
+            // in microseconds
+            "ts": span.start as f64 / 1e3,
+            "dur": (span.end - span.start) as f64 / 1e3,
+            "pid": pipeline_index,
+            "tid": row,
+            "args": {
+                "request_id": span.request_id.to_string(),
+                "operator": &*span.operator,
+                "worker": span.worker,
+            }
+        }));
+    }
+    json!({"traceEvents": events, "displayTimeUnit": "ms"})
+}
+
+fn string_attribute(key: &str, value: String) -> Value {
+    json!({"key": key, "value": {"stringValue": value}})
+}
+
+fn int_attribute(key: &str, value: usize) -> Value {
+    // 64-bit integers are strings in OTLP-JSON
+    json!({"key": key, "value": {"intValue": value.to_string()}})
+}
+
+/// OTLP-JSON (`ExportTraceServiceRequest`) of the spans of a worker process,
+/// the trace id is derived from the request id so the spans of a request are grouped across the workers
+pub fn to_otlp_json(spans: &[Span], pipeline_index: usize, worker_index: usize) -> Value {
+    let otlp_spans = spans.iter().map(|span| {
+        let span_id = splitmix64(
+            span.request_id ^ splitmix64(span.start as u64) ^ splitmix64(span_name(span).bytes().fold(span.worker as u64, |h, b| h.wrapping_mul(31).wrapping_add(b as u64)))
+        );
+        json!({
+            // trace ids must not be all zeros, the request ids start from 0
+            "traceId": format!("{:016x}{:016x}", splitmix64(span.request_id), span.request_id),
+            // span ids must not be all zeros
+            "spanId": format!("{:016x}", std::cmp::max(span_id, 1)),
+            "name": span_name(span),
+            // SPAN_KIND_INTERNAL
+            "kind": 1,
+            "startTimeUnixNano": span.start.to_string(),
+            "endTimeUnixNano": span.end.to_string(),
+            "attributes": [
+                string_attribute("mlflow.request_id", span.request_id.to_string()),
+                string_attribute("mlflow.operator", span.operator.to_string()),
+                string_attribute("mlflow.span", String::from(span.kind.as_str())),
+                int_attribute("mlflow.worker_thread", span.worker),
+            ]
+        })
+    }).collect::<Vec<_>>();
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    string_attribute("service.name", String::from("mlflow")),
+                    int_attribute("mlflow.pipeline", pipeline_index),
+                    int_attribute("mlflow.worker", worker_index),
+                ]
+            },
+            "scopeSpans": [{
+                "scope": {"name": "mlflow"},
+                "spans": otlp_spans
+            }]
+        }]
+    })
+}
+
+/// Write the spans of a worker process in the configured format, returns the path of the trace file
+pub(crate) fn write_traces(spans: &[Span], config: &TracingConfig, pipeline_index: usize, worker_index: usize) -> io::Result<PathBuf> {
+    let path = config.trace_path(pipeline_index, worker_index);
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let trace = match config.format {
+        TraceFormat::Chrome => to_chrome_trace(spans, pipeline_index),
+        TraceFormat::Otlp => to_otlp_json(spans, pipeline_index, worker_index),
+    };
+    let writer = BufWriter::new(File::create(&path)?);
+    serde_json::to_writer(writer, &trace).map_err(io::Error::from)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SpanKind, TraceCollector, TraceFormat, TracingConfig, splitmix64, to_chrome_trace, to_otlp_json};
+
+    fn config(sample_rate: f64) -> TracingConfig {
+        TracingConfig { sample_rate, format: TraceFormat::Chrome, dir: None }
+    }
+
+    #[test]
+    fn sampling_is_deterministic() {
+        let half = config(0.5);
+        let sampled = (0..10000).filter(|id| half.is_sampled(*id)).count();
+        assert!(sampled > 4500 && sampled < 5500, "sampled {} of 10000", sampled);
+        // lower rates sample a subset of the requests
+        let tenth = config(0.1);
+        assert!((0..10000).filter(|id| tenth.is_sampled(*id)).all(|id| half.is_sampled(id)));
+        assert!((0..100).all(|id| config(1.0).is_sampled(id)));
+    }
+
+    #[test]
+    fn export_spans() {
+        let collector = TraceCollector::default();
+        let tracer = collector.tracer(&config(1.0), "Map", 0);
+        // emitted at 1000, 200ns through the relay nodes, queued for 300ns, executed for 500ns
+        tracer.trace_execution(7, 1000, 200, 1500, 2000);
+        tracer.trace_span(9, SpanKind::Wait, 1000, 3000);
+        let spans = collector.take();
+        let kinds = spans.iter().map(|span| (span.request_id, span.kind.as_str(), span.start, span.end)).collect::<Vec<_>>();
+        assert_eq!(kinds, vec![(7, "relay", 1000, 1200), (7, "queue", 1200, 1500), (7, "execute", 1500, 2000), (9, "wait", 1000, 3000)]);
+        assert!(collector.take().is_empty());
+
+        let chrome = to_chrome_trace(&spans, 1);
+        let events = chrome["traceEvents"].as_array().unwrap();
+        // process name, 2 requests and 4 spans
+        assert_eq!(events.len(), 7);
+        let execute = events.iter().find(|event| event["name"] == "Map execute").unwrap();
+        assert_eq!(execute["ph"], "X");
+        assert_eq!(execute["ts"].as_f64(), Some(1.5));
+        assert_eq!(execute["dur"].as_f64(), Some(0.5));
+        assert_eq!(execute["tid"], 0);
+        assert_eq!(execute["args"]["request_id"], "7");
+
+        let otlp = to_otlp_json(&spans, 1, 0);
+        let otlp_spans = otlp["resourceSpans"][0]["scopeSpans"][0]["spans"].as_array().unwrap();
+        assert_eq!(otlp_spans.len(), 4);
+        assert_eq!(otlp_spans[0]["traceId"], format!("{:016x}{:016x}", splitmix64(7), 7));
+        assert_eq!(otlp_spans[3]["name"], "Map wait");
+        assert_eq!(otlp_spans[3]["endTimeUnixNano"], "3000");
+        assert_ne!(otlp_spans[0]["spanId"], otlp_spans[1]["spanId"]);
+    }
+}
@@ -1,8 +1,140 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::iter::FromIterator;
 
 use crate::config::ExecutionConfigGUID; 
 
+/// Errors when inferring the pipeline inputs / outputs from the graph connections
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PipelineIoError {
+    /// Pipeline inputs / outputs are not fully specified and graph connections are not captured
+    MissingGraphConnections,
+    /// An operator assigned to a pipeline does not exist in the graph
+    UnknownOperator { op: String, pipeline_index: usize },
+    /// An operator is assigned to two pipelines
+    AssignedTwice { op: String, first_pipeline: usize, second_pipeline: usize },
+    /// Operators of the graph are not assigned to any pipeline
+    Unassigned(Vec<String>),
+    /// The given input (or output) pipelines of a pipeline do not match the graph connections
+    MismatchedPipelines { pipeline_index: usize, inputs: bool, given: Vec<usize>, inferred: Vec<usize> },
+}
+
+impl fmt::Display for PipelineIoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PipelineIoError::MissingGraphConnections => write!(f, "pipeline inputs / outputs are not fully specified and graph connections are not captured"),
+            PipelineIoError::UnknownOperator { op, pipeline_index } => write!(f, "operator {} assigned to pipeline {} does not exist in the graph", op, pipeline_index),
+            PipelineIoError::AssignedTwice { op, first_pipeline, second_pipeline } => write!(f, "operator {} is assigned to both pipeline {} and pipeline {}", op, first_pipeline, second_pipeline),
+            PipelineIoError::Unassigned(ops) => write!(f, "operators {} are not assigned to any pipeline", ops.join(", ")),
+            PipelineIoError::MismatchedPipelines { pipeline_index, inputs, given, inferred } => write!(
+                f, "{} pipelines {:?} of pipeline {} do not match the graph connections (inferred {:?})",
+                if *inputs { "input" } else { "output" }, given, pipeline_index, inferred
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PipelineIoError {}
+
+fn op_display_name(config: &ExecutionConfigGUID, op: usize) -> String {
+    let name = config.op_name_guid_mapping.as_ref().and_then(
+        |mapping| mapping.iter().find(|(_, gid)| **gid == op).map(|(name, _)| name.clone())
+    );
+    match name {
+        Some(name) => format!("{} (#{})", name, op),
+        None => format!("#{}", op)
+    }
+}
+
+/// Map each operator in the graph to the pipeline it is assigned to,
+/// fails if an operator is assigned to no pipeline or to more than one pipeline
+pub fn infer_operator_pipeline_mapping(config: &ExecutionConfigGUID) -> Result<HashMap<usize, usize>, PipelineIoError> {
+    let graph_connections = config.graph_connections.as_ref().ok_or(PipelineIoError::MissingGraphConnections)?;
+    let mut mapping = HashMap::new();
+    let mut pipeline_indices = config.pipeline_configs.keys().copied().collect::<Vec<_>>();
+    pipeline_indices.sort();
+    for pipeline_idx in pipeline_indices {
+        for op in config.pipeline_configs.get(&pipeline_idx).unwrap().assigned_ops.iter() {
+            if !graph_connections.incoming_edges.contains_key(op) {
+                return Err(PipelineIoError::UnknownOperator { op: op_display_name(config, *op), pipeline_index: pipeline_idx });
+            }
+            if let Some(prev_pipeline_idx) = mapping.insert(*op, pipeline_idx) {
+                if prev_pipeline_idx != pipeline_idx {
+                    return Err(PipelineIoError::AssignedTwice {
+                        op: op_display_name(config, *op),
+                        first_pipeline: prev_pipeline_idx,
+                        second_pipeline: pipeline_idx
+                    });
+                }
+            }
+        }
+    }
+    let mut unassigned = graph_connections.incoming_edges.keys().filter(|op| !mapping.contains_key(*op)).copied().collect::<Vec<_>>();
+    unassigned.sort();
+    if !unassigned.is_empty() {
+        let names = unassigned.into_iter().map(|op| op_display_name(config, op)).collect::<Vec<_>>();
+        return Err(PipelineIoError::Unassigned(names));
+    }
+    Ok(mapping)
+}
+
+/// Fill in the input and output pipelines of every pipeline from the graph connections,
+/// pipelines with explicitly given input (output) pipelines are checked against the inferred ones
+pub fn infer_pipeline_connections(config: &mut ExecutionConfigGUID) -> Result<(), PipelineIoError> {
+    let op_pipeline_mapping = infer_operator_pipeline_mapping(config)?;
+    let graph_connections = config.graph_connections.as_ref().unwrap();
+
+    let mut input_pipelines: HashMap<usize, HashSet<usize>> = HashMap::new();
+    let mut output_pipelines: HashMap<usize, HashSet<usize>> = HashMap::new();
+    for (op, prev_ops) in graph_connections.incoming_edges.iter() {
+        let pipeline_idx = *op_pipeline_mapping.get(op).unwrap();
+        for prev_op in prev_ops.iter() {
+            let prev_pipeline_idx = *op_pipeline_mapping.get(prev_op).expect("input operator does not exist in the graph");
+            if prev_pipeline_idx != pipeline_idx {
+                input_pipelines.entry(pipeline_idx).or_default().insert(prev_pipeline_idx);
+                output_pipelines.entry(prev_pipeline_idx).or_default().insert(pipeline_idx);
+            }
+        }
+    }
+
+    for (pipeline_idx, pipeline_config) in config.pipeline_configs.iter_mut() {
+        let mut inferred_inputs = input_pipelines.remove(pipeline_idx).map(Vec::from_iter).unwrap_or_default();
+        inferred_inputs.sort();
+        let mut inferred_outputs = output_pipelines.remove(pipeline_idx).map(Vec::from_iter).unwrap_or_default();
+        inferred_outputs.sort();
+
+        if pipeline_config.input_pipelines.is_empty() {
+            pipeline_config.input_pipelines = inferred_inputs;
+        }
+        else {
+            let mut given = pipeline_config.input_pipelines.clone();
+            given.sort();
+            if given != inferred_inputs {
+                return Err(PipelineIoError::MismatchedPipelines { pipeline_index: *pipeline_idx, inputs: true, given, inferred: inferred_inputs });
+            }
+        }
+        if pipeline_config.output_pipelines.is_empty() {
+            pipeline_config.output_pipelines = inferred_outputs;
+        }
+        else {
+            let mut given = pipeline_config.output_pipelines.clone();
+            given.sort();
+            if given != inferred_outputs {
+                return Err(PipelineIoError::MismatchedPipelines { pipeline_index: *pipeline_idx, inputs: false, given, inferred: inferred_outputs });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Infer the input / output pipelines and the required input / output operators of all pipelines
+/// from the graph connections, only pipelines missing their input or output operators are inferred
+pub fn infer_pipeline_io_all(config: &mut ExecutionConfigGUID) -> Result<(), PipelineIoError> {
+    infer_pipeline_connections(config)?;
+    infer_pipeline_io_from_config_all(config);
+    Ok(())
+}
+
 pub fn infer_pipeline_inputs(config: &mut ExecutionConfigGUID, pipeline_idx: usize) {
     let mut required_inputs = Vec::new();
     let graph_connections = config.graph_connections.as_ref().expect("graph connections should be provided");
@@ -16,6 +148,8 @@ pub fn infer_pipeline_inputs(config: &mut ExecutionConfigGUID, pipeline_idx: usi
         }
     }
     required_inputs.sort();
+    // an input may be consumed by several assigned operators
+    required_inputs.dedup();
     let pipeline_config = config.pipeline_configs.get_mut(&pipeline_idx).unwrap();
     pipeline_config.required_input_ops = Some(required_inputs);
 }
@@ -59,6 +193,7 @@ pub fn infer_pipeline_io_from_config(config: &mut ExecutionConfigGUID, pipeline_
     }
 
     required_inputs.sort();
+    required_inputs.dedup();
     let mut registered_outputs = Vec::from_iter(registered_outputs);
     registered_outputs.sort();
     
@@ -87,6 +222,7 @@ mod tests {
     use crate::PipelineConfigGUID;
 
     use super::{infer_pipeline_io_from_config, infer_pipeline_io_from_config_all};
+    use super::{infer_pipeline_io_all, infer_operator_pipeline_mapping, PipelineIoError};
     
     #[test]
     fn infer_all() {
@@ -104,6 +240,9 @@ mod tests {
             operator_configs: HashMap::new(),
             request_rates: HashMap::new(),
             source_operators: HashSet::new(),
+            flow_control: None,
+            threads: 1,
+            metrics_endpoint: None,
         };
     
         let pipeline_1_config = PipelineConfigGUID {
@@ -120,6 +259,9 @@ mod tests {
             operator_configs: HashMap::new(),
             request_rates: HashMap::new(),
             source_operators: HashSet::new(),
+            flow_control: None,
+            threads: 1,
+            metrics_endpoint: None,
         };
     
         let pipeline_2_config = PipelineConfigGUID {
@@ -136,6 +278,9 @@ mod tests {
             operator_configs: HashMap::new(),
             request_rates: HashMap::new(),
             source_operators: HashSet::new(),
+            flow_control: None,
+            threads: 1,
+            metrics_endpoint: None,
         };
 
         let config = HashMap::from_iter([
@@ -169,7 +314,9 @@ mod tests {
             pipeline_configs: config,
             op_name_guid_mapping: None, 
             graph_connections: Some(connections),
-            message_buffer_size: None
+            message_buffer_size: None,
+            connection_config: None,
+            tracing: None
         };
 
         infer_pipeline_io_from_config_all(&mut config);
@@ -179,6 +326,131 @@ mod tests {
         assert_eq!(config.pipeline_configs.get(&2).unwrap().required_input_ops.as_ref().unwrap()[1], 3);
     }
 
+    fn test_pipeline_config(pipeline_index: usize, assigned_ops: Vec<usize>) -> PipelineConfigGUID {
+        PipelineConfigGUID {
+            pipeline_index,
+            assigned_ops,
+            required_input_ops: None,
+            output_ops: None,
+            worker_addrs: vec![],
+            relay_addrs: vec![],
+            relay_load_balance_weights: None,
+            input_pipelines: vec![],
+            output_pipelines: vec![],
+            builder_configs: HashMap::new(),
+            operator_configs: HashMap::new(),
+            request_rates: HashMap::new(),
+            source_operators: HashSet::new(),
+            flow_control: None,
+            threads: 1,
+            metrics_endpoint: None,
+        }
+    }
+
+    fn test_graph_connections() -> GraphConnections {
+        GraphConnections {
+            outgoing_edges: HashMap::from_iter([
+                (0, vec![2]),
+                (1, vec![3]),
+                (2, vec![4]),
+                (3, vec![4]),
+                (4, vec![5]),
+                (5, vec![6]),
+                (6, vec![])
+            ]),
+            incoming_edges: HashMap::from_iter([
+                (0, vec![]),
+                (1, vec![]),
+                (2, vec![0]),
+                (3, vec![1]),
+                (4, vec![2, 3]),
+                (5, vec![4]),
+                (6, vec![5])
+            ])
+        }
+    }
+
+    #[test]
+    fn infer_connections() {
+        let mut config = ExecutionConfigGUID {
+            pipeline_configs: HashMap::from_iter([
+                (0, test_pipeline_config(0, vec![1, 3])),
+                (1, test_pipeline_config(1, vec![0, 2])),
+                (2, test_pipeline_config(2, vec![4, 5, 6]))
+            ]),
+            op_name_guid_mapping: None,
+            graph_connections: Some(test_graph_connections()),
+            message_buffer_size: None,
+            connection_config: None,
+            tracing: None,
+        };
+        infer_pipeline_io_all(&mut config).unwrap();
+        assert_eq!(config.pipeline_configs.get(&0).unwrap().output_pipelines, vec![2]);
+        assert_eq!(config.pipeline_configs.get(&1).unwrap().output_pipelines, vec![2]);
+        assert_eq!(config.pipeline_configs.get(&2).unwrap().input_pipelines, vec![0, 1]);
+        assert_eq!(config.pipeline_configs.get(&2).unwrap().required_input_ops.as_ref().unwrap(), &vec![2, 3]);
+        assert_eq!(config.pipeline_configs.get(&1).unwrap().output_ops.as_ref().unwrap(), &vec![2]);
+    }
+
+    #[test]
+    fn infer_shared_input() {
+        // operators 2 and 3 in pipeline 1 both consume operator 1 (e.g., an inspect next to a sink)
+        let connections = GraphConnections {
+            outgoing_edges: HashMap::from_iter([(0, vec![1]), (1, vec![2, 3]), (2, vec![]), (3, vec![])]),
+            incoming_edges: HashMap::from_iter([(0, vec![]), (1, vec![0]), (2, vec![1]), (3, vec![1])])
+        };
+        let mut config = ExecutionConfigGUID {
+            pipeline_configs: HashMap::from_iter([
+                (0, test_pipeline_config(0, vec![0, 1])),
+                (1, test_pipeline_config(1, vec![2, 3]))
+            ]),
+            op_name_guid_mapping: None,
+            graph_connections: Some(connections),
+            message_buffer_size: None,
+            connection_config: None,
+            tracing: None,
+        };
+        infer_pipeline_io_all(&mut config).unwrap();
+        assert_eq!(config.pipeline_configs.get(&1).unwrap().required_input_ops.as_ref().unwrap(), &vec![1]);
+        assert_eq!(config.pipeline_configs.get(&0).unwrap().output_ops.as_ref().unwrap(), &vec![1]);
+    }
+
+    #[test]
+    fn unassigned_operator() {
+        let config = ExecutionConfigGUID {
+            pipeline_configs: HashMap::from_iter([
+                (0, test_pipeline_config(0, vec![0, 1, 2, 3])),
+                (1, test_pipeline_config(1, vec![4, 5]))
+            ]),
+            op_name_guid_mapping: None,
+            graph_connections: Some(test_graph_connections()),
+            message_buffer_size: None,
+            connection_config: None,
+            tracing: None,
+        };
+        let err = infer_operator_pipeline_mapping(&config).unwrap_err();
+        assert_eq!(err, PipelineIoError::Unassigned(vec![String::from("#6")]));
+        assert_eq!(err.to_string(), "operators #6 are not assigned to any pipeline");
+    }
+
+    #[test]
+    fn operator_assigned_twice() {
+        let config = ExecutionConfigGUID {
+            pipeline_configs: HashMap::from_iter([
+                (0, test_pipeline_config(0, vec![0, 1, 2, 3])),
+                (1, test_pipeline_config(1, vec![3, 4, 5, 6]))
+            ]),
+            op_name_guid_mapping: None,
+            graph_connections: Some(test_graph_connections()),
+            message_buffer_size: None,
+            connection_config: None,
+            tracing: None,
+        };
+        let err = infer_operator_pipeline_mapping(&config).unwrap_err();
+        assert_eq!(err, PipelineIoError::AssignedTwice { op: String::from("#3"), first_pipeline: 0, second_pipeline: 1 });
+        assert_eq!(err.to_string(), "operator #3 is assigned to both pipeline 0 and pipeline 1");
+    }
+
     #[test]
     fn simple_inference() {
         let pipeline_0_config = PipelineConfigGUID {
@@ -195,6 +467,9 @@ mod tests {
             operator_configs: HashMap::new(),
             request_rates: HashMap::new(),
             source_operators: HashSet::new(),
+            flow_control: None,
+            threads: 1,
+            metrics_endpoint: None,
         };
     
         let pipeline_1_config = PipelineConfigGUID {
@@ -211,6 +486,9 @@ mod tests {
             operator_configs: HashMap::new(),
             request_rates: HashMap::new(),
             source_operators: HashSet::new(),
+            flow_control: None,
+            threads: 1,
+            metrics_endpoint: None,
         };
     
         let pipeline_2_config = PipelineConfigGUID {
@@ -227,6 +505,9 @@ mod tests {
             operator_configs: HashMap::new(),
             request_rates: HashMap::new(),
             source_operators: HashSet::new(),
+            flow_control: None,
+            threads: 1,
+            metrics_endpoint: None,
         };
 
 
@@ -262,6 +543,8 @@ mod tests {
             op_name_guid_mapping: None, 
             graph_connections: Some(connections),
             message_buffer_size: None,
+            connection_config: None,
+            tracing: None,
         };
         
         infer_pipeline_io_from_config(&mut config, 2);
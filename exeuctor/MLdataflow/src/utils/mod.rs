@@ -1,3 +1,4 @@
 pub mod infer_pipeline_io;
 
-pub use infer_pipeline_io::{infer_pipeline_io_from_config, infer_pipeline_io_from_config_all};
\ No newline at end of file
+pub use infer_pipeline_io::{infer_pipeline_io_from_config, infer_pipeline_io_from_config_all};
+pub use infer_pipeline_io::{infer_pipeline_io_all, infer_pipeline_connections, infer_operator_pipeline_mapping};pub use infer_pipeline_io::PipelineIoError;
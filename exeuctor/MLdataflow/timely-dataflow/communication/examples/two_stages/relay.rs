@@ -4,7 +4,7 @@ use std::ops::Deref;
 
 use structopt::StructOpt;
 
-use timely_communication::{Push, RelayNodeConfig};
+use timely_communication::{ConnectionConfig, Push, RelayNodeConfig};
 use timely_communication::allocator::relay::{InputRelayAllocate, OutputRelayAllocate};
 
 #[derive(StructOpt, Debug, Clone)]
@@ -25,6 +25,7 @@ fn run_pipeline_1_relay() {
         my_index: 0,
         num_relay_nodes_peers: 1,
         report: true,
+        connection: ConnectionConfig::default(),
         relay_log_sender: Box::new(|_| None),
         timely_log_sender: Box::new(|_| None),
     };
@@ -64,6 +65,7 @@ fn run_pipeline_2_relay() {
         my_index: 0,
         num_relay_nodes_peers: 1,
         report: true,
+        connection: ConnectionConfig::default(),
         relay_log_sender: Box::new(|_| None),
         timely_log_sender: Box::new(|_| None),
     };
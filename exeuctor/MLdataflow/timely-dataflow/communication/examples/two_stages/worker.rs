@@ -4,7 +4,7 @@ use std::ops::Deref;
 
 use structopt::StructOpt;
 
-use timely_communication::{Message, RelayConnectAllocate, WorkerWithRelayConfig};
+use timely_communication::{ConnectionConfig, Message, RelayConnectAllocate, WorkerWithRelayConfig};
 
 #[derive(StructOpt, Debug, Clone)]
 #[structopt(about = "Simple two stages relay communication - worker")]
@@ -19,6 +19,7 @@ fn run_pipeline_1_worker() {
         threads: 1,
         relay_addresses: vec!["127.0.0.1:6001".to_string()],
         report: true,
+        connection: ConnectionConfig::default(),
         relay_log_fn: Box::new(|_| None),
     };
 
@@ -45,6 +46,7 @@ fn run_pipeline_2_worker() {
         threads: 1,
         relay_addresses: vec!["127.0.0.1:6002".to_string()],
         report: true,
+        connection: ConnectionConfig::default(),
         relay_log_fn: Box::new(|_| None),
     };
 
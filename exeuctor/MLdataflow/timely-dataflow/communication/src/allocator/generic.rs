@@ -218,6 +218,13 @@ impl RelayConnectAllocate for GenericToRelay {
         }
     }
 
+    fn relay_backpressured(&self) -> bool {
+        match self {
+            GenericToRelay::Process(p) => p.relay_backpressured(),
+            GenericToRelay::ZeroCopy(z) => z.relay_backpressured(),
+        }
+    }
+
     fn await_relay_events(&self, _duration: Option<std::time::Duration>) {
         match self {
             GenericToRelay::Process(p) => p.await_relay_events(_duration),
@@ -130,6 +130,9 @@ pub trait RelayConnectAllocate: Allocate {
     fn relay_events(&self) -> &Rc<RefCell<VecDeque<(usize, Event)>>>;
     /// Whether all inputs (data and frontier updates) have been received input pipelines
     fn relay_input_pipelines_complete(&self) -> bool;
+    /// Whether the messages to the relay nodes pile up (with flow control),
+    /// the input sources should be held back
+    fn relay_backpressured(&self) -> bool { false }
     /// Awaits communication events. from relay
     fn await_relay_events(&self, _duration: Option<Duration>) { }
 }
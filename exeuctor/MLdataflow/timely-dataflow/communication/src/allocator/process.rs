@@ -15,6 +15,7 @@ use crate::allocator::{Allocate, AllocateBuilder, Event, Thread};
 use crate::{Push, Pull, Message, MessageLatency};
 use crate::allocator::zero_copy::bytes_exchange::{MergeQueue, SendEndpoint};
 use crate::buzzer::Buzzer;
+use crate::allocator::relay::TimelyWorkerFlowControl;
 
 /// An allocator for inter-thread, intra-process communication
 pub struct ProcessBuilder {
@@ -38,6 +39,8 @@ pub struct ProcessBuilder {
     relay_futures: Option<Vec<Receiver<MergeQueue>>>,
     // to receive messages from relay node
     relay_promises: Option<Vec<Sender<MergeQueue>>>,
+    // flow control of the connections to relay nodes, shared with the network threads
+    relay_flow_control: Option<Arc<TimelyWorkerFlowControl>>,
 }
 
 impl AllocateBuilder for ProcessBuilder {
@@ -93,6 +96,7 @@ impl AllocateBuilder for ProcessBuilder {
                 relay_staged: Some(Vec::new()),
                 relay_canaries: Some(Rc::new(RefCell::new(Vec::new()))),
                 relay_dropped_channels: Some(HashSet::new()),
+                relay_flow_control: self.relay_flow_control,
             }
         }
         else {
@@ -112,6 +116,7 @@ impl AllocateBuilder for ProcessBuilder {
                 relay_staged: None,
                 relay_canaries: None,
                 relay_dropped_channels: None,
+                relay_flow_control: None,
             }
         }
     }
@@ -142,6 +147,7 @@ pub struct Process {
     pub(crate) relay_staged: Option<Vec<Bytes>>,
     pub(crate) relay_canaries: Option<Rc<RefCell<Vec<usize>>>>,
     pub(crate) relay_dropped_channels: Option<HashSet<usize>>,
+    pub(crate) relay_flow_control: Option<Arc<TimelyWorkerFlowControl>>,
 }
 
 impl Process {
@@ -188,7 +194,8 @@ impl Process {
                     counters_recv: recv,
                     relay_peers: None,
                     relay_promises: None,
-                    relay_futures: None
+                    relay_futures: None,
+                    relay_flow_control: None,
                 }
             })
             .collect()
@@ -196,7 +203,8 @@ impl Process {
     /// Allocate a list of connected intra-process allocators, with communication to relay nodes
     pub fn new_vector_with_relay_connection(
         peers: usize,
-        num_relay_nodes: usize
+        num_relay_nodes: usize,
+        relay_flow_control: Option<Arc<TimelyWorkerFlowControl>>
     ) -> (
         Vec<ProcessBuilder>,
         Vec<Vec<Sender<MergeQueue>>>,
@@ -236,7 +244,8 @@ impl Process {
                     counters_recv: recv,
                     relay_peers: Some(num_relay_nodes),
                     relay_promises: Some(promises),
-                    relay_futures: Some(futures)
+                    relay_futures: Some(futures),
+                    relay_flow_control: relay_flow_control.clone(),
                 }
             })
             .collect();
@@ -0,0 +1,269 @@
+//! Connect retries, startup timeout and readiness handshake
+//! for relay<->relay and relay<->worker connections
+use std::io;
+use std::io::{Read, Result};
+use std::net::{TcpListener, TcpStream};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use abomonation::{encode, decode};
+
+use crate::allocator::relay::flow_control::FlowControlConfig;
+
+// magic number sent back by the accepting side
+// once the handshake of the connecting side is verified
+const READY_HANDSHAKE_MAGIC: u64 = 0x3d5b41e7c2a09f86;
+
+/// Retry and timeout policy to establish the connections of relay nodes and timely workers,
+/// and the delivery guarantee and flow control once they are established
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionConfig {
+    /// Maximum number of attempts to connect to a remote address,
+    /// retry until the startup timeout if None
+    pub max_connect_attempts: Option<usize>,
+    /// Delay before the first retry, doubled after each failed attempt
+    pub initial_backoff: Duration,
+    /// Maximum delay between two attempts
+    pub max_backoff: Duration,
+    /// Maximum time to establish all connections, wait indefinitely if None
+    pub startup_timeout: Option<Duration>,
+    /// Retain the messages sent to the relay nodes of the output pipelines until they are acknowledged,
+    /// replay them to a surviving relay node if the connection is lost (at-least-once delivery).
+    /// Timely workers and relay nodes also survive the loss of a relay node they are connected to
+    pub at_least_once: bool,
+    /// Pause reading from upstream while the messages queued to be sent exceed the high watermark,
+    /// no flow control if None
+    pub flow_control: Option<FlowControlConfig>,
+}
+
+impl Default for ConnectionConfig {
+    fn default() -> Self {
+        ConnectionConfig {
+            max_connect_attempts: None,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            startup_timeout: None,
+            at_least_once: false,
+            flow_control: None,
+        }
+    }
+}
+
+impl ConnectionConfig {
+    /// Deadline to establish all connections, if the startup begins now
+    pub fn deadline(&self) -> Option<Instant> {
+        self.startup_timeout.map(|timeout| Instant::now() + timeout)
+    }
+}
+
+/// How a connection is established
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionReport {
+    /// Number of attempts to connect, 1 for accepted connections
+    pub attempts: usize,
+    /// Time to establish the connection and complete the readiness handshake
+    pub elapsed: Duration,
+}
+
+fn timed_out(during: String) -> io::Error {
+    io::Error::new(io::ErrorKind::TimedOut, format!("startup timeout reached {}", during))
+}
+
+/// Remaining time before the deadline, at least 1ms since zero timeouts are rejected by sockets
+fn remaining(deadline: Option<Instant>) -> Option<Duration> {
+    deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()).max(Duration::from_millis(1)))
+}
+
+/// Connect to `addr` with retries and exponential backoff,
+/// send the handshake and wait for the remote side to acknowledge that the connection is ready.
+/// `local` and `remote` describe both ends of the connection in the reports
+pub(crate) fn connect_with_retry<F>(
+    addr: &str,
+    config: &ConnectionConfig,
+    deadline: Option<Instant>,
+    local: &str,
+    remote: &str,
+    handshake: F,
+) -> Result<(TcpStream, ConnectionReport)>
+where
+    F: Fn(&mut TcpStream) -> Result<()>
+{
+    let start = Instant::now();
+    let mut backoff = config.initial_backoff;
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match TcpStream::connect(addr) {
+            Ok(mut stream) => {
+                stream.set_nodelay(true)?;
+                handshake(&mut stream)?;
+                await_ready(&mut stream, deadline).map_err(
+                    |err| io::Error::new(err.kind(), format!("{}: handshake with {} at {} failed: {}", local, remote, addr, err))
+                )?;
+                return Ok((stream, ConnectionReport { attempts, elapsed: start.elapsed() }));
+            },
+            Err(error) => {
+                if config.max_connect_attempts.map_or(false, |max_attempts| attempts >= max_attempts) {
+                    return Err(io::Error::new(
+                        error.kind(),
+                        format!("{}: failed to connect to {} at {} after {} attempts: {}", local, remote, addr, attempts, error)
+                    ));
+                }
+                let mut delay = backoff;
+                if let Some(deadline) = deadline {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Err(timed_out(format!("while {} connecting to {} at {}: {}", local, remote, addr, error)));
+                    }
+                    delay = delay.min(deadline - now);
+                }
+                println!("{}:\terror connecting to {}: {}; retrying in {:?}", local, remote, error, delay);
+                sleep(delay);
+                backoff = (backoff * 2).min(config.max_backoff);
+            },
+        }
+    }
+}
+
+/// Accept a connection before the deadline,
+/// the returned stream reads with a timeout until the deadline to receive the handshake
+pub(crate) fn accept_before(listener: &TcpListener, deadline: Option<Instant>, local: &str) -> Result<TcpStream> {
+    let stream = match deadline {
+        None => listener.accept()?.0,
+        Some(deadline) => {
+            listener.set_nonblocking(true)?;
+            let accepted = loop {
+                match listener.accept() {
+                    Ok((stream, _)) => break Ok(stream),
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                        if Instant::now() >= deadline {
+                            break Err(timed_out(format!("while {} awaiting connections", local)));
+                        }
+                        sleep(Duration::from_millis(10));
+                    },
+                    Err(err) => break Err(err),
+                }
+            };
+            listener.set_nonblocking(false)?;
+            let stream = accepted?;
+            stream.set_nonblocking(false)?;
+            stream
+        }
+    };
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(remaining(deadline))?;
+    Ok(stream)
+}
+
+/// Acknowledge the verified handshake of an accepted connection
+/// so that the connecting side knows the connection is ready
+pub(crate) fn acknowledge_ready(stream: &mut TcpStream) -> Result<()> {
+    stream.set_read_timeout(None)?;
+    unsafe { encode(&READY_HANDSHAKE_MAGIC, stream) }
+}
+
+fn await_ready(stream: &mut TcpStream, deadline: Option<Instant>) -> Result<()> {
+    stream.set_read_timeout(remaining(deadline))?;
+    let mut buffer = [0u8; 8];
+    stream.read_exact(&mut buffer).map_err(|err| match err.kind() {
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => timed_out(String::from("while awaiting the acknowledgement")),
+        _ => err,
+    })?;
+    stream.set_read_timeout(None)?;
+    match unsafe { decode::<u64>(&mut buffer) } {
+        Some((&READY_HANDSHAKE_MAGIC, _)) => Ok(()),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidData, "received incorrect handshake acknowledgement")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::net::TcpListener;
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use abomonation::encode;
+
+    use super::{accept_before, acknowledge_ready, connect_with_retry, ConnectionConfig};
+
+    // an address nobody listens on
+    fn closed_addr() -> String {
+        TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string()
+    }
+
+    fn config(max_connect_attempts: Option<usize>) -> ConnectionConfig {
+        ConnectionConfig {
+            max_connect_attempts,
+            initial_backoff: Duration::from_millis(20),
+            max_backoff: Duration::from_millis(30),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn connect_ready_after_acknowledgement() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let deadline = Some(Instant::now() + Duration::from_secs(5));
+        let accepting = thread::spawn(move || {
+            let mut stream = accept_before(&listener, deadline, "acceptor").unwrap();
+            acknowledge_ready(&mut stream).unwrap();
+            stream
+        });
+        let (_stream, report) = connect_with_retry(&addr, &config(Some(1)), deadline, "connector", "acceptor", |_| Ok(())).unwrap();
+        assert_eq!(report.attempts, 1);
+        accepting.join().unwrap();
+    }
+
+    #[test]
+    fn connect_retry_limit_exhausted_with_backoff() {
+        let start = Instant::now();
+        let err = connect_with_retry(&closed_addr(), &config(Some(3)), None, "connector", "acceptor", |_| Ok(())).unwrap_err();
+        assert!(err.to_string().contains("after 3 attempts"), "{}", err);
+        // backoff of 20ms then 40ms capped at 30ms between the three attempts
+        assert!(start.elapsed() >= Duration::from_millis(50), "retried after {:?}", start.elapsed());
+    }
+
+    #[test]
+    fn connect_retry_until_startup_timeout() {
+        let start = Instant::now();
+        let deadline = Some(start + Duration::from_millis(100));
+        let err = connect_with_retry(&closed_addr(), &config(None), deadline, "connector", "acceptor", |_| Ok(())).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert!(start.elapsed() < Duration::from_secs(2), "retried after the deadline for {:?}", start.elapsed());
+    }
+
+    #[test]
+    fn accept_until_startup_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let start = Instant::now();
+        let err = accept_before(&listener, Some(start + Duration::from_millis(100)), "acceptor").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn await_ready_handshake_mismatch() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let deadline = Some(Instant::now() + Duration::from_secs(5));
+        let accepting = thread::spawn(move || {
+            let mut stream = accept_before(&listener, deadline, "acceptor").unwrap();
+            unsafe { encode(&0u64, &mut stream) }.unwrap();
+            stream
+        });
+        let err = connect_with_retry(&addr, &config(Some(1)), deadline, "connector", "acceptor", |_| Ok(())).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        accepting.join().unwrap();
+    }
+
+    #[test]
+    fn await_ready_until_startup_timeout() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        // accepted (in the backlog) but never acknowledged
+        let err = connect_with_retry(&addr, &config(Some(1)), Some(Instant::now() + Duration::from_millis(100)), "connector", "acceptor", |_| Ok(())).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        drop(listener);
+    }
+}
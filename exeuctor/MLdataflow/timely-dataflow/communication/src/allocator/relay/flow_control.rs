@@ -0,0 +1,234 @@
+//! Flow control (backpressure) between pipelines, based on the bytes queued at the network threads
+use std::sync::{Condvar, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+/// High and low watermarks (in bytes) of the messages queued to be written to the sockets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FlowControlConfig {
+    /// Stop reading from upstream once more bytes than the high watermark are queued
+    pub high_watermark: usize,
+    /// Resume reading from upstream once the queued bytes drop to the low watermark
+    pub low_watermark: usize,
+}
+
+/// Bytes queued at the send network threads but not written to the sockets yet.
+/// Once the queued bytes exceed the high watermark, the receive network threads pause reading from upstream
+/// (then the TCP connections push back on the upstream senders),
+/// until the queued bytes drop to the low watermark.
+pub struct FlowControl {
+    config: FlowControlConfig,
+    queued: AtomicUsize,
+    paused: AtomicBool,
+    // guards the transitions of `paused`, waited on by the paused threads
+    lock: Mutex<()>,
+    resumed: Condvar,
+    pauses: AtomicUsize,
+    max_queued: AtomicUsize,
+}
+
+impl FlowControl {
+    pub(crate) fn new(config: FlowControlConfig) -> Self {
+        assert!(config.low_watermark <= config.high_watermark, "low watermark must not exceed the high watermark");
+        FlowControl {
+            config,
+            queued: AtomicUsize::new(0),
+            paused: AtomicBool::new(false),
+            lock: Mutex::new(()),
+            resumed: Condvar::new(),
+            pauses: AtomicUsize::new(0),
+            max_queued: AtomicUsize::new(0),
+        }
+    }
+
+    /// `bytes` are queued to be written
+    pub(crate) fn enqueue(&self, bytes: usize) {
+        let queued = self.queued.fetch_add(bytes, Ordering::SeqCst) + bytes;
+        self.max_queued.fetch_max(queued, Ordering::SeqCst);
+        if queued > self.config.high_watermark && !self.paused.load(Ordering::SeqCst) {
+            let _guard = self.lock.lock().unwrap();
+            if !self.paused.load(Ordering::SeqCst) && self.queued.load(Ordering::SeqCst) > self.config.high_watermark {
+                self.paused.store(true, Ordering::SeqCst);
+                self.pauses.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    /// `bytes` are written (or discarded)
+    pub(crate) fn dequeue(&self, bytes: usize) {
+        let queued = self.queued.fetch_sub(bytes, Ordering::SeqCst) - bytes;
+        if queued <= self.config.low_watermark && self.paused.load(Ordering::SeqCst) {
+            let _guard = self.lock.lock().unwrap();
+            if self.paused.load(Ordering::SeqCst) && self.queued.load(Ordering::SeqCst) <= self.config.low_watermark {
+                self.paused.store(false, Ordering::SeqCst);
+                self.resumed.notify_all();
+            }
+        }
+    }
+
+    /// Whether reading from upstream is paused
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    /// Number of bytes currently queued to be written
+    pub fn queued(&self) -> usize {
+        self.queued.load(Ordering::SeqCst)
+    }
+
+    /// Block the current thread while reading from upstream is paused
+    pub(crate) fn wait_until_resumed(&self) {
+        if !self.is_paused() {
+            return;
+        }
+        let mut guard = self.lock.lock().unwrap();
+        while self.is_paused() {
+            guard = self.resumed.wait(guard).unwrap();
+        }
+    }
+
+    /// Summarize the pauses and the queued bytes
+    pub fn report(&self) -> FlowControlReport {
+        FlowControlReport {
+            pauses: self.pauses.load(Ordering::SeqCst),
+            max_queued_bytes: self.max_queued.load(Ordering::SeqCst),
+        }
+    }
+}
+
+/// Flow control of a relay node, shared by its network threads
+pub struct RelayNodeFlowControl {
+    /// Messages to the timely workers, pauses reading from the input pipelines
+    pub to_timely_workers: FlowControl,
+    /// Messages to the output pipelines, pauses reading from the timely workers
+    pub to_output_pipelines: FlowControl,
+}
+
+impl RelayNodeFlowControl {
+    pub(crate) fn new(config: FlowControlConfig) -> Self {
+        RelayNodeFlowControl {
+            to_timely_workers: FlowControl::new(config),
+            to_output_pipelines: FlowControl::new(config),
+        }
+    }
+
+    /// Summarize the pauses and the queued bytes
+    pub fn report(&self) -> RelayFlowControlReport {
+        RelayFlowControlReport {
+            to_timely_workers: self.to_timely_workers.report(),
+            to_output_pipelines: self.to_output_pipelines.report(),
+        }
+    }
+}
+
+/// Flow control of a timely worker process, shared by its network threads to the relay nodes and its worker threads
+pub struct TimelyWorkerFlowControl {
+    /// Messages received from the relay nodes and not taken by the worker threads yet,
+    /// pauses reading from the relay nodes
+    pub from_relay_nodes: FlowControl,
+    /// Messages to the relay nodes, pauses reading from the relay nodes and the input sources
+    pub to_relay_nodes: FlowControl,
+}
+
+impl TimelyWorkerFlowControl {
+    pub(crate) fn new(config: FlowControlConfig) -> Self {
+        TimelyWorkerFlowControl {
+            from_relay_nodes: FlowControl::new(config),
+            to_relay_nodes: FlowControl::new(config),
+        }
+    }
+
+    /// Block the current thread while reading from the relay nodes is paused
+    pub(crate) fn wait_until_resumed(&self) {
+        while self.from_relay_nodes.is_paused() || self.to_relay_nodes.is_paused() {
+            self.from_relay_nodes.wait_until_resumed();
+            self.to_relay_nodes.wait_until_resumed();
+        }
+    }
+}
+
+/// Pauses of reading from upstream
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlowControlReport {
+    /// Number of times reading from upstream is paused
+    pub pauses: usize,
+    /// Maximum number of bytes queued to be written
+    pub max_queued_bytes: usize,
+}
+
+/// Pauses of a relay node reading from its input pipelines and its timely workers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RelayFlowControlReport {
+    /// Messages to the timely workers (pauses reading from the input pipelines)
+    pub to_timely_workers: FlowControlReport,
+    /// Messages to the output pipelines (pauses reading from the timely workers)
+    pub to_output_pipelines: FlowControlReport,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::{FlowControl, FlowControlConfig, FlowControlReport};
+
+    fn flow_control() -> FlowControl {
+        FlowControl::new(FlowControlConfig { high_watermark: 100, low_watermark: 40 })
+    }
+
+    #[test]
+    fn pause_above_high_and_resume_at_low_watermark() {
+        let flow_control = flow_control();
+        flow_control.enqueue(60);
+        flow_control.enqueue(40);
+        // at the high watermark, not above it
+        assert!(!flow_control.is_paused());
+        flow_control.enqueue(30);
+        assert!(flow_control.is_paused());
+        assert_eq!(flow_control.queued(), 130);
+
+        // still paused between the watermarks, even if more bytes are queued
+        flow_control.dequeue(80);
+        assert!(flow_control.is_paused());
+        flow_control.enqueue(70);
+        assert!(flow_control.is_paused());
+        flow_control.dequeue(79);
+        assert!(flow_control.is_paused());
+        flow_control.dequeue(0);
+        assert!(flow_control.is_paused());
+        flow_control.dequeue(1);
+        assert!(!flow_control.is_paused());
+        assert_eq!(flow_control.queued(), 40);
+
+        // not paused again until the high watermark is crossed
+        flow_control.enqueue(60);
+        assert!(!flow_control.is_paused());
+        flow_control.enqueue(1);
+        assert!(flow_control.is_paused());
+        flow_control.dequeue(101);
+        assert!(!flow_control.is_paused());
+
+        assert_eq!(flow_control.report(), FlowControlReport { pauses: 2, max_queued_bytes: 130 });
+    }
+
+    #[test]
+    fn wait_until_resumed_at_low_watermark() {
+        let flow_control = Arc::new(flow_control());
+        flow_control.enqueue(150);
+        let waiting = {
+            let flow_control = flow_control.clone();
+            thread::spawn(move || flow_control.wait_until_resumed())
+        };
+        thread::sleep(Duration::from_millis(50));
+        assert!(!waiting.is_finished());
+        flow_control.dequeue(100);
+        thread::sleep(Duration::from_millis(50));
+        assert!(!waiting.is_finished());
+        flow_control.dequeue(10);
+        waiting.join().unwrap();
+        assert_eq!(flow_control.report(), FlowControlReport { pauses: 1, max_queued_bytes: 150 });
+    }
+}
@@ -0,0 +1,122 @@
+//! Liveness of the timely worker processes connected to a relay node
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use bytes::arc::Bytes;
+use serde::{Deserialize, Serialize};
+
+use crate::MessageLatency;
+use crate::allocator::relay::header::RelayToTimelyMessageHeader;
+
+/// Liveness of the timely worker processes connected to a relay node,
+/// shared by the network threads (which detect lost connections)
+/// and the relay workers (which stop routing data to lost timely worker processes)
+pub struct TimelyWorkersLiveness {
+    // whether the connection to each timely worker process is lost
+    lost: Vec<AtomicBool>,
+    // messages to each timely worker process discarded after its connection is lost
+    lost_messages: Vec<AtomicUsize>,
+    // redeliver the data messages to a lost timely worker process to the surviving ones
+    redelivery: bool,
+    // channels of the data messages that can be redelivered to any timely worker
+    redeliverable_channels: Mutex<HashSet<usize>>,
+    // data messages (with the header) to the lost timely worker processes to redeliver, by channel
+    undelivered: Mutex<HashMap<usize, Vec<Bytes>>>,
+    redelivered_messages: AtomicUsize,
+}
+
+impl TimelyWorkersLiveness {
+    /// All timely worker processes are alive at the beginning,
+    /// with `redelivery`, the data messages to the lost ones are kept to be redelivered
+    pub fn new(num_timely_worker_processes: usize, redelivery: bool) -> Self {
+        TimelyWorkersLiveness {
+            lost: (0..num_timely_worker_processes).map(|_| AtomicBool::new(false)).collect(),
+            lost_messages: (0..num_timely_worker_processes).map(|_| AtomicUsize::new(0)).collect(),
+            redelivery,
+            redeliverable_channels: Mutex::new(HashSet::new()),
+            undelivered: Mutex::new(HashMap::new()),
+            redelivered_messages: AtomicUsize::new(0),
+        }
+    }
+
+    /// Number of timely worker processes connected to the relay node
+    pub fn num_processes(&self) -> usize {
+        self.lost.len()
+    }
+
+    /// Whether the connection to the timely worker process is lost
+    pub fn is_lost(&self, process_index: usize) -> bool {
+        self.lost[process_index].load(Ordering::SeqCst)
+    }
+
+    /// Number of timely worker processes whose connection is lost
+    pub fn num_lost(&self) -> usize {
+        self.lost.iter().filter(|x| x.load(Ordering::SeqCst)).count()
+    }
+
+    /// Mark the connection to the timely worker process as lost,
+    /// returns false if it was already marked
+    pub(crate) fn mark_lost(&self, process_index: usize) -> bool {
+        !self.lost[process_index].swap(true, Ordering::SeqCst)
+    }
+
+    /// Messages of the channel can be redelivered to any timely worker
+    /// if the timely worker process they are sent to is lost.
+    /// Should be called before any message is sent through the channel
+    pub fn register_redeliverable_channel(&self, channel: usize) {
+        self.redeliverable_channels.lock().unwrap().insert(channel);
+    }
+
+    /// Discard the messages to a lost timely worker process,
+    /// keep the ones of redeliverable channels if redelivery is enabled
+    pub(crate) fn discard(&self, process_index: usize, mut bytes: Bytes) {
+        let redeliverable_channels = self.redeliverable_channels.lock().unwrap();
+        let mut undelivered = self.undelivered.lock().unwrap();
+        while let Some(header) = RelayToTimelyMessageHeader::try_read(&mut bytes[..]) {
+            let message = bytes.extract_to(header.required_bytes());
+            if header.length == 0 {
+                continue;
+            }
+            if self.redelivery && redeliverable_channels.contains(&header.channel) {
+                undelivered.entry(header.channel).or_insert_with(Vec::new).push(message);
+            }
+            else {
+                self.lost_messages[process_index].fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    /// Take the undelivered messages of the channel,
+    /// returns the payloads along with the network transmission latency to pass through
+    pub fn take_undelivered(&self, channel: usize) -> Vec<(Bytes, Option<MessageLatency>)> {
+        let messages = self.undelivered.lock().unwrap().remove(&channel).unwrap_or_default();
+        self.redelivered_messages.fetch_add(messages.len(), Ordering::SeqCst);
+        messages.into_iter().map(|mut message| {
+            let header = RelayToTimelyMessageHeader::try_read(&mut message[..]).unwrap();
+            let _ = message.extract_to(std::mem::size_of::<RelayToTimelyMessageHeader>());
+            (message, header.relay_transmission_latency)
+        }).collect()
+    }
+
+    /// Summarize the lost timely worker processes and the discarded messages
+    pub fn report(&self) -> LostWorkersReport {
+        LostWorkersReport {
+            lost_timely_workers: (0..self.num_processes()).filter(|x| self.is_lost(*x)).collect(),
+            lost_messages: self.lost_messages.iter().map(|x| x.load(Ordering::SeqCst)).sum(),
+            redelivered_messages: self.redelivered_messages.load(Ordering::SeqCst),
+        }
+    }
+}
+
+/// Timely worker processes lost by a relay node
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LostWorkersReport {
+    /// Indices of the timely worker processes whose connection is lost
+    pub lost_timely_workers: Vec<usize>,
+    /// Messages (in-flight to the lost timely worker processes) discarded by the relay node
+    pub lost_messages: usize,
+    /// Messages (in-flight to the lost timely worker processes) redelivered to the surviving ones
+    #[serde(default)]
+    pub redelivered_messages: usize,
+}
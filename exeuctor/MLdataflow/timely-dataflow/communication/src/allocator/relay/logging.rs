@@ -24,6 +24,8 @@ pub enum RelayCommunicationEvent {
     Message(RelayMessageEvent),
     /// A state transition.
     State(RelayStateEvent),
+    /// A completed readiness handshake.
+    Handshake(RelayHandshakeEvent),
 }
 
 /// An observed message from relay-relay communication.
@@ -50,6 +52,24 @@ pub struct RelayStateEvent {
     pub start: bool,
 }
 
+/// Readiness handshake of a relay-relay connection.
+#[derive(Abomonation, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct RelayHandshakeEvent {
+    /// Is the connection established to the output pipelines
+    /// or accepted from the input pipelines?
+    pub sender: bool,
+    /// input/output pipeline index
+    pub pipeline_index: usize,
+    /// current relay node (process) index
+    pub local_relay_node_index: usize,
+    /// The relative index of remote relay node in the input/output pipeline
+    pub remote_relay_node_index: usize,
+    /// Number of attempts to connect
+    pub attempts: usize,
+    /// Time (in nanoseconds) to establish the connection and complete the handshake
+    pub elapsed_nanos: u64,
+}
+
 /// Communication setup between relay nodes and timely workers,
 /// the struct works for both timely workers and relay nodes
 #[derive(Abomonation, Debug, PartialEq, Eq, Hash, Clone, Copy)]
@@ -70,6 +90,8 @@ pub enum RelayTimelyCommunicationEvent {
     Message(RelayTimelyMessageEvent),
     /// A state transition.
     State(RelayTimelyStateEvent),
+    /// A lost connection to a timely worker process.
+    Lost(RelayTimelyLostEvent),
 }
 
 /// Two types of relay <-> timely  worker communication message's headers
@@ -104,6 +126,17 @@ pub struct RelayTimelyStateEvent {
     pub start: bool,
 }
 
+/// Connection to a timely worker process lost without a clean shutdown.
+#[derive(Abomonation, Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct RelayTimelyLostEvent {
+    /// Is the loss detected by the send (vs the recv) thread.
+    pub send: bool,
+    /// relay node index
+    pub relay_node_index: usize,
+    /// timely worker process index
+    pub timely_worker_process_index: usize,
+}
+
 
 
 impl From<RelayMessageEvent> for RelayCommunicationEvent {
@@ -113,9 +146,16 @@ impl From<RelayStateEvent> for RelayCommunicationEvent {
     fn from(v: RelayStateEvent) -> RelayCommunicationEvent { RelayCommunicationEvent::State(v) }
 }
 
+impl From<RelayHandshakeEvent> for RelayCommunicationEvent {
+    fn from(v: RelayHandshakeEvent) -> RelayCommunicationEvent { RelayCommunicationEvent::Handshake(v) }
+}
+
 impl From<RelayTimelyMessageEvent> for RelayTimelyCommunicationEvent {
     fn from(v: RelayTimelyMessageEvent) -> RelayTimelyCommunicationEvent { RelayTimelyCommunicationEvent::Message(v) }
 }
 impl From<RelayTimelyStateEvent> for RelayTimelyCommunicationEvent {
     fn from(v: RelayTimelyStateEvent) -> RelayTimelyCommunicationEvent { RelayTimelyCommunicationEvent::State(v) }
 }
+impl From<RelayTimelyLostEvent> for RelayTimelyCommunicationEvent {
+    fn from(v: RelayTimelyLostEvent) -> RelayTimelyCommunicationEvent { RelayTimelyCommunicationEvent::Lost(v) }
+}
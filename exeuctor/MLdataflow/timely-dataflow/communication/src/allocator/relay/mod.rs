@@ -2,6 +2,7 @@
 use std::cell::RefCell;
 use std::collections::VecDeque;
 use std::rc::Rc;
+use std::sync::Arc;
 use std::time::Duration;
 use crate::{Data, Message, Pull, Push};
 use crate::allocator::Event;
@@ -12,6 +13,10 @@ pub mod timely_allocator;
 pub mod logging;
 pub mod relay_initialize;
 pub mod timely_initialize;
+mod connection;
+mod flow_control;
+mod liveness;
+mod replay;
 mod relay_tcp;
 mod relay_network_utils;
 mod timely_network_utlis;
@@ -23,6 +28,10 @@ pub use relay_initialize::initialize_relay_node_networking as relay_initialize_n
 pub use timely_initialize::initialize_networking_to_relay as timely_initialize_networking_cluster;
 pub use timely_initialize::initialize_networking_to_relay_single_worker_process as timely_initialize_networking_process;
 pub use logging::{RelayCommunicationEvent, RelayCommunicationSetup, RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup};
+pub use connection::{ConnectionConfig, ConnectionReport};
+pub use flow_control::{FlowControl, FlowControlConfig, FlowControlReport, RelayFlowControlReport, RelayNodeFlowControl, TimelyWorkerFlowControl};
+pub use liveness::{LostWorkersReport, TimelyWorkersLiveness};
+pub use replay::{OutputPipelineReplay, ReplayReport};
 
 // TODO: implement pusher and puller for raw Bytes
 /// Trait for input pipeline relay worker allocator
@@ -55,6 +64,9 @@ pub trait InputRelayAllocate {
     /// Whether all the relay nodes in the input pipeline has completed?
     /// i.e., TcpStream is closed.
     fn input_relay_completed(&self) -> bool;
+    /// Liveness of the timely worker processes,
+    /// data should not be routed to the lost ones
+    fn timely_workers_liveness(&self) -> &Arc<TimelyWorkersLiveness>;
     /// Perform postparatory work, most likely sending un-full binary buffers.
     fn release(&mut self);
 }
@@ -86,7 +98,10 @@ pub trait OutputRelayAllocate {
     /// Awaits events (messages) from timely workers
     fn await_events(&self, _duration: Option<Duration>);
     /// Whether all the timely workers in current pipeline has completed?
+    /// Lost timely worker processes are considered completed.
     fn timely_workers_completed(&self) -> bool;
+    /// Liveness of the timely worker processes
+    fn timely_workers_liveness(&self) -> &Arc<TimelyWorkersLiveness>;
     /// Perform postparatory work, most likely sending un-full binary buffers.
     fn release(&mut self);
 }
\ No newline at end of file
@@ -4,13 +4,14 @@ use std::rc::Rc;
 use std::cell::RefCell;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::collections::hash_map::Entry;
+use std::sync::Arc;
 use std::time::Duration;
 use crossbeam_channel::{Receiver, Sender};
 use bytes::arc::Bytes;
 use crate::{Data, Message, MessageLatency, Pull, Push};
 use crate::allocator::canary::Canary;
 use crate::allocator::Event;
-use crate::allocator::relay::{InputRelayAllocate, OutputRelayAllocate};
+use crate::allocator::relay::{InputRelayAllocate, OutputRelayAllocate, TimelyWorkersLiveness};
 use crate::allocator::zero_copy::bytes_exchange::{BytesPull, MergeQueue, SendEndpoint};
 use crate::allocator::counters::Puller as CountPuller;
 use crate::allocator::relay::header::{RelayToRelayMessageHeader, RelayToTimelyMessageHeader};
@@ -63,9 +64,11 @@ pub fn new_vector(
     num_peer_relay_nodes: usize,
     num_relay_nodes_input_pipelines: Vec<usize>,
     num_relay_nodes_output_pipelines: Vec<usize>,
-    num_timely_worker_processes: usize,
     num_threads_per_timely_worker_process: usize,
+    // liveness of the timely worker processes, shared with the network threads
+    timely_workers_liveness: Arc<TimelyWorkersLiveness>,
 ) -> RelayBuilder {
+    let num_timely_worker_processes = timely_workers_liveness.num_processes();
     let num_input_pipelines = num_relay_nodes_input_pipelines.len();
     let num_output_pipelines = num_relay_nodes_output_pipelines.len();
 
@@ -89,7 +92,8 @@ pub fn new_vector(
             pipeline_index: index,
             input_relay_promises: relay_thread_promises.pop().unwrap(),
             timely_workers_futures: relay_futures,
-            num_threads_per_timely_worker_process
+            num_threads_per_timely_worker_process,
+            timely_workers_liveness: timely_workers_liveness.clone()
         };
         input_relay_worker_builders.push(builder);
     }
@@ -107,7 +111,8 @@ pub fn new_vector(
             num_relay_nodes: num_peer_relay_nodes,
             pipeline_index: index,
             output_relay_futures: relay_thread_futures.pop().unwrap(),
-            timely_workers_promises: relay_promise
+            timely_workers_promises: relay_promise,
+            timely_workers_liveness: timely_workers_liveness.clone()
         };
         output_relay_worker_builders.push(builder);
     }
@@ -140,7 +145,9 @@ pub struct InputRelayWorkerBuilder {
     // length: #num_timely_workers_processes
     timely_workers_futures: Vec<Receiver<MergeQueue>>,
     // number of threads in a timely worker process
-    num_threads_per_timely_worker_process: usize
+    num_threads_per_timely_worker_process: usize,
+    // liveness of the timely worker processes
+    timely_workers_liveness: Arc<TimelyWorkersLiveness>
 }
 
 impl InputRelayWorkerBuilder {
@@ -177,7 +184,8 @@ impl InputRelayWorkerBuilder {
             staged: Vec::new(),
             input_pipeline_recvs,
             input_recv_to_channels: HashMap::new(),
-            timely_worker_sends: worker_sends
+            timely_worker_sends: worker_sends,
+            timely_workers_liveness: self.timely_workers_liveness
         }
     }
 }
@@ -198,6 +206,8 @@ pub struct OutputRelayWorkerBuilder {
     // that are connected to the workers
     // length: #num_timely_workers
     timely_workers_promises: Vec<Sender<MergeQueue>>,
+    // liveness of the timely worker processes
+    timely_workers_liveness: Arc<TimelyWorkersLiveness>,
 }
 
 impl OutputRelayWorkerBuilder {
@@ -230,7 +240,8 @@ impl OutputRelayWorkerBuilder {
             staged: Vec::new(),
             timely_recvs,
             timely_recv_to_channels: HashMap::new(),
-            output_pipeline_sends
+            output_pipeline_sends,
+            timely_workers_liveness: self.timely_workers_liveness
         }
     }
 }
@@ -256,7 +267,8 @@ pub struct InputRelayWorkerAllocator {
     input_pipeline_recvs: Vec<MergeQueue>,
     // put the staged received data to the corresponding channel
     input_recv_to_channels: HashMap<usize, Rc<RefCell<VecDeque<(Bytes, MessageLatency)>>>>,
-    timely_worker_sends: Vec<Rc<RefCell<SendEndpoint<MergeQueue>>>>
+    timely_worker_sends: Vec<Rc<RefCell<SendEndpoint<MergeQueue>>>>,
+    timely_workers_liveness: Arc<TimelyWorkersLiveness>
 }
 
 impl InputRelayAllocate for InputRelayWorkerAllocator {
@@ -439,6 +451,10 @@ impl InputRelayAllocate for InputRelayWorkerAllocator {
         self.input_pipeline_recvs.iter().all(|x| x.is_complete())
     }
 
+    fn timely_workers_liveness(&self) -> &Arc<TimelyWorkersLiveness> {
+        &self.timely_workers_liveness
+    }
+
     fn release(&mut self) {
         // send everything in buffer
         for send in self.timely_worker_sends.iter_mut() {
@@ -467,7 +483,8 @@ pub struct OutputRelayWorkerAllocator {
     timely_recvs: Vec<MergeQueue>,
     // put the staged received data to the corresponding channel
     timely_recv_to_channels: HashMap<usize, Rc<RefCell<VecDeque<Bytes>>>>,
-    output_pipeline_sends: Vec<Rc<RefCell<SendEndpoint<MergeQueue>>>>
+    output_pipeline_sends: Vec<Rc<RefCell<SendEndpoint<MergeQueue>>>>,
+    timely_workers_liveness: Arc<TimelyWorkersLiveness>
 }
 
 impl OutputRelayAllocate for OutputRelayWorkerAllocator {
@@ -638,9 +655,14 @@ impl OutputRelayAllocate for OutputRelayWorkerAllocator {
     }
 
     fn timely_workers_completed(&self) -> bool {
+        // the network thread of a lost timely worker process drops its MergeQueue
         self.timely_recvs.iter().all(|x| x.is_complete())
     }
 
+    fn timely_workers_liveness(&self) -> &Arc<TimelyWorkersLiveness> {
+        &self.timely_workers_liveness
+    }
+
     fn release(&mut self) {
         for send in self.output_pipeline_sends.iter_mut() {
             (**send).borrow_mut().publish();
@@ -3,15 +3,20 @@ use std::net::TcpStream;
 use std::sync::Arc;
 use crate::allocator::relay::relay_allocator::{InputRelayWorkerBuilder, new_vector, OutputRelayWorkerBuilder};
 use logging_core::Logger;
-use crate::allocator::relay::logging::{RelayCommunicationEvent, RelayCommunicationSetup, RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup};
-use crate::allocator::relay::relay_network_utils::relay_create_sockets;
-use crate::allocator::relay::relay_tcp::{recv_input_pipeline_loop, send_output_pipeline_loop, send_timely_loop, recv_passthrough_broadcast_timely_loop};
+use crate::allocator::relay::connection::ConnectionConfig;
+use crate::allocator::relay::flow_control::{FlowControlConfig, RelayNodeFlowControl};
+use crate::allocator::relay::liveness::TimelyWorkersLiveness;
+use crate::allocator::relay::replay::OutputPipelineReplay;
+use crate::allocator::relay::logging::{RelayCommunicationEvent, RelayCommunicationSetup, RelayHandshakeEvent, RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup};
+use crate::allocator::relay::relay_network_utils::{relay_create_sockets, RelaySocket};
+use crate::allocator::relay::relay_tcp::{recv_input_pipeline_loop, send_output_pipeline_loop, send_timely_loop, recv_passthrough_broadcast_timely_loop, recv_acks_loop};
 use crate::allocator::zero_copy::initialize::CommsGuard;
 
 /// initialize relay node's network connections
 /// create sockets, spin up network threads for each socket
-/// return builder to build allocators for input pipelines and output pipelines
-/// and join guards to the network threads
+/// return builder to build allocators for input pipelines and output pipelines,
+/// the liveness of the timely worker processes, the messages retained for each output pipeline
+/// (with at-least-once delivery), the flow control of the relay node and join guards to the network threads
 pub fn initialize_relay_node_networking(
     input_pipelines_relay_node_addresses: Vec<Vec<String>>,
     output_pipelines_relay_node_addresses: Vec<Vec<String>>,
@@ -25,16 +30,20 @@ pub fn initialize_relay_node_networking(
     // number of worker threads per timely worker process
     threads_per_timely_worker: usize,
     noisy: bool,
+    connection_config: ConnectionConfig,
     relay_log_sender: Box<dyn Fn(RelayCommunicationSetup)->Option<Logger<RelayCommunicationEvent, RelayCommunicationSetup>>+Send+Sync>,
     timely_log_sender: Box<dyn Fn(RelayTimelyCommunicationSetup)->Option<Logger<RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup>>+Send+Sync>
-) -> ::std::io::Result<(Vec<InputRelayWorkerBuilder>, Vec<OutputRelayWorkerBuilder>, CommsGuard)> {
+) -> ::std::io::Result<(Vec<InputRelayWorkerBuilder>, Vec<OutputRelayWorkerBuilder>, Arc<TimelyWorkersLiveness>, Vec<Arc<OutputPipelineReplay>>, Option<Arc<RelayNodeFlowControl>>, CommsGuard)> {
+    let at_least_once = connection_config.at_least_once;
+    let flow_control = connection_config.flow_control;
     let (input_sockets, output_sockets, timely_sockets) = relay_create_sockets(
         input_pipelines_relay_node_addresses,
         output_pipelines_relay_node_addresses,
         timely_workers_addresses,
         relay_node_addr,
         relay_node_index,
-        noisy
+        noisy,
+        connection_config
     )?;
     if noisy {
         println!("all connections established.");
@@ -46,26 +55,30 @@ pub fn initialize_relay_node_networking(
         relay_node_index,
         num_relay_nodes,
         threads_per_timely_worker,
+        at_least_once,
+        flow_control,
         relay_log_sender,
         timely_log_sender
     )
 }
 
 fn initialize_relay_node_networking_from_sockets(
-    mut sockets_to_input_pipeline_relays: Vec<Vec<TcpStream>>,
-    mut sockets_to_output_pipeline_relays: Vec<Vec<TcpStream>>,
+    mut sockets_to_input_pipeline_relays: Vec<Vec<RelaySocket>>,
+    mut sockets_to_output_pipeline_relays: Vec<Vec<RelaySocket>>,
     mut sockets_to_workers: Vec<TcpStream>,
     relay_node_index: usize,
     num_relay_nodes: usize,
     threads_per_timely_worker: usize,
+    at_least_once: bool,
+    flow_control: Option<FlowControlConfig>,
     relay_log_sender: Box<dyn Fn(RelayCommunicationSetup)->Option<Logger<RelayCommunicationEvent, RelayCommunicationSetup>>+Send+Sync>,
     timely_log_sender: Box<dyn Fn(RelayTimelyCommunicationSetup)->Option<Logger<RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup>>+Send+Sync>
-) -> ::std::io::Result<(Vec<InputRelayWorkerBuilder>, Vec<OutputRelayWorkerBuilder>, CommsGuard)>
+) -> ::std::io::Result<(Vec<InputRelayWorkerBuilder>, Vec<OutputRelayWorkerBuilder>, Arc<TimelyWorkersLiveness>, Vec<Arc<OutputPipelineReplay>>, Option<Arc<RelayNodeFlowControl>>, CommsGuard)>
 {
-    for socket in sockets_to_input_pipeline_relays.iter_mut().flatten() {
+    for (socket, _) in sockets_to_input_pipeline_relays.iter_mut().flatten() {
         socket.set_nonblocking(false).expect("failed to set socket to blocking");
     }
-    for socket in sockets_to_output_pipeline_relays.iter_mut().flatten() {
+    for (socket, _) in sockets_to_output_pipeline_relays.iter_mut().flatten() {
         socket.set_nonblocking(false).expect("failed to set socket to blocking");
     }
     for socket in sockets_to_workers.iter_mut() {
@@ -87,13 +100,16 @@ fn initialize_relay_node_networking_from_sockets(
             num_relay_nodes_output_pipelines.push(x.len());
         });
 
+    let timely_workers_liveness = Arc::new(TimelyWorkersLiveness::new(sockets_to_workers.len(), at_least_once));
+    let flow_control = flow_control.map(|config| Arc::new(RelayNodeFlowControl::new(config)));
+
     let relay_builder = new_vector(
         relay_node_index,
         num_relay_nodes,
         num_relay_nodes_input_pipelines.clone(),
         num_relay_nodes_output_pipelines.clone(),
-        sockets_to_workers.len(),
         threads_per_timely_worker,
+        timely_workers_liveness.clone(),
     );
     let input_relay_builders = relay_builder.input_relay_worker_builders;
     let output_relay_builders = relay_builder.output_relay_worker_builders;
@@ -103,23 +119,40 @@ fn initialize_relay_node_networking_from_sockets(
 
     let relay_futures = relay_builder.network_input_relay_worker_futures;
     for (pipeline_index, (pipeline_relay_sockets, futures)) in sockets_to_input_pipeline_relays.into_iter().zip(relay_futures).enumerate() {
-        for (node_index, (socket, future)) in pipeline_relay_sockets.into_iter().zip(futures).enumerate() {
+        for (node_index, ((socket, report), future)) in pipeline_relay_sockets.into_iter().zip(futures).enumerate() {
             let log_sender = relay_log_sender.clone();
+            // acknowledge the received messages to the input pipeline relay node
+            let acks = match at_least_once {
+                true => Some(socket.try_clone()?),
+                false => None
+            };
+            let flow_control = flow_control.clone();
             let join_guard = std::thread::Builder::new()
                 .name(format!("input-pipeline-{}:receiver", pipeline_index))
                 .spawn(move || {
-                    let logger = log_sender(RelayCommunicationSetup {
+                    let mut logger = log_sender(RelayCommunicationSetup {
                         sender: false,
                         local_relay_node_idx: relay_node_index,
                         remote_pipeline_index: pipeline_index,
                         remote_relay_node_idx: node_index
                     });
+                    logger.as_mut().map(|l| l.log(RelayHandshakeEvent {
+                        sender: false,
+                        pipeline_index,
+                        local_relay_node_index: relay_node_index,
+                        remote_relay_node_index: node_index,
+                        attempts: report.attempts,
+                        elapsed_nanos: report.elapsed.as_nanos() as u64,
+                    }));
 
                     recv_input_pipeline_loop(
                         socket,
                         future,
                         pipeline_index,
+                        node_index,
                         relay_node_index,
+                        acks,
+                        flow_control,
                         logger
                     );
                 })?;
@@ -128,24 +161,53 @@ fn initialize_relay_node_networking_from_sockets(
     }
 
     let relay_promises = relay_builder.network_output_relay_worker_promises;
+    let mut output_pipelines_replay = Vec::new();
     for (pipeline_index, (pipeline_relay_sockets, promises)) in sockets_to_output_pipeline_relays.into_iter().zip(relay_promises).enumerate() {
-        for (node_index, (socket, promise)) in pipeline_relay_sockets.into_iter().zip(promises).enumerate() {
+        // retain the messages sent to the output pipeline until they are acknowledged
+        let replay = match at_least_once {
+            true => Some(Arc::new(OutputPipelineReplay::new(pipeline_index, pipeline_relay_sockets.len()))),
+            false => None
+        };
+        output_pipelines_replay.extend(replay.clone());
+        for (node_index, ((socket, report), promise)) in pipeline_relay_sockets.into_iter().zip(promises).enumerate() {
+            if let Some(replay) = replay.clone() {
+                let stream = socket.try_clone()?;
+                let join_guard = std::thread::Builder::new()
+                    .name(format!("output-pipeline-{}:acks", pipeline_index))
+                    .spawn(move || {
+                        recv_acks_loop(stream, pipeline_index, node_index, relay_node_index, replay);
+                    })?;
+                recv_loop_gurads.push(join_guard);
+            }
+            let replay = replay.clone();
+            let flow_control = flow_control.clone();
             let log_sender = relay_log_sender.clone();
             let join_guard = std::thread::Builder::new()
                 .name(format!("output-pipeline-{}:sender", pipeline_index))
                 .spawn(move || {
-                    let logger = log_sender(RelayCommunicationSetup{
+                    let mut logger = log_sender(RelayCommunicationSetup{
                         sender: true,
                         local_relay_node_idx: relay_node_index,
                         remote_pipeline_index: pipeline_index,
                         remote_relay_node_idx: node_index
                     });
+                    logger.as_mut().map(|l| l.log(RelayHandshakeEvent {
+                        sender: true,
+                        pipeline_index,
+                        local_relay_node_index: relay_node_index,
+                        remote_relay_node_index: node_index,
+                        attempts: report.attempts,
+                        elapsed_nanos: report.elapsed.as_nanos() as u64,
+                    }));
 
                     send_output_pipeline_loop(
                         socket,
                         promise,
                         pipeline_index,
+                        node_index,
                         relay_node_index,
+                        replay,
+                        flow_control,
                         logger
                     )
                 })?;
@@ -165,6 +227,8 @@ fn initialize_relay_node_networking_from_sockets(
     for (worker_process_idx, ((socket, promises), futures)) in timely_worker_network_thread_resources {
         {
             let log_sender = timely_log_sender.clone();
+            let liveness = timely_workers_liveness.clone();
+            let flow_control = flow_control.clone();
             let stream = socket.try_clone()?;
             let join_guard = std::thread::Builder::new()
                 .name(format!("timely-connector-{}:sender", worker_process_idx))
@@ -180,6 +244,8 @@ fn initialize_relay_node_networking_from_sockets(
                         promises,
                         worker_process_idx,
                         relay_node_index,
+                        liveness,
+                        flow_control,
                         logger
                     );
                 })?;
@@ -188,6 +254,8 @@ fn initialize_relay_node_networking_from_sockets(
 
         {
             let log_sender = timely_log_sender.clone();
+            let liveness = timely_workers_liveness.clone();
+            let flow_control = flow_control.clone();
             let stream = socket.try_clone()?;
             let join_guard = std::thread::Builder::new()
                 .name(format!("timely-connector-{}:receiver", worker_process_idx))
@@ -203,6 +271,8 @@ fn initialize_relay_node_networking_from_sockets(
                         futures,
                         worker_process_idx,
                         relay_node_index,
+                        liveness,
+                        flow_control,
                         logger
                     );
                 })?;
@@ -210,7 +280,7 @@ fn initialize_relay_node_networking_from_sockets(
         }
     }
 
-    Ok((input_relay_builders, output_relay_builders, CommsGuard{
+    Ok((input_relay_builders, output_relay_builders, timely_workers_liveness, output_pipelines_replay, flow_control, CommsGuard{
         send_guards: send_loop_guards,
         recv_guards: recv_loop_gurads
     }))
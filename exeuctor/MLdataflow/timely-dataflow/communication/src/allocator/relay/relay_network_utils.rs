@@ -2,12 +2,13 @@
 use std::collections::HashMap;
 use std::io;
 use std::io::{Read, Result};
-use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
 use std::rc::Rc;
 use std::thread;
-use std::thread::sleep;
-use std::time::Duration;
+use std::time::Instant;
 use abomonation::{encode, decode};
+use crate::allocator::relay::connection::{accept_before, acknowledge_ready, connect_with_retry, ConnectionConfig, ConnectionReport};
+use crate::networking::bind_listener;
 
 
 // magic numbers to identify relay<->relay connection
@@ -15,6 +16,13 @@ use abomonation::{encode, decode};
 const RELAY_HANDSHAKE_MAGIC: u64 = 0xb8c6aabb07c9703a;
 const WORKER_HANDSHAKE_MAGIC: u64 = 0xe801d7b42c68535e;
 
+/// socket to a relay node and how the connection is established
+pub(crate) type RelaySocket = (TcpStream, ConnectionReport);
+
+fn thread_panicked(_: Box<dyn std::any::Any + Send>) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, "relay node connection thread panicked")
+}
+
 /// create sockets to relay nodes in input pipeline and output pipelines
 /// we establish connections to the relay nodes in the output pipelines
 /// and await connections from the timely workers and the relay nodes in the input pipelines
@@ -26,18 +34,20 @@ pub fn relay_create_sockets(
     my_addr: String,
     relay_node_index: usize,
     noisy: bool,
-) -> Result<(Vec<Vec<TcpStream>>, Vec<Vec<TcpStream>>, Vec<TcpStream>)>
+    connection_config: ConnectionConfig,
+) -> Result<(Vec<Vec<RelaySocket>>, Vec<Vec<RelaySocket>>, Vec<TcpStream>)>
 {
+    let deadline = connection_config.deadline();
     let my_addr_clone = my_addr.clone();
     let start_task = thread::spawn(move || {
         let output_pipelines_relay_node_addresses = Rc::new(output_pipelines_relay_node_addresses);
-        relay_start_connections(output_pipelines_relay_node_addresses, relay_node_index, my_addr_clone, noisy)
+        relay_start_connections(output_pipelines_relay_node_addresses, relay_node_index, my_addr_clone, noisy, &connection_config, deadline)
     });
     let await_task = thread::spawn(move ||
-        relay_await_connections_without_duplicated(timely_workers_addresses, input_pipelines_relay_node_addresses, my_addr, relay_node_index, noisy));
+        relay_await_connections_without_duplicated(timely_workers_addresses, input_pipelines_relay_node_addresses, my_addr, relay_node_index, noisy, deadline));
 
-    let results_output = start_task.join().unwrap()?;
-    let (results_timely, results_input) = await_task.join().unwrap()?;
+    let results_output = start_task.join().map_err(thread_panicked)??;
+    let (results_timely, results_input) = await_task.join().map_err(thread_panicked)??;
 
     if noisy { println!("relay node {}:\tnetwork sockets initialization complete", relay_node_index) }
 
@@ -56,12 +66,14 @@ fn relay_create_sockets_with_duplicated(
     my_addr: String,
     relay_node_index: usize,
     noisy: bool,
-) -> Result<(Vec<Vec<TcpStream>>, Vec<Vec<TcpStream>>, Vec<TcpStream>)>
+    connection_config: ConnectionConfig,
+) -> Result<(Vec<Vec<RelaySocket>>, Vec<Vec<RelaySocket>>, Vec<TcpStream>)>
 {
     // TODO: fix relay node identification
+    let deadline = connection_config.deadline();
     let connect_task = thread::spawn(move || -> Result<_> {
         let output_pipelines_relay_node_addresses = Rc::new(output_pipelines_relay_node_addresses);
-        let output_sockets = Rc::new(relay_start_connections(output_pipelines_relay_node_addresses.clone(), relay_node_index, my_addr.clone(), noisy).unwrap());
+        let output_sockets = Rc::new(relay_start_connections(output_pipelines_relay_node_addresses.clone(), relay_node_index, my_addr.clone(), noisy, &connection_config, deadline)?);
         let (timely_sockets, input_sockets) = relay_await_connections_with_duplicated(
             timely_workers_addresses,
             input_pipelines_relay_node_addresses,
@@ -70,12 +82,13 @@ fn relay_create_sockets_with_duplicated(
             my_addr,
             relay_node_index,
             noisy,
+            deadline,
         )?;
         let output_sockets = Rc::try_unwrap(output_sockets).expect("failed to unwrap sockets to output pipelines");
         Ok((input_sockets, output_sockets, timely_sockets))
     });
 
-    let (results_input, results_output, results_timely) = connect_task.join().unwrap()?;
+    let (results_input, results_output, results_timely) = connect_task.join().map_err(thread_panicked)??;
 
     if noisy { println!("relay node {}:\tnetwork sockets initialization complete", relay_node_index) }
 
@@ -87,30 +100,24 @@ fn relay_start_connections(
     relay_node_index: usize,
     my_addr: String,
     noisy: bool,
-) -> Result<Vec<Vec<TcpStream>>>
+    connection_config: &ConnectionConfig,
+    deadline: Option<Instant>,
+) -> Result<Vec<Vec<RelaySocket>>>
 {
-    let results = output_pipelines_relay_node_addresses.iter().enumerate().map(|(pipeline_idx, addrs)| {
-        addrs.into_iter().enumerate().map(|(relay_idx, addr)| {
-            loop {
-                match TcpStream::connect(&addr[..]) {
-                    Ok(mut stream) => {
-                        stream.set_nodelay(true).expect("set_nodelay call failed");
-                        unsafe { encode(&RELAY_HANDSHAKE_MAGIC, &mut stream) }.expect("failed to encode/send relay handshake magic");
-                        let local_addr = my_addr.to_socket_addrs().unwrap().nth(0).unwrap();
-                        unsafe { encode(&local_addr, &mut stream) }.expect("failed to encode/send local SocketAddr");
-                        if noisy { println!("relay node {}:\tconnection to relay node {} in pipeline {}", relay_node_index, relay_idx, pipeline_idx); }
-                        break stream;
-                    }
-                    Err(error) => {
-                        println!("relay node {}:\terror connecting to relay node {} in pipeline {}: {}; retrying", relay_node_index, relay_idx, pipeline_idx, error);
-                        sleep(Duration::from_secs(1));
-                    }
-                }
-            }
+    let local_addr = my_addr.to_socket_addrs()?.next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("failed to translate {} to SocketAddr", my_addr)))?;
+    let local = format!("relay node {}", relay_node_index);
+    output_pipelines_relay_node_addresses.iter().enumerate().map(|(pipeline_idx, addrs)| {
+        addrs.iter().enumerate().map(|(relay_idx, addr)| {
+            let remote = format!("relay node {} in pipeline {}", relay_idx, pipeline_idx);
+            let (stream, report) = connect_with_retry(addr, connection_config, deadline, &local, &remote, |stream| {
+                unsafe { encode(&RELAY_HANDSHAKE_MAGIC, stream) }?;
+                unsafe { encode(&local_addr, stream) }
+            })?;
+            if noisy { println!("relay node {}:\tconnection to relay node {} in pipeline {} ready after {} attempts", relay_node_index, relay_idx, pipeline_idx, report.attempts); }
+            Ok((stream, report))
         }).collect()
-    }).collect();
-
-    Ok(results)
+    }).collect()
 }
 
 #[allow(dead_code)]
@@ -120,9 +127,13 @@ fn relay_await_connections_without_duplicated(
     my_addr: String,
     relay_node_idx: usize,
     noisy: bool,
-) -> Result<(Vec<TcpStream>, Vec<Vec<TcpStream>>)>
+    deadline: Option<Instant>,
+) -> Result<(Vec<TcpStream>, Vec<Vec<RelaySocket>>)>
 {
-    let listener = TcpListener::bind(my_addr)?;
+    let listen_start = Instant::now();
+    let listener = bind_listener(&my_addr)?;
+    if noisy { println!("relay node {}:\tlistening on {}", relay_node_idx, listener.local_addr()?); }
+    let local = format!("relay node {}", relay_node_idx);
 
     let num_relay_node: usize = input_pipelines_relay_nodes_addresses.iter().map(|x| x.len()).sum();
     let num_workers = timely_workers_addresses.len();
@@ -144,8 +155,7 @@ fn relay_await_connections_without_duplicated(
 
 
     for _ in 0..num_relay_node + num_workers {
-        let mut stream = listener.accept()?.0;
-        stream.set_nodelay(true).expect("set_nodelay call failed");
+        let mut stream = accept_before(&listener, deadline, &local)?;
 
         let mut buffer = [0u8; 8];
         stream.read_exact(&mut buffer)?;
@@ -155,14 +165,19 @@ fn relay_await_connections_without_duplicated(
                 let mut buffer = [0u8; 32];
                 stream.read_exact(&mut buffer)?;
                 let handshake_remote_addr = unsafe { decode::<SocketAddr>(&mut buffer) }.expect("unable to decode relay node addr").0.clone();
-                let (pipeline_idx, relay_idx) = relay_node_addr_to_pipeline_map.get(&handshake_remote_addr).expect("receive connection from unspecified relay node");
-                sockets_to_input_relay_nodes[pipeline_idx.to_owned()].push((relay_idx.to_owned(), stream));
+                let (pipeline_idx, relay_idx) = relay_node_addr_to_pipeline_map.get(&handshake_remote_addr).ok_or_else(
+                    || io::Error::new(io::ErrorKind::InvalidData, format!("received connection from unspecified relay node {}", handshake_remote_addr))
+                )?;
+                acknowledge_ready(&mut stream)?;
+                let report = ConnectionReport { attempts: 1, elapsed: listen_start.elapsed() };
+                sockets_to_input_relay_nodes[pipeline_idx.to_owned()].push((relay_idx.to_owned(), (stream, report)));
                 if noisy { println!("relay node {}:\tconnection from relay node {} in input pipeline {}", relay_node_idx, relay_idx, pipeline_idx); }
             }
             &WORKER_HANDSHAKE_MAGIC => {
                 let mut buffer = [0u8; 8];
                 stream.read_exact(&mut buffer)?;
                 let worker_index = unsafe { decode::<u64>(&mut buffer) }.expect("unable to decode timely worker process index").0.to_owned() as usize;
+                acknowledge_ready(&mut stream)?;
                 sockets_to_timely_workers.push((worker_index.to_owned(), stream));
                 if noisy { println!("relay node {}:\tconnection from timely worker {}", relay_node_idx, worker_index); }
             }
@@ -190,13 +205,17 @@ fn relay_await_connections_with_duplicated(
     timely_workers_addresses: Vec<String>,
     input_pipelines_relay_nodes_addresses: Vec<Vec<String>>,
     output_pipeline_addrs: Rc<Vec<Vec<String>>>,
-    output_pipeline_sockets: Rc<Vec<Vec<TcpStream>>>,
+    output_pipeline_sockets: Rc<Vec<Vec<RelaySocket>>>,
     my_addr: String,
     relay_node_idx: usize,
     noisy: bool,
-) -> Result<(Vec<TcpStream>, Vec<Vec<TcpStream>>)>
+    deadline: Option<Instant>,
+) -> Result<(Vec<TcpStream>, Vec<Vec<RelaySocket>>)>
 {
-    let listener = TcpListener::bind(my_addr)?;
+    let listen_start = Instant::now();
+    let listener = bind_listener(&my_addr)?;
+    if noisy { println!("relay node {}:\tlistening on {}", relay_node_idx, listener.local_addr()?); }
+    let local = format!("relay node {}", relay_node_idx);
 
     let mut output_addr_to_idx_map = HashMap::new();
     for (pipeline_idx, addrs) in output_pipeline_addrs.iter().enumerate() {
@@ -216,8 +235,8 @@ fn relay_await_connections_with_duplicated(
         for (relay_index, addr) in relay_nodes_addrs.into_iter().enumerate() {
             match output_addr_to_idx_map.get(&addr) {
                 Some(&(output_pipeline_idx, output_relay_idx)) => {
-                    let stream = output_pipeline_sockets[output_pipeline_idx][output_relay_idx].try_clone()?;
-                    sockets_to_input_relay_nodes[pipeline_index].insert(relay_index, stream);
+                    let (stream, report) = &output_pipeline_sockets[output_pipeline_idx][output_relay_idx];
+                    sockets_to_input_relay_nodes[pipeline_index].insert(relay_index, (stream.try_clone()?, *report));
                 }
                 None => {
                     let mut socket_addrs = addr.to_socket_addrs().expect("failed to translate addr to SocketAddr").collect::<Vec<_>>();
@@ -231,8 +250,7 @@ fn relay_await_connections_with_duplicated(
     let mut sockets_to_timely_workers = Vec::with_capacity(timely_workers_addresses.len());
 
     for _ in 0..num_relay_node + num_workers {
-        let mut stream = listener.accept()?.0;
-        stream.set_nodelay(true).expect("set_nodelay call failed");
+        let mut stream = accept_before(&listener, deadline, &local)?;
 
         let mut buffer = [0u8; 8];
         stream.read_exact(&mut buffer)?;
@@ -242,14 +260,19 @@ fn relay_await_connections_with_duplicated(
                 let mut buffer = [0u8; 32];
                 stream.read_exact(&mut buffer)?;
                 let handshake_remote_addr = unsafe { decode::<SocketAddr>(&mut buffer) }.expect("unable to decode relay node addr").0.clone();
-                let (pipeline_idx, relay_idx) = relay_node_addr_to_pipeline_map.get(&handshake_remote_addr).expect("receive connection from unspecified relay node");
-                sockets_to_input_relay_nodes[pipeline_idx.to_owned()].insert(relay_idx.to_owned(), stream);
+                let (pipeline_idx, relay_idx) = relay_node_addr_to_pipeline_map.get(&handshake_remote_addr).ok_or_else(
+                    || io::Error::new(io::ErrorKind::InvalidData, format!("received connection from unspecified relay node {}", handshake_remote_addr))
+                )?;
+                acknowledge_ready(&mut stream)?;
+                let report = ConnectionReport { attempts: 1, elapsed: listen_start.elapsed() };
+                sockets_to_input_relay_nodes[pipeline_idx.to_owned()].insert(relay_idx.to_owned(), (stream, report));
                 if noisy { println!("relay node {}:\tconnection from relay node {} in input pipeline {}", relay_node_idx, relay_idx, pipeline_idx); }
             }
             &WORKER_HANDSHAKE_MAGIC => {
                 let mut buffer = [0u8; 8];
                 stream.read_exact(&mut buffer)?;
                 let worker_index = unsafe { decode::<u64>(&mut buffer) }.expect("unable to decode timely worker process index").0.to_owned() as usize;
+                acknowledge_ready(&mut stream)?;
                 sockets_to_timely_workers.push((worker_index.to_owned(), stream));
                 if noisy { println!("relay node {}:\tconnection from timely worker {}", relay_node_idx, worker_index); }
             }
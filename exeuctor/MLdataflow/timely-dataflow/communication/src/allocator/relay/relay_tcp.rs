@@ -4,6 +4,7 @@ use chrono::Utc;
 use crossbeam_channel::{Receiver, Sender};
 use std::io::{Read, Write};
 use std::net::TcpStream;
+use std::sync::Arc;
 
 use crate::allocator::zero_copy::bytes_exchange::{BytesPull, BytesPush, MergeQueue};
 use crate::allocator::zero_copy::bytes_slab::BytesSlab;
@@ -12,16 +13,23 @@ use super::logging::{
     RelayCommunicationEvent, RelayCommunicationSetup, RelayMessageEvent, RelayStateEvent,
 };
 use crate::allocator::relay::header::{RelayToRelayMessageHeader, RelayToTimelyMessageHeader};
+use crate::allocator::relay::flow_control::RelayNodeFlowControl;
+use crate::allocator::relay::liveness::TimelyWorkersLiveness;
+use crate::allocator::relay::replay::{is_data_channel, OutputPipelineReplay};
 use crate::allocator::relay::logging::{
     RelayTimelyCommMessageHeader, RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup,
-    RelayTimelyMessageEvent, RelayTimelyStateEvent,
+    RelayTimelyLostEvent, RelayTimelyMessageEvent, RelayTimelyStateEvent,
 };
 use crate::networking::MessageHeader;
 use logging_core::Logger;
 
 /// Receive data from relay nodes in input pipelines.
 /// One thread for each relay node in the input pipeline,
-/// the thread executes recv_input_pipeline_loop
+/// the thread executes recv_input_pipeline_loop.
+/// With `acks` (at-least-once delivery), the number of messages received so far
+/// is sent back to the input pipeline relay node, and the thread exits
+/// if the connection is closed without a clean shutdown.
+/// With `flow_control`, reading pauses while the messages to the timely workers pile up
 pub fn recv_input_pipeline_loop(
     mut reader: TcpStream,
     // receive the MergeQueue sent from
@@ -30,8 +38,14 @@ pub fn recv_input_pipeline_loop(
     target: Receiver<MergeQueue>,
     // the index of the input pipeline connected to
     pipeline_index: usize,
+    // the index of the input pipeline relay node connected to
+    remote_relay_node_index: usize,
     // current running relay node (running this recv_loop)'s index
     relay_node_index: usize,
+    // writer to acknowledge the received messages
+    mut acks: Option<TcpStream>,
+    // flow control of the current relay node
+    flow_control: Option<Arc<RelayNodeFlowControl>>,
     // Logger
     mut logger: Option<Logger<RelayCommunicationEvent, RelayCommunicationSetup>>,
 ) {
@@ -48,9 +62,15 @@ pub fn recv_input_pipeline_loop(
 
     let mut buffer = BytesSlab::new(20);
     let mut staged = Vec::new();
+    let mut num_received = 0usize;
 
     let mut active = true;
     while active {
+        if let Some(flow_control) = flow_control.as_ref() {
+            // leave the messages in the socket, the input pipeline relay node then stops sending
+            flow_control.to_timely_workers.wait_until_resumed();
+        }
+
         buffer.ensure_capacity(1);
 
         // ensure that we have at least one byte to write
@@ -66,6 +86,11 @@ pub fn recv_input_pipeline_loop(
             }
         };
 
+        if read == 0 && acks.is_some() {
+            // the input pipeline relay node is gone (e.g., crashed or killed)
+            println!("relay node {}: lost connection to relay node {} of input pipeline {}", relay_node_index, remote_relay_node_index, pipeline_index);
+            break;
+        }
         assert!(read > 0);
         buffer.make_valid(read);
 
@@ -97,6 +122,10 @@ pub fn recv_input_pipeline_loop(
                 });
 
                 staged.push(bytes);
+                // only the data messages are retained and acknowledged
+                if is_data_channel(header.channel) {
+                    num_received += 1;
+                }
             } else {
                 // Shutting down upon receiving empty message (a message header with zero length)
                 active = false;
@@ -112,7 +141,14 @@ pub fn recv_input_pipeline_loop(
         }
 
         // pass bytes to the input relay worker thread.
+        let newly_received = !staged.is_empty();
         target.extend(staged.drain(..));
+
+        if let (Some(writer), true) = (acks.as_mut(), newly_received) {
+            // the input pipeline relay node retains the messages until they are acknowledged,
+            // it may have shut down already
+            let _ = writer.write_all(&(num_received as u64).to_le_bytes());
+        }
     }
 
     // Log the receive thread's end.
@@ -127,14 +163,26 @@ pub fn recv_input_pipeline_loop(
 }
 
 /// Repeatedly sends messages into a relay node in an output pipeline.
-/// One thread executing send_loop for each of the connected relay node in all output pipelines
+/// One thread executing send_loop for each of the connected relay node in all output pipelines.
+/// With `replay` (at-least-once delivery), the data messages sent are retained until acknowledged,
+/// once the connection is lost, the unacknowledged data messages and the data messages to send afterwards
+/// are replayed to a surviving relay node of the output pipeline,
+/// the frontier updates to send afterwards are rerouted to it.
+/// Frontier updates are held back while the replayed data messages are not acknowledged.
+/// With `flow_control`, the bytes queued to be written are counted
 pub fn send_output_pipeline_loop(
     writer: TcpStream,
     source: Sender<MergeQueue>,
     // the index of the output pipeline connected to
     pipeline_index: usize,
+    // the index of the output pipeline relay node connected to
+    remote_relay_node_index: usize,
     // current running relay node (running this send_loop)'s index
     relay_node_index: usize,
+    // messages to retain and replay, shared by the threads sending to the same output pipeline
+    replay: Option<Arc<OutputPipelineReplay>>,
+    // flow control of the current relay node
+    flow_control: Option<Arc<RelayNodeFlowControl>>,
     mut logger: Option<Logger<RelayCommunicationEvent, RelayCommunicationSetup>>,
 ) {
     logger.as_mut().map(|l| {
@@ -146,6 +194,10 @@ pub fn send_output_pipeline_loop(
         })
     });
 
+    if let Some(replay) = replay.as_ref() {
+        replay.register(remote_relay_node_index);
+    }
+
     let mut source = {
         let buzzer = crate::buzzer::Buzzer::new();
         let queue = MergeQueue::new(buzzer);
@@ -160,24 +212,80 @@ pub fn send_output_pipeline_loop(
 
     let mut writer = ::std::io::BufWriter::with_capacity(1 << 16, writer);
     let mut stash = Vec::new();
+    // frontier updates held back until the replayed data messages are acknowledged
+    let mut held_progress = Vec::new();
 
+    let mut lost = false;
     let mut active = true;
     while active {
         source.drain_into(&mut stash);
+        // number of messages drained from the source, followed by the replayed messages
+        let num_drained = stash.len();
+        if let Some(replay) = replay.as_ref() {
+            // the thread receiving the acknowledgements may have detected the lost connection
+            lost = lost || replay.is_lost(remote_relay_node_index);
+            stash.extend(replay.take_replayed(remote_relay_node_index));
+            held_progress.extend(replay.take_rerouted_progress(remote_relay_node_index));
+            if !lost && !held_progress.is_empty() && !replay.holds_back_progress() {
+                let mut num_written = 0;
+                let result = held_progress.iter().try_for_each(|message| {
+                    writer.write_all(&message[..]).map(|_| num_written += 1)
+                });
+                // the frontier updates written are never sent again
+                held_progress.drain(..num_written);
+                if let Err(error) = result {
+                    mark_relay_lost(replay, pipeline_index, remote_relay_node_index, relay_node_index, error);
+                    lost = true;
+                }
+            }
+        }
+        if let Some(flow_control) = flow_control.as_ref() {
+            flow_control.to_output_pipelines.enqueue(stash.iter().map(|bytes| bytes.len()).sum());
+        }
 
         if stash.is_empty() {
-            writer.flush().expect("Failed to flush writer.");
+            match (replay.as_ref(), lost) {
+                (None, _) => writer.flush().expect("Failed to flush writer."),
+                (Some(replay), false) => {
+                    if let Err(error) = writer.flush() {
+                        mark_relay_lost(replay, pipeline_index, remote_relay_node_index, relay_node_index, error);
+                        lost = true;
+                    }
+                },
+                (Some(_), true) => {},
+            }
             // if source (MergeQueue) is completed, is dropped by the output relay worker thread
             // this network thread that executes send_loop
             // will be the only one holding the MergeQueue
             if source.is_complete() {
-                active = false;
+                // wait until no more message can be replayed to the relay node
+                active = match replay.as_ref() {
+                    Some(replay) => {
+                        let closed = replay.try_close(remote_relay_node_index);
+                        !lost && !(held_progress.is_empty() && closed)
+                    },
+                    None => false,
+                };
             }
             if active {
-                std::thread::park();
+                match replay {
+                    // acknowledgements do not notify the MergeQueue
+                    Some(_) => std::thread::park_timeout(std::time::Duration::from_millis(100)),
+                    None => std::thread::park(),
+                }
             }
         } else {
-            for mut bytes in stash.drain(..) {
+            for (index, mut bytes) in stash.drain(..).enumerate() {
+                let num_bytes = bytes.len();
+                let replayed = index >= num_drained;
+                if lost {
+                    replay.as_ref().unwrap().forward(remote_relay_node_index, bytes, replayed);
+                    if let Some(flow_control) = flow_control.as_ref() {
+                        flow_control.to_output_pipelines.dequeue(num_bytes);
+                    }
+                    continue;
+                }
+
                 logger.as_mut().map(|logger| {
                     let mut offset = 0;
                     while let Some(header) =
@@ -207,11 +315,44 @@ pub fn send_output_pipeline_loop(
                     offset += header.required_bytes();
                 }
 
-                writer
-                    .write_all(&bytes[..])
-                    .expect("Write failure in send_loop.");
+                match replay.as_ref() {
+                    None => {
+                        writer
+                            .write_all(&bytes[..])
+                            .expect("Write failure in send_loop.");
+                    },
+                    Some(replay) => {
+                        let mut result = Ok(());
+                        while let Some(header) = RelayToRelayMessageHeader::try_read(&mut bytes[..]) {
+                            let message = bytes.extract_to(header.required_bytes());
+                            if is_data_channel(header.channel) {
+                                if result.is_ok() {
+                                    result = writer.write_all(&message[..]);
+                                }
+                                // retain the data messages one by one, as they are acknowledged one by one
+                                replay.retain(remote_relay_node_index, message, replayed);
+                            }
+                            else if result.is_ok() && held_progress.is_empty() && !replay.holds_back_progress() {
+                                result = writer.write_all(&message[..]);
+                            }
+                            else {
+                                held_progress.push(message);
+                            }
+                        }
+                        if let Err(error) = result {
+                            mark_relay_lost(replay, pipeline_index, remote_relay_node_index, relay_node_index, error);
+                            lost = true;
+                        }
+                    },
+                }
+                if let Some(flow_control) = flow_control.as_ref() {
+                    flow_control.to_output_pipelines.dequeue(num_bytes);
+                }
             }
         }
+        if lost && !held_progress.is_empty() {
+            replay.as_ref().unwrap().reroute_progress(remote_relay_node_index, std::mem::take(&mut held_progress));
+        }
     }
 
     let header = RelayToRelayMessageHeader {
@@ -223,10 +364,12 @@ pub fn send_output_pipeline_loop(
         send_timestamp: None,
         recv_timestamp: None,
     };
-    header
-        .write_to(&mut writer)
-        .expect("Failed to write header!");
-    writer.flush().expect("Failed to flush writer.");
+    if !lost {
+        header
+            .write_to(&mut writer)
+            .expect("Failed to write header!");
+        writer.flush().expect("Failed to flush writer.");
+    }
     // writer.get_mut().shutdown(::std::net::Shutdown::Write).expect("Write shutdown failed");
     logger.as_mut().map(|logger| {
         logger.log(RelayMessageEvent {
@@ -245,7 +388,53 @@ pub fn send_output_pipeline_loop(
     });
 }
 
-// Repeatedly sends messages into a timely worker process
+/// Receives the acknowledgements from a relay node in an output pipeline (at-least-once delivery),
+/// i.e., the number of messages it has received so far.
+/// If the connection is closed before the shutdown message is sent,
+/// the relay node is marked as lost and its unacknowledged messages are replayed
+pub fn recv_acks_loop(
+    mut reader: TcpStream,
+    // the index of the output pipeline connected to
+    pipeline_index: usize,
+    // the index of the output pipeline relay node connected to
+    remote_relay_node_index: usize,
+    // current running relay node's index
+    relay_node_index: usize,
+    replay: Arc<OutputPipelineReplay>,
+) {
+    let mut ack = [0u8; std::mem::size_of::<u64>()];
+    loop {
+        match reader.read_exact(&mut ack) {
+            Ok(()) => replay.ack(remote_relay_node_index, u64::from_le_bytes(ack) as usize),
+            Err(error) => {
+                if !replay.is_closed(remote_relay_node_index) {
+                    mark_relay_lost(&replay, pipeline_index, remote_relay_node_index, relay_node_index, error);
+                }
+                break;
+            }
+        }
+    }
+}
+
+/// Mark the connection to the output pipeline relay node as lost and replay its messages,
+/// report it once (by the first of the send and acknowledgement threads detecting it)
+fn mark_relay_lost(
+    replay: &OutputPipelineReplay,
+    pipeline_index: usize,
+    remote_relay_node_index: usize,
+    relay_node_index: usize,
+    error: std::io::Error,
+) {
+    if replay.mark_lost(remote_relay_node_index) {
+        println!("relay node {}: lost connection to relay node {} of output pipeline {}: {}", relay_node_index, remote_relay_node_index, pipeline_index, error);
+    }
+}
+
+// Repeatedly sends messages into a timely worker process,
+// once the connection to the timely worker process is lost
+// (detected by either the send or the recv thread),
+// messages to it are discarded (or kept for redelivery) by `liveness`.
+// With `flow_control`, the bytes queued to be written are counted
 pub fn send_timely_loop(
     writer: TcpStream,
     // the input relay worker will send
@@ -254,6 +443,10 @@ pub fn send_timely_loop(
     timely_worker_process_index: usize,
     // current running relay node (running this recv_loop)'s index
     relay_node_index: usize,
+    // liveness of the timely worker processes, shared with the relay workers
+    liveness: Arc<TimelyWorkersLiveness>,
+    // flow control of the current relay node
+    flow_control: Option<Arc<RelayNodeFlowControl>>,
     mut logger: Option<Logger<RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup>>,
 ) {
     logger.as_mut().map(|l| {
@@ -278,13 +471,24 @@ pub fn send_timely_loop(
     let mut writer = ::std::io::BufWriter::with_capacity(1 << 16, writer);
     let mut stash = Vec::new();
 
+    let mut lost = false;
     while !sources.is_empty() {
         for source in sources.iter_mut() {
             source.drain_into(&mut stash);
         }
+        // the recv thread may have detected the lost connection
+        lost = lost || liveness.is_lost(timely_worker_process_index);
+        if let Some(flow_control) = flow_control.as_ref() {
+            flow_control.to_timely_workers.enqueue(stash.iter().map(|bytes| bytes.len()).sum());
+        }
 
         if stash.is_empty() {
-            writer.flush().expect("Failed to flush writer.");
+            if !lost {
+                if let Err(error) = writer.flush() {
+                    mark_lost(&liveness, true, relay_node_index, timely_worker_process_index, error, &mut logger);
+                    lost = true;
+                }
+            }
             // retain only incomplete sources (from incomplete worker threads)
             sources.retain(|source| !source.is_complete());
             if !sources.is_empty() {
@@ -292,6 +496,15 @@ pub fn send_timely_loop(
             }
         } else {
             for mut bytes in stash.drain(..) {
+                let num_bytes = bytes.len();
+                if lost {
+                    liveness.discard(timely_worker_process_index, bytes);
+                    if let Some(flow_control) = flow_control.as_ref() {
+                        flow_control.to_timely_workers.dequeue(num_bytes);
+                    }
+                    continue;
+                }
+
                 logger.as_mut().map(|logger| {
                     let mut offset = 0;
                     // MessageHeader::try_read reads and clones the MessageHeader from bytes
@@ -308,9 +521,14 @@ pub fn send_timely_loop(
                     }
                 });
 
-                writer
-                    .write_all(&bytes[..])
-                    .expect("Write failure in send_loop.");
+                if let Err(error) = writer.write_all(&bytes[..]) {
+                    mark_lost(&liveness, true, relay_node_index, timely_worker_process_index, error, &mut logger);
+                    lost = true;
+                    liveness.discard(timely_worker_process_index, bytes);
+                }
+                if let Some(flow_control) = flow_control.as_ref() {
+                    flow_control.to_timely_workers.dequeue(num_bytes);
+                }
             }
         }
     }
@@ -323,10 +541,10 @@ pub fn send_timely_loop(
         seqno: 0,
         relay_transmission_latency: None,
     };
-    header
-        .write_to(&mut writer)
-        .expect("Failed to write header!");
-    writer.flush().expect("Failed to flush writer.");
+    if !lost {
+        // the timely worker process may have shut down after completing its work
+        let _ = header.write_to(&mut writer).and_then(|_| writer.flush());
+    }
     // writer.get_mut().shutdown(::std::net::Shutdown::Write).expect("Write shutdown failed");
     let header = RelayTimelyCommMessageHeader::TimelyRecv(header);
     logger.as_mut().map(|logger| {
@@ -346,10 +564,35 @@ pub fn send_timely_loop(
     });
 }
 
+/// Mark the connection to the timely worker process as lost,
+/// report it once (by the first of the send and recv threads detecting it)
+fn mark_lost(
+    liveness: &TimelyWorkersLiveness,
+    send: bool,
+    relay_node_index: usize,
+    timely_worker_process_index: usize,
+    error: std::io::Error,
+    logger: &mut Option<Logger<RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup>>,
+) {
+    if liveness.mark_lost(timely_worker_process_index) {
+        println!("relay node {}: lost connection to timely worker process {}: {}", relay_node_index, timely_worker_process_index, error);
+        logger.as_mut().map(|l| {
+            l.log(RelayTimelyLostEvent {
+                send,
+                relay_node_index,
+                timely_worker_process_index,
+            })
+        });
+    }
+}
+
 /// Repeatedly receives messages from a timely worker process,
 /// broadcast messages received to every output pipeline relay worker,
 /// directly passthrough the pointer to the same underlying space of bytes
-/// to each relay worker
+/// to each relay worker.
+/// If the connection is closed without a clean shutdown,
+/// the timely worker process is marked as lost and the thread exits.
+/// With `flow_control`, reading pauses while the messages to the output pipelines pile up
 #[allow(dead_code)]
 pub fn recv_passthrough_broadcast_timely_loop(
     mut reader: TcpStream,
@@ -362,6 +605,10 @@ pub fn recv_passthrough_broadcast_timely_loop(
     timely_worker_process_index: usize,
     // current running relay node (running this recv_loop)'s index
     relay_node_index: usize,
+    // liveness of the timely worker processes, shared with the relay workers
+    liveness: Arc<TimelyWorkersLiveness>,
+    // flow control of the current relay node
+    flow_control: Option<Arc<RelayNodeFlowControl>>,
     // Logger
     mut logger: Option<Logger<RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup>>,
 ) {
@@ -389,21 +636,28 @@ pub fn recv_passthrough_broadcast_timely_loop(
     let mut active = true;
 
     while active {
+        if let Some(flow_control) = flow_control.as_ref() {
+            // leave the messages in the socket, the timely workers then hold back their sources
+            flow_control.to_output_pipelines.wait_until_resumed();
+        }
+
         buffer.ensure_capacity(1);
         // ensure that we have at least one byte to write
         assert!(!buffer.empty().is_empty());
 
         let read = match reader.read(&mut buffer.empty()) {
             // it returns how many bytes were read
+            Ok(0) => Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "connection closed without shutdown")),
+            result => result,
+        };
+        let read = match read {
             Ok(n) => n,
-            Err(x) => {
-                // We don't expect this, as socket closure results in Ok(0) reads.
-                println!("Error: {:?}", x);
-                0
+            Err(error) => {
+                // the timely worker process is gone (e.g., crashed or killed)
+                mark_lost(&liveness, false, relay_node_index, timely_worker_process_index, error, &mut logger);
+                break;
             }
         };
-
-        assert!(read > 0);
         buffer.make_valid(read);
 
         while let Some(header) = MessageHeader::try_read(buffer.valid()) {
@@ -597,3 +851,193 @@ pub fn recv_copy_broadcast_timely_loop(
         })
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Shutdown, TcpListener, TcpStream};
+    use std::sync::Arc;
+    use std::thread::JoinHandle;
+    use std::time::Duration;
+
+    use bytes::arc::Bytes;
+    use crossbeam_channel::unbounded;
+
+    use crate::allocator::relay::header::{RelayToRelayMessageHeader, RelayToTimelyMessageHeader};
+    use crate::allocator::relay::liveness::TimelyWorkersLiveness;
+    use crate::allocator::relay::replay::OutputPipelineReplay;
+    use crate::allocator::zero_copy::bytes_exchange::{BytesPull, BytesPush, MergeQueue};
+    use super::{recv_acks_loop, recv_input_pipeline_loop, recv_passthrough_broadcast_timely_loop, send_output_pipeline_loop, send_timely_loop};
+
+    const FRONTIER_CHANNEL: usize = 0;
+    const DATA_CHANNEL: usize = 1;
+
+    fn message(channel: usize, record: u64) -> Bytes {
+        let header = RelayToRelayMessageHeader {
+            channel,
+            source: 0,
+            target: 0,
+            length: std::mem::size_of::<u64>(),
+            seqno: 0,
+            send_timestamp: None,
+            recv_timestamp: None,
+        };
+        let mut bytes = Vec::new();
+        header.write_to(&mut bytes).unwrap();
+        bytes.extend_from_slice(&record.to_le_bytes());
+        Bytes::from(bytes)
+    }
+
+    fn connect() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let sender = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (receiver, _) = listener.accept().unwrap();
+        (sender, receiver)
+    }
+
+    // receive (and acknowledge) the messages as a relay node of the output pipeline
+    fn receive(receiver: TcpStream, relay_node_index: usize) -> (MergeQueue, JoinHandle<()>) {
+        let acks = receiver.try_clone().unwrap();
+        let (target_send, target_recv) = unbounded();
+        let target = MergeQueue::new(crate::buzzer::Buzzer::new());
+        target_send.send(target.clone()).unwrap();
+        let thread = std::thread::spawn(move || recv_input_pipeline_loop(receiver, target_recv, 0, 0, relay_node_index, Some(acks), None, None));
+        (target, thread)
+    }
+
+    fn received(target: &mut MergeQueue) -> Vec<(usize, u64)> {
+        let mut stash = Vec::new();
+        target.drain_into(&mut stash);
+        let mut messages = Vec::new();
+        for mut bytes in stash {
+            while let Some(header) = RelayToRelayMessageHeader::try_read(&mut bytes[..]) {
+                let message = bytes.extract_to(header.required_bytes());
+                let mut record = [0u8; 8];
+                record.copy_from_slice(&message[std::mem::size_of::<RelayToRelayMessageHeader>()..]);
+                messages.push((header.channel, u64::from_le_bytes(record)));
+            }
+        }
+        messages
+    }
+
+    #[test]
+    fn lost_relay_node_data_replayed_before_frontier() {
+        let replay = Arc::new(OutputPipelineReplay::new(0, 3));
+        let mut queues = Vec::new();
+        let mut receivers = Vec::new();
+        let mut threads = Vec::new();
+        for node_index in 0..3 {
+            let (sender, receiver) = connect();
+            let (queue_send, queue_recv) = unbounded();
+            let stream = sender.try_clone().unwrap();
+            let acks_replay = replay.clone();
+            threads.push(std::thread::spawn(move || recv_acks_loop(stream, 0, node_index, 0, acks_replay)));
+            let send_replay = Some(replay.clone());
+            threads.push(std::thread::spawn(move || send_output_pipeline_loop(sender, queue_send, 0, node_index, 0, send_replay, None, None)));
+            queues.push(queue_recv.recv().unwrap());
+            receivers.push(Some(receiver));
+        }
+
+        // relay node 0 receives the first data messages, then crashes before acknowledging them
+        let mut crashed = receivers[0].take().unwrap();
+        queues[0].extend((0..5).map(|record| message(DATA_CHANNEL, record)));
+        let mut num_bytes = 0;
+        let mut buffer = [0u8; 1024];
+        while num_bytes < 5 * message(DATA_CHANNEL, 0).len() {
+            num_bytes += std::io::Read::read(&mut crashed, &mut buffer).unwrap();
+        }
+        crashed.shutdown(Shutdown::Both).unwrap();
+        while !replay.is_lost(0) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        // the frontier update to relay node 2 is held back
+        // until relay node 1 acknowledges the data replayed to it
+        let (mut target_2, receiving_2) = receive(receivers[2].take().unwrap(), 2);
+        queues[2].extend(Some(message(FRONTIER_CHANNEL, 0)));
+        queues[2].extend((5..10).map(|record| message(DATA_CHANNEL, record)));
+        std::thread::sleep(Duration::from_millis(200));
+        let mut messages_2 = received(&mut target_2);
+        assert!(!messages_2.contains(&(FRONTIER_CHANNEL, 0)), "frontier update sent before the replayed data is acknowledged");
+
+        let (mut target_1, receiving_1) = receive(receivers[1].take().unwrap(), 1);
+        queues[0].extend((10..15).map(|record| message(DATA_CHANNEL, record)));
+        queues.clear();
+        receiving_1.join().unwrap();
+        receiving_2.join().unwrap();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        messages_2.extend(received(&mut target_2));
+        assert!(messages_2.contains(&(FRONTIER_CHANNEL, 0)));
+        let mut records = received(&mut target_1).into_iter().chain(messages_2)
+            .filter(|(channel, _)| *channel == DATA_CHANNEL)
+            .map(|(_, record)| record)
+            .collect::<Vec<_>>();
+        records.sort_unstable();
+        records.dedup();
+        assert_eq!(records, (0..15).collect::<Vec<_>>());
+        let report = replay.report();
+        assert_eq!(report.lost_relay_nodes, vec![0]);
+        assert_eq!(report.unrecoverable_messages, 0);
+    }
+
+    fn timely_message(channel: usize, record: u64) -> Bytes {
+        let header = RelayToTimelyMessageHeader {
+            channel,
+            source: 0,
+            target: 0,
+            length: std::mem::size_of::<u64>(),
+            seqno: 0,
+            relay_transmission_latency: None,
+        };
+        let mut bytes = Vec::new();
+        header.write_to(&mut bytes).unwrap();
+        bytes.extend_from_slice(&record.to_le_bytes());
+        Bytes::from(bytes)
+    }
+
+    #[test]
+    fn lost_timely_worker_data_redelivered() {
+        let liveness = Arc::new(TimelyWorkersLiveness::new(2, true));
+        liveness.register_redeliverable_channel(DATA_CHANNEL);
+
+        // the relay node is connected to timely worker process 1
+        let (relay, worker) = connect();
+        let reader = relay.try_clone().unwrap();
+        let (source_send, source_recv) = unbounded();
+        let (target_send, target_recv) = unbounded();
+        target_send.send(MergeQueue::new(crate::buzzer::Buzzer::new())).unwrap();
+        let send_liveness = liveness.clone();
+        let sending = std::thread::spawn(move || send_timely_loop(relay, vec![source_send], 1, 0, send_liveness, None, None));
+        let recv_liveness = liveness.clone();
+        let receiving = std::thread::spawn(move || recv_passthrough_broadcast_timely_loop(reader, vec![target_recv], 1, 0, recv_liveness, None, None));
+        let mut source = source_recv.recv().unwrap();
+
+        // the timely worker process crashes without a clean shutdown
+        worker.shutdown(Shutdown::Both).unwrap();
+        drop(worker);
+        receiving.join().unwrap();
+        assert!(liveness.is_lost(1));
+        assert!(!liveness.is_lost(0));
+
+        // the data messages to it are kept to be rerouted to the surviving workers,
+        // the frontier messages (only meaningful to the lost workers) are discarded
+        source.extend((0..5).map(|record| timely_message(DATA_CHANNEL, record)));
+        source.extend(Some(timely_message(FRONTIER_CHANNEL, 0)));
+        drop(source);
+        sending.join().unwrap();
+
+        let redelivered = liveness.take_undelivered(DATA_CHANNEL).into_iter().map(|(bytes, _)| {
+            let mut record = [0u8; 8];
+            record.copy_from_slice(&bytes[..]);
+            u64::from_le_bytes(record)
+        }).collect::<Vec<_>>();
+        assert_eq!(redelivered, (0..5).collect::<Vec<_>>());
+        assert!(liveness.take_undelivered(FRONTIER_CHANNEL).is_empty());
+        let report = liveness.report();
+        assert_eq!(report.lost_timely_workers, vec![1]);
+        assert_eq!(report.lost_messages, 1);
+        assert_eq!(report.redelivered_messages, 5);
+    }
+}
@@ -0,0 +1,291 @@
+//! Retention and replay of the messages sent to the relay nodes of an output pipeline (at-least-once delivery)
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::Thread;
+
+use bytes::arc::Bytes;
+use serde::{Deserialize, Serialize};
+
+use crate::allocator::relay::header::RelayToRelayMessageHeader;
+
+#[derive(Default)]
+struct RelayNodeRetention {
+    // data messages sent but not acknowledged yet (one message per Bytes), in the order they are sent,
+    // with whether they are replayed from a lost relay node
+    unacked: VecDeque<(Bytes, bool)>,
+    // number of data messages acknowledged so far
+    acked: usize,
+    // data messages replayed from the lost relay nodes, to be sent to this relay node
+    replayed: Vec<Bytes>,
+    // frontier updates not sent to the lost relay nodes, to be sent to this relay node instead
+    rerouted_progress: Vec<Bytes>,
+    // the connection to the relay node is lost
+    lost: bool,
+    // nothing but replayed messages is to be sent to the relay node
+    drained: bool,
+    // the shutdown message is sent, nothing can be replayed to the relay node anymore
+    closed: bool,
+    // network thread that sends to the relay node
+    thread: Option<Thread>,
+}
+
+/// Data messages sent to the relay nodes of an output pipeline that are not acknowledged yet,
+/// shared by the network threads connected to these relay nodes.
+/// Once the connection to a relay node is lost, its unacknowledged data messages
+/// (and the data messages sent to it afterwards) are replayed to a surviving relay node.
+/// Data messages are acknowledged once the relay node receives them,
+/// so messages received right before its failure may be delivered twice.
+/// Frontier updates are changes to the frontier counts, they are never retained nor replayed
+/// (a duplicate would corrupt the frontier), the ones not sent yet are rerouted instead.
+/// They are held back while replayed data messages are unacknowledged,
+/// such that the frontier does not pass the timestamps of the replayed data.
+pub struct OutputPipelineReplay {
+    pipeline_index: usize,
+    relay_nodes: Vec<Mutex<RelayNodeRetention>>,
+    // number of lost relay nodes whose messages are being moved to a surviving relay node
+    replaying: AtomicUsize,
+    // number of replayed data messages not acknowledged by a surviving relay node yet
+    pending_replayed: AtomicUsize,
+    replayed_messages: AtomicUsize,
+    unrecoverable_messages: AtomicUsize,
+}
+
+impl OutputPipelineReplay {
+    pub(crate) fn new(pipeline_index: usize, num_relay_nodes: usize) -> Self {
+        OutputPipelineReplay {
+            pipeline_index,
+            relay_nodes: (0..num_relay_nodes).map(|_| Mutex::new(RelayNodeRetention::default())).collect(),
+            replaying: AtomicUsize::new(0),
+            pending_replayed: AtomicUsize::new(0),
+            replayed_messages: AtomicUsize::new(0),
+            unrecoverable_messages: AtomicUsize::new(0),
+        }
+    }
+
+    /// Index of the output pipeline
+    pub fn pipeline_index(&self) -> usize {
+        self.pipeline_index
+    }
+
+    /// Register the current thread as the network thread sending to the relay node
+    pub(crate) fn register(&self, relay_node_index: usize) {
+        self.relay_nodes[relay_node_index].lock().unwrap().thread = Some(std::thread::current());
+    }
+
+    /// Retain a (single) data message sent to the relay node until it is acknowledged,
+    /// `replayed` if it is replayed from a lost relay node
+    pub(crate) fn retain(&self, relay_node_index: usize, message: Bytes, replayed: bool) {
+        self.relay_nodes[relay_node_index].lock().unwrap().unacked.push_back((message, replayed));
+    }
+
+    /// The relay node has received `num_acked` data messages since the connection is established
+    pub(crate) fn ack(&self, relay_node_index: usize, num_acked: usize) {
+        {
+            let mut relay_node = self.relay_nodes[relay_node_index].lock().unwrap();
+            let newly_acked = num_acked.saturating_sub(relay_node.acked);
+            let newly_acked = std::cmp::min(newly_acked, relay_node.unacked.len());
+            let replayed_acked = relay_node.unacked.drain(..newly_acked).filter(|(_, replayed)| *replayed).count();
+            relay_node.acked += newly_acked;
+            self.pending_replayed.fetch_sub(replayed_acked, Ordering::SeqCst);
+        }
+        // the network threads may be waiting for every message to be acknowledged to shut down,
+        // or for the replayed messages to be acknowledged to send the frontier updates
+        self.unpark_all();
+    }
+
+    /// Take the data messages replayed to the relay node
+    pub(crate) fn take_replayed(&self, relay_node_index: usize) -> Vec<Bytes> {
+        std::mem::take(&mut self.relay_nodes[relay_node_index].lock().unwrap().replayed)
+    }
+
+    /// Take the frontier updates rerouted to the relay node
+    pub(crate) fn take_rerouted_progress(&self, relay_node_index: usize) -> Vec<Bytes> {
+        std::mem::take(&mut self.relay_nodes[relay_node_index].lock().unwrap().rerouted_progress)
+    }
+
+    /// Whether the frontier updates are to be held back,
+    /// i.e., some replayed data messages are not acknowledged yet
+    pub(crate) fn holds_back_progress(&self) -> bool {
+        self.replaying.load(Ordering::SeqCst) > 0 || self.pending_replayed.load(Ordering::SeqCst) > 0
+    }
+
+    /// Whether the connection to the relay node is lost
+    pub fn is_lost(&self, relay_node_index: usize) -> bool {
+        self.relay_nodes[relay_node_index].lock().unwrap().lost
+    }
+
+    /// Whether the shutdown message has been sent to the relay node
+    pub(crate) fn is_closed(&self, relay_node_index: usize) -> bool {
+        self.relay_nodes[relay_node_index].lock().unwrap().closed
+    }
+
+    /// Mark the connection to the relay node as lost and replay its unacknowledged data messages,
+    /// returns false if it was already marked
+    pub(crate) fn mark_lost(&self, relay_node_index: usize) -> bool {
+        self.replaying.fetch_add(1, Ordering::SeqCst);
+        let messages = {
+            let mut relay_node = self.relay_nodes[relay_node_index].lock().unwrap();
+            if relay_node.lost {
+                None
+            }
+            else {
+                relay_node.lost = true;
+                let mut num_replayed = 0;
+                let mut messages = Vec::with_capacity(relay_node.unacked.len());
+                for (message, replayed) in relay_node.unacked.drain(..) {
+                    num_replayed += replayed as usize;
+                    messages.push(message);
+                }
+                num_replayed += relay_node.replayed.iter_mut().map(|bytes| count_messages(&mut bytes[..])).sum::<usize>();
+                messages.append(&mut relay_node.replayed);
+                let progress = std::mem::take(&mut relay_node.rerouted_progress);
+                Some((messages, num_replayed, progress))
+            }
+        };
+        let newly_lost = messages.is_some();
+        if let Some((messages, num_replayed, progress)) = messages {
+            self.replay(relay_node_index, messages);
+            // the replayed messages of the lost relay node are replayed once more
+            self.pending_replayed.fetch_sub(num_replayed, Ordering::SeqCst);
+            self.reroute_progress(relay_node_index, progress);
+        }
+        self.replaying.fetch_sub(1, Ordering::SeqCst);
+        newly_lost
+    }
+
+    /// Forward a message not sent to a lost relay node:
+    /// its data messages are replayed and its frontier updates are rerouted
+    /// to the next surviving relay node, `replayed` if it is replayed already
+    pub(crate) fn forward(&self, relay_node_index: usize, mut bytes: Bytes, replayed: bool) {
+        let mut data = Vec::new();
+        let mut progress = Vec::new();
+        while let Some(header) = RelayToRelayMessageHeader::try_read(&mut bytes[..]) {
+            let message = bytes.extract_to(header.required_bytes());
+            match is_data_channel(header.channel) {
+                true => data.push(message),
+                false => progress.push(message),
+            }
+        }
+        let num_replayed = if replayed { data.len() } else { 0 };
+        self.replay(relay_node_index, data);
+        self.pending_replayed.fetch_sub(num_replayed, Ordering::SeqCst);
+        self.reroute_progress(relay_node_index, progress);
+    }
+
+    /// Replay the data messages of a lost relay node to the next surviving one
+    pub(crate) fn replay(&self, relay_node_index: usize, mut messages: Vec<Bytes>) {
+        let num_messages = messages.iter_mut().map(|bytes| count_messages(&mut bytes[..])).sum::<usize>();
+        if num_messages == 0 {
+            return;
+        }
+        let num_relay_nodes = self.relay_nodes.len();
+        for offset in 1..num_relay_nodes {
+            let mut relay_node = self.relay_nodes[(relay_node_index + offset) % num_relay_nodes].lock().unwrap();
+            if !relay_node.lost && !relay_node.closed {
+                relay_node.replayed.extend(messages);
+                self.pending_replayed.fetch_add(num_messages, Ordering::SeqCst);
+                if let Some(thread) = relay_node.thread.as_ref() {
+                    thread.unpark();
+                }
+                self.replayed_messages.fetch_add(num_messages, Ordering::SeqCst);
+                return;
+            }
+        }
+        self.unrecoverable_messages.fetch_add(num_messages, Ordering::SeqCst);
+    }
+
+    /// Reroute the frontier updates not sent to a lost relay node to the next surviving one,
+    /// they are dropped if every relay node is lost (nothing is to receive them)
+    pub(crate) fn reroute_progress(&self, relay_node_index: usize, messages: Vec<Bytes>) {
+        if messages.is_empty() {
+            return;
+        }
+        let num_relay_nodes = self.relay_nodes.len();
+        for offset in 1..num_relay_nodes {
+            let mut relay_node = self.relay_nodes[(relay_node_index + offset) % num_relay_nodes].lock().unwrap();
+            if !relay_node.lost && !relay_node.closed {
+                relay_node.rerouted_progress.extend(messages);
+                if let Some(thread) = relay_node.thread.as_ref() {
+                    thread.unpark();
+                }
+                return;
+            }
+        }
+    }
+
+    /// Close the connection to the relay node if no more message can be replayed to it,
+    /// i.e., every message sent to the surviving relay nodes is acknowledged.
+    /// Should only be called once nothing else is to be sent to the relay node.
+    pub(crate) fn try_close(&self, relay_node_index: usize) -> bool {
+        self.relay_nodes[relay_node_index].lock().unwrap().drained = true;
+        let others_acked = self.relay_nodes.iter().enumerate()
+            .filter(|(index, _)| *index != relay_node_index)
+            .all(|(_, relay_node)| {
+                let relay_node = relay_node.lock().unwrap();
+                // the thread sending to a lost relay node may still forward its messages
+                (relay_node.lost && relay_node.drained) || relay_node.closed
+                    || (relay_node.drained && relay_node.unacked.is_empty()
+                        && relay_node.replayed.is_empty() && relay_node.rerouted_progress.is_empty())
+            });
+        if !others_acked {
+            return false;
+        }
+        let mut relay_node = self.relay_nodes[relay_node_index].lock().unwrap();
+        if relay_node.unacked.is_empty() && relay_node.replayed.is_empty() && relay_node.rerouted_progress.is_empty()
+            && self.replaying.load(Ordering::SeqCst) == 0 {
+            relay_node.closed = true;
+        }
+        relay_node.closed
+    }
+
+    fn unpark_all(&self) {
+        for relay_node in self.relay_nodes.iter() {
+            if let Some(thread) = relay_node.lock().unwrap().thread.as_ref() {
+                thread.unpark();
+            }
+        }
+    }
+
+    /// Summarize the lost relay nodes and the replayed messages
+    pub fn report(&self) -> ReplayReport {
+        ReplayReport {
+            output_pipeline_index: self.pipeline_index,
+            lost_relay_nodes: (0..self.relay_nodes.len()).filter(|x| self.is_lost(*x)).collect(),
+            replayed_messages: self.replayed_messages.load(Ordering::SeqCst),
+            unrecoverable_messages: self.unrecoverable_messages.load(Ordering::SeqCst),
+        }
+    }
+}
+
+/// Relay nodes of an output pipeline lost by a relay node
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayReport {
+    /// Index of the output pipeline
+    pub output_pipeline_index: usize,
+    /// Indices of the relay nodes in the output pipeline whose connection is lost
+    pub lost_relay_nodes: Vec<usize>,
+    /// Messages replayed to the surviving relay nodes
+    pub replayed_messages: usize,
+    /// Messages that cannot be replayed since every relay node in the output pipeline is lost
+    pub unrecoverable_messages: usize,
+}
+
+/// Whether the relay-to-relay channel carries data (`2 * output_index + 1`)
+/// rather than frontier updates (`2 * output_index`)
+pub(crate) fn is_data_channel(channel: usize) -> bool {
+    channel % 2 == 1
+}
+
+/// Number of (non-empty) messages to relay nodes contained in `bytes`
+fn count_messages(bytes: &mut [u8]) -> usize {
+    let mut count = 0;
+    let mut offset = 0;
+    while let Some(header) = RelayToRelayMessageHeader::try_read(&mut bytes[offset..]) {
+        if header.length > 0 {
+            count += 1;
+        }
+        offset += header.required_bytes();
+    }
+    count
+}
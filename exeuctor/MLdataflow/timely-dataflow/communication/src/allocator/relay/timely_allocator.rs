@@ -70,6 +70,10 @@ where
         for recv in self.recvs_relay.as_mut().unwrap().iter_mut() {
             recv.drain_into(staged);
         }
+        if let Some(flow_control) = self.relay_flow_control.as_ref() {
+            // the network threads may resume reading from the relay nodes
+            flow_control.from_relay_nodes.dequeue(staged.iter().map(|bytes| bytes.len()).sum());
+        }
 
         let mut events = self.relay_events.as_mut().unwrap().borrow_mut();
 
@@ -130,6 +134,10 @@ where
         self.recvs_relay.as_ref().unwrap().iter().all(|x| x.is_complete())
     }
 
+    fn relay_backpressured(&self) -> bool {
+        self.relay_flow_control.as_ref().map_or(false, |flow_control| flow_control.to_relay_nodes.is_paused())
+    }
+
     fn await_relay_events(&self, _duration: Option<std::time::Duration>) {
         if self.relay_events.as_ref().unwrap().borrow().is_empty() && self.events().borrow().is_empty() {
             if let Some(duration) = _duration {
@@ -195,6 +203,10 @@ impl RelayConnectAllocate for Process
         for recv in self.recvs_relay.as_mut().unwrap().iter_mut() {
             recv.drain_into(staged);
         }
+        if let Some(flow_control) = self.relay_flow_control.as_ref() {
+            // the network threads may resume reading from the relay nodes
+            flow_control.from_relay_nodes.dequeue(staged.iter().map(|bytes| bytes.len()).sum());
+        }
 
         let mut events = self.relay_events.as_mut().unwrap().borrow_mut();
 
@@ -255,6 +267,10 @@ impl RelayConnectAllocate for Process
         self.recvs_relay.as_ref().unwrap().iter().all(|x| x.is_complete())
     }
 
+    fn relay_backpressured(&self) -> bool {
+        self.relay_flow_control.as_ref().map_or(false, |flow_control| flow_control.to_relay_nodes.is_paused())
+    }
+
     fn await_relay_events(&self, _duration: Option<std::time::Duration>) {
         if self.relay_events.as_ref().unwrap().borrow().is_empty() && self.events().borrow().is_empty() {
             if let Some(duration) = _duration {
@@ -3,6 +3,8 @@ use std::net::TcpStream;
 use std::sync::Arc;
 use logging_core::Logger;
 use crate::allocator::process::ProcessBuilder;
+use crate::allocator::relay::connection::ConnectionConfig;
+use crate::allocator::relay::flow_control::{FlowControlConfig, TimelyWorkerFlowControl};
 use crate::allocator::relay::logging::{RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup};
 use crate::allocator::relay::timely_network_utlis::create_sockets_to_relay;
 use crate::allocator::relay::timely_tcp::{recv_loop_from_relay, send_loop_to_relay};
@@ -22,17 +24,20 @@ pub fn initialize_networking_to_relay(
     my_index: usize,
     threads: usize,
     noisy: bool,
+    connection_config: ConnectionConfig,
     worker_log_sender: Box<dyn Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync>,
     relay_log_sender: Box<dyn Fn(RelayTimelyCommunicationSetup)->Option<Logger<RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup>>+Send+Sync>)
 -> ::std::io::Result<(Vec<TcpBuilder<ProcessBuilder>>, CommsGuard)>
 {
     let sockets_to_workers = create_sockets(worker_addresses, my_index, noisy)?;
-    let sockets_to_relay = create_sockets_to_relay(relay_addresses, my_index, noisy)?;
+    let sockets_to_relay = create_sockets_to_relay(relay_addresses, my_index, noisy, connection_config)?;
     initialize_networking_to_relay_from_sockets(
         sockets_to_workers,
         sockets_to_relay,
         my_index,
         threads,
+        connection_config.at_least_once,
+        connection_config.flow_control,
         worker_log_sender,
         relay_log_sender
     )
@@ -47,6 +52,10 @@ fn initialize_networking_to_relay_from_sockets(
     my_index: usize,
     // num worker threads per worker process
     threads: usize,
+    // survive the loss of relay nodes
+    at_least_once: bool,
+    // watermarks of the messages queued from / to the relay nodes
+    flow_control: Option<FlowControlConfig>,
     worker_log_sender: Box<dyn Fn(CommunicationSetup)->Option<Logger<CommunicationEvent, CommunicationSetup>>+Send+Sync>,
     relay_log_sender: Box<dyn Fn(RelayTimelyCommunicationSetup)->Option<Logger<RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup>>+Send+Sync>)
 -> ::std::io::Result<(Vec<TcpBuilder<ProcessBuilder>>, CommsGuard)>
@@ -56,13 +65,15 @@ fn initialize_networking_to_relay_from_sockets(
     let relay_log_sender = Arc::new(relay_log_sender);
     let processes = sockets_to_workers.len();
     let num_relay_nodes = sockets_to_relay.len();
+    let flow_control = flow_control.map(|config| Arc::new(TimelyWorkerFlowControl::new(config)));
 
     let process_allocators = crate::allocator::process::Process::new_vector(threads);
     let (builders, promises, futures, relay_promises, relay_futures) = new_vector_with_relay_connection(
         process_allocators,
         num_relay_nodes,
         my_index,
-        processes
+        processes,
+        flow_control.clone()
     );
 
     let mut promises_iter = promises.into_iter();
@@ -120,6 +131,7 @@ fn initialize_networking_to_relay_from_sockets(
         let remote_recv = promises_iter.next().unwrap();
         {
             let log_sender = relay_log_sender.clone();
+            let flow_control = flow_control.clone();
             let stream = stream.try_clone()?;
             let join_guard =
                 ::std::thread::Builder::new()
@@ -130,7 +142,7 @@ fn initialize_networking_to_relay_from_sockets(
                             relay_node_index: index,
                             timely_worker_process_index: my_index,
                         });
-                        send_loop_to_relay(stream, remote_recv, my_index, index, logger);
+                        send_loop_to_relay(stream, remote_recv, my_index, index, at_least_once, flow_control, logger);
                     })?;
 
             send_guards.push(join_guard);
@@ -139,6 +151,7 @@ fn initialize_networking_to_relay_from_sockets(
         let remote_send = futures_iter.next().unwrap();
         {
             let log_sender = relay_log_sender.clone();
+            let flow_control = flow_control.clone();
             let stream = stream.try_clone()?;
             let join_guard =
                 ::std::thread::Builder::new()
@@ -149,7 +162,7 @@ fn initialize_networking_to_relay_from_sockets(
                             relay_node_index: index,
                             timely_worker_process_index: my_index,
                         });
-                        recv_loop_from_relay(stream, remote_send, threads * my_index, my_index, index, logger);
+                        recv_loop_from_relay(stream, remote_send, threads * my_index, my_index, index, at_least_once, flow_control, logger);
                     })?;
 
             recv_guards.push(join_guard);
@@ -167,13 +180,16 @@ pub fn initialize_networking_to_relay_single_worker_process(
     relay_addresses: Vec<String>,
     num_worker_threads: usize,
     noisy: bool,
+    connection_config: ConnectionConfig,
     relay_log_sender: Box<dyn Fn(RelayTimelyCommunicationSetup)->Option<Logger<RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup>>+Send+Sync>)
     -> ::std::io::Result<(Vec<ProcessBuilder>, CommsGuard)>
 {
-    let sockets_to_relay = create_sockets_to_relay(relay_addresses, 0, noisy)?;
+    let sockets_to_relay = create_sockets_to_relay(relay_addresses, 0, noisy, connection_config)?;
     initialize_networking_to_relay_from_sockets_single_worker_process(
         sockets_to_relay,
         num_worker_threads,
+        connection_config.at_least_once,
+        connection_config.flow_control,
         relay_log_sender
     )
 }
@@ -182,14 +198,19 @@ fn initialize_networking_to_relay_from_sockets_single_worker_process(
     // sockets to the relay nodes
     sockets_to_relay: Vec<TcpStream>,
     worker_threads: usize,
+    // survive the loss of relay nodes
+    at_least_once: bool,
+    // watermarks of the messages queued from / to the relay nodes
+    flow_control: Option<FlowControlConfig>,
     // log sender
     relay_log_sender: Box<dyn Fn(RelayTimelyCommunicationSetup)->Option<Logger<RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup>>+Send+Sync>)
     -> ::std::io::Result<(Vec<ProcessBuilder>, CommsGuard)>
 {
     let relay_log_sender = Arc::new(relay_log_sender);
     let num_relay_nodes = sockets_to_relay.len();
+    let flow_control = flow_control.map(|config| Arc::new(TimelyWorkerFlowControl::new(config)));
 
-    let (builders, promises, futures)  = crate::allocator::process::Process::new_vector_with_relay_connection(worker_threads, num_relay_nodes);
+    let (builders, promises, futures)  = crate::allocator::process::Process::new_vector_with_relay_connection(worker_threads, num_relay_nodes, flow_control.clone());
 
     let mut send_guards = Vec::with_capacity(sockets_to_relay.len());
     let mut recv_guards = Vec::with_capacity(sockets_to_relay.len());
@@ -202,6 +223,7 @@ fn initialize_networking_to_relay_from_sockets_single_worker_process(
         let remote_recv = promises_iter.next().unwrap();
         {
             let log_sender = relay_log_sender.clone();
+            let flow_control = flow_control.clone();
             let stream = stream.try_clone()?;
             let join_guard =
                 ::std::thread::Builder::new()
@@ -212,7 +234,7 @@ fn initialize_networking_to_relay_from_sockets_single_worker_process(
                             relay_node_index: index,
                             timely_worker_process_index: 0,
                         });
-                        send_loop_to_relay(stream, remote_recv, 0, index, logger);
+                        send_loop_to_relay(stream, remote_recv, 0, index, at_least_once, flow_control, logger);
                     })?;
 
             send_guards.push(join_guard);
@@ -221,6 +243,7 @@ fn initialize_networking_to_relay_from_sockets_single_worker_process(
         let remote_send = futures_iter.next().unwrap();
         {
             let log_sender = relay_log_sender.clone();
+            let flow_control = flow_control.clone();
             let stream = stream.try_clone()?;
             let join_guard =
                 ::std::thread::Builder::new()
@@ -231,7 +254,7 @@ fn initialize_networking_to_relay_from_sockets_single_worker_process(
                             relay_node_index: index,
                             timely_worker_process_index: 0,
                         });
-                        recv_loop_from_relay(stream, remote_send, 0, 0, index, logger);
+                        recv_loop_from_relay(stream, remote_send, 0, 0, index, at_least_once, flow_control, logger);
                     })?;
 
             recv_guards.push(join_guard);
@@ -1,10 +1,11 @@
 //! utlis to create sockets to relay nodes for timely workers
+use std::io;
 use std::io::Result;
 use std::net::TcpStream;
 use std::thread;
-use std::thread::sleep;
-use std::time::Duration;
+use std::time::Instant;
 use abomonation::encode;
+use crate::allocator::relay::connection::{connect_with_retry, ConnectionConfig};
 
 const WORKER_HANDSHAKE_MAGIC: u64 = 0xe801d7b42c68535e;
 
@@ -12,44 +13,41 @@ const WORKER_HANDSHAKE_MAGIC: u64 = 0xe801d7b42c68535e;
 pub fn create_sockets_to_relay(
     relay_addresses: Vec<String>,
     worker_process_index: usize,
-    noisy: bool
+    noisy: bool,
+    connection_config: ConnectionConfig,
 ) -> Result<Vec<TcpStream>>
 {
+    let deadline = connection_config.deadline();
     let start_task = thread::spawn(move ||
-        start_connections_to_relay(relay_addresses, worker_process_index, noisy));
+        start_connections_to_relay(relay_addresses, worker_process_index, noisy, &connection_config, deadline));
 
-    let results = start_task.join().unwrap()?;
+    let results = start_task.join().map_err(
+        |_| io::Error::new(io::ErrorKind::Other, "timely worker connection thread panicked")
+    )??;
 
     if noisy { println!("worker {}:\t to relay sockets initialization complete", worker_process_index) }
 
     Ok(results)
 }
 
-/// timely workers establish connections to relay node
+/// timely workers establish connections to relay node,
+/// retry with backoff until the relay node acknowledges the handshake
 pub fn start_connections_to_relay(
     relay_addresses: Vec<String>,
     worker_process_index: usize,
-    noisy: bool
+    noisy: bool,
+    connection_config: &ConnectionConfig,
+    deadline: Option<Instant>,
 ) -> Result<Vec<TcpStream>>
 {
-    let results = relay_addresses.iter().enumerate().map(|(index, address)| {
-        loop {
-            match TcpStream::connect(address) {
-                Ok(mut stream) => {
-                    stream.set_nodelay(true).expect("set_nodelay call failed");
-                    unsafe { encode(&WORKER_HANDSHAKE_MAGIC, &mut stream) }.expect("failed to encode/send handshake magic");
-                    unsafe { encode(&(worker_process_index as u64), &mut stream) }.expect("failed to encode/send worker index");
-                    if noisy { println!("worker {}:\tconnection to worker {}", worker_process_index, index); }
-                    break stream;
-                },
-                Err(error) => {
-                    println!("worker {}:\terror connecting to relay node {}: {}; retrying", worker_process_index, index, error);
-                    sleep(Duration::from_secs(1));
-                },
-            }
-        }
-    }).collect();
-
-    Ok(results)
+    let local = format!("worker {}", worker_process_index);
+    relay_addresses.iter().enumerate().map(|(index, address)| {
+        let remote = format!("relay node {}", index);
+        let (stream, report) = connect_with_retry(address, connection_config, deadline, &local, &remote, |stream| {
+            unsafe { encode(&WORKER_HANDSHAKE_MAGIC, stream) }?;
+            unsafe { encode(&(worker_process_index as u64), stream) }
+        })?;
+        if noisy { println!("worker {}:\tconnection to relay node {} ready after {} attempts", worker_process_index, index, report.attempts); }
+        Ok(stream)
+    }).collect()
 }
-
@@ -1,15 +1,19 @@
 //! Network threads functions of timely workers for communicating with relay nodes
 use std::io::{Read, Write};
 use std::net::TcpStream;
+use std::sync::Arc;
 use crossbeam_channel::{Receiver, Sender};
 use logging_core::Logger;
+use crate::allocator::relay::flow_control::TimelyWorkerFlowControl;
 use crate::allocator::relay::header::RelayToTimelyMessageHeader;
 use crate::allocator::relay::logging::{RelayTimelyCommMessageHeader, RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup, RelayTimelyMessageEvent, RelayTimelyStateEvent};
 use crate::allocator::zero_copy::bytes_exchange::MergeQueue;
 use crate::allocator::zero_copy::bytes_slab::BytesSlab;
 use crate::networking::MessageHeader;
 
-/// network thread to receive from relay nodes
+/// network thread to receive from relay nodes,
+/// with `survive_relay_loss`, the thread exits if the connection is closed without a clean shutdown,
+/// with `flow_control`, reading pauses while the worker threads fall behind or the messages to the relay nodes pile up
 pub fn recv_loop_from_relay(
     mut reader: TcpStream,
     // from worker threads
@@ -17,6 +21,8 @@ pub fn recv_loop_from_relay(
     worker_offset: usize,
     worker_process_index: usize,
     relay_node_index: usize,
+    survive_relay_loss: bool,
+    flow_control: Option<Arc<TimelyWorkerFlowControl>>,
     mut logger: Option<Logger<RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup>>)
 {
     logger.as_mut().map(|l| l.log(RelayTimelyStateEvent{
@@ -36,6 +42,11 @@ pub fn recv_loop_from_relay(
 
     let mut active = true;
     while active {
+        if let Some(flow_control) = flow_control.as_ref() {
+            // leave the messages in the socket, the relay node then stops sending
+            flow_control.wait_until_resumed();
+        }
+
         buffer.ensure_capacity(1);
         assert!(!buffer.empty().is_empty());
         let read = match reader.read(&mut buffer.empty()) {
@@ -45,6 +56,11 @@ pub fn recv_loop_from_relay(
                 0
             },
         };
+        if read == 0 && survive_relay_loss {
+            // the relay node is gone, the messages in-flight are replayed by the input pipelines
+            println!("worker process {}: lost connection to relay node {}", worker_process_index, relay_node_index);
+            break;
+        }
         assert!(read > 0);
         buffer.make_valid(read);
 
@@ -74,6 +90,10 @@ pub fn recv_loop_from_relay(
 
         for (index, staged) in stageds.iter_mut().enumerate() {
             use crate::allocator::zero_copy::bytes_exchange::BytesPush;
+            if let Some(flow_control) = flow_control.as_ref() {
+                // taken by the worker threads in `receive_from_relay`
+                flow_control.from_relay_nodes.enqueue(staged.iter().map(|bytes| bytes.len()).sum());
+            }
             targets[index].extend(staged.drain(..));
         }
     }
@@ -86,13 +106,17 @@ pub fn recv_loop_from_relay(
     }));
 }
 
-/// network thread to send messages to relay node
+/// network thread to send messages to relay node,
+/// with `survive_relay_loss`, messages are discarded once the connection is lost,
+/// with `flow_control`, the bytes queued to be written are counted
 pub fn send_loop_to_relay(
     writer: TcpStream,
     // to worker threads
     sources: Vec<Sender<MergeQueue>>,
     worker_process_index: usize,
     relay_node_index: usize,
+    survive_relay_loss: bool,
+    flow_control: Option<Arc<TimelyWorkerFlowControl>>,
     mut logger: Option<Logger<RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup>>)
 {
     logger.as_mut().map(|l| l.log(RelayTimelyStateEvent{
@@ -112,14 +136,33 @@ pub fn send_loop_to_relay(
     let mut writer = ::std::io::BufWriter::with_capacity(1 << 16, writer);
     let mut stash = Vec::new();
 
+    let mut lost = false;
+    // fails on write errors unless the loss of the relay node is survived
+    let check = |result: std::io::Result<()>, lost: &mut bool| {
+        if let Err(error) = result {
+            if !survive_relay_loss {
+                panic!("Write failure in send_loop: {:?}", error);
+            }
+            if !*lost {
+                println!("worker process {}: lost connection to relay node {}: {}", worker_process_index, relay_node_index, error);
+                *lost = true;
+            }
+        }
+    };
+
     while !sources.is_empty() {
         for source in sources.iter_mut() {
             use crate::allocator::zero_copy::bytes_exchange::BytesPull;
             source.drain_into(&mut stash);
         }
+        if let Some(flow_control) = flow_control.as_ref() {
+            flow_control.to_relay_nodes.enqueue(stash.iter().map(|bytes| bytes.len()).sum());
+        }
 
         if stash.is_empty() {
-            writer.flush().expect("Failed to flush writer.");
+            if !lost {
+                check(writer.flush(), &mut lost);
+            }
             sources.retain(|source| !source.is_complete());
             if !sources.is_empty() {
                 std::thread::park();
@@ -136,7 +179,12 @@ pub fn send_loop_to_relay(
                     }
                 });
 
-                writer.write_all(&bytes[..]).expect("Write failure in send_loop.");
+                if !lost {
+                    check(writer.write_all(&bytes[..]), &mut lost);
+                }
+                if let Some(flow_control) = flow_control.as_ref() {
+                    flow_control.to_relay_nodes.dequeue(bytes.len());
+                }
             }
         }
     }
@@ -148,8 +196,9 @@ pub fn send_loop_to_relay(
         length:     0,
         seqno:      0,
     };
-    header.write_to(&mut writer).expect("Failed to write header!");
-    writer.flush().expect("Failed to flush writer.");
+    if !lost {
+        check(header.write_to(&mut writer).and_then(|_| writer.flush()), &mut lost);
+    }
     // writer.get_mut().shutdown(::std::net::Shutdown::Write).expect("Write shutdown failed");
     let header = RelayTimelyCommMessageHeader::TimelySend(header);
     logger.as_mut().map(|logger| logger.log(RelayTimelyMessageEvent { is_send: true, header }));
@@ -1,6 +1,7 @@
 // READ, Sep 8 2021
 //! Zero-copy allocator based on TCP.
 use std::rc::Rc;
+use std::sync::Arc;
 use std::cell::RefCell;
 use std::collections::{VecDeque, HashMap, hash_map::Entry, HashSet};
 use crossbeam_channel::{Sender, Receiver};
@@ -13,6 +14,7 @@ use crate::{Allocate, Message, Data, Push, Pull, MessageLatency};
 use crate::allocator::{AllocateBuilder};
 use crate::allocator::Event;
 use crate::allocator::canary::Canary;
+use crate::allocator::relay::TimelyWorkerFlowControl;
 
 use super::bytes_exchange::{BytesPull, SendEndpoint, MergeQueue};
 use super::push_pull::{Pusher, PullerInner};
@@ -43,6 +45,8 @@ pub struct TcpBuilder<A: AllocateBuilder> {
     relay_futures: Option<Vec<Receiver<MergeQueue>>>,
     // to receive messages from relay node
     relay_promises: Option<Vec<Sender<MergeQueue>>>,
+    // flow control of the connections to relay nodes, shared with the network threads
+    relay_flow_control: Option<Arc<TimelyWorkerFlowControl>>,
 }
 
 /// Creates a vector of builders, sharing appropriate state.
@@ -91,7 +95,8 @@ pub fn new_vector<A: AllocateBuilder>(
                 futures,
                 relay_peers: None,
                 relay_promises: None,
-                relay_futures: None
+                relay_futures: None,
+                relay_flow_control: None,
             }})
         .collect();
 
@@ -104,7 +109,8 @@ pub fn new_vector_with_relay_connection<A: AllocateBuilder>(
     allocators: Vec<A>,
     num_relay_nodes: usize,
     my_process: usize,
-    processes: usize)
+    processes: usize,
+    relay_flow_control: Option<Arc<TimelyWorkerFlowControl>>)
 -> (Vec<TcpBuilder<A>>,
     Vec<Vec<Sender<MergeQueue>>>,
     Vec<Vec<Receiver<MergeQueue>>>,
@@ -138,7 +144,8 @@ pub fn new_vector_with_relay_connection<A: AllocateBuilder>(
                     futures,
                     relay_peers: Some(num_relay_nodes),
                     relay_promises: Some(relay_promises),
-                    relay_futures: Some(relay_futures)
+                    relay_futures: Some(relay_futures),
+                    relay_flow_control: relay_flow_control.clone(),
                 }})
             .collect();
 
@@ -213,6 +220,7 @@ impl<A: AllocateBuilder> TcpBuilder<A> {
                 relay_staged: Some(Vec::new()),
                 relay_canaries: Some(Rc::new(RefCell::new(Vec::new()))),
                 relay_dropped_channels: Some(HashSet::new()),
+                relay_flow_control: self.relay_flow_control,
             }
         }
         else {
@@ -234,6 +242,7 @@ impl<A: AllocateBuilder> TcpBuilder<A> {
                 relay_staged: None,
                 relay_canaries: None,
                 relay_dropped_channels: None,
+                relay_flow_control: None,
             }
         }
 
@@ -274,6 +283,7 @@ pub struct TcpAllocator<A: Allocate> {
     pub(crate) relay_staged: Option<Vec<Bytes>>,
     pub(crate) relay_canaries: Option<Rc<RefCell<Vec<usize>>>>,
     pub(crate) relay_dropped_channels: Option<HashSet<usize>>,
+    pub(crate) relay_flow_control: Option<Arc<TimelyWorkerFlowControl>>,
 }
 
 impl<A: Allocate> Allocate for TcpAllocator<A> {
@@ -11,7 +11,7 @@ use serde_json;
 
 use logging_core::Logger;
 
-use crate::allocator::relay::{relay_initialize_networking, RelayCommunicationEvent, RelayCommunicationSetup, RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup};
+use crate::allocator::relay::{relay_initialize_networking, ConnectionConfig, OutputPipelineReplay, RelayNodeFlowControl, TimelyWorkersLiveness, RelayCommunicationEvent, RelayCommunicationSetup, RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup};
 use crate::allocator::relay::relay_allocator::{InputRelayWorkerAllocator, InputRelayWorkerBuilder, OutputRelayWorkerAllocator, OutputRelayWorkerBuilder};
 
 #[derive(Serialize, Deserialize)]
@@ -41,6 +41,8 @@ pub struct Config {
     pub num_relay_nodes_peers: usize,
     /// Verbosely report connection process
     pub report: bool,
+    /// Retries and timeout to establish connections
+    pub connection: ConnectionConfig,
     /// Closure to create a new logger for a communication (network) thread to relay nodes input/output pipelines
     pub relay_log_sender: Box<dyn Fn(RelayCommunicationSetup)->Option<Logger<RelayCommunicationEvent, RelayCommunicationSetup>> + Send + Sync>,
     /// Closure to create a new logger for a communication (network) thread to timely worker processes
@@ -80,6 +82,7 @@ impl Config {
             my_index: index,
             num_relay_nodes_peers: num_relays,
             report,
+            connection: ConnectionConfig::default(),
             relay_log_sender: Box::new(|_| None),
             timely_log_sender: Box::new(|_| None)
         })
@@ -96,8 +99,8 @@ impl Config {
     }
 
     /// Attempts to assemble the described relay node communication infrastructure.
-    pub fn try_build(self) -> Result<(Vec<InputRelayWorkerBuilder>, Vec<OutputRelayWorkerBuilder>, Box<dyn Any+Send>), String> {
-        let (input_builders, output_builders, comm_guard) = relay_initialize_networking(
+    pub fn try_build(self) -> Result<(Vec<InputRelayWorkerBuilder>, Vec<OutputRelayWorkerBuilder>, Arc<TimelyWorkersLiveness>, Vec<Arc<OutputPipelineReplay>>, Option<Arc<RelayNodeFlowControl>>, Box<dyn Any+Send>), String> {
+        let (input_builders, output_builders, liveness, replays, flow_control, comm_guard) = relay_initialize_networking(
             self.input_relay_nodes_addresses,
             self.output_relay_nodes_addresses,
             self.timely_workers_addresses,
@@ -106,10 +109,11 @@ impl Config {
             self.num_relay_nodes_peers,
             self.threads_per_timely_worker_process,
             self.report,
+            self.connection,
             self.relay_log_sender,
             self.timely_log_sender
         ).map_err(|err| format!("failed to init networking: {}", err))?;
-        Ok((input_builders, output_builders, Box::new(comm_guard)))
+        Ok((input_builders, output_builders, liveness, replays, flow_control, Box::new(comm_guard)))
     }
 }
 
@@ -126,10 +130,13 @@ where
     F1: Fn(InputRelayWorkerAllocator) -> T1+Send+Sync+'static,
     F2: Fn(OutputRelayWorkerAllocator) -> T2+Send+Sync+'static
 {
-    let (input_relay_builders, output_relay_builders, comm_guards) = config.try_build()?;
+    let (input_relay_builders, output_relay_builders, liveness, replays, flow_control, comm_guards) = config.try_build()?;
     initialize_with_input_output_from(
         input_relay_builders,
         output_relay_builders,
+        liveness,
+        replays,
+        flow_control,
         comm_guards,
         func_input_pipeline,
         func_output_pipeline
@@ -146,9 +153,12 @@ where
     T: Send+'static,
     F: Fn(InputRelayWorkerAllocator) -> T+Send+Sync+'static,
 {
-    let (input_relay_builders, _output_relay_builders, comm_guards) = config.try_build()?;
+    let (input_relay_builders, _output_relay_builders, liveness, replays, flow_control, comm_guards) = config.try_build()?;
     initialize_with_input_only_from(
         input_relay_builders,
+        liveness,
+        replays,
+        flow_control,
         comm_guards,
         func
     )
@@ -164,9 +174,12 @@ where
     T: Send+'static,
     F: Fn(OutputRelayWorkerAllocator) -> T+Send+Sync+'static,
 {
-    let (_input_relay_builders, output_relay_builders, comm_guards) = config.try_build()?;
+    let (_input_relay_builders, output_relay_builders, liveness, replays, flow_control, comm_guards) = config.try_build()?;
     initialize_with_output_only_from(
         output_relay_builders,
+        liveness,
+        replays,
+        flow_control,
         comm_guards,
         func
     )
@@ -175,6 +188,9 @@ where
 fn initialize_with_input_output_from<T1, T2, F1, F2>(
     input_relay_worker_builders: Vec<InputRelayWorkerBuilder>,
     output_relay_worker_builders: Vec<OutputRelayWorkerBuilder>,
+    liveness: Arc<TimelyWorkersLiveness>,
+    replays: Vec<Arc<OutputPipelineReplay>>,
+    flow_control: Option<Arc<RelayNodeFlowControl>>,
     others: Box<dyn Any+Send>,
     func_input_pipeline: F1,
     func_output_pipeline: F2
@@ -213,11 +229,14 @@ where
             .map_err(|e| format!("{:?}", e))?);
     }
 
-    Ok(WorkerGuards { input_relay_guards, output_relay_guards, others })
+    Ok(WorkerGuards { input_relay_guards, output_relay_guards, liveness, replays, flow_control, others })
 }
 
 fn initialize_with_input_only_from<T, F>(
     input_relay_worker_builders: Vec<InputRelayWorkerBuilder>,
+    liveness: Arc<TimelyWorkersLiveness>,
+    replays: Vec<Arc<OutputPipelineReplay>>,
+    flow_control: Option<Arc<RelayNodeFlowControl>>,
     others: Box<dyn Any+Send>,
     func: F,
 ) -> Result<WorkerGuards<T, T>, String>
@@ -238,11 +257,14 @@ where
             .map_err(|e| format!("{:?}", e))?);
     }
 
-    Ok(WorkerGuards { input_relay_guards, output_relay_guards: Vec::new(), others })
+    Ok(WorkerGuards { input_relay_guards, output_relay_guards: Vec::new(), liveness, replays, flow_control, others })
 }
 
 fn initialize_with_output_only_from<T, F>(
     output_relay_worker_builders: Vec<OutputRelayWorkerBuilder>,
+    liveness: Arc<TimelyWorkersLiveness>,
+    replays: Vec<Arc<OutputPipelineReplay>>,
+    flow_control: Option<Arc<RelayNodeFlowControl>>,
     others: Box<dyn Any+Send>,
     func: F
 ) -> Result<WorkerGuards<T, T>, String>
@@ -263,7 +285,7 @@ where
             .map_err(|e| format!("{:?}", e))?);
     }
 
-    Ok(WorkerGuards { input_relay_guards: Vec::new(), output_relay_guards, others })
+    Ok(WorkerGuards { input_relay_guards: Vec::new(), output_relay_guards, liveness, replays, flow_control, others })
 }
 
 /// Maintains join handles for input relay workers and output relay workers,
@@ -271,6 +293,9 @@ where
 pub struct WorkerGuards<T1: Send+'static, T2: Send+'static> {
     input_relay_guards: Vec<thread::JoinHandle<T1>>,
     output_relay_guards: Vec<thread::JoinHandle<T2>>,
+    liveness: Arc<TimelyWorkersLiveness>,
+    replays: Vec<Arc<OutputPipelineReplay>>,
+    flow_control: Option<Arc<RelayNodeFlowControl>>,
     others: Box<dyn Any+Send>,
 }
 
@@ -279,6 +304,20 @@ impl<T1: Send+'static, T2: Send+'static> WorkerGuards<T1, T2> {
     pub fn guards(&self) -> (&[std::thread::JoinHandle<T1>], &[std::thread::JoinHandle<T2>])  {
         (&self.input_relay_guards[..], &self.output_relay_guards)
     }
+    /// Liveness of the timely worker processes connected to the relay node,
+    /// complete once the guards are joined
+    pub fn timely_workers_liveness(&self) -> &Arc<TimelyWorkersLiveness> {
+        &self.liveness
+    }
+    /// Messages retained for each output pipeline (empty without at-least-once delivery),
+    /// complete once the guards are joined
+    pub fn output_pipelines_replay(&self) -> &[Arc<OutputPipelineReplay>] {
+        &self.replays
+    }
+    /// Flow control of the relay node (None if disabled)
+    pub fn flow_control(&self) -> Option<&Arc<RelayNodeFlowControl>> {
+        self.flow_control.as_ref()
+    }
     /// Provides access to handles that are not worker threads.
     pub fn others(&self) -> &Box<dyn Any+Send> {
         &self.others
@@ -14,7 +14,7 @@ use logging_core::Logger;
 
 use crate::allocator::AllocateBuilder;
 use crate::allocator::generic::{GenericToRelay, GenericToRelayBuilder};
-use crate::allocator::relay::{RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup, timely_initialize_networking_cluster, timely_initialize_networking_process};
+use crate::allocator::relay::{ConnectionConfig, RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup, timely_initialize_networking_cluster, timely_initialize_networking_process};
 use crate::logging::{CommunicationEvent, CommunicationSetup};
 use crate::WorkerGuards;
 
@@ -36,6 +36,8 @@ pub enum Config {
         relay_addresses: Vec<String>,
         /// Verbosely report connection process
         report: bool,
+        /// Retries and timeout to establish connections to the relay nodes
+        connection: ConnectionConfig,
         /// Closure to create a new logger for a communication (network) thread to relay nodes
         relay_log_fn: Box<dyn Fn(RelayTimelyCommunicationSetup)->Option<Logger<RelayTimelyCommunicationEvent, RelayTimelyCommunicationSetup>> + Send + Sync>
     },
@@ -51,6 +53,8 @@ pub enum Config {
         relay_addresses: Vec<String>,
         /// Verbosely report connection process
         report: bool,
+        /// Retries and timeout to establish connections to the relay nodes
+        connection: ConnectionConfig,
         /// Closure to create a new logger for a communication thread to other timely worker processes
         worker_log_fn: Box<dyn Fn(CommunicationSetup) -> Option<Logger<CommunicationEvent, CommunicationSetup>> + Send + Sync>,
         /// Closure to create a new logger for a communication (network) thread to relay nodes
@@ -92,6 +96,7 @@ impl Config {
                 worker_addresses: worker_addrs,
                 relay_addresses: relay_addrs,
                 report,
+                connection: ConnectionConfig::default(),
                 worker_log_fn: Box::new(|_| None),
                 relay_log_fn: Box::new(|_| None),
             })
@@ -100,6 +105,7 @@ impl Config {
                 threads,
                 relay_addresses: relay_addrs,
                 report,
+                connection: ConnectionConfig::default(),
                 relay_log_fn: Box::new(|_| None),
             })
         }
@@ -113,27 +119,37 @@ impl Config {
         Config::from_matches(&matches)
     }
 
+    /// Whether to verbosely report the connection process (and lost connections)
+    pub fn report(&self) -> bool {
+        match self {
+            Config::Process { report, .. } => *report,
+            Config::Cluster { report, .. } => *report,
+        }
+    }
+
     /// Attempts to assemble the described communication infrastructure.
     pub fn try_build(self) -> Result<(Vec<GenericToRelayBuilder>, Box<dyn Any+Send>), String> {
         match self {
-            Config::Process {threads, relay_addresses, report, relay_log_fn} => {
+            Config::Process {threads, relay_addresses, report, connection, relay_log_fn} => {
                 match timely_initialize_networking_process(
                     relay_addresses,
                     threads,
                     report,
+                    connection,
                     relay_log_fn
                 ) {
                     Ok((builders, guard)) => Ok((builders.into_iter().map(|x| GenericToRelayBuilder::Process(x)).collect(), Box::new(guard))),
                     Err(err) => Err(format!("failed to initialize networking: {}", err))
                 }
             },
-            Config::Cluster { threads, process, worker_addresses, relay_addresses, report, worker_log_fn, relay_log_fn } => {
+            Config::Cluster { threads, process, worker_addresses, relay_addresses, report, connection, worker_log_fn, relay_log_fn } => {
                 match timely_initialize_networking_cluster(
                     worker_addresses,
                     relay_addresses,
                     process,
                     threads,
                     report,
+                    connection,
                     worker_log_fn,
                     relay_log_fn
                 ) {
@@ -101,6 +101,7 @@ pub use allocator::direct_relay::generic::GenericDirectRelay as DirectRelayAlloc
 pub use allocator::Generic as Allocator;
 pub use allocator::GenericToRelay as AllocatorWithRelay;
 pub use allocator::RelayConnectAllocate;
+pub use allocator::relay::{ConnectionConfig, FlowControlConfig, LostWorkersReport, RelayFlowControlReport, ReplayReport};
 pub use initialize::{Config as WorkerConfig, initialize, initialize_from, WorkerGuards};
 pub use initialize_relay_node::Config as RelayNodeConfig;
 pub use initialize_relay_node::initialize_with_input_only as relay_initialize_with_input_only;
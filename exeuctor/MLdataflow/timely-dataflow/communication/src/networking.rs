@@ -3,8 +3,8 @@
 
 use std::io;
 use std::io::{Read, Result};
-use std::net::{TcpListener, TcpStream};
-use std::sync::Arc;
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::thread::sleep;
 use std::time::Duration;
@@ -16,6 +16,28 @@ use abomonation::{encode, decode};
 // other traffic on the same port.
 const HANDSHAKE_MAGIC: u64 = 0xc2f1fb770118add9;
 
+// Listeners bound before the networking is initialized (e.g., to reserve ports),
+// taken over by `bind_listener` instead of binding their addresses again
+static PREBOUND_LISTENERS: Mutex<Vec<TcpListener>> = Mutex::new(Vec::new());
+
+/// Hand over a listener bound before the networking is initialized,
+/// the worker (or relay node) listening on its address accepts connections from it.
+/// Connections arriving in the meantime are queued in its backlog
+pub fn prebind_listener(listener: TcpListener) {
+    PREBOUND_LISTENERS.lock().expect("failed to acquire lock").push(listener);
+}
+
+/// Take over the prebound listener on `addr` if there is one, otherwise bind a new listener
+pub fn bind_listener(addr: &str) -> Result<TcpListener> {
+    let addrs = addr.to_socket_addrs()?.collect::<Vec<_>>();
+    let mut listeners = PREBOUND_LISTENERS.lock().expect("failed to acquire lock");
+    let prebound = listeners.iter().position(|listener| listener.local_addr().map(|x| addrs.contains(&x)).unwrap_or(false));
+    match prebound {
+        Some(index) => Ok(listeners.swap_remove(index)),
+        None => TcpListener::bind(&addrs[..]),
+    }
+}
+
 /// Framing data for each `Vec<u8>` transmission, indicating a typed channel, the source and
 /// destination workers, and the length in bytes.
 #[derive(Abomonation, Debug, PartialEq, Eq, Hash, Clone, Copy)]
@@ -124,7 +146,7 @@ pub fn start_connections(addresses: Arc<Vec<String>>, my_index: usize, noisy: bo
 pub fn await_connections(addresses: Arc<Vec<String>>, my_index: usize, noisy: bool) -> Result<Vec<Option<TcpStream>>> {
     // preallocate space
     let mut results: Vec<_> = (0..(addresses.len() - my_index - 1)).map(|_| None).collect();
-    let listener = TcpListener::bind(&addresses[my_index][..])?;
+    let listener = bind_listener(&addresses[my_index])?;
 
     for _ in (my_index + 1) .. addresses.len() {
         let mut stream = listener.accept()?.0;
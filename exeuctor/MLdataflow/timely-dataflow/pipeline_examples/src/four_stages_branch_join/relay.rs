@@ -3,7 +3,7 @@ use std::iter::FromIterator;
 
 use structopt::StructOpt;
 
-use timely::communication::RelayNodeConfig;
+use timely::communication::{ConnectionConfig, RelayNodeConfig};
 use timely::relay::{execute_from_config, RelayConfig};
 
 #[derive(StructOpt, Debug, Clone)]
@@ -25,6 +25,7 @@ fn run_relay_pipeline_0() {
             my_index: 0,
             num_relay_nodes_peers: 1,
             report: true,
+            connection: ConnectionConfig::default(),
             relay_log_sender: Box::new(|_| None),
             timely_log_sender: Box::new(|_| None)
         },
@@ -33,8 +34,9 @@ fn run_relay_pipeline_0() {
         input_to_worker_exchange_patterns: None,
         relay_to_output_exchange_pattern: None,
         output_pipelines_relay_load_balance_ratios: HashMap::new(),
+        timely_workers_load_balance_ratios: None
     };
-    execute_from_config(config);
+    execute_from_config(config).unwrap();
 }
 
 
@@ -49,6 +51,7 @@ fn run_relay_pipeline_1() {
             my_index: 0,
             num_relay_nodes_peers: 1,
             report: true,
+            connection: ConnectionConfig::default(),
             relay_log_sender: Box::new(|_| None),
             timely_log_sender: Box::new(|_| None)
         },
@@ -60,9 +63,10 @@ fn run_relay_pipeline_1() {
         input_to_worker_exchange_patterns: None,
         relay_to_output_exchange_pattern: None,
         output_pipelines_relay_load_balance_ratios: HashMap::new(),
+        timely_workers_load_balance_ratios: None
     };
 
-    execute_from_config(config);
+    execute_from_config(config).unwrap();
 }
 
 fn run_relay_pipeline_2() {
@@ -76,6 +80,7 @@ fn run_relay_pipeline_2() {
             my_index: 0,
             num_relay_nodes_peers: 1,
             report: true,
+            connection: ConnectionConfig::default(),
             relay_log_sender: Box::new(|_| None),
             timely_log_sender: Box::new(|_| None)
         },
@@ -86,9 +91,10 @@ fn run_relay_pipeline_2() {
         input_to_worker_exchange_patterns: None,
         relay_to_output_exchange_pattern: None,
         output_pipelines_relay_load_balance_ratios: HashMap::new(),
+        timely_workers_load_balance_ratios: None
     };
 
-    execute_from_config(config);
+    execute_from_config(config).unwrap();
 }
 
 fn run_relay_pipeline_3() {
@@ -102,6 +108,7 @@ fn run_relay_pipeline_3() {
             my_index: 0,
             num_relay_nodes_peers: 1,
             report: true,
+            connection: ConnectionConfig::default(),
             relay_log_sender: Box::new(|_| None),
             timely_log_sender: Box::new(|_| None)
         },
@@ -113,9 +120,10 @@ fn run_relay_pipeline_3() {
         input_to_worker_exchange_patterns: None,
         relay_to_output_exchange_pattern: None,
         output_pipelines_relay_load_balance_ratios: HashMap::new(),
+        timely_workers_load_balance_ratios: None
     };
 
-    execute_from_config(config);
+    execute_from_config(config).unwrap();
 }
 
 
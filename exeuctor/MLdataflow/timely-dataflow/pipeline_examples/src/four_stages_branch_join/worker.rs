@@ -1,6 +1,7 @@
 use structopt::StructOpt;
 
 use timely::CommunicationWithRelayConfig;
+use timely::communication::ConnectionConfig;
 use timely::dataflow::{InputHandle, ProbeHandle};
 use timely::dataflow::operators::{Input, Inspect, Probe, Map, Exchange};
 use timely::execute_pipeline::{Config, execute};
@@ -27,6 +28,7 @@ fn run_pipeline_0_worker(process_index: usize) {
             worker_addresses: vec![String::from("127.0.0.1:5000"), String::from("127.0.0.1:5001")],
             relay_addresses: vec![String::from("127.0.0.1:6000")],
             report: true,
+            connection: ConnectionConfig::default(),
             worker_log_fn: Box::new(|_| None),
             relay_log_fn: Box::new(|_| None)
         },
@@ -65,6 +67,7 @@ fn run_pipeline_1_worker() {
             threads: 2,
             relay_addresses: vec![String::from("127.0.0.1:6100")],
             report: true,
+            connection: ConnectionConfig::default(),
             relay_log_fn: Box::new(|_| None)
         },
         worker: WorkerConfig::default()
@@ -88,6 +91,7 @@ fn run_pipeline_2_worker() {
             threads: 1,
             relay_addresses: vec![String::from("127.0.0.1:6200")],
             report: true,
+            connection: ConnectionConfig::default(),
             relay_log_fn: Box::new(|_| None)
         },
         worker: WorkerConfig::default()
@@ -110,6 +114,7 @@ fn run_pipeline_3_worker(process_index: usize) {
             worker_addresses: vec![String::from("127.0.0.1:5300"), String::from("127.0.0.1:5301")],
             relay_addresses: vec![String::from("127.0.0.1:6300")],
             report: true,
+            connection: ConnectionConfig::default(),
             worker_log_fn: Box::new(|_| None),
             relay_log_fn: Box::new(|_| None)
         },
@@ -3,7 +3,7 @@ use std::iter::FromIterator;
 
 use structopt::StructOpt;
 
-use timely::communication::RelayNodeConfig;
+use timely::communication::{ConnectionConfig, RelayNodeConfig};
 use timely::relay::{execute_from_config, InputToWorkerExchangePattern, RelayConfig, RelayToOutputExchangePattern};
 
 #[derive(StructOpt, Debug, Clone)]
@@ -25,6 +25,7 @@ fn run_relay_pipeline_0() {
             my_index: 0,
             num_relay_nodes_peers: 1,
             report: true,
+            connection: ConnectionConfig::default(),
             relay_log_sender: Box::new(|_| None),
             timely_log_sender: Box::new(|_| None),
         },
@@ -33,8 +34,9 @@ fn run_relay_pipeline_0() {
         input_to_worker_exchange_patterns: Some(HashMap::new()),
         relay_to_output_exchange_pattern: Some(RelayToOutputExchangePattern::Random),
         output_pipelines_relay_load_balance_ratios: HashMap::new(),
+        timely_workers_load_balance_ratios: None
     };
-    execute_from_config(config);
+    execute_from_config(config).unwrap();
 }
 
 
@@ -49,6 +51,7 @@ fn run_relay_pipeline_1() {
             my_index: 0,
             num_relay_nodes_peers: 1,
             report: true,
+            connection: ConnectionConfig::default(),
             relay_log_sender: Box::new(|_| None),
             timely_log_sender: Box::new(|_| None),
         },
@@ -57,9 +60,10 @@ fn run_relay_pipeline_1() {
         input_to_worker_exchange_patterns: Some(HashMap::new()),
         relay_to_output_exchange_pattern: Some(RelayToOutputExchangePattern::Random),
         output_pipelines_relay_load_balance_ratios: HashMap::new(),
+        timely_workers_load_balance_ratios: None
     };
 
-    execute_from_config(config);
+    execute_from_config(config).unwrap();
 }
 
 fn run_relay_pipeline_2() {
@@ -73,6 +77,7 @@ fn run_relay_pipeline_2() {
             my_index: 0,
             num_relay_nodes_peers: 1,
             report: true,
+            connection: ConnectionConfig::default(),
             relay_log_sender: Box::new(|_| None),
             timely_log_sender: Box::new(|_| None),
         },
@@ -87,9 +92,10 @@ fn run_relay_pipeline_2() {
         ])),
         relay_to_output_exchange_pattern: Some(RelayToOutputExchangePattern::Random),
         output_pipelines_relay_load_balance_ratios: HashMap::new(),
+        timely_workers_load_balance_ratios: None
     };
 
-    execute_from_config(config);
+    execute_from_config(config).unwrap();
 }
 
 
@@ -1,6 +1,7 @@
 use structopt::StructOpt;
 
 use timely::CommunicationWithRelayConfig::Process;
+use timely::communication::ConnectionConfig;
 use timely::dataflow::{InputHandle, ProbeHandle};
 use timely::dataflow::operators::{Input, Inspect, Map, Probe};
 use timely::execute_pipeline::{Config, execute};
@@ -20,6 +21,7 @@ fn run_pipeline_0_worker() {
             threads: 1,
             relay_addresses: vec![String::from("127.0.0.1:6001")],
             report: true,
+            connection: ConnectionConfig::default(),
             relay_log_fn: Box::new(|_| None),
         },
         worker: WorkerConfig::default(),
@@ -51,6 +53,7 @@ fn run_pipeline_1_worker() {
             threads: 1,
             relay_addresses: vec![String::from("127.0.0.1:6002")],
             report: true,
+            connection: ConnectionConfig::default(),
             relay_log_fn: Box::new(|_| None),
         },
         worker: WorkerConfig::default(),
@@ -83,6 +86,7 @@ fn run_pipeline_2_worker() {
             threads: 1,
             relay_addresses: vec![String::from("127.0.0.1:6003")],
             report: true,
+            connection: ConnectionConfig::default(),
             relay_log_fn: Box::new(|_| None),
         },
         worker: WorkerConfig::default(),
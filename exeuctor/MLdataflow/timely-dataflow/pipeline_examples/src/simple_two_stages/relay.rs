@@ -3,7 +3,7 @@ use std::iter::FromIterator;
 
 use structopt::StructOpt;
 
-use timely::communication::RelayNodeConfig;
+use timely::communication::{ConnectionConfig, RelayNodeConfig};
 use timely::relay::{execute_from_config, InputToWorkerExchangePattern, RelayConfig, RelayToOutputExchangePattern};
 
 #[derive(StructOpt, Debug, Clone)]
@@ -33,6 +33,7 @@ fn run_relay_pipeline_0(relay_index: usize) {
             my_index: relay_index,
             num_relay_nodes_peers: 1,
             report: true,
+            connection: ConnectionConfig::default(),
             relay_log_sender: Box::new(|_| None),
             timely_log_sender: Box::new(|_| None)
         },
@@ -41,8 +42,9 @@ fn run_relay_pipeline_0(relay_index: usize) {
         input_to_worker_exchange_patterns: Some(HashMap::new()),
         relay_to_output_exchange_pattern: Some(RelayToOutputExchangePattern::Random),
         output_pipelines_relay_load_balance_ratios: HashMap::new(),
+        timely_workers_load_balance_ratios: None
     };
-    execute_from_config(config);
+    execute_from_config(config).unwrap();
 }
 
 
@@ -63,6 +65,7 @@ fn run_relay_pipeline_1(relay_index: usize) {
             my_index: relay_index,
             num_relay_nodes_peers: 1,
             report: true,
+            connection: ConnectionConfig::default(),
             relay_log_sender: Box::new(|_| None),
             timely_log_sender: Box::new(|_| None)
         },
@@ -77,9 +80,10 @@ fn run_relay_pipeline_1(relay_index: usize) {
         ])),
         relay_to_output_exchange_pattern: Some(RelayToOutputExchangePattern::Random),
         output_pipelines_relay_load_balance_ratios: HashMap::new(),
+        timely_workers_load_balance_ratios: None
     };
 
-    execute_from_config(config);
+    execute_from_config(config).unwrap();
 }
 
 fn main() {
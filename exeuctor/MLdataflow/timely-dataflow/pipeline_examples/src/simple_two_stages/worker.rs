@@ -1,6 +1,7 @@
 use structopt::StructOpt;
 
 use timely::CommunicationWithRelayConfig::Process;
+use timely::communication::ConnectionConfig;
 use timely::dataflow::{InputHandle, ProbeHandle};
 use timely::dataflow::operators::{Input, Inspect, Probe, Map};
 use timely::execute_pipeline::{Config, execute};
@@ -21,6 +22,7 @@ fn run_pipeline_0_worker() {
             threads: 1,
             relay_addresses: vec![String::from("127.0.0.1:6001"), String::from("127.0.0.1:6002")],
             report: true,
+            connection: ConnectionConfig::default(),
             relay_log_fn: Box::new(|_| None)
         },
         worker: WorkerConfig::default()
@@ -55,6 +57,7 @@ fn run_pipeline_1_worker() {
             threads: 1,
             relay_addresses: vec![String::from("127.0.0.1:6003"), String::from("127.0.0.1:6004")],
             report: true,
+            connection: ConnectionConfig::default(),
             relay_log_fn: Box::new(|_| None)
         },
         worker: WorkerConfig::default()
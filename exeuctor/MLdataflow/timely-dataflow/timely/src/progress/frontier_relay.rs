@@ -28,18 +28,18 @@ where
     messages_to_push: Vec<Option<FrontierUpdateMsg<T>>>,
     pushers: Vec<Vec<Box<dyn Push<FrontierUpdateMsg<T>>>>>,
     pullers: Vec<Box<dyn Pull<FrontierUpdateMsg<T>>>>,
-    init_pullers: Vec<Box<dyn Pull<Message<i32>>>>,
+    init_pullers: Vec<Box<dyn Pull<Message<i64>>>>,
     // scope inputs index -> input port id in subgraph mapping
     input_index_id_map: HashMap<usize, usize>,
     // scope output id in subgraph -> output index mapping
     output_id_index_map: HashMap<usize, usize>,
     // counters for #frontier updates message, one counter for each scope output
     counters: Vec<usize>,
-    // one u32 counter for each scope input
+    // one counter for each scope input
     // indicate whether we have received the first frontier message
     // from all input pipelines that contribute?
     // drop to 0, all init frontiers of this input received
-    frontier_received: Vec<i32>,
+    frontier_received: Vec<i64>,
     // remaining #scope inputs pending
     remaining_to_recv: usize,
     // mapping function
@@ -74,7 +74,7 @@ where
             let (_channel_pushers, puller) = worker.allocate_relay_channel(2 * index);
             std::mem::drop(_channel_pushers);
             pullers.push(puller);
-            let (_channel_pushers, puller) = worker.allocate_relay_channel::<i32>(2 * (num_scope_inputs + num_scope_outputs) + index);
+            let (_channel_pushers, puller) = worker.allocate_relay_channel::<i64>(2 * (num_scope_inputs + num_scope_outputs) + index);
             std::mem::drop(_channel_pushers);
             init_pullers.push(puller);
         }
@@ -142,6 +142,7 @@ where
             blocked_inputs.insert(port);
         }
         Pipeline {
+            noisy: worker.config().get::<bool>("noisy").copied().unwrap_or(false),
             worker: worker.clone(),
             blocked_inputs,
             subgraph,
@@ -169,6 +170,7 @@ where
             blocked_inputs.insert(port);
         }
         Pipeline {
+            noisy: worker.config().get::<bool>("noisy").copied().unwrap_or(false),
             worker: worker.clone(),
             blocked_inputs,
             subgraph,
@@ -198,6 +200,8 @@ where
     R: FrontierRelay<TInner>
 {
     worker: A,
+    // report the input frontiers closed on behalf of lost workers
+    noisy: bool,
     blocked_inputs: HashSet<usize>,
     subgraph: Subgraph<TOuter, TInner>,
     staged_input_frontier_changes: ChangeBatch<(usize, TInner)>,
@@ -250,6 +254,14 @@ where
                 }
             }
         }
+        // no more frontier changes will be received after the input pipelines have completed,
+        // close the input frontiers still held by lost workers of the input pipelines
+        if self.worker.relay_input_pipelines_completed() {
+            self.frontier_relay.recv(&mut self.staged_input_frontier_changes, &mut self.input_frontier_started);
+            if self.subgraph.close_pipeline_inputs_frontier(&mut self.staged_input_frontier_changes) && self.noisy {
+                println!("worker {}: closed the input frontiers held by lost workers of the input pipelines", self.worker.index());
+            }
+        }
         let frontier_empty = self.subgraph.peek_pipeline_inputs_frontier_is_empty(&mut self.staged_input_frontier_changes);
         if frontier_empty {
             // drain all remaining inputs before update frontiers
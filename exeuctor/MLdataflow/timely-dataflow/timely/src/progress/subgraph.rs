@@ -450,6 +450,22 @@ where
         pointstamps.iter().any(|x| x.is_empty())
     }
 
+    /// Retract the remaining pointstamps at the scope inputs after `frontier_changes` committed,
+    /// should be called once the input pipelines have completed, i.e., no more frontier changes will be received,
+    /// the remaining pointstamps were held by lost workers of the input pipelines.
+    /// Returns whether any pointstamp is retracted
+    pub(super) fn close_pipeline_inputs_frontier(&mut self, frontier_changes: &mut ChangeBatch<(usize, TInner)>) -> bool {
+        let mut pointstamps = self.pointstamp_tracker.node_state(0).sources.iter().map(|x| x.pointstamps.clone()).collect::<Vec<_>>();
+        for ((input_port, time), delta) in frontier_changes.iter() {
+            pointstamps[*input_port].update_iter(Some((time.clone(), *delta)));
+        }
+        let mut retracted = false;
+        for (input_port, pointstamps) in pointstamps.iter_mut().enumerate() {
+            retracted |= retract_pointstamps(input_port, pointstamps, frontier_changes);
+        }
+        retracted
+    }
+
     /// Accept scope inputs' frontiers from the relay node (received from input pipelines)
     pub(super) fn accept_pipeline_inputs_frontier(&mut self, frontier_changes: &mut ChangeBatch<(usize, TInner)>) {
         for ((input_port, time), delta) in frontier_changes.drain() {
@@ -938,3 +954,52 @@ impl<T: Timestamp> Drop for PerOperatorState<T> {
         self.shut_down();
     }
 }
+
+/// Retract all pointstamps of the scope input `input_port` into `frontier_changes`,
+/// returns whether any pointstamp is retracted
+fn retract_pointstamps<T: Timestamp>(input_port: usize, pointstamps: &mut MutableAntichain<T>, frontier_changes: &mut ChangeBatch<(usize, T)>) -> bool {
+    let mut retracted = false;
+    while !pointstamps.is_empty() {
+        let retractions = pointstamps.frontier().iter().map(|time| (time.clone(), -pointstamps.count_for(time))).collect::<Vec<_>>();
+        for (time, delta) in retractions.iter() {
+            frontier_changes.update((input_port, time.clone()), *delta);
+        }
+        pointstamps.update_iter(retractions);
+        retracted = true;
+    }
+    retracted
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::progress::ChangeBatch;
+    use crate::progress::frontier::MutableAntichain;
+    use super::retract_pointstamps;
+
+    #[test]
+    fn retract_pointstamps_held_by_lost_workers() {
+        // two workers of the input pipeline hold the scope input at 3 and 5,
+        // the one at 3 advanced to 7 before the other one is lost
+        let mut pointstamps = MutableAntichain::new();
+        pointstamps.update_iter(vec![(3u64, 1), (5, 1)]);
+        let mut frontier_changes = ChangeBatch::new();
+        frontier_changes.update((1, 7u64), 1);
+        frontier_changes.update((1, 3u64), -1);
+        pointstamps.update_iter(frontier_changes.iter().map(|((_, time), delta)| (*time, *delta)));
+
+        assert!(retract_pointstamps(1, &mut pointstamps, &mut frontier_changes));
+        assert!(pointstamps.is_empty());
+
+        // the retractions are committed along with the received changes
+        let mut closed = MutableAntichain::new();
+        closed.update_iter(vec![(3u64, 1), (5, 1)]);
+        closed.update_iter(frontier_changes.drain().map(|((input_port, time), delta)| {
+            assert_eq!(input_port, 1);
+            (time, delta)
+        }));
+        assert!(closed.is_empty());
+
+        assert!(!retract_pointstamps(1, &mut pointstamps, &mut frontier_changes));
+        assert!(frontier_changes.is_empty());
+    }
+}
@@ -8,25 +8,30 @@ use rand::thread_rng;
 use weighted_rs::{SmoothWeight, Weight};
 
 use timely_communication::{Message, Pull, Push};
+use timely_communication::allocator::relay::TimelyWorkersLiveness;
 
-use crate::communication::{relay_initialize, RelayNodeConfig as RelayNodeCommConfig};
+use crate::communication::{relay_initialize, RelayGuards, RelayNodeConfig as RelayNodeCommConfig};
 use crate::communication::allocator::relay::{InputRelayAllocate, OutputRelayAllocate};
 use crate::communication::allocator::relay::relay_allocator::{InputRelayWorkerAllocator, OutputRelayWorkerAllocator};
 use crate::progress::Timestamp;
 use crate::relay::{InputToWorkerExchangePattern, RelayConfig, RelayToOutputExchangePattern};
 use crate::relay::registry::{InputRelayRegistry, OutputRelayRegistry};
 
-pub fn execute_from_config(config: RelayConfig) {
+/// Spin up the relay workers described by the config,
+/// returns an error if the connections to the timely workers and the relay nodes could not be established,
+/// otherwise returns the guards of the relay workers (dropping the guards waits for the relay workers to finish)
+pub fn execute_from_config(config: RelayConfig) -> Result<RelayGuards<(), ()>, String> {
     let comm_config = config.comm_config;
     let input_index_mapping = config.input_index_mapping;
     let input_exchange_pattern = config.input_to_worker_exchange_patterns;
+    let timely_workers_load_ratios = config.timely_workers_load_balance_ratios;
 
     let mut input_indices = HashMap::new();
     let mut output_indices = HashSet::new();
     for input_index_map in input_index_mapping.iter() {
         for (_, input_idx) in input_index_map.iter() {
             if !input_indices.contains_key(input_idx) {
-                input_indices.insert(*input_idx, 0i32);
+                input_indices.insert(*input_idx, 0i64);
             }
             *input_indices.get_mut(input_idx).unwrap() += 1;
         }
@@ -69,9 +74,9 @@ pub fn execute_from_config(config: RelayConfig) {
         // each input relay worker connects to an input pipeline
         // and is in charge of some of the scope inputs of the current pipeline
         let mut input_indices = Vec::with_capacity(num_inputs_current_input_pipeline);
-        // for the Balance exchange pattern, for each of the input, we maintain a counter
-        // to distribute it evenly among the timely workers (threads)
-        let mut send_counter = HashMap::with_capacity(num_inputs_current_input_pipeline);
+        // for the Balance exchange pattern, for each of the input, we maintain a load balancer
+        // to distribute it among the timely workers (threads)
+        let mut workers_load_balancer = HashMap::with_capacity(num_inputs_current_input_pipeline);
 
         // allocate channels
         for (output_index, input_index) in pipeline_input_index_mapping.iter() {
@@ -92,7 +97,6 @@ pub fn execute_from_config(config: RelayConfig) {
             frontier_init_pushers.push(to_timely_frontier_init_pushers);
             first_frontier_received.push(false);
             input_indices.push(input_index.to_owned());
-            send_counter.insert(input_index.to_owned(), 0usize);
         }
 
         allocator.finish_channel_allocation();
@@ -101,8 +105,27 @@ pub fn execute_from_config(config: RelayConfig) {
         let relay_index = allocator.relay_index();
         let direct_pass_flag = num_peer_relay_nodes == num_timely_workers;
 
+        // stop routing data to the timely workers whose process is lost,
+        // re-build the load balancers over the surviving ones whenever a process is lost
+        let liveness = allocator.timely_workers_liveness().clone();
+        let threads_per_process = num_timely_workers / liveness.num_processes();
+        let mut num_lost_processes = 0;
+        let mut alive_workers = (0..num_timely_workers).collect::<Vec<_>>();
+        for input_index in input_indices.iter() {
+            workers_load_balancer.insert(*input_index, timely_workers_load_balancer(&timely_workers_load_ratios, &lost_processes(&liveness), threads_per_process));
+            // data not broadcast can be redelivered to any surviving timely worker
+            // if the timely worker process it was sent to is lost (with at-least-once delivery)
+            let exchange_pattern = match pipeline_input_exchange_pattern {
+                Some(patterns) => *patterns.get(input_index).unwrap(),
+                None => InputToWorkerExchangePattern::Balance
+            };
+            if !matches!(exchange_pattern, InputToWorkerExchangePattern::Broadcast) {
+                liveness.register_redeliverable_channel(2 * input_index + 1);
+            }
+        }
+
         let mut rng = thread_rng();
-        let uniform_dist = Uniform::new(0, num_timely_workers);
+        let mut uniform_dist = Uniform::new(0, num_timely_workers);
 
         let mut active = true;
         while active {
@@ -111,19 +134,30 @@ pub fn execute_from_config(config: RelayConfig) {
             allocator.receive_pipeline_input();
             allocator.events().borrow_mut().drain(..);
 
+            if liveness.num_lost() != num_lost_processes {
+                num_lost_processes = liveness.num_lost();
+                alive_workers = (0..num_timely_workers).filter(|x| !liveness.is_lost(*x / threads_per_process)).collect();
+                if !alive_workers.is_empty() {
+                    uniform_dist = Uniform::new(0, alive_workers.len());
+                }
+                for lb in workers_load_balancer.values_mut() {
+                    *lb = timely_workers_load_balancer(&timely_workers_load_ratios, &lost_processes(&liveness), threads_per_process);
+                }
+            }
+
             // broadcast frontier changes for each of the scope input obtained from this input pipeline
             for (((puller, pushers), init_pushers), mark) in
             frontier_pullers.iter_mut().zip(frontier_pushers.iter_mut()).zip(frontier_init_pushers.iter_mut()).zip(first_frontier_received.iter_mut()) {
                 while let Some((element, latency)) = puller.recv_with_transmission_latency() {
                     let element = &mut Some(element);
-                    // we should broadcast frontier updates to every timely worker in the current pipeline
-                    for pusher in pushers.iter_mut() {
-                        pusher.push_with_latency_passthrough(element, Some(latency));
+                    // we should broadcast frontier updates to every (surviving) timely worker in the current pipeline
+                    for worker_index in alive_workers.iter() {
+                        pushers[*worker_index].push_with_latency_passthrough(element, Some(latency));
                     }
 
                     if !*mark {
                         for init_pusher in init_pushers.iter_mut() {
-                            init_pusher.send_with_latency_passthrough(Message::from_typed(-1i32), 0);
+                            init_pusher.send_with_latency_passthrough(Message::from_typed(-1i64), 0);
                         }
                         for init_pusher in init_pushers.iter_mut() { init_pusher.done() }
                         *mark = true;
@@ -136,6 +170,13 @@ pub fn execute_from_config(config: RelayConfig) {
             // receive and forward data for each scope input
             for (index, (puller, pushers)) in
             input_indices.iter().zip(data_pullers.iter_mut().zip(data_pushers.iter_mut())) {
+                // redeliver the data sent to the lost timely worker processes
+                if num_lost_processes > 0 && !alive_workers.is_empty() {
+                    for (element, latency) in liveness.take_undelivered(2 * index + 1) {
+                        let worker_to_send = workers_load_balancer.get_mut(index).unwrap().next().unwrap();
+                        pushers[worker_to_send].push_with_latency_passthrough(&mut Some(element), latency);
+                    }
+                }
                 while let Some((element, latency)) = puller.recv_with_transmission_latency() {
                     let element = &mut Some(element);
                     // distribute the data (Bundle<T, D>) according to the user defined pattern
@@ -146,27 +187,32 @@ pub fn execute_from_config(config: RelayConfig) {
                     };
                     match exchange_pattern {
                         InputToWorkerExchangePattern::Random => {
-                            let worker_to_send = uniform_dist.sample(&mut rng);
+                            // if all timely worker processes are lost, the data is discarded by the network threads
+                            let worker_to_send = match alive_workers.is_empty() {
+                                false => alive_workers[uniform_dist.sample(&mut rng)],
+                                true => uniform_dist.sample(&mut rng)
+                            };
                             let pusher = &mut pushers[worker_to_send];
                             pusher.push_with_latency_passthrough(element, Some(latency));
                         },
                         InputToWorkerExchangePattern::Balance => {
                             // TODO: balance between timely worker process
                             // i.e., distribute to different timely worker processes between two consecutive calls
-                            if direct_pass_flag {
+                            if direct_pass_flag && !liveness.is_lost(relay_index / threads_per_process) {
                                 let pusher = &mut pushers[relay_index];
                                 pusher.push_with_latency_passthrough(element, Some(latency));
                             }
                             else {
-                                let count = send_counter.get_mut(index).unwrap();
-                                let pusher = &mut pushers[*count];
-                                *count = (*count + 1) % num_timely_workers;
+                                // the timely worker paired with the relay node is lost,
+                                // redistribute its share of data to the surviving ones
+                                let worker_to_send = workers_load_balancer.get_mut(index).unwrap().next().unwrap();
+                                let pusher = &mut pushers[worker_to_send];
                                 pusher.push_with_latency_passthrough(element, Some(latency));
                             }
                         },
                         InputToWorkerExchangePattern::Broadcast => {
-                            for pusher in pushers.iter_mut() {
-                                pusher.push_with_latency_passthrough(element, Some(latency));
+                            for worker_index in alive_workers.iter() {
+                                pushers[*worker_index].push_with_latency_passthrough(element, Some(latency));
                             }
                         }
                     }
@@ -317,7 +363,44 @@ pub fn execute_from_config(config: RelayConfig) {
         }
     };
 
-    relay_initialize(comm_config, input_relay_worker, output_relay_worker).unwrap();
+    relay_initialize(comm_config, input_relay_worker, output_relay_worker)
+}
+
+/// Whether the connection to each of the timely worker processes is lost
+fn lost_processes(liveness: &TimelyWorkersLiveness) -> Vec<bool> {
+    (0..liveness.num_processes()).map(|x| liveness.is_lost(x)).collect()
+}
+
+/// Smooth weighted round-robin over the timely workers (threads),
+/// the ratios of the timely worker processes are re-normalized over the surviving processes.
+/// If all processes are lost, balance over all of them (the network threads discard the data)
+fn timely_workers_load_balancer(ratios: &Option<Vec<f64>>, lost: &[bool], threads_per_process: usize) -> SmoothWeight<usize> {
+    let num_processes = lost.len();
+    let mut processes = (0..num_processes).filter(|x| !lost[*x]).collect::<Vec<_>>();
+    if processes.is_empty() {
+        processes = (0..num_processes).collect();
+    }
+    let mut lb = SmoothWeight::new();
+    match ratios {
+        Some(ratios) => {
+            assert!(ratios.len() == num_processes, "weight for each of the timely worker process must be provided");
+            let total_sum = processes.iter().map(|x| ratios[*x]).sum::<f64>();
+            for process in processes {
+                let weight = std::cmp::max((ratios[process] * 100.0_f64 / total_sum).round() as isize, 1isize);
+                for thread in 0..threads_per_process {
+                    lb.add(process * threads_per_process + thread, weight);
+                }
+            }
+        },
+        None => {
+            for process in processes {
+                for thread in 0..threads_per_process {
+                    lb.add(process * threads_per_process + thread, 1);
+                }
+            }
+        }
+    }
+    lb
 }
 
 /// Execute from UDF functions that take in InputRelayRegistry and OutputRelayRegistry
@@ -388,3 +471,29 @@ where
     relay_initialize(comm_config, input_relay_worker, output_relay_worker).unwrap();
 }
 
+#[cfg(test)]
+mod tests {
+    use weighted_rs::Weight;
+
+    use super::timely_workers_load_balancer;
+
+    fn routed(lost: &[bool], ratios: Option<Vec<f64>>, num_messages: usize) -> Vec<usize> {
+        let mut lb = timely_workers_load_balancer(&ratios, lost, 2);
+        let mut counts = vec![0; lost.len() * 2];
+        for _ in 0..num_messages {
+            counts[lb.next().unwrap()] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn reroute_around_lost_processes() {
+        assert_eq!(routed(&[false, false, false], None, 60), vec![10; 6]);
+        // the share of the lost process 1 is redistributed to the surviving ones
+        assert_eq!(routed(&[false, true, false], None, 60), vec![15, 15, 0, 0, 15, 15]);
+        // the ratios are re-normalized over the surviving processes
+        assert_eq!(routed(&[false, true, false], Some(vec![3.0, 2.0, 1.0]), 200), vec![75, 75, 0, 0, 25, 25]);
+        // if all processes are lost, balance over all of them (the network threads discard the data)
+        assert_eq!(routed(&[true, true, true], None, 60), vec![10; 6]);
+    }
+}
@@ -7,7 +7,7 @@ use structopt::StructOpt;
 
 use timely::relay::{execute_from_config, InputToWorkerExchangePattern, RelayToOutputExchangePattern};
 use timely::relay::RelayConfig;
-use timely::communication::RelayNodeConfig as RelayNodeCommConfig;
+use timely::communication::{ConnectionConfig, RelayNodeConfig as RelayNodeCommConfig};
 
 
 #[derive(Serialize, Deserialize)]
@@ -77,6 +77,7 @@ fn main() {
         my_index: index,
         num_relay_nodes_peers: num_relay_peers,
         report: true,
+        connection: ConnectionConfig::default(),
         relay_log_sender: Box::new(|_| None),
         timely_log_sender: Box::new(|_| None)
     };
@@ -87,8 +88,9 @@ fn main() {
         required_outputs: json_config.required_outputs,
         input_to_worker_exchange_patterns: json_config.input_to_worker_exchange_patterns,
         relay_to_output_exchange_pattern: json_config.relay_to_output_exchange_pattern,
-        output_pipelines_relay_load_balance_ratios: HashMap::new()
+        output_pipelines_relay_load_balance_ratios: HashMap::new(),
+        timely_workers_load_balance_ratios: None
     };
 
-    execute_from_config(relay_config);
+    execute_from_config(relay_config).unwrap();
 }
\ No newline at end of file
@@ -55,5 +55,9 @@ pub struct RelayConfig {
     /// We need to distribute the data to the workers according to some ratios
     /// This is a map that maps the output pipeline index to a vector of ratios to distribute
     /// to each output pipeline relay node.
-    pub output_pipelines_relay_load_balance_ratios: HashMap<usize, Vec<f64>>
+    pub output_pipelines_relay_load_balance_ratios: HashMap<usize, Vec<f64>>,
+    /// Ratios to distribute the data to each timely worker process of the current pipeline
+    /// (for the Balance exchange pattern, evenly if None),
+    /// re-normalized over the surviving processes if some are lost.
+    pub timely_workers_load_balance_ratios: Option<Vec<f64>>
 }
\ No newline at end of file
@@ -145,10 +145,10 @@ def main():
     if args.output:
         if args.output.endswith(".yaml"):
             with open(args.output, 'wt') as f:
-                json.dump(best_assignment, f, sort_keys=True, indent=4)
+                yaml.dump(best_assignment, f,)
         elif args.output.endswith(".json"):
             with open(args.output, 'wt') as f:
-                yaml.dump(best_assignment, f,)
+                json.dump(best_assignment, f, sort_keys=True, indent=4)
         else:
             raise ValueError("invalid output file format")
 